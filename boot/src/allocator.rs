@@ -304,6 +304,17 @@ impl<T: Into<PhysMemRegion> + Copy + 'static> BumpAllocator<T> {
 
         Ok(())
     }
+
+    /**
+        Returns the span of the current arena, if the allocator
+        has been initialized
+
+        The base of the span is the head of the allocator, and
+        its size is the capacity left in the arena.
+    */
+    pub fn arena(&self) -> Option<RegionSpan> {
+        self.state.lock().as_ref().map(|s| s.current_arena)
+    }
 }
 
 // TODO REVIEW + FIXME: WTF is this madness!?
@@ -381,3 +392,122 @@ unsafe impl<T: Into<PhysMemRegion> + Copy + 'static> GlobalAlloc for BumpAllocat
 
 unsafe impl<T: Into<PhysMemRegion> + Copy + 'static> Sync for BumpAllocator<T> {}
 unsafe impl<T: Into<PhysMemRegion> + Copy + 'static> Send for BumpAllocator<T> {}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod tests {
+    use super::*;
+    use common::shared::mm::PhysMemKind;
+
+    // - addresses are only computed, never dereferenced
+    static PHYS: [PhysMemRegion; 5] = [
+        PhysMemRegion::new(0x0, 0x9fc00, PhysMemKind::regular()),
+        PhysMemRegion::new(0x9fc00, 0x400, PhysMemKind::reserved(None)),
+        PhysMemRegion::new(0x10_0000, 0x10_0000, PhysMemKind::regular()),
+        PhysMemRegion::new(0x20_0000, 0x10_0000, PhysMemKind::reserved(None)),
+        PhysMemRegion::new(0x30_0008, 0x40_0000, PhysMemKind::regular()),
+    ];
+
+    static IMAGE: [BootImageRegion; 1] = [BootImageRegion::new(
+        0x7c00,
+        0x18400,
+        BootImage::new(true, false),
+    )];
+
+    // Internal: state with the provided current arena
+    fn state(base: usize, size: usize) -> BumpAllocatorState<PhysMemRegion> {
+        BumpAllocatorState {
+            phys_mem_layout: &PHYS,
+            logical_mem_layout: &IMAGE,
+            current_arena: RegionSpan::new(base, size),
+        }
+    }
+
+    // Internal: returns the current arena as `(base, size)`
+    fn arena(s: &BumpAllocatorState<PhysMemRegion>) -> (usize, usize) {
+        (s.current_arena.base(), s.current_arena.size())
+    }
+
+    #[test]
+    fn round_up() {
+        assert_eq!(round_addr(0x1000, 0x1000), 0x1000);
+        assert_eq!(round_addr(0x1001, 0x1000), 0x2000);
+        assert_eq!(round_addr(0x0, 16), 0x0);
+        assert_eq!(round_addr(0x7, 1), 0x7);
+    }
+
+    #[test]
+    fn locate_next_region() {
+        // - skips the rest of the current region, the
+        // reserved region, then aligns the next base
+        let mut s = state(0x10_0100, 0x10);
+        s.locate_new_arena(0x1000, 0x1000).unwrap();
+        assert_eq!(arena(&s), (0x30_1000, 0x40_0000 - 0xff8));
+    }
+
+    #[test]
+    fn locate_from_zero_size_arena() {
+        // - an exhausted arena at a region's base
+        // doesn't rule out that region
+        let mut s = state(0x10_0000, 0);
+        s.locate_new_arena(0x10, 0x10).unwrap();
+        assert_eq!(arena(&s), (0x10_0000, 0x10_0000));
+
+        // - the boot image and reserved regions are never chosen
+        let mut s = state(0x0, 0);
+        s.locate_new_arena(0x10, 0x10).unwrap();
+        assert_eq!(arena(&s), (0x10_0000, 0x10_0000));
+    }
+
+    #[test]
+    fn locate_out_of_memory() {
+        // - too large for any region
+        let mut s = state(0x0, 0);
+        assert!(s.locate_new_arena(0x100_0000, 0x10).is_err());
+        assert_eq!(arena(&s), (0x0, 0));
+
+        // - nothing above the last region
+        let mut s = state(0x70_0008, 0);
+        assert!(s.locate_new_arena(0x10, 0x10).is_err());
+    }
+
+    #[test]
+    fn init_once() {
+        let a = BumpAllocator::new();
+        assert!(a.init(&PHYS, 0x100_0000, &IMAGE).is_err());
+        assert!(a.arena().is_none());
+
+        a.init(&PHYS, 0, &IMAGE).unwrap();
+        assert!(a.init(&PHYS, 0, &IMAGE).is_err());
+
+        let inner = a.state.lock();
+        assert_eq!(arena(inner.as_ref().unwrap()), (0x10_0000, 0x10_0000));
+    }
+
+    #[test]
+    fn bump_and_relocate() {
+        let a = BumpAllocator::new();
+        a.init(&PHYS, 0x1000, &IMAGE).unwrap();
+
+        let alloc = |size, align| unsafe {
+            a.alloc(Layout::from_size_align(size, align).unwrap()) as usize
+        };
+
+        assert_eq!(alloc(0x10, 8), 0x10_0000);
+        assert_eq!(alloc(0x1, 1), 0x10_0010);
+        assert_eq!(alloc(0x10, 0x10), 0x10_0020);
+
+        let arena = a.arena().unwrap();
+        assert_eq!((arena.base(), arena.size()), (0x10_0030, 0x10_0000 - 0x30));
+
+        // - zero-size allocations only align the head
+        assert_eq!(alloc(0, 0x100), 0x10_0100);
+        assert_eq!(alloc(0x8, 8), 0x10_0030);
+
+        // - exhausting the arena moves on to the next region
+        assert_eq!(alloc(0x20_0000, 0x1000), 0x30_1000);
+        assert_eq!(alloc(0x10, 0x10), 0x50_1000);
+
+        // - requests that fit nowhere fail
+        assert_eq!(alloc(0x40_0000, 8), 0);
+    }
+}
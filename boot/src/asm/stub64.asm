@@ -73,8 +73,14 @@ _stub64:
     call _start
     ; --- fall-through (unlikely) --- ;
 
-.spin:
+.halt:
+%ifdef DEBUG_SPIN
     ; Freeze without eternally halting
     ; (to avoid killing debuggers...)
     pause               ; Signal spin loop
-    jmp .spin           ; Repeat
\ No newline at end of file
+%else
+    ; Halt, waking up only for NMIs and SMIs
+    cli                 ; Disable interrupts
+    hlt                 ; Halt
+%endif
+    jmp .halt           ; Repeat
\ No newline at end of file
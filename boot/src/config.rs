@@ -0,0 +1,993 @@
+/*!
+    Internal module defining the boot configuration parser
+
+    The stage-2 loader reads its configuration from `BOOT.CFG` in
+    the root directory of the boot volume. The format is a simple,
+    line-based key-value format with INI-style sections:
+    ```text
+    # Global settings
+    timeout = 5
+    default = magnetite
+    console = vga
+    loglevel = info
+    panic = reboot,10
+
+    # Boot entries
+    [magnetite]
+    kernel = /KERNEL.ELF
+    cmdline = "console=serial,115200 loglevel=debug"
+    module = /INITRD.IMG
+    ```
+
+    # Syntax
+    - Comments start with `#`, and must occupy their own line.
+    - Keys before the first section are global settings, whereas
+      keys after a section header belong to that boot entry.
+    - Values extend to the end of the line, and are trimmed. Values
+      can be enclosed in double quotes to preserve surrounding
+      whitespace; no escape sequences are recognized.
+
+    # Global keys
+    - `timeout` - menu timeout in seconds
+    - `default` - name of the default boot entry (defaults to the first)
+    - `console` - one of `vga`, `framebuffer` and `serial`
+    - `loglevel` - one of `error`, `warn`, `info`, `debug` and `trace`
+    - `panic` - one of `halt`, `poweroff`, `reboot` and `reboot,<seconds>`
+
+    # Entry keys
+    - `kernel` - path to the kernel image (required)
+    - `cmdline` - kernel command line, which must be valid as per
+      [`common::shared::cmdline`]; as quotes are only special at the
+      start of a value, `cmdline = root="disk 0" quiet` works as expected
+    - `module` - path to a boot module (may be repeated)
+
+    The parser does not allocate; the resulting [`BootConfig`]
+    borrows from the provided source text.
+*/
+
+// Standard definitions
+use core::fmt;
+use core::str;
+
+// Command-line tokenizer
+use common::shared::cmdline::Cmdline;
+
+// Log filter
+use common::shared::log::LevelFilter;
+
+// Panic policy
+use common::plat::pc_bios::power::PanicPolicy;
+
+/// Maximum number of boot entries
+pub const MAX_ENTRIES: usize = 8;
+
+/// Maximum number of modules per boot entry
+pub const MAX_MODULES: usize = 4;
+
+/// Default menu timeout in seconds
+pub const DEF_TIMEOUT: u32 = 5;
+
+/// Console used by the bootloader
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ConsoleKind {
+    /// VGA text console
+    Vga,
+
+    /// Linear framebuffer console
+    Framebuffer,
+
+    /// Serial console (COM1)
+    Serial,
+}
+
+/// Verbosity of the bootloader
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub enum LogLevel {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl From<LogLevel> for LevelFilter {
+    fn from(value: LogLevel) -> LevelFilter {
+        match value {
+            LogLevel::Error => LevelFilter::Error,
+            LogLevel::Warn => LevelFilter::Warn,
+            LogLevel::Info => LevelFilter::Info,
+            LogLevel::Debug => LevelFilter::Debug,
+            LogLevel::Trace => LevelFilter::Trace,
+        }
+    }
+}
+
+/// Kinds of errors encountered while parsing a boot configuration
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum ParseErrorKind {
+    /// A key was not followed by `=`
+    ExpectedEquals,
+
+    /// A line started with `=`
+    EmptyKey,
+
+    /// The key is not recognized
+    UnknownKey,
+
+    /// The key is recognized, but not valid in its current
+    /// position (global key in an entry, or vice versa)
+    MisplacedKey,
+
+    /// The key was already set in the current scope
+    DuplicateKey,
+
+    /// The value could not be interpreted
+    InvalidValue,
+
+    /// A quoted value lacks a closing quote
+    UnterminatedQuote,
+
+    /// A section header lacks a closing bracket
+    UnterminatedSection,
+
+    /// A section header has an empty name
+    EmptySectionName,
+
+    /// Characters were found after a closing quote or bracket
+    TrailingCharacters,
+
+    /// Two entries have the same name
+    DuplicateEntry,
+
+    /// More than [`MAX_ENTRIES`] entries were defined
+    TooManyEntries,
+
+    /// More than [`MAX_MODULES`] modules were defined for an entry
+    TooManyModules,
+
+    /// An entry lacks the `kernel` key
+    MissingKernel,
+
+    /// The default entry doesn't exist
+    UnknownDefault,
+
+    /// The source is not valid UTF-8
+    InvalidEncoding,
+}
+
+impl ParseErrorKind {
+    /// Returns a human-readable description
+    pub fn message(&self) -> &'static str {
+        match self {
+            ParseErrorKind::ExpectedEquals => "expected '=' after key",
+            ParseErrorKind::EmptyKey => "expected key before '='",
+            ParseErrorKind::UnknownKey => "unknown key",
+            ParseErrorKind::MisplacedKey => "key not allowed here",
+            ParseErrorKind::DuplicateKey => "key already set",
+            ParseErrorKind::InvalidValue => "invalid value",
+            ParseErrorKind::UnterminatedQuote => "missing closing '\"'",
+            ParseErrorKind::UnterminatedSection => "missing closing ']'",
+            ParseErrorKind::EmptySectionName => "empty entry name",
+            ParseErrorKind::TrailingCharacters => "unexpected trailing characters",
+            ParseErrorKind::DuplicateEntry => "entry already defined",
+            ParseErrorKind::TooManyEntries => "too many entries",
+            ParseErrorKind::TooManyModules => "too many modules",
+            ParseErrorKind::MissingKernel => "entry lacks a kernel",
+            ParseErrorKind::UnknownDefault => "default entry not found",
+            ParseErrorKind::InvalidEncoding => "invalid UTF-8 sequence",
+        }
+    }
+}
+
+/**
+    Error encountered while parsing a boot configuration
+
+    Both the line and the column are one-based, and the column
+    is counted in characters.
+*/
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ParseError {
+    line: usize,
+    col: usize,
+    kind: ParseErrorKind,
+}
+
+impl ParseError {
+    /// Returns the (one-based) line number
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// Returns the (one-based) column number
+    pub fn col(&self) -> usize {
+        self.col
+    }
+
+    /// Returns the error kind
+    pub fn kind(&self) -> ParseErrorKind {
+        self.kind
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.col, self.kind.message())
+    }
+}
+
+/// A single boot entry
+#[derive(Copy, Clone, Debug)]
+pub struct BootEntry<'a> {
+    name: &'a str,
+    kernel: &'a str,
+    cmdline: &'a str,
+    modules: [&'a str; MAX_MODULES],
+    num_modules: usize,
+    // - position of the section header, for error reporting
+    pos: Pos,
+}
+
+impl<'a> BootEntry<'a> {
+    // Internal: create an empty entry
+    const fn new(name: &'a str, pos: Pos) -> Self {
+        BootEntry {
+            name,
+            kernel: "",
+            cmdline: "",
+            modules: [""; MAX_MODULES],
+            num_modules: 0,
+            pos,
+        }
+    }
+
+    /// Returns the entry name
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+
+    /// Returns the path to the kernel image
+    pub fn kernel(&self) -> &'a str {
+        self.kernel
+    }
+
+    /// Returns the kernel command line
+    pub fn cmdline(&self) -> &'a str {
+        self.cmdline
+    }
+
+    /// Returns the paths to the boot modules
+    pub fn modules(&self) -> &[&'a str] {
+        &self.modules[..self.num_modules]
+    }
+}
+
+/// Parsed boot configuration
+#[derive(Clone, Debug)]
+pub struct BootConfig<'a> {
+    timeout: u32,
+    default: usize,
+    console: ConsoleKind,
+    log_level: LogLevel,
+    panic_policy: PanicPolicy,
+    entries: [BootEntry<'a>; MAX_ENTRIES],
+    num_entries: usize,
+}
+
+impl<'a> BootConfig<'a> {
+    /// Create new instance of `BootConfig` with default settings and no entries
+    pub const fn new() -> Self {
+        BootConfig {
+            timeout: DEF_TIMEOUT,
+            default: 0,
+            console: ConsoleKind::Vga,
+            log_level: LogLevel::Info,
+            panic_policy: PanicPolicy::Halt,
+            entries: [BootEntry::new("", Pos { line: 0, col: 0 }); MAX_ENTRIES],
+            num_entries: 0,
+        }
+    }
+
+    /// Parse the provided configuration source
+    pub fn parse(src: &'a str) -> Result<Self, ParseError> {
+        Parser::new(src).run()
+    }
+
+    /**
+        Parse the provided configuration source, which is raw file contents
+
+        Invalid UTF-8 sequences are reported at their position.
+    */
+    pub fn parse_bytes(src: &'a [u8]) -> Result<Self, ParseError> {
+        match str::from_utf8(src) {
+            Ok(s) => Self::parse(s),
+            Err(e) => {
+                // - the prefix is valid by definition
+                let valid = str::from_utf8(&src[..e.valid_up_to()]).unwrap_or_default();
+                let last = valid.rsplit('\n').next().unwrap_or_default();
+
+                Err(ParseError {
+                    line: valid.matches('\n').count() + 1,
+                    col: last.chars().count() + 1,
+                    kind: ParseErrorKind::InvalidEncoding,
+                })
+            }
+        }
+    }
+
+    /// Returns the menu timeout in seconds
+    pub fn timeout(&self) -> u32 {
+        self.timeout
+    }
+
+    /// Returns the index of the default entry
+    pub fn default_index(&self) -> usize {
+        self.default
+    }
+
+    /// Returns the default entry, if any entries are defined
+    pub fn default_entry(&self) -> Option<&BootEntry<'a>> {
+        self.entries().get(self.default)
+    }
+
+    /// Returns the console type
+    pub fn console(&self) -> ConsoleKind {
+        self.console
+    }
+
+    /// Returns the log level
+    pub fn log_level(&self) -> LogLevel {
+        self.log_level
+    }
+
+    /// Returns the action to take after a panic
+    pub fn panic_policy(&self) -> PanicPolicy {
+        self.panic_policy
+    }
+
+    /// Returns the boot entries
+    pub fn entries(&self) -> &[BootEntry<'a>] {
+        &self.entries[..self.num_entries]
+    }
+}
+
+impl Default for BootConfig<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Internal: (one-based) position within the source
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+struct Pos {
+    line: usize,
+    col: usize,
+}
+
+// Internal: bit flags for detecting duplicate keys
+const SEEN_TIMEOUT: u8 = 1 << 0;
+const SEEN_DEFAULT: u8 = 1 << 1;
+const SEEN_CONSOLE: u8 = 1 << 2;
+const SEEN_LOGLEVEL: u8 = 1 << 3;
+const SEEN_PANIC: u8 = 1 << 4;
+const SEEN_KERNEL: u8 = 1 << 5;
+const SEEN_CMDLINE: u8 = 1 << 6;
+
+// Internal: single-use parser state
+struct Parser<'a> {
+    src: &'a str,
+    config: BootConfig<'a>,
+    default: Option<(&'a str, Pos)>,
+    seen_global: u8,
+    seen_entry: u8,
+    // - the line currently being parsed, and its number
+    line: &'a str,
+    line_no: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(src: &'a str) -> Self {
+        Parser {
+            src,
+            config: BootConfig::new(),
+            default: None,
+            seen_global: 0,
+            seen_entry: 0,
+            line: "",
+            line_no: 0,
+        }
+    }
+
+    // Generate an error at the provided byte offset within the current line
+    fn error_at(&self, off: usize, kind: ParseErrorKind) -> ParseError {
+        // - count characters rather than bytes
+        let col = self.line[..off].chars().count() + 1;
+
+        ParseError {
+            line: self.line_no,
+            col,
+            kind,
+        }
+    }
+
+    // Obtain position of the provided byte offset within the current line
+    fn pos_at(&self, off: usize) -> Pos {
+        let e = self.error_at(off, ParseErrorKind::InvalidValue);
+        Pos {
+            line: e.line,
+            col: e.col,
+        }
+    }
+
+    fn run(mut self) -> Result<BootConfig<'a>, ParseError> {
+        for (i, raw) in self.src.split('\n').enumerate() {
+            self.line = raw.strip_suffix('\r').unwrap_or(raw);
+            self.line_no = i + 1;
+            self.parse_line()?;
+        }
+
+        self.finish()
+    }
+
+    fn parse_line(&mut self) -> Result<(), ParseError> {
+        let line = self.line;
+
+        // - skip leading whitespace, then decide what kind of line this is
+        let start = line.len() - line.trim_start().len();
+        let rest = &line[start..];
+
+        if rest.is_empty() || rest.starts_with('#') {
+            Ok(())
+        } else if rest.starts_with('[') {
+            self.parse_section(start)
+        } else {
+            self.parse_pair(start)
+        }
+    }
+
+    // Parse a section header `[name]` starting at byte offset `start`
+    fn parse_section(&mut self, start: usize) -> Result<(), ParseError> {
+        let line = self.line;
+        let close = match line[start..].find(']') {
+            Some(c) => start + c,
+            None => return Err(self.error_at(start, ParseErrorKind::UnterminatedSection)),
+        };
+
+        // - only whitespace may follow the closing bracket
+        let tail = &line[close + 1..];
+        if !tail.trim().is_empty() {
+            let off = close + 1 + (tail.len() - tail.trim_start().len());
+            return Err(self.error_at(off, ParseErrorKind::TrailingCharacters));
+        }
+
+        let inner = &line[start + 1..close];
+        let name = inner.trim();
+        let name_off = start + 1 + (inner.len() - inner.trim_start().len());
+
+        if name.is_empty() {
+            return Err(self.error_at(start + 1, ParseErrorKind::EmptySectionName));
+        }
+
+        if self.config.entries().iter().any(|e| e.name == name) {
+            return Err(self.error_at(name_off, ParseErrorKind::DuplicateEntry));
+        }
+
+        if self.config.num_entries >= MAX_ENTRIES {
+            return Err(self.error_at(start, ParseErrorKind::TooManyEntries));
+        }
+
+        let pos = self.pos_at(start);
+        self.config.entries[self.config.num_entries] = BootEntry::new(name, pos);
+        self.config.num_entries += 1;
+        self.seen_entry = 0;
+
+        Ok(())
+    }
+
+    // Parse a key-value pair starting at byte offset `start`
+    fn parse_pair(&mut self, start: usize) -> Result<(), ParseError> {
+        let line = self.line;
+        let eq = match line[start..].find('=') {
+            Some(e) => start + e,
+            None => {
+                // - point just past the would-be key
+                let end = line.trim_end().len();
+                return Err(self.error_at(end, ParseErrorKind::ExpectedEquals));
+            }
+        };
+
+        let key = line[start..eq].trim_end();
+        if key.is_empty() {
+            return Err(self.error_at(eq, ParseErrorKind::EmptyKey));
+        }
+
+        // - locate the value, then strip optional quotes
+        let raw = &line[eq + 1..];
+        let value_off = eq + 1 + (raw.len() - raw.trim_start().len());
+        let (value, value_off) = self.unquote(value_off)?;
+
+        if self.config.num_entries == 0 {
+            self.global_key(key, start, value, value_off)
+        } else {
+            self.entry_key(key, start, value, value_off)
+        }
+    }
+
+    // Strip quotes from the value starting at byte offset `off`,
+    // returning the value and the offset of its first character
+    fn unquote(&self, off: usize) -> Result<(&'a str, usize), ParseError> {
+        let line = self.line;
+        let value = line[off..].trim_end();
+
+        if !value.starts_with('"') {
+            return Ok((value, off));
+        }
+
+        let close = match value[1..].find('"') {
+            Some(c) => off + 1 + c,
+            None => return Err(self.error_at(off, ParseErrorKind::UnterminatedQuote)),
+        };
+
+        // - only whitespace may follow the closing quote
+        let tail = &line[close + 1..];
+        if !tail.trim().is_empty() {
+            let off = close + 1 + (tail.len() - tail.trim_start().len());
+            return Err(self.error_at(off, ParseErrorKind::TrailingCharacters));
+        }
+
+        Ok((&line[off + 1..close], off + 1))
+    }
+
+    // Mark key as seen, reporting duplicates
+    fn mark_seen(&mut self, flag: u8, key_off: usize) -> Result<(), ParseError> {
+        let seen = if flag >= SEEN_KERNEL {
+            &mut self.seen_entry
+        } else {
+            &mut self.seen_global
+        };
+
+        if *seen & flag != 0 {
+            return Err(self.error_at(key_off, ParseErrorKind::DuplicateKey));
+        }
+
+        *seen |= flag;
+        Ok(())
+    }
+
+    fn global_key(
+        &mut self,
+        key: &str,
+        key_off: usize,
+        value: &'a str,
+        value_off: usize,
+    ) -> Result<(), ParseError> {
+        let invalid = |p: &Self| p.error_at(value_off, ParseErrorKind::InvalidValue);
+
+        match key {
+            "timeout" => {
+                self.mark_seen(SEEN_TIMEOUT, key_off)?;
+                self.config.timeout = value.parse::<u32>().map_err(|_| invalid(self))?;
+            }
+            "default" => {
+                self.mark_seen(SEEN_DEFAULT, key_off)?;
+                if value.is_empty() {
+                    return Err(invalid(self));
+                }
+
+                self.default = Some((value, self.pos_at(value_off)));
+            }
+            "console" => {
+                self.mark_seen(SEEN_CONSOLE, key_off)?;
+                self.config.console = match_ignore_case(
+                    value,
+                    &[
+                        ("vga", ConsoleKind::Vga),
+                        ("framebuffer", ConsoleKind::Framebuffer),
+                        ("fb", ConsoleKind::Framebuffer),
+                        ("serial", ConsoleKind::Serial),
+                    ],
+                )
+                .ok_or_else(|| invalid(self))?;
+            }
+            "loglevel" => {
+                self.mark_seen(SEEN_LOGLEVEL, key_off)?;
+                self.config.log_level = match_ignore_case(
+                    value,
+                    &[
+                        ("error", LogLevel::Error),
+                        ("warn", LogLevel::Warn),
+                        ("info", LogLevel::Info),
+                        ("debug", LogLevel::Debug),
+                        ("trace", LogLevel::Trace),
+                    ],
+                )
+                .ok_or_else(|| invalid(self))?;
+            }
+            "panic" => {
+                self.mark_seen(SEEN_PANIC, key_off)?;
+                self.config.panic_policy = value.parse().map_err(|_| invalid(self))?;
+            }
+            "kernel" | "cmdline" | "module" => {
+                return Err(self.error_at(key_off, ParseErrorKind::MisplacedKey));
+            }
+            _ => return Err(self.error_at(key_off, ParseErrorKind::UnknownKey)),
+        }
+
+        Ok(())
+    }
+
+    fn entry_key(
+        &mut self,
+        key: &str,
+        key_off: usize,
+        value: &'a str,
+        value_off: usize,
+    ) -> Result<(), ParseError> {
+        let idx = self.config.num_entries - 1;
+
+        match key {
+            "kernel" => {
+                self.mark_seen(SEEN_KERNEL, key_off)?;
+                if value.is_empty() {
+                    return Err(self.error_at(value_off, ParseErrorKind::InvalidValue));
+                }
+
+                self.config.entries[idx].kernel = value;
+            }
+            "cmdline" => {
+                self.mark_seen(SEEN_CMDLINE, key_off)?;
+
+                // - point to the offending character within the command line
+                if let Err(e) = Cmdline::parse(value) {
+                    return Err(self.error_at(value_off + e.pos(), ParseErrorKind::InvalidValue));
+                }
+
+                self.config.entries[idx].cmdline = value;
+            }
+            "module" => {
+                if value.is_empty() {
+                    return Err(self.error_at(value_off, ParseErrorKind::InvalidValue));
+                }
+
+                let entry = &mut self.config.entries[idx];
+                if entry.num_modules >= MAX_MODULES {
+                    return Err(self.error_at(key_off, ParseErrorKind::TooManyModules));
+                }
+
+                entry.modules[entry.num_modules] = value;
+                entry.num_modules += 1;
+            }
+            "timeout" | "default" | "console" | "loglevel" | "panic" => {
+                return Err(self.error_at(key_off, ParseErrorKind::MisplacedKey));
+            }
+            _ => return Err(self.error_at(key_off, ParseErrorKind::UnknownKey)),
+        }
+
+        Ok(())
+    }
+
+    // Perform checks that require the whole file
+    fn finish(mut self) -> Result<BootConfig<'a>, ParseError> {
+        // - every entry needs a kernel
+        if let Some(e) = self.config.entries().iter().find(|e| e.kernel.is_empty()) {
+            return Err(ParseError {
+                line: e.pos.line,
+                col: e.pos.col,
+                kind: ParseErrorKind::MissingKernel,
+            });
+        }
+
+        // - resolve the default entry by name
+        if let Some((name, pos)) = self.default {
+            match self.config.entries().iter().position(|e| e.name == name) {
+                Some(i) => self.config.default = i,
+                None => {
+                    return Err(ParseError {
+                        line: pos.line,
+                        col: pos.col,
+                        kind: ParseErrorKind::UnknownDefault,
+                    });
+                }
+            }
+        }
+
+        Ok(self.config)
+    }
+}
+
+// Internal: match value against a table of case-insensitive names
+fn match_ignore_case<T: Copy>(value: &str, table: &[(&str, T)]) -> Option<T> {
+    table
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(value))
+        .map(|&(_, v)| v)
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod tests {
+    use super::*;
+
+    // Parse and unwrap the error
+    fn parse_err(src: &str) -> ParseError {
+        BootConfig::parse(src).unwrap_err()
+    }
+
+    // Assert error kind and position
+    fn assert_err(src: &str, line: usize, col: usize, kind: ParseErrorKind) {
+        let e = parse_err(src);
+        assert_eq!(
+            (e.line(), e.col(), e.kind()),
+            (line, col, kind),
+            "source: {:?}",
+            src
+        );
+    }
+
+    #[test]
+    fn empty_source_yields_defaults() {
+        let cfg = BootConfig::parse("").unwrap();
+
+        assert_eq!(cfg.timeout(), DEF_TIMEOUT);
+        assert_eq!(cfg.console(), ConsoleKind::Vga);
+        assert_eq!(cfg.log_level(), LogLevel::Info);
+        assert_eq!(cfg.panic_policy(), PanicPolicy::Halt);
+        assert!(cfg.entries().is_empty());
+        assert!(cfg.default_entry().is_none());
+    }
+
+    #[test]
+    fn full_config() {
+        let src = "\
+# global settings
+timeout = 10
+default = rescue
+console = Serial
+loglevel = debug
+panic = Reboot,30
+
+[magnetite]
+kernel = /KERNEL.ELF
+cmdline = \"  console=serial,115200 loglevel=debug  \"
+module = /INITRD.IMG
+module = /FONT.PSF
+
+  [ rescue ]
+kernel=/RESCUE.ELF
+";
+        let cfg = BootConfig::parse(src).unwrap();
+
+        assert_eq!(cfg.timeout(), 10);
+        assert_eq!(cfg.console(), ConsoleKind::Serial);
+        assert_eq!(cfg.log_level(), LogLevel::Debug);
+        assert_eq!(cfg.panic_policy(), PanicPolicy::Reboot(30));
+        assert_eq!(cfg.entries().len(), 2);
+        assert_eq!(cfg.default_index(), 1);
+
+        let e = &cfg.entries()[0];
+        assert_eq!(e.name(), "magnetite");
+        assert_eq!(e.kernel(), "/KERNEL.ELF");
+        assert_eq!(e.cmdline(), "  console=serial,115200 loglevel=debug  ");
+        assert_eq!(e.modules(), &["/INITRD.IMG", "/FONT.PSF"]);
+
+        let d = cfg.default_entry().unwrap();
+        assert_eq!(d.name(), "rescue");
+        assert_eq!(d.kernel(), "/RESCUE.ELF");
+        assert_eq!(d.cmdline(), "");
+        assert!(d.modules().is_empty());
+    }
+
+    #[test]
+    fn crlf_line_endings() {
+        let cfg = BootConfig::parse("timeout = 3\r\n[a]\r\nkernel = /A\r\n").unwrap();
+
+        assert_eq!(cfg.timeout(), 3);
+        assert_eq!(cfg.entries()[0].kernel(), "/A");
+    }
+
+    #[test]
+    fn missing_equals() {
+        assert_err("timeout 5", 1, 10, ParseErrorKind::ExpectedEquals);
+        assert_err("\n\n  timeout   ", 3, 10, ParseErrorKind::ExpectedEquals);
+    }
+
+    #[test]
+    fn empty_key() {
+        assert_err("  = 5", 1, 3, ParseErrorKind::EmptyKey);
+    }
+
+    #[test]
+    fn unknown_and_misplaced_keys() {
+        assert_err("colour = red", 1, 1, ParseErrorKind::UnknownKey);
+        assert_err("kernel = /A", 1, 1, ParseErrorKind::MisplacedKey);
+        assert_err(
+            "[a]\nkernel = /A\n  timeout = 1",
+            3,
+            3,
+            ParseErrorKind::MisplacedKey,
+        );
+        assert_err(
+            "[a]\nkernel = /A\ncolour = red",
+            3,
+            1,
+            ParseErrorKind::UnknownKey,
+        );
+    }
+
+    #[test]
+    fn duplicate_keys() {
+        assert_err(
+            "timeout = 1\ntimeout = 2",
+            2,
+            1,
+            ParseErrorKind::DuplicateKey,
+        );
+        assert_err(
+            "[a]\nkernel = /A\nkernel = /B",
+            3,
+            1,
+            ParseErrorKind::DuplicateKey,
+        );
+
+        // - entry keys may be repeated across entries
+        assert!(BootConfig::parse("[a]\nkernel = /A\n[b]\nkernel = /B").is_ok());
+    }
+
+    #[test]
+    fn invalid_values() {
+        assert_err("timeout = soon", 1, 11, ParseErrorKind::InvalidValue);
+        assert_err("timeout = -1", 1, 11, ParseErrorKind::InvalidValue);
+        assert_err("timeout = 99999999999", 1, 11, ParseErrorKind::InvalidValue);
+        assert_err("console = lcd", 1, 11, ParseErrorKind::InvalidValue);
+        assert_err("loglevel=loud", 1, 10, ParseErrorKind::InvalidValue);
+        assert_err("default =", 1, 10, ParseErrorKind::InvalidValue);
+        assert_err("[a]\nkernel =   ", 2, 12, ParseErrorKind::InvalidValue);
+        assert_err(
+            "[a]\nkernel = /A\nmodule = \"\"",
+            3,
+            11,
+            ParseErrorKind::InvalidValue,
+        );
+    }
+
+    #[test]
+    fn invalid_cmdline() {
+        let src = "[a]\nkernel = /A\ncmdline = quiet root=\"disk 0";
+        assert_err(src, 3, 22, ParseErrorKind::InvalidValue);
+
+        let src = "[a]\nkernel = /A\ncmdline = \"  quiet\\\"";
+        assert_err(src, 3, 19, ParseErrorKind::InvalidValue);
+    }
+
+    #[test]
+    fn quoting() {
+        assert_err(
+            "[a]\ncmdline = \"abc",
+            2,
+            11,
+            ParseErrorKind::UnterminatedQuote,
+        );
+        assert_err(
+            "[a]\ncmdline = \"abc\" def",
+            2,
+            17,
+            ParseErrorKind::TrailingCharacters,
+        );
+
+        // - quotes are only special at the start of a value
+        let cfg = BootConfig::parse("[a]\nkernel = /A\ncmdline = a=\"b c\"").unwrap();
+        assert_eq!(cfg.entries()[0].cmdline(), "a=\"b c\"");
+    }
+
+    #[test]
+    fn panic_policies() {
+        let policy = |src| BootConfig::parse(src).unwrap().panic_policy();
+
+        assert_eq!(policy("panic = halt"), PanicPolicy::Halt);
+        assert_eq!(policy("panic = POWEROFF"), PanicPolicy::PowerOff);
+        assert_eq!(policy("panic = reboot"), PanicPolicy::Reboot(0));
+        assert_eq!(policy("panic = reboot,5"), PanicPolicy::Reboot(5));
+
+        assert_err("panic = reboot,", 1, 9, ParseErrorKind::InvalidValue);
+        assert_err("panic = halt,5", 1, 9, ParseErrorKind::InvalidValue);
+        assert_err("panic = sleep", 1, 9, ParseErrorKind::InvalidValue);
+        assert_err(
+            "[a]\nkernel = /A\npanic = halt",
+            3,
+            1,
+            ParseErrorKind::MisplacedKey,
+        );
+    }
+
+    #[test]
+    fn sections() {
+        assert_err("[abc", 1, 1, ParseErrorKind::UnterminatedSection);
+        assert_err("  [   ]", 1, 4, ParseErrorKind::EmptySectionName);
+        assert_err("[a] x", 1, 5, ParseErrorKind::TrailingCharacters);
+        assert_err(
+            "[a]\nkernel = /A\n[ a ]",
+            3,
+            3,
+            ParseErrorKind::DuplicateEntry,
+        );
+        assert_err("[a]\n\n# no kernel\n", 1, 1, ParseErrorKind::MissingKernel);
+    }
+
+    #[test]
+    fn too_many_entries_and_modules() {
+        let mut src = [0u8; 512];
+        let mut n = 0;
+        for i in 0..=MAX_ENTRIES {
+            for &b in b"[e"
+                .iter()
+                .chain(&[b'0' + i as u8])
+                .chain(b"]\nkernel=/K\n")
+            {
+                src[n] = b;
+                n += 1;
+            }
+        }
+
+        let src = core::str::from_utf8(&src[..n]).unwrap();
+        assert_err(src, 2 * MAX_ENTRIES + 1, 1, ParseErrorKind::TooManyEntries);
+
+        let src = "[a]\nkernel=/K\nmodule=/1\nmodule=/2\nmodule=/3\nmodule=/4\nmodule=/5";
+        assert_err(src, 7, 1, ParseErrorKind::TooManyModules);
+    }
+
+    #[test]
+    fn unknown_default() {
+        assert_err(
+            "default = b\n[a]\nkernel = /A",
+            1,
+            11,
+            ParseErrorKind::UnknownDefault,
+        );
+        assert_err(
+            "default = \"a \"\n[a]\nkernel = /A",
+            1,
+            12,
+            ParseErrorKind::UnknownDefault,
+        );
+    }
+
+    #[test]
+    fn columns_count_characters() {
+        // - 'é' is two bytes long, but one character wide
+        assert_err("# é\né = 1", 2, 1, ParseErrorKind::UnknownKey);
+        assert_err("[é] x", 1, 5, ParseErrorKind::TrailingCharacters);
+    }
+
+    #[test]
+    fn invalid_encoding() {
+        let e = BootConfig::parse_bytes(b"timeout = 1\n[a\xffb]").unwrap_err();
+        assert_eq!(
+            (e.line(), e.col(), e.kind()),
+            (2, 3, ParseErrorKind::InvalidEncoding)
+        );
+
+        assert!(BootConfig::parse_bytes(b"timeout = 1").is_ok());
+    }
+
+    #[test]
+    fn display() {
+        let e = parse_err("\ntimeout 5");
+        let mut buf = [0u8; 64];
+        let mut w = Cursor(&mut buf, 0);
+        fmt::write(&mut w, format_args!("{}", e)).unwrap();
+        let n = w.1;
+
+        assert_eq!(&buf[..n], b"2:10: expected '=' after key");
+    }
+
+    // Minimal `fmt::Write` sink for the `display` test
+    struct Cursor<'a>(&'a mut [u8], usize);
+
+    impl fmt::Write for Cursor<'_> {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            let b = s.as_bytes();
+            self.0[self.1..self.1 + b.len()].copy_from_slice(b);
+            self.1 += b.len();
+            Ok(())
+        }
+    }
+}
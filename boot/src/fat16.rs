@@ -0,0 +1,210 @@
+/*!
+    Internal module defining a minimal, read-only FAT16 driver
+
+    The driver only understands the root directory and 8.3 file
+    names, which mirrors what the VBR does when it locates
+    `BOOT1.BIN`. It is used by the stage-2 loader to read its
+    configuration (and, eventually, the kernel) from the boot volume.
+*/
+
+// Definition uses
+use alloc::vec::Vec;
+
+use common::plat::pc_bios::structs::BiosPB;
+use common::shared::io::{Error, ErrorKind, ErrorPayload};
+use common::shared::traits::BlockDevice;
+
+// Size of a directory entry in bytes
+const DIR_ENTRY_SIZE: usize = 32;
+
+// Directory entry attributes
+const ATTR_VOLUME_ID: u8 = 0x08;
+const ATTR_DIRECTORY: u8 = 0x10;
+const ATTR_LFN: u8 = 0x0f;
+
+// Directory entry markers (first name byte)
+const ENTRY_END: u8 = 0x00;
+const ENTRY_DELETED: u8 = 0xe5;
+
+// Cluster values
+const CLUSTER_FIRST: u16 = 2;
+const CLUSTER_BAD: u16 = 0xfff7;
+
+/// Root directory entry describing a regular file
+#[derive(Debug, Copy, Clone)]
+pub struct DirEntry {
+    name: [u8; 11],
+    first_cluster: u16,
+    size: u32,
+}
+
+impl DirEntry {
+    /// Return raw (space-padded) 8.3 name
+    pub fn raw_name(&self) -> &[u8; 11] {
+        &self.name
+    }
+
+    /// Return file size in bytes
+    pub fn size(&self) -> usize {
+        self.size as usize
+    }
+}
+
+/// Read-only FAT16 volume on a block device
+pub struct Fat16<'d, D: BlockDevice> {
+    dev: &'d mut D,
+    bytes_per_sector: usize,
+    sectors_per_cluster: usize,
+    fat_lba: u64,
+    root_lba: u64,
+    root_entries: usize,
+    data_lba: u64,
+}
+
+impl<'d, D: BlockDevice> Fat16<'d, D> {
+    /**
+        Create new instance of `Fat16` using the geometry described by `bpb`
+
+        The sector size must match the block size of the device.
+    */
+    pub fn new(dev: &'d mut D, bpb: &BiosPB) -> Result<Self, Error> {
+        let bytes_per_sector = bpb.bytes_per_sector();
+        let sectors_per_cluster = bpb.sectors_per_cluster();
+
+        if bytes_per_sector != dev.block_size() || sectors_per_cluster == 0 {
+            return Err(Error::new(
+                ErrorKind::Unsupported,
+                ErrorPayload::Message("unsupported FAT16 volume geometry"),
+            ));
+        }
+
+        // - mirror the layout computation performed by the VBR
+        let fat_lba = (bpb.hidden_sectors() + bpb.reserved_sectors()) as u64;
+        let root_lba = fat_lba + (bpb.fat_count() * bpb.sectors_per_fat()) as u64;
+        let root_entries = bpb.root_dir_entries();
+        let root_sectors = (root_entries * DIR_ENTRY_SIZE).div_ceil(bytes_per_sector);
+        let data_lba = root_lba + root_sectors as u64;
+
+        Ok(Fat16 {
+            dev,
+            bytes_per_sector,
+            sectors_per_cluster,
+            fat_lba,
+            root_lba,
+            root_entries,
+            data_lba,
+        })
+    }
+
+    /**
+        Look up a regular file in the root directory
+
+        The name is given in its usual dotted form (such as
+        `BOOT.CFG`), and is compared case-insensitively.
+    */
+    pub fn find(&mut self, name: &str) -> Result<DirEntry, Error> {
+        let Some(short) = short_name(name) else {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                ErrorPayload::Message("not a valid 8.3 file name"),
+            ));
+        };
+
+        let mut sector = vec![0u8; self.bytes_per_sector];
+        let per_sector = self.bytes_per_sector / DIR_ENTRY_SIZE;
+        let num_sectors = self.root_entries.div_ceil(per_sector);
+
+        for i in 0..num_sectors {
+            self.dev
+                .read_blocks(self.root_lba + i as u64, &mut sector)?;
+
+            for raw in sector.chunks_exact(DIR_ENTRY_SIZE) {
+                let attr = raw[11];
+
+                match raw[0] {
+                    ENTRY_END => return Err(Error::E_NOT_FOUND),
+                    ENTRY_DELETED => continue,
+                    _ if attr == ATTR_LFN => continue,
+                    _ if attr & (ATTR_VOLUME_ID | ATTR_DIRECTORY) != 0 => continue,
+                    _ => {}
+                }
+
+                if raw[..11] == short {
+                    return Ok(DirEntry {
+                        name: short,
+                        first_cluster: u16::from_le_bytes([raw[26], raw[27]]),
+                        size: u32::from_le_bytes([raw[28], raw[29], raw[30], raw[31]]),
+                    });
+                }
+            }
+        }
+
+        Err(Error::E_NOT_FOUND)
+    }
+
+    /// Read the contents of the provided file
+    pub fn read(&mut self, entry: &DirEntry) -> Result<Vec<u8>, Error> {
+        let cluster_size = self.bytes_per_sector * self.sectors_per_cluster;
+        let size = entry.size();
+
+        // - read whole clusters, then truncate
+        let mut buf = vec![0u8; size.div_ceil(cluster_size) * cluster_size];
+        let mut cluster = entry.first_cluster;
+
+        for chunk in buf.chunks_exact_mut(cluster_size) {
+            if !(CLUSTER_FIRST..CLUSTER_BAD).contains(&cluster) {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    ErrorPayload::Message("broken FAT16 cluster chain"),
+                ));
+            }
+
+            let lba = self.data_lba
+                + ((cluster - CLUSTER_FIRST) as usize * self.sectors_per_cluster) as u64;
+            self.dev.read_blocks(lba, chunk)?;
+            cluster = self.next_cluster(cluster)?;
+        }
+
+        buf.truncate(size);
+        Ok(buf)
+    }
+
+    // Look up the successor of `cluster` in the first FAT
+    fn next_cluster(&mut self, cluster: u16) -> Result<u16, Error> {
+        let offset = cluster as usize * 2;
+        let lba = self.fat_lba + (offset / self.bytes_per_sector) as u64;
+        let idx = offset % self.bytes_per_sector;
+
+        let mut sector = vec![0u8; self.bytes_per_sector];
+        self.dev.read_blocks(lba, &mut sector)?;
+
+        Ok(u16::from_le_bytes([sector[idx], sector[idx + 1]]))
+    }
+}
+
+// Convert a dotted file name into its space-padded 8.3 form
+fn short_name(name: &str) -> Option<[u8; 11]> {
+    let (base, ext) = name.split_once('.').unwrap_or((name, ""));
+
+    if base.is_empty() || base.len() > 8 || ext.len() > 3 || ext.contains('.') {
+        return None;
+    }
+
+    let mut short = [b' '; 11];
+    for (dst, b) in short[..8].iter_mut().zip(base.bytes()) {
+        *dst = b.to_ascii_uppercase();
+    }
+    for (dst, b) in short[8..].iter_mut().zip(ext.bytes()) {
+        *dst = b.to_ascii_uppercase();
+    }
+
+    // - reject characters that would never match a valid entry
+    if short
+        .iter()
+        .any(|&b| b < 0x20 || b"\"*+,/:;<=>?[\\]|".contains(&b))
+    {
+        return None;
+    }
+
+    Some(short)
+}
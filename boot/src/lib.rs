@@ -4,31 +4,74 @@
 */
 
 #![no_std]
-#![no_main]
+#![cfg_attr(any(not(test), target_os = "none"), no_main)]
+// - the runtime entry points are compiled out for host tests
+#![cfg_attr(test, allow(dead_code, unused_imports))]
+// - in-OS tests run under QEMU (refer to `scripts/qemu_test.py`)
+#![cfg_attr(all(test, target_os = "none"), feature(custom_test_frameworks))]
+#![cfg_attr(
+    all(test, target_os = "none"),
+    test_runner(common::plat::pc_bios::qemu::test_runner)
+)]
+#![cfg_attr(
+    all(test, target_os = "none"),
+    reexport_test_harness_main = "test_main"
+)]
 
 // Definition uses
-use core::hint;
 use core::panic::PanicInfo;
+use core::ptr::addr_of;
 use core::slice::from_raw_parts;
 use core::sync::atomic::{AtomicUsize, Ordering};
 
 #[macro_use]
 extern crate alloc;
+use alloc::boxed::Box;
+use alloc::string::String;
 use alloc::vec::Vec;
 
 // - internal definitions
 extern crate common;
-use common::shared::GenericError;
-use common::shared::io::Write;
+use common::arch::x86::backtrace::{self, StackWalker};
+use common::arch::x86::idle::halt_forever;
+use common::shared::cmdline::Cmdline;
+use common::shared::io::{self, Write};
+use common::shared::log::kmsg::LogRing;
+use common::shared::log::{self, LevelFilter};
+use common::shared::mm::RegionSpan;
 use common::shared::structs::array_like::ArrayLike;
+use common::shared::structs::mmio::{Mmio, Register};
 use common::shared::structs::spin_lock::Mutex;
-use common::shared::structs::volatile::VolatileCell;
+use common::shared::symbols::SymbolTable;
+use common::shared::{Context, GenericError};
+use common::{error, info, warn};
 
 // - expose allocator module
 pub mod allocator;
 use allocator::{BootImage, BootImageRegion, BumpAllocator};
 
+// - expose boot configuration module
+pub mod config;
+use config::{BootConfig, ConsoleKind};
+
+// - expose boot menu module
+pub mod menu;
+use menu::Menu;
+
+// - expose FAT16 driver module
+pub mod fat16;
+use fat16::Fat16;
+
+// - expose boot summary module
+pub mod summary;
+
 // - BIOS-specific structures
+use common::plat::pc_bios::ata::{AtaDrive, AtaPio};
+use common::plat::pc_bios::handoff::KernHandoff;
+use common::plat::pc_bios::pit::PitClock;
+use common::plat::pc_bios::power::{self, PanicPolicy, PowerControl};
+use common::plat::pc_bios::ps2::Ps2Keyboard;
+use common::plat::pc_bios::serial::{COM1_BASE, DEF_BAUD_RATE, SerialPort};
 use common::plat::pc_bios::structs::{BiosPB, LongE820};
 use common::plat::pc_bios::vesa::ScreenInfo;
 use common::plat::pc_bios::vga::console;
@@ -42,6 +85,17 @@ static BOOT_IMAGE_LAYOUT: [BootImageRegion; 1] = [BootImageRegion::new(
     BootImage::new(true, false),
 )];
 
+// Name of the boot configuration file in the root directory
+static CONFIG_FILE: &str = "BOOT.CFG";
+
+// Size of the boot log ring, including its header
+const BOOT_LOG_LEN: usize = 16 << 10;
+
+// Stack bounds (refer to the memory map in `boot/src/asm/defs.asm`)
+const STACK_BOTTOM: usize = 0x1000;
+const STACK_TOP: usize = 0x7b00;
+const STACK: RegionSpan = RegionSpan::new(STACK_BOTTOM, STACK_TOP - STACK_BOTTOM);
+
 // Double-panic message
 static MSG_DOUBLE_PANIC: &'static str =
     "(2/2) **bootloader panicked** (info corrupted or too risky to acquire)";
@@ -49,17 +103,38 @@ static MSG_DOUBLE_PANIC: &'static str =
 // Keep track of panic invocations to prevent re-entry
 static PANIC_FLAG: AtomicUsize = AtomicUsize::new(0);
 
+// Bounds of the embedded symbol table (refer to `link_boot1.ld`)
+#[cfg(not(test))]
+unsafe extern "C" {
+    static _ksyms_start: u8;
+    static _ksyms_end: u8;
+}
+
 // Instatiate allocator
-#[global_allocator]
+#[cfg_attr(any(not(test), target_os = "none"), global_allocator)]
 #[unsafe(link_section = ".bss.allocator")]
 static ALLOCATOR: BumpAllocator<LongE820> = BumpAllocator::new();
 
 // Instantiate VGA console with default values
 static VGA_CONSOLE: Mutex<VgaConsole> = Mutex::new(unsafe { VgaConsole::defaults() });
 
+// COM1, for the boot summary and the serial console
+// - it may well be absent, which is checked at runtime
+static SERIAL: Mutex<SerialPort> = Mutex::new(unsafe { SerialPort::new(COM1_BASE) });
+
+// Boot log, to be handed over to the kernel
+// - it stays detached until the allocator is up
+static DMESG: Mutex<LogRing<'static>> = Mutex::new(LogRing::detached());
+
+// Power management state, and what to do with it after a panic
+// - legacy methods are used until the ACPI tables are parsed
+static POWER: Mutex<PowerControl> = Mutex::new(PowerControl::legacy());
+static PANIC_POLICY: Mutex<PanicPolicy> = Mutex::new(PanicPolicy::Halt);
+
 // Initial routine
 //  - call it '_start' for the sake of brevity
 // TODO
+#[cfg(not(test))]
 #[inline(never)]
 #[unsafe(no_mangle)]
 extern "C" fn _start(
@@ -71,22 +146,46 @@ extern "C" fn _start(
     // Attempt to validate the E820 map descriptor
     // TODO: validate it *properly*
     if let Ok(e820_map) = e820_map_desc.try_into() {
-        main(bios_pb, bootdev, e820_map, screen_info).unwrap();
+        // - the error code can be decoded on the host
+        if let Err(e) = main(bios_pb, bootdev, e820_map, screen_info) {
+            panic!("{} [{}]", e, e.code());
+        }
     } else {
         panic!("received an invalid E820 map descriptor");
     }
 
-    let mut handle = VGA_CONSOLE.lock();
-    handle.unset_shadowed();
+    VGA_CONSOLE.lock().unset_shadowed();
 
-    writeln!(
-        &mut handle,
-        "\n W: We somehow escaped `main()`...\n\tIn future revisions, this will be considered an error."
-    )
-    .unwrap();
+    warn!(
+        "We somehow escaped `main()`...\n\tIn future revisions, this will be considered an error."
+    );
 
     // Halt the system
-    freeze();
+    halt_forever();
+}
+
+// Initial routine for in-OS tests
+// - only the allocator is set up, so that tests
+//   can exercise anything else from scratch
+#[cfg(all(test, target_os = "none"))]
+#[inline(never)]
+#[unsafe(no_mangle)]
+extern "C" fn _start(
+    _bios_pb: &'static BiosPB,
+    _bootdev: u64,
+    e820_map_desc: &'static ArrayLike<'static, LongE820>,
+    _screen_info: &'static ScreenInfo,
+) -> ! {
+    let e820_map = e820_map_desc
+        .try_into()
+        .expect("received an invalid E820 map descriptor");
+    ALLOCATOR
+        .init(e820_map, 0, &BOOT_IMAGE_LAYOUT)
+        .expect("allocator init");
+
+    // - the test runner exits QEMU once it's done
+    test_main();
+    halt_forever();
 }
 
 // Inner main routine
@@ -96,19 +195,21 @@ extern "C" fn _start(
 // - in general, the error types must implement
 //   `Into<GenericError>`
 fn main(
-    _bios_bp: &BiosPB,
+    bios_pb: &BiosPB,
     bootdev: u64,
     e820_map: &'static [LongE820],
     screen_info: &'static ScreenInfo,
 ) -> Result<(), GenericError> {
     // Initialize allocator
-    ALLOCATOR.init(e820_map, 0, &BOOT_IMAGE_LAYOUT)?;
+    ALLOCATOR
+        .init(e820_map, 0, &BOOT_IMAGE_LAYOUT)
+        .context("allocator init")?;
 
     // Obtain lock handle
     let mut handle = VGA_CONSOLE.lock();
 
     // Clear screen
-    handle.clear()?;
+    handle.clear().context("console init")?;
 
     // Initialize text buffer
     let (cells_x, cells_y) = (screen_info.cells_x(), screen_info.cells_y());
@@ -124,6 +225,73 @@ fn main(
     }
     handle.init(text_buf);
 
+    // Route log records to the console
+    // - the console is locked by every record, so
+    //   release it before logging anything
+    drop(handle);
+
+    // Keep every log line in the boot log as well
+    // - the ring and its clock are leaked, as they must
+    //   outlive the bootloader
+    let mut ring = LogRing::new(vec![0u8; BOOT_LOG_LEN].leak()).context("boot log init")?;
+    ring.set_clock(Box::leak(Box::new(unsafe { PitClock::new() })));
+    *DMESG.lock() = ring;
+
+    for sink in [&VGA_CONSOLE as &'static dyn log::LogSink, &DMESG] {
+        log::register_sink(sink, LevelFilter::Trace).context("log init")?;
+    }
+
+    // Probe COM1
+    let serial = SERIAL.lock().init(DEF_BAUD_RATE);
+    if let Err(e) = serial {
+        info!("No serial port ({})", e);
+    }
+    let has_serial = serial.is_ok();
+
+    // Load boot configuration, falling back to defaults
+    let config = load_config(bios_pb);
+    log::set_max_level(config.log_level().into());
+    *PANIC_POLICY.lock() = config.panic_policy();
+
+    // Route log records to COM1 as well, if requested
+    // - the VGA console stays up, as the menu draws on it
+    match config.console() {
+        ConsoleKind::Vga => {}
+        ConsoleKind::Serial => match serial {
+            Ok(()) => {
+                log::register_sink(&SERIAL, LevelFilter::Trace).context("serial console init")?;
+            }
+            Err(e) => warn!("Serial console unavailable ({}), staying on VGA", e),
+        },
+        kind => warn!("{:?} console not supported yet, staying on VGA", kind),
+    }
+
+    // Gather power management state
+    // - the BIOS areas are identity-mapped
+    let power = unsafe { PowerControl::from_acpi(None) };
+    *POWER.lock() = power;
+
+    // Let the user pick a boot entry, then start afresh
+    // - without entries, there is nothing to pick from, and
+    //   any configuration diagnostics should stay visible
+    let mut handle = VGA_CONSOLE.lock();
+    let selection = match Menu::new(&config) {
+        Some(mut menu) => {
+            let mut keys = unsafe { Ps2Keyboard::new() };
+            let mut clock = unsafe { PitClock::new() };
+            let selection = menu
+                .run(&mut *handle, &mut keys, &mut clock)
+                .context("boot menu")?;
+
+            handle.clear()?;
+            let rows = handle.rows();
+            handle.set_cursor_pos(0, rows - 1);
+
+            Some(selection)
+        }
+        None => None,
+    };
+
     // Write to screen
     writeln!(
         &mut handle,
@@ -155,21 +323,47 @@ fn main(
         screen_info.cells_x(),
         screen_info.cells_y()
     )?;
+    writeln!(&mut handle)?;
+    drop(handle);
+
+    info!(
+        "Boot configuration: {} entries, default \"{}\", timeout {} s, {:?} console, {:?} log level, panic policy \"{}\"",
+        config.entries().len(),
+        config.default_entry().map_or("(none)", |e| e.name()),
+        config.timeout(),
+        config.console(),
+        config.log_level(),
+        config.panic_policy()
+    );
+    info!(
+        "ACPI reset: {}, ACPI power-off: {}",
+        power.has_acpi_reset(),
+        power.has_acpi_power_off()
+    );
+
+    if let Some(selection) = &selection {
+        let entry = &config.entries()[selection.index()];
+        info!(
+            "Selected entry \"{}\": {} \"{}\"",
+            entry.name(),
+            entry.kernel(),
+            selection.cmdline()
+        );
+
+        // - the command line may have been edited in the menu
+        if let Err(e) = Cmdline::parse(selection.cmdline()) {
+            warn!("Invalid command line ({})", e);
+        }
+    }
 
     // Print boot device number
-    writeln!(
-        &mut handle,
-        "\nBoot device identifier: 0x{:0>2x}\n",
-        bootdev
-    )?;
+    info!("Boot device identifier: 0x{:0>2x}", bootdev);
 
     // Iterate over E820 map entries, then show them
     // - we trust that `e820_map` points to real entries
-    writeln!(
-        &mut handle,
-        "I: E820 entries (base, size, type, ACPI attributes):"
-    )?;
+    info!("E820 entries (base, size, type, ACPI attributes):");
 
+    let mut handle = VGA_CONSOLE.lock();
     for entry in e820_map {
         // Print debug representation of each entry
         writeln!(
@@ -188,18 +382,53 @@ fn main(
         e820_map as *const _ as *const (),
         e820_map.len(),
     )?;
+    drop(handle);
 
     // Dump allocator state
-    writeln!(
-        &mut handle,
-        " I: Allocator location: {:?}",
-        &ALLOCATOR as *const _
-    )?;
+    info!("Allocator location: {:?}", &ALLOCATOR as *const _);
+
+    // Report the boot log's location
+    // - the console and the ring must not be locked while logging
+    let (log_span, log_len) = {
+        let ring = DMESG.lock();
+        (ring.span(), ring.len())
+    };
+    info!(
+        "Boot log: {} entries at 0x{:0>8x} ({} bytes)",
+        log_len,
+        log_span.base(),
+        log_span.size()
+    );
+
+    // Prepare the kernel handoff, which carries the boot log along
+    // - the kernel starts on the bootloader's stack
+    // - the command line and the handoff are leaked, as they
+    //   must outlive the bootloader
+    // TODO: start the kernel's `main` with it once the kernel is loaded
+    let cmdline: &'static str = selection.map_or("", |s| String::from(s.cmdline()).leak());
+    let handoff: &'static KernHandoff<'static> = Box::leak(Box::new(KernHandoff::new(
+        bootdev,
+        e820_map,
+        screen_info,
+        cmdline,
+        Some(log_span),
+        STACK,
+    )));
+    info!("Kernel handoff at {:p}", handoff);
+
+    // Write the boot summary, for headless runs to check
+    // - the allocator must not be locked while writing
+    if has_serial {
+        let arena = ALLOCATOR.arena();
+        summary::write_summary(&mut *SERIAL.lock(), bootdev, e820_map, screen_info, arena)
+            .context("boot summary")?;
+    }
 
     // Commit changes
+    let mut handle = VGA_CONSOLE.lock();
     handle.flush()?;
 
-    //freeze();
+    //halt_forever();
 
     // Instantiate vector and loop from it
     let v: Vec<usize> = vec![1, 2, 3, 5, 8, 13, 21, 36];
@@ -212,6 +441,45 @@ fn main(
     Ok(())
 }
 
+// Read and parse the boot configuration file
+// - the configuration is optional, so failing to read
+//   it is not an error, but failing to parse it is reported
+// - the file contents are leaked, as the configuration
+//   borrows from them for the remainder of the boot process
+fn load_config(bios_pb: &BiosPB) -> BootConfig<'static> {
+    let src: &'static [u8] = match read_config(bios_pb) {
+        Ok(buf) => buf.leak(),
+        Err(e) => {
+            warn!(
+                "Could not read {} ({:?}), using defaults",
+                CONFIG_FILE,
+                e.kind()
+            );
+            return BootConfig::new();
+        }
+    };
+
+    match BootConfig::parse_bytes(src) {
+        Ok(config) => config,
+        Err(e) => {
+            error!("{}:{}, using defaults", CONFIG_FILE, e);
+            BootConfig::new()
+        }
+    }
+}
+
+// Read the boot configuration file from the boot volume
+// - there are no BIOS services left to translate the boot
+//   device number, so assume the primary master drive
+fn read_config(bios_pb: &BiosPB) -> Result<Vec<u8>, io::Error> {
+    let mut disk = unsafe { AtaPio::primary(AtaDrive::Master) };
+    let mut volume = Fat16::new(&mut disk, bios_pb)?;
+    let entry = volume.find(CONFIG_FILE)?;
+
+    volume.read(&entry)
+}
+
+#[cfg(not(test))]
 #[panic_handler]
 fn panic(info: &PanicInfo<'_>) -> ! {
     // Increment panic flag, then process it
@@ -223,6 +491,13 @@ fn panic(info: &PanicInfo<'_>) -> ! {
     };
 }
 
+// - in-OS tests report failures over the serial port
+#[cfg(all(test, target_os = "none"))]
+#[panic_handler]
+fn panic(info: &PanicInfo<'_>) -> ! {
+    common::plat::pc_bios::qemu::test_panic(info)
+}
+
 // Routine for fetching the value of
 // `PANIC_FLAG`, then incremenitng it
 #[inline(always)]
@@ -236,6 +511,12 @@ fn panic_fetch_add() -> usize {
 #[inline(always)]
 #[doc(hidden)]
 fn single_panic(info: &PanicInfo<'_>) -> ! {
+    // 0. make logging non-blocking, as the
+    // console may be held by the panicking code
+    // - the frame pointer is the root of the backtrace
+    log::enter_panic_mode();
+    let fp = backtrace::frame_pointer();
+
     // 1. forcibly unlock the console, if necessary
     unsafe {
         VGA_CONSOLE.unlock();
@@ -250,14 +531,18 @@ fn single_panic(info: &PanicInfo<'_>) -> ! {
                 "(1/2) **bootloader panicked** ({})\n E: {}",
                 loc,
                 info.message()
-            )
+            )?;
         } else {
             writeln!(
                 c,
                 "(1/2) **bootloader panicked** (source location unknown)\n E: {}",
                 info.message()
-            )
+            )?;
         }
+
+        // - the walker checks every frame against the stack bounds
+        let frames = unsafe { StackWalker::new(fp, STACK) };
+        backtrace::write_backtrace(c, frames, &symbols())
     };
 
     // - absorb errors, rather than unwrapping them
@@ -283,8 +568,31 @@ fn single_panic(info: &PanicInfo<'_>) -> ! {
         };
     }
 
-    // 4. freeze the system
-    freeze();
+    // 4. carry out the panic policy
+    // - both locks are only taken by `main()`, which is
+    //   not coming back, so fall back to halting
+    let policy = PANIC_POLICY.try_lock().map_or(PanicPolicy::Halt, |p| *p);
+    let power = POWER.try_lock().map_or(PowerControl::legacy(), |p| *p);
+    unsafe { power.apply(policy, &mut PitClock::new()) }
+}
+
+// Embedded symbol table, for resolving backtraces
+// - the first link of the stage has an empty table, and
+//   a malformed table is treated as if it were empty
+#[cfg(not(test))]
+fn symbols() -> SymbolTable<'static> {
+    let start = addr_of!(_ksyms_start);
+    let len = addr_of!(_ksyms_end) as usize - start as usize;
+
+    // SAFETY: the linker script places the table between the symbols
+    let raw = unsafe { from_raw_parts(start, len) };
+    SymbolTable::from_bytes(raw).unwrap_or(SymbolTable::empty())
+}
+
+// - host tests are not linked against a table
+#[cfg(test)]
+fn symbols() -> SymbolTable<'static> {
+    SymbolTable::empty()
 }
 
 // Routine for second panic invocation
@@ -296,9 +604,9 @@ fn single_panic(info: &PanicInfo<'_>) -> ! {
 fn double_panic(_info: &PanicInfo<'_>) -> ! {
     // 1. create a window into the default VGA text buffer
     // - don't try to be smart here
-    let buf: &[VolatileCell<u16>] = unsafe {
-        from_raw_parts(
-            console::DEF_BUF_ADDR as *const _,
+    let buf: Mmio<[Register<u16>]> = unsafe {
+        Mmio::identity_slice(
+            console::DEF_BUF_ADDR,
             console::DEF_NUM_COLS * console::DEF_NUM_ROWS,
         )
     };
@@ -310,28 +618,135 @@ fn double_panic(_info: &PanicInfo<'_>) -> ! {
     // - be paranoid, and truncate the message
     let n = msg_b.len().min(buf.len());
 
-    for i in 0..n {
-        let c = console::DEF_ATTR | (msg_b[i] as u16);
-        buf[i].store(c)
+    for (i, &b) in msg_b[..n].iter().enumerate() {
+        let c = console::DEF_ATTR | (b as u16);
+        if let Some(cell) = buf.get(i) {
+            cell.write(c);
+        }
     }
 
-    // 3. freeze the system
-    freeze();
+    // 3. halt the system
+    halt_forever();
 }
 
 // Routine for third panic invocation
-// TODO: consider resetting the system
+// - nothing can be trusted anymore, so reset the
+//   system in the bluntest way possible
 #[inline(always)]
 #[doc(hidden)]
 fn triple_panic(_info: &PanicInfo<'_>) -> ! {
-    freeze();
+    unsafe { power::triple_fault() }
 }
 
-// Freeze the system
-// TODO: use native instructions
-#[inline(always)]
-fn freeze() -> ! {
-    loop {
-        hint::spin_loop();
+// In-OS tests, run by QEMU (refer to `scripts/qemu_test.py`)
+#[cfg(all(test, target_os = "none"))]
+mod tests {
+    use super::*;
+    use common::plat::pc_bios::serial::{COM1_BASE, DEF_BAUD_RATE, SerialPort};
+    use common::shared::structs::mmio::{Mmio, Register};
+    use common::shared::traits::{BlockDevice, Clock};
+
+    #[test_case]
+    fn heap_is_above_boot_image() {
+        let v: Vec<u32> = (0..1024).collect();
+        let b = Box::new(0x5a5a_u64);
+
+        assert_eq!(v.iter().sum::<u32>(), 1023 * 1024 / 2);
+        assert_eq!(*b, 0x5a5a);
+
+        let image = BOOT_IMAGE_LAYOUT[0].span();
+        assert!(!image.contains_addr(v.as_ptr() as usize));
+        assert!(!image.contains_addr(&*b as *const u64 as usize));
+    }
+
+    #[test_case]
+    fn boot_disk_is_readable() {
+        let mut disk = unsafe { AtaPio::primary(AtaDrive::Master) };
+        let mut sector = [0u8; 512];
+        disk.read_blocks(0, &mut sector).unwrap();
+
+        // - the image is partitioned, so sector 0 is the MBR
+        assert_eq!(sector[510..], [0x55, 0xaa]);
+    }
+
+    #[test_case]
+    fn console_writes_video_memory() {
+        let mut handle = VGA_CONSOLE.lock();
+        write!(&mut *handle, "\nOK").unwrap();
+        let (cols, rows) = (handle.cols(), handle.rows());
+        drop(handle);
+
+        // - the text lands at the start of the bottom row
+        let buf: Mmio<[Register<u16>]> =
+            unsafe { Mmio::identity_slice(console::DEF_BUF_ADDR, cols * rows) };
+        let cell = |i| buf.get((rows - 1) * cols + i).unwrap().read() as u8;
+        assert_eq!([cell(0), cell(1)], *b"OK");
+    }
+
+    #[test_case]
+    fn com1_is_present() {
+        let mut com1 = unsafe { SerialPort::new(COM1_BASE) };
+        com1.init(DEF_BAUD_RATE).unwrap();
+        assert!(com1.init(0).is_err());
+    }
+
+    #[test_case]
+    fn pit_clock_advances() {
+        let mut clock = unsafe { PitClock::new() };
+        let start = clock.millis();
+
+        // - QEMU's PIT runs in real time
+        while clock.millis() < start + 10 {
+            core::hint::spin_loop();
+        }
+    }
+
+    #[test_case]
+    fn pit_clocks_share_the_count() {
+        let mut a = unsafe { PitClock::new() };
+        while a.millis() < 5 {
+            core::hint::spin_loop();
+        }
+
+        // - a new instance doesn't reset the PIT under `a`
+        let before = a.ticks();
+        let mut b = unsafe { PitClock::new() };
+        assert!(a.ticks() >= before);
+        assert!(b.ticks() < a.ticks());
+    }
+    #[test_case]
+    fn irq_mutex_restores_interrupt_flag() {
+        use common::arch::x86::idle::{InterruptGuard, interrupts_enabled};
+        use common::arch::x86::io::ReadWritePort;
+        use common::shared::structs::spin_lock::IrqMutex;
+
+        // - no IDT is loaded, so every PIC line is masked
+        //   before interrupts are enabled
+        let pic = unsafe {
+            [
+                ReadWritePort::<u8>::new(0x21),
+                ReadWritePort::<u8>::new(0xa1),
+            ]
+        };
+        let masks = pic.each_ref().map(|p| p.read());
+        pic.iter().for_each(|p| p.write(0xff));
+
+        let m = IrqMutex::new(0u32);
+        for enabled in [false, true] {
+            let _outer = match enabled {
+                true => unsafe { InterruptGuard::enable() },
+                false => InterruptGuard::disable(),
+            };
+
+            {
+                let mut guard = m.lock();
+                *guard += 1;
+                assert!(!interrupts_enabled());
+            }
+            assert_eq!(interrupts_enabled(), enabled);
+        }
+
+        pic.iter().zip(masks).for_each(|(p, mask)| p.write(mask));
+        assert_eq!(*m.lock(), 2);
     }
 }
@@ -0,0 +1,744 @@
+/*!
+    Internal module defining the interactive boot menu
+
+    The menu lists the entries of a [`BootConfig`], highlights the
+    default one, and counts down the configured timeout. The user
+    can pick an entry with the arrow keys, and edit its command line
+    (with `e` or Tab) before booting it.
+
+    The menu is agnostic of its surroundings: it draws on a
+    [`MenuCanvas`], reads keys from a [`KeySource`], and keeps
+    time with a [`Clock`]. This allows the menu logic to be
+    exercised on the host with scripted key sequences.
+*/
+
+// Definition uses
+use alloc::string::String;
+use core::hint;
+
+use common::plat::pc_bios::vga::console::VgaConsole;
+use common::shared::io::{self, Write};
+use common::shared::traits::{Clock, KeyEvent, KeySource};
+
+use crate::config::BootConfig;
+
+/// Maximum length of an edited command line in bytes
+pub const MAX_CMDLINE_LEN: usize = 255;
+
+// Prefix of the command line row, used to place the cursor
+const CMDLINE_PREFIX: &str = "  cmdline: ";
+
+/**
+    Trait to mark type as a surface the menu can be drawn on
+
+    The menu redraws every row on each update, so implementors
+    need not keep track of previous contents.
+*/
+pub trait MenuCanvas {
+    /// Return number of rows
+    fn rows(&self) -> usize;
+
+    /// Return number of columns
+    fn cols(&self) -> usize;
+
+    /**
+        Draw `text` on row `row`, replacing its previous contents
+
+        Text exceeding the width of the canvas is truncated. If
+        `highlight` is set, the row is drawn with inverted colors.
+        If `cursor` is set, the cell at that column is drawn as
+        the text cursor.
+    */
+    fn draw_line(
+        &mut self,
+        row: usize,
+        text: &str,
+        highlight: bool,
+        cursor: Option<usize>,
+    ) -> Result<(), io::Error>;
+
+    /// Commit drawn rows to the display
+    fn flush(&mut self) -> Result<(), io::Error>;
+}
+
+/// Entry picked by the user (or by the timeout)
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Selection {
+    index: usize,
+    cmdline: String,
+}
+
+impl Selection {
+    /// Return index of the selected entry
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Return command line to boot the entry with
+    pub fn cmdline(&self) -> &str {
+        &self.cmdline
+    }
+}
+
+// Internal: menu mode
+#[derive(Debug)]
+enum Mode {
+    Select,
+    Edit { buf: String, cursor: usize },
+}
+
+/// Interactive boot menu state
+#[derive(Debug)]
+pub struct Menu<'c, 'a> {
+    config: &'c BootConfig<'a>,
+    selected: usize,
+    remaining_ms: Option<u64>,
+    mode: Mode,
+}
+
+impl<'c, 'a> Menu<'c, 'a> {
+    /**
+        Create new instance of `Menu`, with the default entry selected
+
+        Returns `None` if the configuration has no entries.
+    */
+    pub fn new(config: &'c BootConfig<'a>) -> Option<Self> {
+        if config.entries().is_empty() {
+            return None;
+        }
+
+        Some(Menu {
+            config,
+            selected: config.default_index(),
+            remaining_ms: Some(config.timeout() as u64 * 1000),
+            mode: Mode::Select,
+        })
+    }
+
+    /// Return index of the highlighted entry
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    /// Check whether the command line is being edited
+    pub fn is_editing(&self) -> bool {
+        matches!(self.mode, Mode::Edit { .. })
+    }
+
+    /// Return remaining seconds (rounded up), unless the countdown was cancelled
+    pub fn remaining_secs(&self) -> Option<u64> {
+        self.remaining_ms.map(|ms| ms.div_ceil(1000))
+    }
+
+    // Select the entry at `index` with its configured command line
+    fn select(&self, index: usize) -> Selection {
+        Selection {
+            index,
+            cmdline: String::from(self.config.entries()[index].cmdline()),
+        }
+    }
+
+    /**
+        Advance the countdown by `elapsed_ms` milliseconds
+
+        Returns the default entry once the countdown expires.
+    */
+    pub fn tick(&mut self, elapsed_ms: u64) -> Option<Selection> {
+        let remaining = self.remaining_ms.as_mut()?;
+        *remaining = remaining.saturating_sub(elapsed_ms);
+
+        if *remaining == 0 {
+            return Some(self.select(self.config.default_index()));
+        }
+
+        None
+    }
+
+    /**
+        Process a key event
+
+        Any key cancels the countdown. Returns the selected entry
+        once the user confirms it.
+    */
+    pub fn handle_key(&mut self, key: KeyEvent) -> Option<Selection> {
+        self.remaining_ms = None;
+
+        match &mut self.mode {
+            Mode::Select => self.handle_select(key),
+            Mode::Edit { buf, cursor } => match key {
+                KeyEvent::Enter => Some(Selection {
+                    index: self.selected,
+                    cmdline: core::mem::take(buf),
+                }),
+                KeyEvent::Escape => {
+                    // - discard changes
+                    self.mode = Mode::Select;
+                    None
+                }
+                _ => {
+                    edit_line(buf, cursor, key);
+                    None
+                }
+            },
+        }
+    }
+
+    // Process a key event in selection mode
+    fn handle_select(&mut self, key: KeyEvent) -> Option<Selection> {
+        let last = self.config.entries().len() - 1;
+
+        match key {
+            KeyEvent::Up => self.selected = self.selected.saturating_sub(1),
+            KeyEvent::Down => self.selected = (self.selected + 1).min(last),
+            KeyEvent::Home => self.selected = 0,
+            KeyEvent::End => self.selected = last,
+            KeyEvent::Enter => return Some(self.select(self.selected)),
+            KeyEvent::Tab | KeyEvent::Char(b'e' | b'E') => {
+                let mut buf = String::from(self.config.entries()[self.selected].cmdline());
+
+                // - keep within bounds, without splitting characters
+                let mut len = buf.len().min(MAX_CMDLINE_LEN);
+                while !buf.is_char_boundary(len) {
+                    len -= 1;
+                }
+                buf.truncate(len);
+
+                self.mode = Mode::Edit { buf, cursor: len };
+            }
+            _ => {}
+        }
+
+        None
+    }
+
+    /// Draw the menu on the provided canvas
+    pub fn render<C: MenuCanvas>(&self, canvas: &mut C) -> Result<(), io::Error> {
+        let rows = canvas.rows();
+        let entry = &self.config.entries()[self.selected];
+        let mut row = 0;
+
+        // - draw rows top to bottom, blanking the gaps
+        let mut line = |canvas: &mut C, r: usize, text: &str, hl: bool, cur: Option<usize>| {
+            while row < r {
+                canvas.draw_line(row, "", false, None)?;
+                row += 1;
+            }
+            canvas.draw_line(r, text, hl, cur)?;
+            row = r + 1;
+            Ok::<(), io::Error>(())
+        };
+
+        line(canvas, 0, "  magnetite_os boot menu", false, None)?;
+
+        for (i, e) in self.config.entries().iter().enumerate() {
+            line(
+                canvas,
+                2 + i,
+                &format!("  {}", e.name()),
+                i == self.selected,
+                None,
+            )?;
+        }
+
+        let r = 3 + self.config.entries().len();
+        line(
+            canvas,
+            r,
+            &format!("  kernel:  {}", entry.kernel()),
+            false,
+            None,
+        )?;
+
+        match &self.mode {
+            Mode::Select => {
+                let text = format!("{}{}", CMDLINE_PREFIX, entry.cmdline());
+                line(canvas, r + 1, &text, false, None)?;
+            }
+            Mode::Edit { buf, cursor } => {
+                let text = format!("{}{}", CMDLINE_PREFIX, buf);
+                let col = CMDLINE_PREFIX.len() + buf[..*cursor].chars().count();
+                line(canvas, r + 1, &text, false, Some(col))?;
+            }
+        }
+
+        let help = if self.is_editing() {
+            "  Left/Right: move, Enter: boot, Esc: discard changes"
+        } else {
+            "  Up/Down: select, Enter: boot, E: edit command line"
+        };
+        line(canvas, rows.saturating_sub(2), help, false, None)?;
+
+        match self.remaining_secs() {
+            Some(secs) => {
+                let name = self.config.entries()[self.config.default_index()].name();
+                let text = format!("  Booting \"{}\" automatically in {} s", name, secs);
+                line(canvas, rows.saturating_sub(1), &text, false, None)
+            }
+            None => line(canvas, rows.saturating_sub(1), "", false, None),
+        }
+    }
+
+    /**
+        Run the menu until an entry is selected
+
+        A zero timeout selects the default entry without
+        drawing anything.
+    */
+    pub fn run<C: MenuCanvas, K: KeySource, T: Clock>(
+        &mut self,
+        canvas: &mut C,
+        keys: &mut K,
+        clock: &mut T,
+    ) -> Result<Selection, io::Error> {
+        if let Some(sel) = self.tick(0) {
+            return Ok(sel);
+        }
+
+        let mut last = clock.millis();
+        let mut dirty = true;
+
+        loop {
+            if dirty {
+                self.render(canvas)?;
+                canvas.flush()?;
+                dirty = false;
+            }
+
+            if let Some(key) = keys.poll_key() {
+                if let Some(sel) = self.handle_key(key) {
+                    return Ok(sel);
+                }
+                dirty = true;
+            }
+
+            // - only redraw when the displayed countdown changes
+            let now = clock.millis();
+            let secs = self.remaining_secs();
+
+            if let Some(sel) = self.tick(now - last) {
+                return Ok(sel);
+            }
+
+            last = now;
+            dirty |= self.remaining_secs() != secs;
+
+            hint::spin_loop();
+        }
+    }
+}
+
+// Apply an editing key to a line buffer
+// - `cursor` is a byte offset, and always lies on a character boundary
+fn edit_line(buf: &mut String, cursor: &mut usize, key: KeyEvent) {
+    // - length of the characters around the cursor
+    let prev_len = buf[..*cursor].chars().next_back().map_or(0, char::len_utf8);
+    let next_len = buf[*cursor..].chars().next().map_or(0, char::len_utf8);
+
+    match key {
+        KeyEvent::Left => *cursor -= prev_len,
+        KeyEvent::Right => *cursor += next_len,
+        KeyEvent::Home => *cursor = 0,
+        KeyEvent::End => *cursor = buf.len(),
+        KeyEvent::Backspace if prev_len > 0 => {
+            *cursor -= prev_len;
+            buf.remove(*cursor);
+        }
+        KeyEvent::Delete if next_len > 0 => {
+            buf.remove(*cursor);
+        }
+        // - only printable ASCII is accepted, as bytes past 0x7f would
+        //   be widened to multi-byte characters
+        KeyEvent::Char(c) if (c.is_ascii_graphic() || c == b' ') && buf.len() < MAX_CMDLINE_LEN => {
+            buf.insert(*cursor, c as char);
+            *cursor += 1;
+        }
+        _ => {}
+    }
+}
+
+// Swap foreground and background colors of a VGA attribute
+fn invert_attr(attr: u16) -> u16 {
+    ((attr & 0x0f00) << 4) | ((attr & 0xf000) >> 4)
+}
+
+impl MenuCanvas for VgaConsole<'_> {
+    fn rows(&self) -> usize {
+        VgaConsole::rows(self)
+    }
+
+    fn cols(&self) -> usize {
+        VgaConsole::cols(self)
+    }
+
+    fn draw_line(
+        &mut self,
+        row: usize,
+        text: &str,
+        highlight: bool,
+        cursor: Option<usize>,
+    ) -> Result<(), io::Error> {
+        let attr = self.attr();
+        let inverted = invert_attr(attr);
+        let mut chars = text.chars();
+
+        // - leave the last column alone, as writing to it
+        //   wraps the cursor (and may scroll the console)
+        self.set_cursor_pos(0, row);
+        for col in 0..self.cols().saturating_sub(1) {
+            let c = match chars.next() {
+                Some(c) if c.is_ascii() && !c.is_ascii_control() => c as u8,
+                Some(_) => b'?',
+                None => b' ',
+            };
+
+            self.set_attr(if highlight != (cursor == Some(col)) {
+                inverted
+            } else {
+                attr
+            });
+            self.write(&[c])?;
+        }
+
+        self.set_attr(attr);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), io::Error> {
+        Write::flush(self)
+    }
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod tests {
+    use super::*;
+    use alloc::collections::VecDeque;
+    use alloc::vec::Vec;
+
+    const CONFIG: &str = "\
+timeout = 3
+default = second
+
+[first]
+kernel = /FIRST.ELF
+cmdline = quiet
+
+[second]
+kernel = /SECOND.ELF
+cmdline = \"loglevel=debug\"
+
+[third]
+kernel = /THIRD.ELF
+";
+
+    // Key source replaying a script, where `None` means "no key this poll"
+    struct ScriptedKeys(VecDeque<Option<KeyEvent>>);
+
+    impl ScriptedKeys {
+        fn new(script: &[Option<KeyEvent>]) -> Self {
+            ScriptedKeys(script.iter().copied().collect())
+        }
+    }
+
+    impl KeySource for ScriptedKeys {
+        fn poll_key(&mut self) -> Option<KeyEvent> {
+            self.0.pop_front().expect("key script exhausted")
+        }
+    }
+
+    // Clock advancing by a fixed step on every reading
+    struct SteppingClock {
+        now: u64,
+        step: u64,
+    }
+
+    impl Clock for SteppingClock {
+        fn millis(&mut self) -> u64 {
+            self.now += self.step;
+            self.now
+        }
+    }
+
+    // Canvas recording rows as text, with highlights and cursors
+    struct TextCanvas {
+        lines: Vec<String>,
+        highlighted: Vec<usize>,
+        cursor: Option<(usize, usize)>,
+        flushes: usize,
+    }
+
+    impl TextCanvas {
+        fn new() -> Self {
+            TextCanvas {
+                lines: vec![String::new(); 25],
+                highlighted: Vec::new(),
+                cursor: None,
+                flushes: 0,
+            }
+        }
+    }
+
+    impl MenuCanvas for TextCanvas {
+        fn rows(&self) -> usize {
+            self.lines.len()
+        }
+
+        fn cols(&self) -> usize {
+            80
+        }
+
+        fn draw_line(
+            &mut self,
+            row: usize,
+            text: &str,
+            highlight: bool,
+            cursor: Option<usize>,
+        ) -> Result<(), io::Error> {
+            self.lines[row] = String::from(text);
+            self.highlighted.retain(|&r| r != row);
+            if highlight {
+                self.highlighted.push(row);
+            }
+            if let Some(col) = cursor {
+                self.cursor = Some((row, col));
+            } else if self.cursor.is_some_and(|(r, _)| r == row) {
+                self.cursor = None;
+            }
+            Ok(())
+        }
+
+        fn flush(&mut self) -> Result<(), io::Error> {
+            self.flushes += 1;
+            Ok(())
+        }
+    }
+
+    // Run the menu against a key script, ticking 100 ms per poll
+    fn run(config: &BootConfig, script: &[Option<KeyEvent>]) -> (Selection, TextCanvas) {
+        let mut menu = Menu::new(config).unwrap();
+        let mut canvas = TextCanvas::new();
+        let mut keys = ScriptedKeys::new(script);
+        let mut clock = SteppingClock { now: 0, step: 100 };
+
+        let sel = menu.run(&mut canvas, &mut keys, &mut clock).unwrap();
+        assert!(keys.0.is_empty(), "key script not fully consumed");
+
+        (sel, canvas)
+    }
+
+    fn keys(s: &str) -> Vec<Option<KeyEvent>> {
+        s.bytes().map(|b| Some(KeyEvent::Char(b))).collect()
+    }
+
+    #[test]
+    fn no_entries_no_menu() {
+        let config = BootConfig::parse("timeout = 1").unwrap();
+        assert!(Menu::new(&config).is_none());
+    }
+
+    #[test]
+    fn timeout_boots_default() {
+        let config = BootConfig::parse(CONFIG).unwrap();
+        let (sel, canvas) = run(&config, &[None; 30]);
+
+        assert_eq!(sel.index(), 1);
+        assert_eq!(sel.cmdline(), "loglevel=debug");
+
+        // - one redraw per displayed second
+        assert_eq!(canvas.flushes, 3);
+    }
+
+    #[test]
+    fn zero_timeout_boots_immediately() {
+        let config = BootConfig::parse("timeout = 0\n[a]\nkernel = /A\ncmdline = x").unwrap();
+        let (sel, canvas) = run(&config, &[]);
+
+        assert_eq!((sel.index(), sel.cmdline()), (0, "x"));
+        assert_eq!(canvas.flushes, 0);
+    }
+
+    #[test]
+    fn render_highlights_default_and_counts_down() {
+        let config = BootConfig::parse(CONFIG).unwrap();
+        let menu = Menu::new(&config).unwrap();
+        let mut canvas = TextCanvas::new();
+        menu.render(&mut canvas).unwrap();
+
+        assert_eq!(canvas.lines[2], "  first");
+        assert_eq!(canvas.lines[3], "  second");
+        assert_eq!(canvas.lines[4], "  third");
+        assert_eq!(canvas.highlighted, [3]);
+        assert_eq!(canvas.lines[6], "  kernel:  /SECOND.ELF");
+        assert_eq!(canvas.lines[7], "  cmdline: loglevel=debug");
+        assert_eq!(
+            canvas.lines[24],
+            "  Booting \"second\" automatically in 3 s"
+        );
+        assert!(canvas.cursor.is_none());
+    }
+
+    #[test]
+    fn arrows_select_and_clamp() {
+        let config = BootConfig::parse(CONFIG).unwrap();
+        let (sel, _) = run(
+            &config,
+            &[
+                Some(KeyEvent::Up),
+                Some(KeyEvent::Up),
+                Some(KeyEvent::Up),
+                Some(KeyEvent::Down),
+                Some(KeyEvent::Enter),
+            ],
+        );
+        assert_eq!((sel.index(), sel.cmdline()), (1, "loglevel=debug"));
+
+        let (sel, _) = run(
+            &config,
+            &[
+                Some(KeyEvent::Down),
+                Some(KeyEvent::Down),
+                Some(KeyEvent::Enter),
+            ],
+        );
+        assert_eq!((sel.index(), sel.cmdline()), (2, ""));
+
+        let (sel, _) = run(&config, &[Some(KeyEvent::Home), Some(KeyEvent::Enter)]);
+        assert_eq!((sel.index(), sel.cmdline()), (0, "quiet"));
+    }
+
+    #[test]
+    fn key_cancels_countdown() {
+        let config = BootConfig::parse(CONFIG).unwrap();
+
+        // - idle well past the timeout after the first key
+        let mut script = vec![Some(KeyEvent::Down)];
+        script.extend([None; 100]);
+        script.push(Some(KeyEvent::Enter));
+
+        let (sel, canvas) = run(&config, &script);
+        assert_eq!(sel.index(), 2);
+        assert_eq!(canvas.lines[24], "");
+    }
+
+    #[test]
+    fn edit_cmdline() {
+        let config = BootConfig::parse(CONFIG).unwrap();
+
+        // - "loglevel=debug" -> "loglevel=info nosmp"
+        let mut script = vec![Some(KeyEvent::Char(b'e'))];
+        script.extend([Some(KeyEvent::Backspace); 5]);
+        script.extend(keys("info smp"));
+        script.extend([Some(KeyEvent::Left); 3]);
+        script.extend(keys("no"));
+        script.push(Some(KeyEvent::Enter));
+
+        let (sel, _) = run(&config, &script);
+        assert_eq!((sel.index(), sel.cmdline()), (1, "loglevel=info nosmp"));
+
+        // - the configuration itself is untouched
+        assert_eq!(config.entries()[1].cmdline(), "loglevel=debug");
+    }
+
+    #[test]
+    fn edit_cursor_movement_and_delete() {
+        let config = BootConfig::parse(CONFIG).unwrap();
+
+        // - "quiet" -> "xuie"
+        let script = [
+            Some(KeyEvent::Up),
+            Some(KeyEvent::Tab),
+            Some(KeyEvent::Home),
+            Some(KeyEvent::Delete),
+            Some(KeyEvent::Char(b'x')),
+            Some(KeyEvent::End),
+            Some(KeyEvent::Backspace),
+            Some(KeyEvent::Right),
+            Some(KeyEvent::Delete),
+            Some(KeyEvent::Enter),
+        ];
+
+        let (sel, _) = run(&config, &script);
+        assert_eq!((sel.index(), sel.cmdline()), (0, "xuie"));
+    }
+
+    #[test]
+    fn edit_render_shows_cursor() {
+        let config = BootConfig::parse(CONFIG).unwrap();
+        let mut menu = Menu::new(&config).unwrap();
+        let mut canvas = TextCanvas::new();
+
+        menu.handle_key(KeyEvent::Char(b'e'));
+        menu.handle_key(KeyEvent::Home);
+        menu.handle_key(KeyEvent::Right);
+        menu.render(&mut canvas).unwrap();
+
+        assert!(menu.is_editing());
+        assert_eq!(canvas.cursor, Some((7, CMDLINE_PREFIX.len() + 1)));
+        assert!(canvas.lines[23].contains("Esc"));
+    }
+
+    #[test]
+    fn escape_discards_edit() {
+        let config = BootConfig::parse(CONFIG).unwrap();
+
+        let mut script = vec![Some(KeyEvent::Char(b'E'))];
+        script.extend(keys(" extra"));
+        script.push(Some(KeyEvent::Escape));
+        script.push(Some(KeyEvent::Enter));
+
+        let (sel, _) = run(&config, &script);
+        assert_eq!((sel.index(), sel.cmdline()), (1, "loglevel=debug"));
+    }
+
+    #[test]
+    fn edit_respects_length_limit() {
+        let config = BootConfig::parse(CONFIG).unwrap();
+        let mut menu = Menu::new(&config).unwrap();
+
+        menu.handle_key(KeyEvent::Char(b'e'));
+        for _ in 0..2 * MAX_CMDLINE_LEN {
+            menu.handle_key(KeyEvent::Char(b'a'));
+        }
+
+        let sel = menu.handle_key(KeyEvent::Enter).unwrap();
+        assert_eq!(sel.cmdline().len(), MAX_CMDLINE_LEN);
+    }
+
+    #[test]
+    fn edit_handles_multibyte_characters() {
+        let config = BootConfig::parse("[a]\nkernel = /A\ncmdline = aéb").unwrap();
+        let mut menu = Menu::new(&config).unwrap();
+
+        for key in [
+            KeyEvent::Tab,
+            KeyEvent::Left,
+            KeyEvent::Backspace,
+            KeyEvent::Left,
+            KeyEvent::Right,
+            KeyEvent::Right,
+            KeyEvent::Right,
+        ] {
+            menu.handle_key(key);
+        }
+        menu.handle_key(KeyEvent::Char(b'!'));
+
+        let sel = menu.handle_key(KeyEvent::Enter).unwrap();
+        assert_eq!(sel.cmdline(), "ab!");
+    }
+
+    #[test]
+    fn edit_ignores_non_printable_bytes() {
+        let config = BootConfig::parse("[a]\nkernel = /A\ncmdline = ab").unwrap();
+        let mut menu = Menu::new(&config).unwrap();
+
+        menu.handle_key(KeyEvent::Tab);
+        for c in [0xe9, b'\x01', 0x7f, 0xff, b' '] {
+            menu.handle_key(KeyEvent::Char(c));
+        }
+        for key in [KeyEvent::Left, KeyEvent::Left, KeyEvent::Char(b'!')] {
+            menu.handle_key(key);
+        }
+
+        let sel = menu.handle_key(KeyEvent::Enter).unwrap();
+        assert_eq!(sel.cmdline(), "a!b ");
+    }
+}
@@ -0,0 +1,161 @@
+/*!
+    Internal module defining the machine-readable boot summary
+
+    The summary describes the state the bootloader ends up in, and
+    is written to the serial port, so that headless runs can be
+    checked (and diffed) on the host. Human-readable output goes
+    to the console, as usual.
+
+    # Format
+    Every line starts with [`PREFIX`], followed by a record name
+    and space-separated `key=value` pairs, as in:
+    ```text
+    boot-summary: begin version=1
+    boot-summary: bootdev id=0x80
+    boot-summary: screen mode=0x003 bpp=0 width=0 height=0 pitch=0 cells=80x25
+    boot-summary: e820 base=0x0000000000000000 size=0x000000000009fc00 type=1 attr=0x00000001
+    boot-summary: allocator head=0x0000000000100020 free=0x0000000001edffe0
+    boot-summary: end records=4
+    ```
+    Numbers are in hexadecimal when they are addresses, sizes or
+    identifiers, and padded to a fixed width, so that lines only
+    differ where values do. An uninitialized allocator is reported
+    as `allocator none`.
+*/
+
+// Definition uses
+use common::plat::pc_bios::structs::LongE820;
+use common::plat::pc_bios::vesa::ScreenInfo;
+use common::shared::io::{Error, Write};
+use common::shared::mm::RegionSpan;
+
+/// Prefix of every summary line
+pub const PREFIX: &str = "boot-summary:";
+
+/// Version of the summary format
+pub const VERSION: usize = 1;
+
+/**
+    Write the boot summary to `w`
+
+    `arena` is the current arena of the allocator
+    (refer to [`BumpAllocator::arena()`]).
+
+    [`BumpAllocator::arena()`]: crate::allocator::BumpAllocator::arena
+*/
+pub fn write_summary<W: Write + ?Sized>(
+    w: &mut W,
+    bootdev: u64,
+    e820_map: &[LongE820],
+    screen_info: &ScreenInfo,
+    arena: Option<RegionSpan>,
+) -> Result<(), Error> {
+    writeln!(w, "{} begin version={}", PREFIX, VERSION)?;
+    writeln!(w, "{} bootdev id=0x{:0>2x}", PREFIX, bootdev)?;
+
+    writeln!(
+        w,
+        "{} screen mode=0x{:0>3x} bpp={} width={} height={} pitch={} cells={}x{}",
+        PREFIX,
+        screen_info.mode(),
+        screen_info.bits_per_pixel(),
+        screen_info.width(),
+        screen_info.height(),
+        screen_info.pitch(),
+        screen_info.cells_x(),
+        screen_info.cells_y()
+    )?;
+
+    for entry in e820_map {
+        writeln!(
+            w,
+            "{} e820 base=0x{:0>16x} size=0x{:0>16x} type={} attr=0x{:0>8x}",
+            PREFIX,
+            entry.base(),
+            entry.size(),
+            entry.area_type(),
+            entry.acpi_attr()
+        )?;
+    }
+
+    match arena {
+        Some(a) => writeln!(
+            w,
+            "{} allocator head=0x{:0>16x} free=0x{:0>16x}",
+            PREFIX,
+            a.base(),
+            a.size()
+        )?,
+        None => writeln!(w, "{} allocator none", PREFIX)?,
+    }
+
+    // - the record count lets truncated summaries be told apart
+    writeln!(w, "{} end records={}", PREFIX, e820_map.len() + 3)?;
+    w.flush()
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod tests {
+    use super::*;
+    use alloc::string::String;
+    use alloc::vec::Vec;
+    use common::shared::structs::array_vec::ArrayVec;
+    use core::mem::transmute;
+
+    // Internal: describe an E820 entry
+    fn e820(base: u64, size: u64, area_type: u32, attr: u32) -> LongE820 {
+        // SAFETY: `LongE820` mirrors the layout of the BIOS entry
+        unsafe { transmute([base, size, ((attr as u64) << 32) | area_type as u64]) }
+    }
+
+    // Internal: describe VGA text mode 3
+    fn text_mode() -> ScreenInfo {
+        let mut raw = [0u16; 14];
+        raw[0] = 0x3;
+        (raw[6], raw[7]) = (80, 25);
+
+        // SAFETY: `ScreenInfo` is a `repr(C)` structure of 14 words
+        unsafe { transmute(raw) }
+    }
+
+    // Internal: write the summary, then split it into lines
+    fn summary(map: &[LongE820], arena: Option<RegionSpan>) -> Vec<String> {
+        let mut out: ArrayVec<u8, 1024> = ArrayVec::new();
+        write_summary(&mut out, 0x80, map, &text_mode(), arena).unwrap();
+
+        core::str::from_utf8(&out)
+            .unwrap()
+            .lines()
+            .map(Into::into)
+            .collect()
+    }
+
+    #[test]
+    fn records() {
+        let map = [e820(0x0, 0x9fc00, 1, 1), e820(0x10_0000, 0x1ef_0000, 1, 1)];
+        let lines = summary(&map, Some(RegionSpan::new(0x10_0020, 0x1ef_ffe0)));
+
+        assert_eq!(
+            lines,
+            [
+                "boot-summary: begin version=1",
+                "boot-summary: bootdev id=0x80",
+                "boot-summary: screen mode=0x003 bpp=0 width=0 height=0 pitch=0 cells=80x25",
+                "boot-summary: e820 base=0x0000000000000000 size=0x000000000009fc00 type=1 attr=0x00000001",
+                "boot-summary: e820 base=0x0000000000100000 size=0x0000000001ef0000 type=1 attr=0x00000001",
+                "boot-summary: allocator head=0x0000000000100020 free=0x0000000001efffe0",
+                "boot-summary: end records=5",
+            ]
+        );
+    }
+
+    #[test]
+    fn uninitialized_allocator() {
+        let lines = summary(&[], None);
+
+        assert_eq!(lines.len(), 5);
+        assert_eq!(lines[3], "boot-summary: allocator none");
+        assert_eq!(lines[4], "boot-summary: end records=3");
+        assert!(lines.iter().all(|l| l.starts_with(PREFIX)));
+    }
+}
@@ -33,3 +33,80 @@ arch_mod!(cfg(any(target_arch = "x86", target_arch = "x86_64")), x86);
 pub(crate) mod __io {
     pub use super::__arch::io::*;
 }
+
+// Catch-all exposure of ISA-specific idle definitions
+// - enforces presence of `__arch::idle`
+pub(crate) mod __idle {
+    pub use super::__arch::idle::*;
+}
+
+// Port register blocks
+// - defined here rather than next to the port types, as
+//   macros exported from `arch_mod!` modules can't be
+//   referred to by path within the crate
+/**
+    Defines a block of port registers, at fixed offsets from a base
+
+    The macro defines a struct with one port field per register,
+    along with an unsafe `const fn new(base: u16)` and `base()`.
+    Registers that share an offset, such as a read-only status
+    register and a write-only command register, may both be listed.
+
+    # Syntax
+    ```rust
+    # use common::port_block;
+    # use common::arch::x86::io::{ReadOnlyPort, ReadWritePort, WriteOnlyPort};
+    port_block! {
+        /// Command block of an IDE channel
+        pub struct AtaRegs {
+            pub data: ReadWritePort<u16> = 0,
+            pub status: ReadOnlyPort<u8> = 7,
+            pub command: WriteOnlyPort<u8> = 7,
+        }
+    }
+    ```
+*/
+#[macro_export]
+macro_rules! port_block {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident {
+            $(
+                $(#[$field_meta:meta])*
+                $field_vis:vis $field:ident : $field_ty:ty = $offset:expr
+            ),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Copy, Clone)]
+        $vis struct $name {
+            base: u16,
+            $(
+                $(#[$field_meta])*
+                $field_vis $field: $field_ty,
+            )*
+        }
+
+        #[allow(dead_code)]
+        impl $name {
+            #[doc = concat!("Create new instance of `", stringify!($name), "` at the port `base`")]
+            ///
+            /// # Safety
+            /// Every register must satisfy the requirements of its port's `new()`.
+            $vis const unsafe fn new(base: u16) -> Self {
+                // - offsets are evaluated outside of `unsafe`
+                $( let $field: u16 = base + $offset; )*
+
+                $name {
+                    base,
+                    $( $field: unsafe { <$field_ty>::new($field) }, )*
+                }
+            }
+
+            /// Returns the base port of the block
+            $vis const fn base(&self) -> u16 {
+                self.base
+            }
+        }
+    };
+}
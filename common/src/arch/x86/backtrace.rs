@@ -0,0 +1,321 @@
+/*!
+    Frame-pointer-based stack unwinding
+
+    With frame pointers enabled (refer to `"frame-pointer"` in
+    the target specification), every function starts with the
+    usual prologue:
+    ```text
+    push rbp
+    mov rbp, rsp
+    ```
+
+    Each frame pointer therefore points at the caller's frame
+    pointer, followed by the return address into the caller.
+    The bootstrap stubs terminate the chain by pointing the first
+    frame pointer at the top of the stack (or at zero).
+
+    # Robustness
+    The walker is meant to be used by panic handlers, which can
+    be invoked with a corrupted stack. Frame pointers are only
+    dereferenced if they are aligned and lie within the provided
+    stack bounds, and must strictly increase from frame to frame,
+    so that a corrupted chain ends the walk rather than faulting
+    or looping.
+*/
+
+// Definition uses
+use core::arch::asm;
+use core::mem::size_of;
+use core::ptr::read_volatile;
+
+use crate::shared::io::{Error, Write};
+use crate::shared::mm::RegionSpan;
+use crate::shared::symbols::SymbolTable;
+
+/// Maximum number of frames yielded by a [`StackWalker`]
+pub const MAX_FRAMES: usize = 32;
+
+// Size of a stack slot
+const WORD: usize = size_of::<usize>();
+
+/// Single stack frame
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Frame {
+    fp: usize,
+    ret_addr: usize,
+}
+
+impl Frame {
+    /// Returns the frame pointer
+    pub fn frame_pointer(&self) -> usize {
+        self.fp
+    }
+
+    /// Returns the return address into the caller
+    pub fn return_addr(&self) -> usize {
+        self.ret_addr
+    }
+}
+
+/// Returns the current frame pointer
+#[inline(always)]
+pub fn frame_pointer() -> usize {
+    let fp: usize;
+
+    #[cfg(target_arch = "x86_64")]
+    unsafe {
+        asm!("mov {}, rbp", out(reg) fp, options(nomem, nostack, preserves_flags));
+    }
+
+    #[cfg(target_arch = "x86")]
+    unsafe {
+        asm!("mov {}, ebp", out(reg) fp, options(nomem, nostack, preserves_flags));
+    }
+
+    fp
+}
+
+/**
+    Iterator over the frames of a stack, innermost first
+
+    # Usage
+    ```text
+    let stack = RegionSpan::new(STACK_BOTTOM, STACK_TOP - STACK_BOTTOM);
+    for frame in unsafe { StackWalker::current(stack) } {
+        ...
+    }
+    ```
+*/
+#[derive(Clone, Debug)]
+pub struct StackWalker {
+    fp: usize,
+    stack: RegionSpan,
+    depth: usize,
+}
+
+impl StackWalker {
+    /**
+        Create new instance of `StackWalker`, starting at frame pointer `fp`
+
+        # Safety
+        The caller must ensure that `stack` is mapped and readable.
+    */
+    pub unsafe fn new(fp: usize, stack: RegionSpan) -> Self {
+        StackWalker {
+            fp,
+            stack,
+            depth: 0,
+        }
+    }
+
+    /**
+        Create new instance of `StackWalker`, starting at the caller's frame
+
+        # Safety
+        The caller must ensure that `stack` is mapped and readable.
+    */
+    #[inline(always)]
+    pub unsafe fn current(stack: RegionSpan) -> Self {
+        unsafe { Self::new(frame_pointer(), stack) }
+    }
+
+    // Internal: checks whether a frame record at `fp` can be read
+    fn is_valid(&self, fp: usize) -> bool {
+        fp.is_multiple_of(WORD)
+            && fp >= self.stack.base()
+            && fp
+                .checked_add(2 * WORD)
+                .is_some_and(|end| end <= self.stack.limit())
+    }
+}
+
+impl Iterator for StackWalker {
+    type Item = Frame;
+
+    fn next(&mut self) -> Option<Frame> {
+        if self.depth >= MAX_FRAMES || !self.is_valid(self.fp) {
+            return None;
+        }
+
+        // SAFETY: the frame record lies within the stack bounds
+        let (next_fp, ret_addr) = unsafe {
+            (
+                read_volatile(self.fp as *const usize),
+                read_volatile((self.fp + WORD) as *const usize),
+            )
+        };
+
+        if ret_addr == 0 {
+            return None;
+        }
+
+        let frame = Frame {
+            fp: self.fp,
+            ret_addr,
+        };
+
+        // - callers' frames lie above their callees' frames,
+        //   so anything else marks the end of the chain
+        self.fp = if next_fp > self.fp { next_fp } else { 0 };
+        self.depth += 1;
+
+        Some(frame)
+    }
+}
+
+/**
+    Write a backtrace to `w`, one line per frame
+
+    Return addresses are resolved through `symbols`. As they point
+    past the call instruction, they are looked up one byte earlier,
+    so that calls at the very end of a function resolve correctly.
+*/
+pub fn write_backtrace<W: Write, I: Iterator<Item = Frame>>(
+    w: &mut W,
+    frames: I,
+    symbols: &SymbolTable<'_>,
+) -> Result<(), Error> {
+    writeln!(w, " --- (Backtrace) --- ")?;
+
+    for (i, frame) in frames.enumerate() {
+        let addr = frame.return_addr();
+
+        match symbols.lookup(addr.wrapping_sub(1)) {
+            Some((sym, off)) => writeln!(
+                w,
+                " >  #{:<2} 0x{:0>16x}  {}+0x{:x}",
+                i,
+                addr,
+                sym.name(),
+                off + 1
+            )?,
+            None => writeln!(w, " >  #{:<2} 0x{:0>16x}  ??", i, addr)?,
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use crate::shared::structs::array_vec::ArrayVec;
+    use std::vec::Vec;
+
+    // Internal: synthetic stack of `N` words
+    struct Stack<const N: usize>([usize; N]);
+
+    impl<const N: usize> Stack<N> {
+        // Internal: address of slot `i`
+        fn addr(&self, i: usize) -> usize {
+            &self.0[i] as *const usize as usize
+        }
+
+        // Internal: place a frame record (next fp, return address) at slot `i`
+        fn frame(&mut self, i: usize, next_fp: usize, ret_addr: usize) {
+            self.0[i] = next_fp;
+            self.0[i + 1] = ret_addr;
+        }
+
+        // Internal: bounds of the whole stack
+        fn span(&self) -> RegionSpan {
+            RegionSpan::new(self.addr(0), N * WORD)
+        }
+
+        // Internal: walk from `fp`, collecting return addresses
+        fn walk(&self, fp: usize) -> Vec<usize> {
+            unsafe { StackWalker::new(fp, self.span()) }
+                .map(|f| f.return_addr())
+                .collect()
+        }
+    }
+
+    #[test]
+    fn walk_to_null() {
+        let mut s = Stack([0usize; 16]);
+        let (f1, f2) = (s.addr(4), s.addr(10));
+        s.frame(0, f1, 0x1111);
+        s.frame(4, f2, 0x2222);
+        s.frame(10, 0, 0x3333);
+
+        assert_eq!(s.walk(s.addr(0)), [0x1111, 0x2222, 0x3333]);
+        assert!(s.walk(0).is_empty());
+
+        let frame = unsafe { StackWalker::new(s.addr(4), s.span()) }.next();
+        assert_eq!(frame.map(|f| f.frame_pointer()), Some(f1));
+    }
+
+    #[test]
+    fn walk_stops_at_zero_return() {
+        let mut s = Stack([0usize; 8]);
+        s.frame(0, s.addr(2), 0x1111);
+        s.frame(2, s.addr(4), 0);
+        s.frame(4, 0, 0x3333);
+
+        assert_eq!(s.walk(s.addr(0)), [0x1111]);
+    }
+
+    #[test]
+    fn walk_stops_at_bad_frame_pointer() {
+        let mut s = Stack([0usize; 16]);
+
+        // - misaligned
+        s.frame(0, s.addr(4) + 1, 0x1111);
+        assert_eq!(s.walk(s.addr(0)), [0x1111]);
+        assert!(s.walk(s.addr(0) + 1).is_empty());
+
+        // - below the stack, or with a record past its top
+        s.frame(0, s.addr(0) - 2 * WORD, 0x1111);
+        assert_eq!(s.walk(s.addr(0)), [0x1111]);
+        s.frame(0, s.addr(15), 0x1111);
+        assert_eq!(s.walk(s.addr(0)), [0x1111]);
+        assert!(s.walk(s.addr(15)).is_empty());
+
+        // - pointing back down (or at itself) would loop
+        s.frame(4, s.addr(4), 0x2222);
+        s.frame(6, s.addr(4), 0x3333);
+        assert_eq!(s.walk(s.addr(4)), [0x2222]);
+        assert_eq!(s.walk(s.addr(6)), [0x3333]);
+    }
+
+    #[test]
+    fn walk_is_bounded() {
+        // - a chain longer than `MAX_FRAMES`
+        let mut s = Stack([0usize; 2 * MAX_FRAMES + 4]);
+        for i in 0..MAX_FRAMES + 1 {
+            let next = s.addr(2 * i + 2);
+            s.frame(2 * i, next, 0x1000 + i);
+        }
+
+        assert_eq!(s.walk(s.addr(0)).len(), MAX_FRAMES);
+    }
+
+    #[test]
+    fn resolved_backtrace() {
+        let frames = [0x1005usize, 0x1010, 0x9000].map(|ret_addr| Frame { fp: 0, ret_addr });
+
+        let mut raw = Vec::new();
+        raw.extend_from_slice(b"SYMS");
+        raw.extend_from_slice(&1u32.to_le_bytes());
+        raw.extend_from_slice(&0x1000u64.to_le_bytes());
+        raw.extend_from_slice(&0x10u64.to_le_bytes());
+        raw.extend_from_slice(&32u32.to_le_bytes());
+        raw.extend_from_slice(&4u32.to_le_bytes());
+        raw.extend_from_slice(b"main");
+        let symbols = SymbolTable::from_bytes(&raw).unwrap();
+
+        let mut out: ArrayVec<u8, 256> = ArrayVec::new();
+        write_backtrace(&mut out, frames.into_iter(), &symbols).unwrap();
+
+        // - calls at the very end of a function still resolve to it
+        assert_eq!(
+            core::str::from_utf8(&out).unwrap(),
+            " --- (Backtrace) --- \n \
+             >  #0  0x0000000000001005  main+0x5\n \
+             >  #1  0x0000000000001010  main+0x10\n \
+             >  #2  0x0000000000009000  ??\n"
+        );
+    }
+}
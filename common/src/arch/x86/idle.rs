@@ -0,0 +1,184 @@
+/*!
+    Idle and halt primitives
+
+    Halting with `hlt` lets emulators put the host thread to sleep,
+    whereas spinning keeps a host core busy. Spinning is friendlier
+    to debuggers, which can't always interrupt a halted guest, so
+    the `debug-spin` feature swaps every `hlt` for a `pause` loop.
+
+    `cli` and `sti` fault outside of ring 0, so the guards can't
+    be exercised by host tests, only by the in-OS ones.
+
+    # Usage
+    ```text
+    // - interrupts are restored to their previous state on drop
+    let _guard = InterruptGuard::disable();
+    ...
+
+    // - nothing left to do
+    halt_forever();
+    ```
+*/
+
+// Definition uses
+use core::arch::asm;
+
+/// Interrupt enable flag in RFLAGS (EFLAGS on IA-32)
+pub const FLAGS_IF: usize = 1 << 9;
+
+/// Returns the contents of RFLAGS (EFLAGS on IA-32)
+#[inline(always)]
+pub fn flags() -> usize {
+    let flags: usize;
+
+    #[cfg(target_arch = "x86_64")]
+    unsafe {
+        asm!("pushfq", "pop {}", out(reg) flags, options(nomem, preserves_flags));
+    }
+
+    #[cfg(target_arch = "x86")]
+    unsafe {
+        asm!("pushfd", "pop {}", out(reg) flags, options(nomem, preserves_flags));
+    }
+
+    flags
+}
+
+/// Checks whether maskable interrupts are enabled
+#[inline(always)]
+pub fn interrupts_enabled() -> bool {
+    flags() & FLAGS_IF != 0
+}
+
+/**
+    Enable maskable interrupts
+
+    # Safety
+    The caller must ensure that an IDT able to handle
+    every unmasked interrupt is loaded.
+*/
+#[inline(always)]
+pub unsafe fn enable_interrupts() {
+    // - not `nomem`, so that memory accesses aren't moved across it
+    unsafe {
+        asm!("sti", options(nostack));
+    }
+}
+
+/// Disable maskable interrupts
+#[inline(always)]
+pub fn disable_interrupts() {
+    // - not `nomem`, so that memory accesses aren't moved across it
+    unsafe {
+        asm!("cli", options(nostack));
+    }
+}
+
+/**
+    Guard restoring the interrupt flag when dropped
+
+    Guards may be nested, as each one restores the
+    state that it found rather than a fixed one.
+*/
+#[derive(Debug)]
+#[must_use = "interrupts are restored as soon as the guard is dropped"]
+pub struct InterruptGuard {
+    was_enabled: bool,
+}
+
+impl InterruptGuard {
+    /// Disable maskable interrupts until the guard is dropped
+    #[inline(always)]
+    pub fn disable() -> Self {
+        let was_enabled = interrupts_enabled();
+        disable_interrupts();
+
+        InterruptGuard { was_enabled }
+    }
+
+    /**
+        Enable maskable interrupts until the guard is dropped
+
+        # Safety
+        Refer to [`enable_interrupts()`].
+    */
+    #[inline(always)]
+    pub unsafe fn enable() -> Self {
+        let was_enabled = interrupts_enabled();
+        unsafe { enable_interrupts() };
+
+        InterruptGuard { was_enabled }
+    }
+
+    /// Checks whether interrupts were enabled when the guard was created
+    pub fn was_enabled(&self) -> bool {
+        self.was_enabled
+    }
+}
+
+impl Drop for InterruptGuard {
+    #[inline(always)]
+    fn drop(&mut self) {
+        if self.was_enabled {
+            // SAFETY: interrupts were already enabled before
+            unsafe { enable_interrupts() };
+        } else {
+            disable_interrupts();
+        }
+    }
+}
+
+/// Run `f` with maskable interrupts disabled
+#[inline(always)]
+pub fn without_interrupts<R, F: FnOnce() -> R>(f: F) -> R {
+    let _guard = InterruptGuard::disable();
+    f()
+}
+
+/**
+    Enable maskable interrupts and wait for the next one
+
+    `sti` only takes effect after the following instruction,
+    so no interrupt can slip in between `sti` and `hlt` and
+    leave the processor halted with nothing left to wake it.
+    Interrupts stay enabled afterwards.
+
+    With the `debug-spin` feature, this returns immediately,
+    so callers are expected to loop on their own condition.
+
+    # Safety
+    Refer to [`enable_interrupts()`].
+*/
+#[inline(always)]
+pub unsafe fn wait_for_interrupt() {
+    #[cfg(not(feature = "debug-spin"))]
+    unsafe {
+        asm!("sti", "hlt", options(nostack));
+    }
+
+    #[cfg(feature = "debug-spin")]
+    unsafe {
+        asm!("sti", "pause", options(nostack));
+    }
+}
+
+/**
+    Halt the processor for good, with maskable interrupts disabled
+
+    Non-maskable interrupts and SMIs still wake the processor up,
+    hence the loop.
+*/
+#[inline(always)]
+pub fn halt_forever() -> ! {
+    disable_interrupts();
+
+    loop {
+        #[cfg(not(feature = "debug-spin"))]
+        unsafe {
+            asm!("hlt", options(nomem, nostack));
+        }
+
+        #[cfg(feature = "debug-spin")]
+        core::hint::spin_loop();
+    }
+}
@@ -85,3 +85,136 @@ pub unsafe fn out_d(port: u16, val: u32) {
         );
     }
 }
+
+/**
+    Read bytes from provided port into provided buffer (`rep insb`)
+
+    # Safety
+    Refer to [`in_b()`].
+*/
+#[inline(always)]
+pub unsafe fn ins_b(port: u16, buf: &mut [u8]) {
+    unsafe {
+        asm!(
+            "rep insb",
+            in("dx") port,
+            inout("edi") buf.as_mut_ptr() => _,
+            inout("ecx") buf.len() => _,
+        );
+    }
+}
+
+/**
+    Read words from provided port into provided buffer (`rep insw`)
+
+    # Safety
+    Refer to [`in_w()`].
+*/
+#[inline(always)]
+pub unsafe fn ins_w(port: u16, buf: &mut [u16]) {
+    unsafe {
+        asm!(
+            "rep insw",
+            in("dx") port,
+            inout("edi") buf.as_mut_ptr() => _,
+            inout("ecx") buf.len() => _,
+        );
+    }
+}
+
+/**
+    Read double words from provided port into provided buffer (`rep insd`)
+
+    # Safety
+    Refer to [`in_d()`].
+*/
+#[inline(always)]
+pub unsafe fn ins_d(port: u16, buf: &mut [u32]) {
+    unsafe {
+        asm!(
+            "rep insd",
+            in("dx") port,
+            inout("edi") buf.as_mut_ptr() => _,
+            inout("ecx") buf.len() => _,
+        );
+    }
+}
+
+/**
+    Write bytes from provided buffer to provided port (`rep outsb`)
+
+    # Safety
+    Refer to [`out_b()`].
+*/
+#[inline(always)]
+pub unsafe fn outs_b(port: u16, buf: &[u8]) {
+    unsafe {
+        asm!(
+            "rep outsb",
+            in("dx") port,
+            inout("esi") buf.as_ptr() => _,
+            inout("ecx") buf.len() => _,
+        );
+    }
+}
+
+/**
+    Write words from provided buffer to provided port (`rep outsw`)
+
+    # Safety
+    Refer to [`out_w()`].
+*/
+#[inline(always)]
+pub unsafe fn outs_w(port: u16, buf: &[u16]) {
+    unsafe {
+        asm!(
+            "rep outsw",
+            in("dx") port,
+            inout("esi") buf.as_ptr() => _,
+            inout("ecx") buf.len() => _,
+        );
+    }
+}
+
+/**
+    Write double words from provided buffer to provided port (`rep outsd`)
+
+    # Safety
+    Refer to [`out_d()`].
+*/
+#[inline(always)]
+pub unsafe fn outs_d(port: u16, buf: &[u32]) {
+    unsafe {
+        asm!(
+            "rep outsd",
+            in("dx") port,
+            inout("esi") buf.as_ptr() => _,
+            inout("ecx") buf.len() => _,
+        );
+    }
+}
+
+/**
+    Wait for roughly a microsecond, by writing to an unused port
+
+    Port `0x80` is used for POST diagnostics, and
+    writing to it is harmless once the firmware is done.
+*/
+#[inline(always)]
+pub fn io_wait() {
+    unsafe {
+        out_b(IO_WAIT_PORT, 0);
+    }
+}
+
+// Port used for short I/O delays (POST diagnostics)
+const IO_WAIT_PORT: u16 = 0x80;
+
+/* Typed ports */
+
+// Typed port handles and register blocks
+mod port;
+pub use port::{
+    Port, PortReadable, PortValue, PortWritable, ReadOnly, ReadOnlyPort, ReadWrite, ReadWritePort,
+    WriteOnly, WriteOnlyPort,
+};
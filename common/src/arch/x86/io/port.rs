@@ -0,0 +1,255 @@
+/*!
+    Typed port handles and register blocks
+
+    A [`Port`] pairs a port number with the width of its accesses
+    and the directions it supports, so that drivers only need
+    `unsafe` once, when claiming the port, rather than on every
+    access. Devices with several registers describe their layout
+    once with [`port_block!`](crate::port_block), which is defined
+    in `arch` so that it can be exported.
+
+    # Usage
+    ```rust,no_run
+    # use common::port_block;
+    # use common::arch::x86::io::{ReadWritePort, WriteOnlyPort};
+    port_block! {
+        /// Registers of the 8253/8254 PIT
+        struct PitPorts {
+            ch0: ReadWritePort<u8> = 0,
+            cmd: WriteOnlyPort<u8> = 3,
+        }
+    }
+
+    let pit = unsafe { PitPorts::new(0x40) };
+    pit.cmd.write(0x00);
+    let lo = pit.ch0.read();
+    ```
+*/
+
+// Definition uses
+use core::fmt;
+use core::marker::PhantomData;
+
+use super::{in_b, in_d, in_w, ins_b, ins_d, ins_w, out_b, out_d, out_w, outs_b, outs_d, outs_w};
+
+// Internal: seals the traits below, as their
+// implementors are tied to machine instructions
+mod sealed {
+    pub trait Sealed {}
+}
+
+/**
+    Trait to mark type as transferable through a port
+
+    Implemented for `u8`, `u16` and `u32`, which
+    select byte, word and double word accesses.
+*/
+pub trait PortValue: Copy + sealed::Sealed {
+    /**
+        Read a value from `port`
+
+        # Safety
+        Refer to [`in_b()`] and its siblings.
+    */
+    unsafe fn read_port(port: u16) -> Self;
+
+    /**
+        Write a value to `port`
+
+        # Safety
+        Refer to [`out_b()`] and its siblings.
+    */
+    unsafe fn write_port(port: u16, val: Self);
+
+    /**
+        Read values from `port` into `buf`
+
+        # Safety
+        Refer to [`ins_b()`] and its siblings.
+    */
+    unsafe fn read_port_into(port: u16, buf: &mut [Self]);
+
+    /**
+        Write values from `buf` to `port`
+
+        # Safety
+        Refer to [`outs_b()`] and its siblings.
+    */
+    unsafe fn write_port_from(port: u16, buf: &[Self]);
+}
+
+// Implement `PortValue` over the raw routines of a given width
+macro_rules! port_value {
+    ($ty:ty, $in:ident, $out:ident, $ins:ident, $outs:ident) => {
+        impl sealed::Sealed for $ty {}
+
+        impl PortValue for $ty {
+            #[inline(always)]
+            unsafe fn read_port(port: u16) -> Self {
+                unsafe { $in(port) }
+            }
+
+            #[inline(always)]
+            unsafe fn write_port(port: u16, val: Self) {
+                unsafe { $out(port, val) }
+            }
+
+            #[inline(always)]
+            unsafe fn read_port_into(port: u16, buf: &mut [Self]) {
+                unsafe { $ins(port, buf) }
+            }
+
+            #[inline(always)]
+            unsafe fn write_port_from(port: u16, buf: &[Self]) {
+                unsafe { $outs(port, buf) }
+            }
+        }
+    };
+}
+
+port_value!(u8, in_b, out_b, ins_b, outs_b);
+port_value!(u16, in_w, out_w, ins_w, outs_w);
+port_value!(u32, in_d, out_d, ins_d, outs_d);
+
+/// Trait to mark port access as allowing reads
+pub trait PortReadable: sealed::Sealed {}
+
+/// Trait to mark port access as allowing writes
+pub trait PortWritable: sealed::Sealed {}
+
+/// Access marker of ports that can only be read
+#[derive(Debug, Copy, Clone)]
+pub struct ReadOnly;
+
+/// Access marker of ports that can only be written
+#[derive(Debug, Copy, Clone)]
+pub struct WriteOnly;
+
+/// Access marker of ports that can be both read and written
+#[derive(Debug, Copy, Clone)]
+pub struct ReadWrite;
+
+impl sealed::Sealed for ReadOnly {}
+impl sealed::Sealed for WriteOnly {}
+impl sealed::Sealed for ReadWrite {}
+
+impl PortReadable for ReadOnly {}
+impl PortReadable for ReadWrite {}
+impl PortWritable for WriteOnly {}
+impl PortWritable for ReadWrite {}
+
+/**
+    I/O port accessed with values of type `T`
+
+    Accesses are safe, as the obligations are taken on when
+    the port is created (refer to [`new()`](Self::new)).
+*/
+pub struct Port<T: PortValue, A = ReadWrite> {
+    port: u16,
+    _marker: PhantomData<(T, A)>,
+}
+
+/// I/O port that can only be read
+pub type ReadOnlyPort<T> = Port<T, ReadOnly>;
+
+/// I/O port that can only be written
+pub type WriteOnlyPort<T> = Port<T, WriteOnly>;
+
+/// I/O port that can be both read and written
+pub type ReadWritePort<T> = Port<T, ReadWrite>;
+
+impl<T: PortValue, A> Port<T, A> {
+    /**
+        Create new instance of `Port` for the provided port number
+
+        # Safety
+        The caller must ensure that the port belongs to a device that
+        accepts accesses of this width and direction, that accessing it
+        has no effect on memory safety, and that no other code accesses
+        the device concurrently in a conflicting way.
+    */
+    pub const unsafe fn new(port: u16) -> Self {
+        Port {
+            port,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns the port number
+    pub const fn port(&self) -> u16 {
+        self.port
+    }
+}
+
+impl<T: PortValue, A: PortReadable> Port<T, A> {
+    /// Read a value from the port
+    #[inline(always)]
+    pub fn read(&self) -> T {
+        // SAFETY: refer to `new()`
+        unsafe { T::read_port(self.port) }
+    }
+
+    /// Fill `buf` with values read from the port (string I/O)
+    #[inline(always)]
+    pub fn read_into(&self, buf: &mut [T]) {
+        // SAFETY: refer to `new()`
+        unsafe { T::read_port_into(self.port, buf) }
+    }
+}
+
+impl<T: PortValue, A: PortWritable> Port<T, A> {
+    /// Write a value to the port
+    #[inline(always)]
+    pub fn write(&self, val: T) {
+        // SAFETY: refer to `new()`
+        unsafe { T::write_port(self.port, val) }
+    }
+
+    /// Write every value of `buf` to the port (string I/O)
+    #[inline(always)]
+    pub fn write_from(&self, buf: &[T]) {
+        // SAFETY: refer to `new()`
+        unsafe { T::write_port_from(self.port, buf) }
+    }
+}
+
+impl<T: PortValue, A> Clone for Port<T, A> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: PortValue, A> Copy for Port<T, A> {}
+
+impl<T: PortValue, A> fmt::Debug for Port<T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Port({:#x})", self.port)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    crate::port_block! {
+        /// Block for checking the layout
+        struct TestRegs {
+            data: ReadWritePort<u16> = 0,
+            status: ReadOnlyPort<u8> = 7,
+            command: WriteOnlyPort<u8> = 7,
+            wide: ReadWritePort<u32> = 0x10,
+        }
+    }
+
+    #[test]
+    fn block_layout() {
+        // - no port is accessed, only the numbers are checked
+        let regs = unsafe { TestRegs::new(0x1f0) };
+
+        assert_eq!(regs.base(), 0x1f0);
+        assert_eq!(regs.data.port(), 0x1f0);
+        assert_eq!(regs.status.port(), 0x1f7);
+        assert_eq!(regs.command.port(), 0x1f7);
+        assert_eq!(regs.wide.port(), 0x200);
+    }
+}
@@ -8,3 +8,9 @@ pub mod structs;
 
 // x86-specific I/O definitions
 pub mod io;
+
+// Frame-pointer-based stack unwinding
+pub mod backtrace;
+
+// Idle and halt primitives
+pub mod idle;
@@ -0,0 +1,304 @@
+/*!
+    Typed wrappers around the x86 control registers
+
+    The flag types mirror the bits that the bootstrap stubs
+    manipulate by hand (see `PG_ENABLE`, `PAE_ENABLE` and
+    friends in `boot/src/asm/defs.asm`).
+*/
+
+// Definition uses
+use core::arch::asm;
+
+bit_flags! {
+    /// Flags found in CR0
+    pub struct Cr0Flags: usize {
+        /// Protected mode enable
+        const PROTECTED_MODE = 1 << 0;
+        /// Monitor co-processor
+        const MONITOR_COPROCESSOR = 1 << 1;
+        /// x87 emulation
+        const EMULATE_COPROCESSOR = 1 << 2;
+        /// Task switched
+        const TASK_SWITCHED = 1 << 3;
+        /// Extension type (hard-wired on modern processors)
+        const EXTENSION_TYPE = 1 << 4;
+        /// Native x87 error reporting
+        const NUMERIC_ERROR = 1 << 5;
+        /// Write-protect read-only pages in supervisor mode
+        const WRITE_PROTECT = 1 << 16;
+        /// Alignment mask
+        const ALIGNMENT_MASK = 1 << 18;
+        /// Not write-through
+        const NOT_WRITE_THROUGH = 1 << 29;
+        /// Cache disable
+        const CACHE_DISABLE = 1 << 30;
+        /// Paging enable
+        const PAGING = 1 << 31;
+    }
+}
+
+bit_flags! {
+    /// Flags found in the lower bits of CR3
+    pub struct Cr3Flags: usize {
+        /// Page-level write-through for the top-level table
+        const PAGE_LEVEL_WRITE_THROUGH = 1 << 3;
+        /// Page-level cache disable for the top-level table
+        const PAGE_LEVEL_CACHE_DISABLE = 1 << 4;
+    }
+}
+
+bit_flags! {
+    /// Flags found in CR4
+    pub struct Cr4Flags: usize {
+        /// Virtual-8086 mode extensions
+        const VIRTUAL_8086_EXTENSIONS = 1 << 0;
+        /// Protected-mode virtual interrupts
+        const PROTECTED_VIRTUAL_INTERRUPTS = 1 << 1;
+        /// Restrict `rdtsc` to ring 0
+        const TIMESTAMP_DISABLE = 1 << 2;
+        /// Debugging extensions
+        const DEBUGGING_EXTENSIONS = 1 << 3;
+        /// Page size extensions (large pages)
+        const PAGE_SIZE_EXTENSION = 1 << 4;
+        /// Physical address extension
+        const PHYSICAL_ADDRESS_EXTENSION = 1 << 5;
+        /// Machine check enable
+        const MACHINE_CHECK = 1 << 6;
+        /// Global pages
+        const PAGE_GLOBAL = 1 << 7;
+        /// Allow `rdpmc` outside of ring 0
+        const PERFORMANCE_COUNTER = 1 << 8;
+        /// OS support for `fxsave`/`fxrstor`
+        const OSFXSR = 1 << 9;
+        /// OS support for unmasked SIMD exceptions
+        const OSXMMEXCPT = 1 << 10;
+        /// User-mode instruction prevention
+        const USER_MODE_INSTRUCTION_PREVENTION = 1 << 11;
+        /// 5-level paging
+        const L5_PAGING = 1 << 12;
+        /// VMX enable
+        const VMX = 1 << 13;
+        /// SMX enable
+        const SMX = 1 << 14;
+        /// `rdfsbase`/`wrfsbase` and friends
+        const FSGSBASE = 1 << 16;
+        /// Process-context identifiers
+        const PCID = 1 << 17;
+        /// OS support for `xsave`
+        const OSXSAVE = 1 << 18;
+        /// Supervisor-mode execution prevention
+        const SUPERVISOR_MODE_EXECUTION_PROTECTION = 1 << 20;
+        /// Supervisor-mode access prevention
+        const SUPERVISOR_MODE_ACCESS_PREVENTION = 1 << 21;
+        /// Protection keys for user-mode pages
+        const PROTECTION_KEY = 1 << 22;
+    }
+}
+
+/// Mask for the page-aligned physical address in CR3
+pub const CR3_ADDR_MASK: usize = !0xfff;
+
+/// Handle to the CR0 register
+pub struct Cr0;
+
+impl Cr0 {
+    /// Read the raw contents of CR0
+    #[inline(always)]
+    pub fn read_raw() -> usize {
+        let val: usize;
+        unsafe {
+            asm!("mov {}, cr0", out(reg) val, options(nomem, nostack, preserves_flags));
+        }
+
+        val
+    }
+
+    /// Read CR0, discarding reserved bits
+    #[inline(always)]
+    pub fn read() -> Cr0Flags {
+        Cr0Flags::from_bits_truncate(Self::read_raw())
+    }
+
+    /**
+        Write the raw contents of CR0
+
+        # Safety
+        Changing CR0 may disable protection, caching or paging
+        altogether. The caller must ensure that the new value
+        is consistent with the current execution environment.
+    */
+    #[inline(always)]
+    pub unsafe fn write_raw(val: usize) {
+        unsafe {
+            asm!("mov cr0, {}", in(reg) val, options(nostack, preserves_flags));
+        }
+    }
+
+    /**
+        Write the provided flags to CR0, preserving reserved bits
+
+        # Safety
+        See [`write_raw()`].
+
+        [`write_raw()`]: Self::write_raw
+    */
+    #[inline(always)]
+    pub unsafe fn write(flags: Cr0Flags) {
+        let reserved = Self::read_raw() & !Cr0Flags::all().bits();
+        unsafe { Self::write_raw(reserved | flags.bits()) }
+    }
+
+    /**
+        Apply a read-modify-write to CR0
+
+        # Safety
+        See [`write_raw()`].
+
+        [`write_raw()`]: Self::write_raw
+    */
+    #[inline(always)]
+    pub unsafe fn update<F: FnOnce(&mut Cr0Flags)>(f: F) {
+        let mut flags = Self::read();
+        f(&mut flags);
+        unsafe { Self::write(flags) }
+    }
+}
+
+/// Handle to the CR2 register
+pub struct Cr2;
+
+impl Cr2 {
+    /// Read the linear address that caused the last page fault
+    #[inline(always)]
+    pub fn read() -> usize {
+        let val: usize;
+        unsafe {
+            asm!("mov {}, cr2", out(reg) val, options(nomem, nostack, preserves_flags));
+        }
+
+        val
+    }
+}
+
+/// Handle to the CR3 register
+pub struct Cr3;
+
+impl Cr3 {
+    /// Read the raw contents of CR3
+    #[inline(always)]
+    pub fn read_raw() -> usize {
+        let val: usize;
+        unsafe {
+            asm!("mov {}, cr3", out(reg) val, options(nomem, nostack, preserves_flags));
+        }
+
+        val
+    }
+
+    /**
+        Read CR3, returning the physical address of the
+        top-level paging structure and the associated flags
+    */
+    #[inline(always)]
+    pub fn read() -> (usize, Cr3Flags) {
+        let val = Self::read_raw();
+        (val & CR3_ADDR_MASK, Cr3Flags::from_bits_truncate(val))
+    }
+
+    /**
+        Write the raw contents of CR3
+
+        # Safety
+        Writing to CR3 switches the active address space and
+        flushes non-global TLB entries. The caller must ensure
+        that the new paging hierarchy maps the currently running
+        code, the stack, and any live references.
+    */
+    #[inline(always)]
+    pub unsafe fn write_raw(val: usize) {
+        unsafe {
+            asm!("mov cr3, {}", in(reg) val, options(nostack, preserves_flags));
+        }
+    }
+
+    /**
+        Write the provided top-level table address and flags to CR3
+
+        The address is truncated to a page boundary.
+
+        # Safety
+        See [`write_raw()`].
+
+        [`write_raw()`]: Self::write_raw
+    */
+    #[inline(always)]
+    pub unsafe fn write(addr: usize, flags: Cr3Flags) {
+        unsafe { Self::write_raw((addr & CR3_ADDR_MASK) | flags.bits()) }
+    }
+}
+
+/// Handle to the CR4 register
+pub struct Cr4;
+
+impl Cr4 {
+    /// Read the raw contents of CR4
+    #[inline(always)]
+    pub fn read_raw() -> usize {
+        let val: usize;
+        unsafe {
+            asm!("mov {}, cr4", out(reg) val, options(nomem, nostack, preserves_flags));
+        }
+
+        val
+    }
+
+    /// Read CR4, discarding reserved bits
+    #[inline(always)]
+    pub fn read() -> Cr4Flags {
+        Cr4Flags::from_bits_truncate(Self::read_raw())
+    }
+
+    /**
+        Write the raw contents of CR4
+
+        # Safety
+        Setting an unsupported bit raises `#GP`, and clearing
+        bits like `PHYSICAL_ADDRESS_EXTENSION` in long mode is
+        not permitted. The caller must consult CPUID first.
+    */
+    #[inline(always)]
+    pub unsafe fn write_raw(val: usize) {
+        unsafe {
+            asm!("mov cr4, {}", in(reg) val, options(nostack, preserves_flags));
+        }
+    }
+
+    /**
+        Write the provided flags to CR4, preserving reserved bits
+
+        # Safety
+        See [`write_raw()`].
+
+        [`write_raw()`]: Self::write_raw
+    */
+    #[inline(always)]
+    pub unsafe fn write(flags: Cr4Flags) {
+        let reserved = Self::read_raw() & !Cr4Flags::all().bits();
+        unsafe { Self::write_raw(reserved | flags.bits()) }
+    }
+
+    /**
+        Apply a read-modify-write to CR4
+
+        # Safety
+        See [`write_raw()`].
+
+        [`write_raw()`]: Self::write_raw
+    */
+    #[inline(always)]
+    pub unsafe fn update<F: FnOnce(&mut Cr4Flags)>(f: F) {
+        let mut flags = Self::read();
+        f(&mut flags);
+        unsafe { Self::write(flags) }
+    }
+}
@@ -0,0 +1,520 @@
+/*!
+    Global descriptor table, segment descriptors and selectors
+
+    The descriptors built here are bit-for-bit identical to the
+    ones assembled by hand in `boot/src/asm/stub32.asm`, so that
+    the Rust stages can replace the bootstrap GDT with one that
+    also carries a task state segment.
+*/
+
+// Definition uses
+use core::arch::asm;
+use core::mem;
+
+#[cfg(target_arch = "x86_64")]
+use super::tss::TaskStateSegment;
+
+bit_flags! {
+    /**
+        Flags found in a segment descriptor
+
+        The access byte occupies bits 40-47, and the flags nibble
+        occupies bits 52-55 of the descriptor.
+    */
+    pub struct DescriptorFlags: u64 {
+        /// Accessed bit (set by the processor)
+        const ACCESSED = 1 << 40;
+        /// Readable (code) or writable (data) segment
+        const READ_WRITE = 1 << 41;
+        /// Conforming (code) or expand-down (data) segment
+        const CONFORMING = 1 << 42;
+        /// Executable segment
+        const EXECUTABLE = 1 << 43;
+        /// Non-system segment
+        const NOT_SYSTEM = 1 << 44;
+        /// Descriptor privilege level 3
+        const DPL_RING_3 = 3 << 45;
+        /// Present bit
+        const PRESENT = 1 << 47;
+        /// Available for system software
+        const AVAILABLE = 1 << 52;
+        /// Long mode segment
+        const LONG_MODE = 1 << 53;
+        /// 32-bit default operand size
+        const SIZE_32 = 1 << 54;
+        /// Page granularity
+        const GRANULARITY_4K = 1 << 55;
+        /// Limit bits 0-15
+        const LIMIT_LOW = 0xffff;
+        /// Limit bits 16-19
+        const LIMIT_HIGH = 0xf << 48;
+    }
+}
+
+impl DescriptorFlags {
+    // - the same base flags as `gdt64` in `stub32.asm`
+    const COMMON: Self = Self::from_bits_retain(
+        Self::LIMIT_LOW.bits()
+            | Self::LIMIT_HIGH.bits()
+            | Self::GRANULARITY_4K.bits()
+            | Self::PRESENT.bits()
+            | Self::NOT_SYSTEM.bits()
+            | Self::READ_WRITE.bits(),
+    );
+
+    /// Flags for a 64-bit kernel code segment
+    pub const KERNEL_CODE64: Self =
+        Self::from_bits_retain(Self::COMMON.bits() | Self::EXECUTABLE.bits() | Self::LONG_MODE.bits());
+
+    /// Flags for a 32-bit kernel code segment
+    pub const KERNEL_CODE32: Self =
+        Self::from_bits_retain(Self::COMMON.bits() | Self::EXECUTABLE.bits() | Self::SIZE_32.bits());
+
+    /// Flags for a kernel data segment
+    pub const KERNEL_DATA: Self =
+        Self::from_bits_retain(Self::COMMON.bits() | Self::SIZE_32.bits());
+
+    /// Flags for a 64-bit user code segment
+    pub const USER_CODE64: Self =
+        Self::from_bits_retain(Self::KERNEL_CODE64.bits() | Self::DPL_RING_3.bits());
+
+    /// Flags for a user data segment
+    pub const USER_DATA: Self =
+        Self::from_bits_retain(Self::KERNEL_DATA.bits() | Self::DPL_RING_3.bits());
+}
+
+/// Privilege level (ring) of a selector or descriptor
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[repr(u8)]
+pub enum PrivilegeLevel {
+    Ring0 = 0,
+    Ring1 = 1,
+    Ring2 = 2,
+    Ring3 = 3,
+}
+
+impl PrivilegeLevel {
+    /// Creates a privilege level from the lower two bits of `value`
+    pub const fn from_bits(value: u16) -> Self {
+        match value & 3 {
+            0 => PrivilegeLevel::Ring0,
+            1 => PrivilegeLevel::Ring1,
+            2 => PrivilegeLevel::Ring2,
+            _ => PrivilegeLevel::Ring3,
+        }
+    }
+}
+
+/**
+    Segment selector
+
+    A selector consists of a descriptor index, a table
+    indicator (always zero, as LDTs aren't used) and a
+    requested privilege level.
+*/
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[repr(transparent)]
+pub struct SegmentSelector(u16);
+
+impl SegmentSelector {
+    /// The null selector
+    pub const NULL: Self = SegmentSelector(0);
+
+    /// Creates a selector from a GDT index and requested privilege level
+    pub const fn new(index: u16, rpl: PrivilegeLevel) -> Self {
+        SegmentSelector((index << 3) | (rpl as u16))
+    }
+
+    /// Creates a selector from its raw representation
+    pub const fn from_raw(raw: u16) -> Self {
+        SegmentSelector(raw)
+    }
+
+    /// Returns the raw representation
+    pub const fn raw(&self) -> u16 {
+        self.0
+    }
+
+    /// Returns the descriptor index
+    pub const fn index(&self) -> u16 {
+        self.0 >> 3
+    }
+
+    /// Returns the requested privilege level
+    pub const fn rpl(&self) -> PrivilegeLevel {
+        PrivilegeLevel::from_bits(self.0)
+    }
+}
+
+/**
+    A segment descriptor, as stored in the GDT
+
+    System descriptors (such as the 64-bit TSS descriptor)
+    occupy two consecutive entries.
+*/
+#[derive(Copy, Clone, Debug)]
+pub enum Descriptor {
+    /// Code or data segment descriptor
+    UserSegment(u64),
+
+    /// System segment descriptor (low, high)
+    SystemSegment(u64, u64),
+}
+
+impl Descriptor {
+    /// Creates a 64-bit kernel code segment descriptor
+    pub const fn kernel_code_segment() -> Self {
+        Descriptor::UserSegment(DescriptorFlags::KERNEL_CODE64.bits())
+    }
+
+    /// Creates a 32-bit kernel code segment descriptor
+    pub const fn kernel_code32_segment() -> Self {
+        Descriptor::UserSegment(DescriptorFlags::KERNEL_CODE32.bits())
+    }
+
+    /// Creates a kernel data segment descriptor
+    pub const fn kernel_data_segment() -> Self {
+        Descriptor::UserSegment(DescriptorFlags::KERNEL_DATA.bits())
+    }
+
+    /// Creates a 64-bit user code segment descriptor
+    pub const fn user_code_segment() -> Self {
+        Descriptor::UserSegment(DescriptorFlags::USER_CODE64.bits())
+    }
+
+    /// Creates a user data segment descriptor
+    pub const fn user_data_segment() -> Self {
+        Descriptor::UserSegment(DescriptorFlags::USER_DATA.bits())
+    }
+
+    /**
+        Creates a 64-bit available TSS descriptor
+
+        The TSS must outlive the GDT it is loaded into,
+        hence the `'static` requirement.
+    */
+    #[cfg(target_arch = "x86_64")]
+    pub fn tss_segment(tss: &'static TaskStateSegment) -> Self {
+        let base = tss as *const _ as u64;
+        let limit = (mem::size_of::<TaskStateSegment>() - 1) as u64;
+
+        // - type 0x9 (available 64-bit TSS), present
+        let mut low = DescriptorFlags::PRESENT.bits() | (0x9 << 40);
+
+        // - limit (0-15, 16-19)
+        low |= limit & 0xffff;
+        low |= ((limit >> 16) & 0xf) << 48;
+
+        // - base (0-23, 24-31)
+        low |= (base & 0xff_ffff) << 16;
+        low |= ((base >> 24) & 0xff) << 56;
+
+        // - base (32-63)
+        let high = base >> 32;
+
+        Descriptor::SystemSegment(low, high)
+    }
+
+    /// Returns the privilege level encoded in the descriptor
+    pub const fn dpl(&self) -> PrivilegeLevel {
+        let low = match self {
+            Descriptor::UserSegment(l) => *l,
+            Descriptor::SystemSegment(l, _) => *l,
+        };
+
+        PrivilegeLevel::from_bits((low >> 45) as u16)
+    }
+}
+
+/**
+    Pointer to a descriptor table, as expected by `lgdt` and `lidt`
+*/
+#[derive(Copy, Clone, Debug)]
+#[repr(C, packed(2))]
+pub struct DescriptorTablePointer {
+    /// Size of the table in bytes, minus one
+    pub limit: u16,
+
+    /// Linear address of the table
+    pub base: usize,
+}
+
+/**
+    Global descriptor table with room for `N` entries
+
+    The first entry is always the null descriptor. System
+    descriptors occupy two entries each.
+
+    # Example use
+    ```rust
+    # use common::arch::x86::structs::gdt::{Descriptor, GlobalDescriptorTable};
+    # use common::shared::structs::spin_lock::Mutex;
+    static GDT: Mutex<GlobalDescriptorTable<8>> = Mutex::new(GlobalDescriptorTable::new());
+
+    let mut gdt = GDT.lock();
+    let code = gdt.add_entry(Descriptor::kernel_code_segment()).unwrap();
+    let data = gdt.add_entry(Descriptor::kernel_data_segment()).unwrap();
+    ```
+*/
+#[derive(Clone, Debug)]
+#[repr(C, align(8))]
+pub struct GlobalDescriptorTable<const N: usize> {
+    table: [u64; N],
+    len: usize,
+}
+
+impl<const N: usize> GlobalDescriptorTable<N> {
+    /// Creates a GDT containing only the null descriptor
+    pub const fn new() -> Self {
+        // - a GDT cannot exceed 8192 entries, and must
+        //   accomodate the null descriptor
+        assert!(N > 0 && N <= 8192, "GDT size must be within 1..=8192");
+
+        GlobalDescriptorTable {
+            table: [0; N],
+            len: 1,
+        }
+    }
+
+    /// Returns the number of occupied entries (including the null descriptor)
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Checks whether only the null descriptor is present
+    pub const fn is_empty(&self) -> bool {
+        self.len <= 1
+    }
+
+    /// Returns the occupied entries
+    pub fn entries(&self) -> &[u64] {
+        &self.table[..self.len]
+    }
+
+    /**
+        Appends a descriptor to the table, returning a
+        selector for it (with the descriptor's privilege
+        level as the requested privilege level)
+
+        Returns `None` if the table is full.
+    */
+    pub const fn add_entry(&mut self, entry: Descriptor) -> Option<SegmentSelector> {
+        let index = self.len;
+
+        match entry {
+            Descriptor::UserSegment(v) => {
+                if index + 1 > N {
+                    return None;
+                }
+
+                self.table[index] = v;
+                self.len += 1;
+            }
+            Descriptor::SystemSegment(l, h) => {
+                if index + 2 > N {
+                    return None;
+                }
+
+                self.table[index] = l;
+                self.table[index + 1] = h;
+                self.len += 2;
+            }
+        }
+
+        Some(SegmentSelector::new(index as u16, entry.dpl()))
+    }
+
+    /// Returns a pointer to the table, as expected by `lgdt`
+    pub fn pointer(&self) -> DescriptorTablePointer {
+        DescriptorTablePointer {
+            limit: (self.len * mem::size_of::<u64>() - 1) as u16,
+            base: self.table.as_ptr() as usize,
+        }
+    }
+
+    /**
+        Loads the table into GDTR
+
+        Segment registers keep their cached descriptors until they
+        are reloaded (see [`reload_segments()`]).
+
+        # Safety
+        The caller must ensure that the selectors currently loaded
+        into the segment registers remain valid in the new table.
+
+        [`reload_segments()`]: reload_segments
+    */
+    pub unsafe fn load(&'static self) {
+        unsafe { lgdt(&self.pointer()) }
+    }
+}
+
+impl<const N: usize> Default for GlobalDescriptorTable<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/**
+    Load the provided descriptor table pointer into GDTR
+
+    # Safety
+    The pointer must refer to a valid GDT that outlives
+    its use by the processor.
+*/
+#[inline(always)]
+pub unsafe fn lgdt(ptr: &DescriptorTablePointer) {
+    unsafe {
+        asm!("lgdt [{}]", in(reg) ptr, options(readonly, nostack, preserves_flags));
+    }
+}
+
+/// Store GDTR into a descriptor table pointer
+#[inline(always)]
+pub fn sgdt() -> DescriptorTablePointer {
+    let mut ptr = DescriptorTablePointer { limit: 0, base: 0 };
+    unsafe {
+        asm!("sgdt [{}]", in(reg) &mut ptr, options(nostack, preserves_flags));
+    }
+
+    ptr
+}
+
+/**
+    Load the provided selector into the task register
+
+    # Safety
+    The selector must refer to an available TSS descriptor in
+    the currently loaded GDT. The descriptor is marked busy by
+    the processor, so it cannot be loaded twice.
+*/
+#[inline(always)]
+pub unsafe fn ltr(sel: SegmentSelector) {
+    unsafe {
+        asm!("ltr {0:x}", in(reg) sel.raw(), options(nostack, preserves_flags));
+    }
+}
+
+/**
+    Reload CS with the provided code selector, and the data
+    segment registers (DS, ES, FS, GS, SS) with the provided
+    data selector
+
+    CS is reloaded through a far return, as 64-bit mode
+    doesn't offer a direct far jump to a register operand.
+
+    # Safety
+    Both selectors must refer to valid descriptors in the
+    currently loaded GDT.
+*/
+#[cfg(target_arch = "x86_64")]
+#[inline(always)]
+pub unsafe fn reload_segments(code: SegmentSelector, data: SegmentSelector) {
+    unsafe {
+        asm!(
+            "push {code}",
+            "lea {tmp}, [rip + 2f]",
+            "push {tmp}",
+            "retfq",
+            "2:",
+            "mov ds, {data:x}",
+            "mov es, {data:x}",
+            "mov fs, {data:x}",
+            "mov gs, {data:x}",
+            "mov ss, {data:x}",
+            code = in(reg) code.raw() as u64,
+            data = in(reg) data.raw(),
+            tmp = out(reg) _,
+            options(preserves_flags),
+        );
+    }
+}
+
+/// Read the selector currently loaded into CS
+#[inline(always)]
+pub fn read_cs() -> SegmentSelector {
+    let val: u16;
+    unsafe {
+        asm!("mov {0:x}, cs", out(reg) val, options(nomem, nostack, preserves_flags));
+    }
+
+    SegmentSelector(val)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Descriptors assembled by `boot/src/asm/stub32.asm`
+    const GDT32_KERN_CS: u64 = 0x00cf_9a00_0000_ffff;
+    const GDT32_KERN_DS: u64 = 0x00cf_9200_0000_ffff;
+    const GDT64_CODE: u64 = 0x00af_9a00_0000_ffff;
+    const GDT64_DATA: u64 = 0x00cf_9200_0000_ffff;
+
+    fn raw(d: Descriptor) -> u64 {
+        match d {
+            Descriptor::UserSegment(v) => v,
+            Descriptor::SystemSegment(..) => panic!("not a user segment"),
+        }
+    }
+
+    #[test]
+    fn segments_match_stub32() {
+        assert_eq!(raw(Descriptor::kernel_code_segment()), GDT64_CODE);
+        assert_eq!(raw(Descriptor::kernel_data_segment()), GDT64_DATA);
+        assert_eq!(raw(Descriptor::kernel_code32_segment()), GDT32_KERN_CS);
+        assert_eq!(raw(Descriptor::kernel_data_segment()), GDT32_KERN_DS);
+    }
+
+    #[test]
+    fn user_segments() {
+        // - only the DPL differs from the kernel segments
+        assert_eq!(raw(Descriptor::user_code_segment()), GDT64_CODE | (3 << 45));
+        assert_eq!(raw(Descriptor::user_data_segment()), GDT64_DATA | (3 << 45));
+        assert_eq!(Descriptor::user_code_segment().dpl(), PrivilegeLevel::Ring3);
+        assert_eq!(Descriptor::kernel_code_segment().dpl(), PrivilegeLevel::Ring0);
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn tss_segment() {
+        static TSS: TaskStateSegment = TaskStateSegment::new();
+
+        let base = &TSS as *const _ as u64;
+        let Descriptor::SystemSegment(low, high) = Descriptor::tss_segment(&TSS) else {
+            panic!("not a system segment");
+        };
+
+        // - limit 0x67, present available 64-bit TSS, no flags
+        assert_eq!(low & 0xffff, 0x67);
+        assert_eq!((low >> 40) & 0xff, 0x89);
+        assert_eq!((low >> 48) & 0xff, 0);
+        assert_eq!((low >> 16) & 0xff_ffff, base & 0xff_ffff);
+        assert_eq!(low >> 56, (base >> 24) & 0xff);
+        assert_eq!(high, base >> 32);
+    }
+
+    #[test]
+    fn table_entries() {
+        let mut gdt: GlobalDescriptorTable<4> = GlobalDescriptorTable::new();
+        assert!(gdt.is_empty());
+
+        let code = gdt.add_entry(Descriptor::kernel_code_segment()).unwrap();
+        let data = gdt.add_entry(Descriptor::kernel_data_segment()).unwrap();
+
+        // - the selectors match `gdt64.code` and `gdt64.data`
+        assert_eq!((code.raw(), data.raw()), (0x08, 0x10));
+        assert_eq!(gdt.entries(), [0, GDT64_CODE, GDT64_DATA]);
+        assert_eq!({ gdt.pointer().limit }, 3 * 8 - 1);
+
+        // - a system segment needs two free entries
+        let tss = Descriptor::SystemSegment(0, 0);
+        assert!(gdt.add_entry(tss).is_none());
+        assert_eq!(gdt.len(), 3);
+
+        let user = gdt.add_entry(Descriptor::user_data_segment()).unwrap();
+        assert_eq!(user.rpl(), PrivilegeLevel::Ring3);
+        assert_eq!(user.index(), 3);
+        assert!(gdt.add_entry(Descriptor::user_data_segment()).is_none());
+    }
+}
@@ -1,5 +1,158 @@
 /*!
     x86-specific structures
 
-    Currently a placeholder module
+    This module exposes typed wrappers around the control
+    registers and the descriptor tables, so that the Rust
+    stages don't have to rely on the magic constants found
+    in `boot/src/asm/defs.asm`.
 */
+
+/*
+    A macro to rapidly define bit-flag types
+
+    The generated type is a transparent wrapper around an integer,
+    and offers the usual set-like operations (union, intersection,
+    complement) through the bitwise operators.
+
+    # Syntax
+    ```rust
+    bit_flags! {
+        /// Doc-comment
+        pub struct Flags: usize {
+            /// Doc-comment
+            const FLAG_A = 1 << 0;
+            const FLAG_B = 1 << 1;
+        }
+    }
+    ```
+*/
+macro_rules! bit_flags {
+    (
+        $(#[$outer:meta])*
+        $vis:vis struct $name:ident : $ty:ty {
+            $(
+                $(#[$inner:meta])*
+                const $flag:ident = $value:expr;
+            )*
+        }
+    ) => {
+        $(#[$outer])*
+        #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+        #[repr(transparent)]
+        $vis struct $name($ty);
+
+        impl $name {
+            $(
+                $(#[$inner])*
+                pub const $flag: Self = Self($value);
+            )*
+
+            /// Returns an empty set of flags
+            pub const fn empty() -> Self {
+                Self(0)
+            }
+
+            /// Returns the set of all known flags
+            pub const fn all() -> Self {
+                Self(0 $(| $value)*)
+            }
+
+            /// Returns the raw bits
+            pub const fn bits(&self) -> $ty {
+                self.0
+            }
+
+            /// Creates a set of flags from raw bits,
+            /// discarding unknown bits
+            pub const fn from_bits_truncate(bits: $ty) -> Self {
+                Self(bits & Self::all().0)
+            }
+
+            /// Creates a set of flags from raw bits,
+            /// retaining unknown bits
+            pub const fn from_bits_retain(bits: $ty) -> Self {
+                Self(bits)
+            }
+
+            /// Checks whether no flags are set
+            pub const fn is_empty(&self) -> bool {
+                self.0 == 0
+            }
+
+            /// Checks whether all of the provided flags are set
+            pub const fn contains(&self, other: Self) -> bool {
+                (self.0 & other.0) == other.0
+            }
+
+            /// Checks whether any of the provided flags are set
+            pub const fn intersects(&self, other: Self) -> bool {
+                (self.0 & other.0) != 0
+            }
+
+            /// Sets the provided flags
+            pub fn insert(&mut self, other: Self) {
+                self.0 |= other.0;
+            }
+
+            /// Clears the provided flags
+            pub fn remove(&mut self, other: Self) {
+                self.0 &= !other.0;
+            }
+
+            /// Sets or clears the provided flags
+            pub fn set(&mut self, other: Self, value: bool) {
+                if value {
+                    self.insert(other);
+                } else {
+                    self.remove(other);
+                }
+            }
+        }
+
+        impl core::ops::BitOr for $name {
+            type Output = Self;
+
+            fn bitor(self, rhs: Self) -> Self {
+                Self(self.0 | rhs.0)
+            }
+        }
+
+        impl core::ops::BitOrAssign for $name {
+            fn bitor_assign(&mut self, rhs: Self) {
+                self.0 |= rhs.0;
+            }
+        }
+
+        impl core::ops::BitAnd for $name {
+            type Output = Self;
+
+            fn bitand(self, rhs: Self) -> Self {
+                Self(self.0 & rhs.0)
+            }
+        }
+
+        impl core::ops::BitAndAssign for $name {
+            fn bitand_assign(&mut self, rhs: Self) {
+                self.0 &= rhs.0;
+            }
+        }
+
+        impl core::ops::Not for $name {
+            type Output = Self;
+
+            fn not(self) -> Self {
+                Self::from_bits_truncate(!self.0)
+            }
+        }
+    };
+}
+
+// Control registers (CR0, CR2, CR3, CR4)
+pub mod ctrl_regs;
+
+// Global descriptor table and segment selectors
+pub mod gdt;
+
+// 64-bit task state segment
+#[cfg(target_arch = "x86_64")]
+pub mod tss;
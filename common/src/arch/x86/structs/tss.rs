@@ -0,0 +1,234 @@
+/*!
+    64-bit task state segment
+
+    In long mode, the TSS no longer holds task state; it only
+    provides the privilege stacks used on ring transitions, the
+    interrupt stack table (IST), and the I/O permission map base.
+*/
+
+// Definition uses
+use core::cell::UnsafeCell;
+use core::mem;
+use core::ptr;
+
+/// Number of privilege stack entries (rings 0-2)
+pub const NUM_PRIVILEGE_STACKS: usize = 3;
+
+/// Number of interrupt stack table entries
+pub const NUM_IST_ENTRIES: usize = 7;
+
+/// IST index conventionally reserved for the double-fault handler
+pub const DOUBLE_FAULT_IST_INDEX: usize = 0;
+
+/**
+    64-bit task state segment
+
+    # Usage
+    The fields are private, as the structure is packed, and
+    references to unaligned fields are not permitted. Use the
+    provided accessors instead:
+    ```rust
+    use common::arch::x86::structs::tss::*;
+    use common::shared::structs::once::Lazy;
+
+    static DF_STACK: IstStack<4096> = IstStack::new();
+
+    // - stack addresses are only known at run time
+    static TSS: Lazy<TaskStateSegment> = Lazy::new(|| {
+        let mut tss = TaskStateSegment::new();
+        tss.set_ist(DOUBLE_FAULT_IST_INDEX, DF_STACK.top());
+        tss
+    });
+
+    assert_eq!(TSS.ist(DOUBLE_FAULT_IST_INDEX), Some(DF_STACK.top()));
+    ```
+*/
+#[derive(Clone, Copy, Debug)]
+#[repr(C, packed(4))]
+pub struct TaskStateSegment {
+    _reserved_1: u32,
+    _privilege_stacks: [u64; NUM_PRIVILEGE_STACKS],
+    _reserved_2: u64,
+    _ist: [u64; NUM_IST_ENTRIES],
+    _reserved_3: u64,
+    _reserved_4: u16,
+    _iomap_base: u16,
+}
+
+impl TaskStateSegment {
+    /**
+        Creates a TSS with empty stack tables and no I/O
+        permission map (the I/O map base points past the limit)
+    */
+    pub const fn new() -> Self {
+        TaskStateSegment {
+            _reserved_1: 0,
+            _privilege_stacks: [0; NUM_PRIVILEGE_STACKS],
+            _reserved_2: 0,
+            _ist: [0; NUM_IST_ENTRIES],
+            _reserved_3: 0,
+            _reserved_4: 0,
+            _iomap_base: mem::size_of::<TaskStateSegment>() as u16,
+        }
+    }
+
+    /**
+        Returns the stack top used when entering ring `ring`
+
+        Returns `None` if `ring` is out of bounds.
+    */
+    pub fn privilege_stack(&self, ring: usize) -> Option<u64> {
+        if ring >= NUM_PRIVILEGE_STACKS {
+            return None;
+        }
+
+        // - copy the array out of the packed struct
+        let stacks = unsafe { ptr::read_unaligned(&raw const self._privilege_stacks) };
+        Some(stacks[ring])
+    }
+
+    /**
+        Sets the stack top used when entering ring `ring`
+
+        Out-of-bounds rings are ignored, and `false` is returned.
+    */
+    pub const fn set_privilege_stack(&mut self, ring: usize, top: u64) -> bool {
+        if ring >= NUM_PRIVILEGE_STACKS {
+            return false;
+        }
+
+        let mut stacks = unsafe { ptr::read_unaligned(&raw const self._privilege_stacks) };
+        stacks[ring] = top;
+        unsafe { ptr::write_unaligned(&raw mut self._privilege_stacks, stacks) };
+
+        true
+    }
+
+    /**
+        Returns the stack top for IST entry `index`
+
+        The index is zero-based, whereas the IST field in an IDT
+        gate is one-based (`index + 1`). Returns `None` if
+        `index` is out of bounds.
+    */
+    pub fn ist(&self, index: usize) -> Option<u64> {
+        if index >= NUM_IST_ENTRIES {
+            return None;
+        }
+
+        let ist = unsafe { ptr::read_unaligned(&raw const self._ist) };
+        Some(ist[index])
+    }
+
+    /**
+        Sets the stack top for IST entry `index`
+
+        Out-of-bounds indices are ignored, and `false` is returned.
+    */
+    pub const fn set_ist(&mut self, index: usize, top: u64) -> bool {
+        if index >= NUM_IST_ENTRIES {
+            return false;
+        }
+
+        let mut ist = unsafe { ptr::read_unaligned(&raw const self._ist) };
+        ist[index] = top;
+        unsafe { ptr::write_unaligned(&raw mut self._ist, ist) };
+
+        true
+    }
+
+    /// Returns the I/O permission map base
+    pub fn iomap_base(&self) -> u16 {
+        self._iomap_base
+    }
+}
+
+impl Default for TaskStateSegment {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/**
+    Statically allocated, 16-byte aligned stack for use
+    with the privilege stack table or the IST
+
+    Stacks grow downwards, so the TSS must be given the
+    address returned by [`top()`].
+
+    The stack is zeroed and interior-mutable, so a `static`
+    of this type is placed in `.bss`, rather than in a
+    read-only section the processor would fault writing to.
+
+    [`top()`]: Self::top
+*/
+#[repr(C, align(16))]
+pub struct IstStack<const N: usize>(UnsafeCell<[u8; N]>);
+
+// - the stack is only ever accessed by the processor,
+//   through the address handed to the TSS
+unsafe impl<const N: usize> Sync for IstStack<N> {}
+
+impl<const N: usize> IstStack<N> {
+    /// Creates a zeroed stack
+    pub const fn new() -> Self {
+        IstStack(UnsafeCell::new([0; N]))
+    }
+
+    /// Returns the (exclusive) top of the stack
+    pub fn top(&self) -> u64 {
+        // - `N` is a multiple of 16 in any sane configuration,
+        //   but round down just in case
+        ((self.0.get() as usize + N) & !0xf) as u64
+    }
+}
+
+impl<const N: usize> Default for IstStack<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layout() {
+        // - the size and offsets are fixed by the architecture
+        assert_eq!(mem::size_of::<TaskStateSegment>(), 104);
+        assert_eq!(mem::offset_of!(TaskStateSegment, _privilege_stacks), 0x04);
+        assert_eq!(mem::offset_of!(TaskStateSegment, _ist), 0x24);
+        assert_eq!(mem::offset_of!(TaskStateSegment, _iomap_base), 0x66);
+        assert_eq!(TaskStateSegment::new().iomap_base(), 104);
+    }
+
+    #[test]
+    fn stack_tables() {
+        let mut tss = TaskStateSegment::new();
+
+        assert!(tss.set_privilege_stack(2, 0x2000));
+        assert!(!tss.set_privilege_stack(NUM_PRIVILEGE_STACKS, 0x3000));
+        assert_eq!(tss.privilege_stack(2), Some(0x2000));
+        assert_eq!(tss.privilege_stack(NUM_PRIVILEGE_STACKS), None);
+
+        assert!(tss.set_ist(NUM_IST_ENTRIES - 1, 0x4000));
+        assert!(!tss.set_ist(NUM_IST_ENTRIES, 0x5000));
+        assert_eq!(tss.ist(NUM_IST_ENTRIES - 1), Some(0x4000));
+        assert_eq!(tss.ist(0), Some(0));
+    }
+
+    #[test]
+    fn ist_stack_top() {
+        static STACK: IstStack<4096> = IstStack::new();
+        static ODD: IstStack<100> = IstStack::new();
+
+        let base = STACK.0.get() as u64;
+        assert_eq!(base % 16, 0);
+        assert_eq!(STACK.top(), base + 4096);
+
+        // - the top is rounded down to stay aligned
+        let base = ODD.0.get() as u64;
+        assert_eq!(ODD.top(), base + 96);
+    }
+}
@@ -7,7 +7,10 @@
 */
 
 #![no_std]
-#![no_main]
+#![cfg_attr(not(test), no_main)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
 
 // ISA-specific definitions
 pub mod arch;
@@ -0,0 +1,541 @@
+/*!
+    Minimal ACPI table access
+
+    Only what power management needs is covered: locating the
+    RSDP, walking the RSDT/XSDT, reading the FADT, and extracting
+    the `\_S5_` sleep type from the DSDT - without an AML
+    interpreter, by pattern-matching the package that firmware
+    invariably emits for it.
+
+    # Safety
+    Tables are accessed through their physical addresses, so
+    they must be identity-mapped. The lower 4 GiB are mapped
+    by both boot paths, and firmware places its tables there.
+*/
+
+// Definition uses
+use core::slice::from_raw_parts;
+
+use super::multiboot2::Rsdp;
+
+/// Size of the common table header, in bytes
+pub const SDT_HEADER_LEN: usize = 36;
+
+/// Largest table accepted, in bytes
+// - anything larger is assumed to be garbage
+pub const MAX_SDT_LEN: usize = 1 << 20;
+
+/// Signature of the FADT
+pub const SIG_FADT: &[u8; 4] = b"FACP";
+
+/// Address space of system memory (in a [`GenericAddress`])
+pub const SPACE_MEMORY: u8 = 0;
+
+/// Address space of system I/O ports (in a [`GenericAddress`])
+pub const SPACE_IO: u8 = 1;
+
+// BIOS areas that may contain the RSDP
+const EBDA_SEG_PTR: usize = 0x40e;
+const EBDA_SCAN_LEN: usize = 1 << 10;
+const BIOS_ROM_START: usize = 0xe0000;
+const BIOS_ROM_END: usize = 0x100000;
+
+// Length of an ACPI 2.0+ RSDP
+const RSDP_LEN: usize = 36;
+
+// FADT field offsets
+const FADT_DSDT: usize = 40;
+const FADT_SMI_CMD: usize = 48;
+const FADT_ACPI_ENABLE: usize = 52;
+const FADT_PM1A_CNT_BLK: usize = 64;
+const FADT_PM1B_CNT_BLK: usize = 68;
+const FADT_FLAGS: usize = 112;
+const FADT_RESET_REG: usize = 116;
+const FADT_RESET_VALUE: usize = 128;
+const FADT_X_DSDT: usize = 140;
+
+// FADT flags
+const FLAG_RESET_REG_SUP: u32 = 1 << 10;
+
+// AML opcodes used in `\_S5_`
+const AML_NAME_OP: u8 = 0x08;
+const AML_PACKAGE_OP: u8 = 0x12;
+const AML_ZERO_OP: u8 = 0x00;
+const AML_ONE_OP: u8 = 0x01;
+const AML_BYTE_PREFIX: u8 = 0x0a;
+
+/**
+    Find the RSDP by scanning the BIOS areas
+
+    The first KiB of the EBDA is scanned first, followed by the
+    BIOS ROM area, on 16-byte boundaries as per the specification.
+
+    # Safety
+    The first MiB of physical memory must be identity-mapped.
+*/
+pub unsafe fn scan_rsdp() -> Option<Rsdp<'static>> {
+    let ebda = unsafe { (EBDA_SEG_PTR as *const u16).read_unaligned() as usize } << 4;
+
+    let scan = |start: usize, end: usize| {
+        (start..end).step_by(16).find_map(|addr| {
+            // SAFETY: the BIOS areas lie within the first MiB
+            let bytes = unsafe { from_raw_parts(addr as *const u8, RSDP_LEN) };
+            Rsdp::from_bytes(bytes).filter(|r| r.is_valid())
+        })
+    };
+
+    // - a zero segment means that there's no EBDA
+    let in_ebda = if ebda != 0 {
+        scan(ebda, ebda + EBDA_SCAN_LEN)
+    } else {
+        None
+    };
+
+    in_ebda.or_else(|| scan(BIOS_ROM_START, BIOS_ROM_END))
+}
+
+/// View into a system description table
+#[derive(Copy, Clone, Debug)]
+pub struct Sdt<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Sdt<'a> {
+    /**
+        Create new instance of `Sdt` from the table at `addr`
+
+        Returns `None` if the stated length is implausible,
+        or if the checksum is invalid.
+
+        # Safety
+        The table must be identity-mapped.
+    */
+    pub unsafe fn from_addr(addr: usize) -> Option<Self> {
+        if addr == 0 {
+            return None;
+        }
+
+        let hdr = unsafe { from_raw_parts(addr as *const u8, SDT_HEADER_LEN) };
+        let len = read_u32(hdr, 4)? as usize;
+
+        if !(SDT_HEADER_LEN..=MAX_SDT_LEN).contains(&len) {
+            return None;
+        }
+
+        let bytes = unsafe { from_raw_parts(addr as *const u8, len) };
+        let sum = bytes.iter().fold(0u8, |a, &x| a.wrapping_add(x));
+
+        if sum == 0 { Some(Sdt { bytes }) } else { None }
+    }
+
+    /// Returns the signature
+    pub fn signature(&self) -> &'a [u8] {
+        &self.bytes[..4]
+    }
+
+    /// Returns the revision
+    pub fn revision(&self) -> u8 {
+        self.bytes[8]
+    }
+
+    /// Returns the whole table, including the header
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// Returns the table contents, excluding the header
+    pub fn data(&self) -> &'a [u8] {
+        &self.bytes[SDT_HEADER_LEN..]
+    }
+}
+
+/**
+    Find the table with signature `sig`, through the XSDT if
+    available, and through the RSDT otherwise
+
+    # Safety
+    The RSDT/XSDT and the tables it refers to must be identity-mapped.
+*/
+pub unsafe fn find_table(rsdp: &Rsdp<'_>, sig: &[u8; 4]) -> Option<Sdt<'static>> {
+    // - the XSDT holds 64-bit pointers, the RSDT 32-bit ones
+    let (root, width) = match rsdp.xsdt_addr().filter(|&a| a != 0) {
+        Some(xsdt) => (unsafe { Sdt::from_addr(xsdt)? }, 8),
+        None => (unsafe { Sdt::from_addr(rsdp.rsdt_addr())? }, 4),
+    };
+
+    root.data().chunks_exact(width).find_map(|p| {
+        let addr = if width == 8 {
+            usize::try_from(read_u64(p, 0)?).ok()?
+        } else {
+            read_u32(p, 0)? as usize
+        };
+
+        unsafe { Sdt::from_addr(addr) }.filter(|t| t.signature() == sig)
+    })
+}
+
+/// Register location, as described by ACPI
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct GenericAddress {
+    space: u8,
+    bit_width: u8,
+    bit_offset: u8,
+    access_size: u8,
+    address: u64,
+}
+
+impl GenericAddress {
+    // Internal: parse a 12-byte generic address structure
+    fn parse(b: &[u8]) -> Option<Self> {
+        Some(GenericAddress {
+            space: *b.first()?,
+            bit_width: *b.get(1)?,
+            bit_offset: *b.get(2)?,
+            access_size: *b.get(3)?,
+            address: read_u64(b, 4)?,
+        })
+    }
+
+    /// Returns the address space (refer to [`SPACE_MEMORY`] and [`SPACE_IO`])
+    pub fn space(&self) -> u8 {
+        self.space
+    }
+
+    /// Returns the register width, in bits
+    pub fn bit_width(&self) -> u8 {
+        self.bit_width
+    }
+
+    /// Returns the register offset, in bits
+    pub fn bit_offset(&self) -> u8 {
+        self.bit_offset
+    }
+
+    /// Returns the access size (0 for undefined, 1 for bytes, 2 for words, ...)
+    pub fn access_size(&self) -> u8 {
+        self.access_size
+    }
+
+    /// Returns the address within the address space
+    pub fn address(&self) -> u64 {
+        self.address
+    }
+}
+
+/// View into the fixed ACPI description table
+#[derive(Copy, Clone, Debug)]
+pub struct Fadt<'a> {
+    sdt: Sdt<'a>,
+}
+
+impl<'a> Fadt<'a> {
+    /// Create new instance of `Fadt`, if `sdt` is a FADT
+    pub fn new(sdt: Sdt<'a>) -> Option<Self> {
+        if sdt.signature() == SIG_FADT {
+            Some(Fadt { sdt })
+        } else {
+            None
+        }
+    }
+
+    /**
+        Find the FADT through the provided RSDP, or through
+        the BIOS areas if none is provided
+
+        # Safety
+        Refer to [`scan_rsdp()`] and [`find_table()`].
+    */
+    pub unsafe fn locate(rsdp: Option<Rsdp<'_>>) -> Option<Fadt<'static>> {
+        let table = match rsdp {
+            Some(r) => unsafe { find_table(&r, SIG_FADT) },
+            None => unsafe { find_table(&scan_rsdp()?, SIG_FADT) },
+        };
+
+        Fadt::new(table?)
+    }
+
+    /// Returns the physical address of the DSDT
+    pub fn dsdt_addr(&self) -> usize {
+        // - prefer the 64-bit pointer, if present and set
+        let x_dsdt = read_u64(self.sdt.bytes, FADT_X_DSDT)
+            .and_then(|a| usize::try_from(a).ok())
+            .filter(|&a| a != 0);
+
+        x_dsdt.unwrap_or_else(|| self.read_u32(FADT_DSDT) as usize)
+    }
+
+    /// Returns the SMI command port, which is zero if unsupported
+    pub fn smi_cmd(&self) -> u16 {
+        self.read_u32(FADT_SMI_CMD) as u16
+    }
+
+    /// Returns the value that hands control over to ACPI when written to [`smi_cmd()`]
+    ///
+    /// [`smi_cmd()`]: Self::smi_cmd
+    pub fn acpi_enable(&self) -> u8 {
+        self.sdt.bytes.get(FADT_ACPI_ENABLE).copied().unwrap_or(0)
+    }
+
+    /// Returns the PM1a control block port
+    pub fn pm1a_cnt_blk(&self) -> u16 {
+        self.read_u32(FADT_PM1A_CNT_BLK) as u16
+    }
+
+    /// Returns the PM1b control block port, which is zero if absent
+    pub fn pm1b_cnt_blk(&self) -> u16 {
+        self.read_u32(FADT_PM1B_CNT_BLK) as u16
+    }
+
+    /// Returns the reset register and the value to write to it, if supported
+    pub fn reset_reg(&self) -> Option<(GenericAddress, u8)> {
+        if self.read_u32(FADT_FLAGS) & FLAG_RESET_REG_SUP == 0 {
+            return None;
+        }
+
+        let b = self.sdt.bytes;
+        let reg = GenericAddress::parse(b.get(FADT_RESET_REG..FADT_RESET_REG + 12)?)?;
+        let value = *b.get(FADT_RESET_VALUE)?;
+
+        Some((reg, value))
+    }
+
+    // Internal: read field, yielding zero if the table is too short
+    fn read_u32(&self, at: usize) -> u32 {
+        read_u32(self.sdt.bytes, at).unwrap_or(0)
+    }
+}
+
+/**
+    Extract the `SLP_TYPa` and `SLP_TYPb` values for
+    the S5 (soft-off) state from the DSDT
+
+    The DSDT is searched for `Name (\_S5_, Package () { a, b, ... })`,
+    which is encoded as a `NameOp`, the name, a `PackageOp`, the
+    package length, the element count, and then the elements.
+*/
+pub fn s5_sleep_type(dsdt: &Sdt<'_>) -> Option<(u8, u8)> {
+    let aml = dsdt.data();
+
+    aml.windows(4)
+        .enumerate()
+        .filter(|&(_, w)| w == b"_S5_")
+        .find_map(|(i, _)| {
+            // - the name may be preceded by a root prefix (`\`)
+            let named = match i {
+                0 => false,
+                1 => aml[0] == AML_NAME_OP,
+                _ => {
+                    aml[i - 1] == AML_NAME_OP || (aml[i - 1] == b'\\' && aml[i - 2] == AML_NAME_OP)
+                }
+            };
+
+            if !named || *aml.get(i + 4)? != AML_PACKAGE_OP {
+                return None;
+            }
+
+            // - the upper two bits of the lead byte give the
+            //   number of additional package length bytes
+            let lead = *aml.get(i + 5)?;
+            let mut at = i + 6 + (lead >> 6) as usize;
+
+            // - skip the element count
+            at += 1;
+
+            let a = aml_byte(aml, &mut at)?;
+            let b = aml_byte(aml, &mut at)?;
+
+            Some((a, b))
+        })
+}
+
+// Internal: parse a byte-sized AML integer, advancing the cursor
+fn aml_byte(aml: &[u8], at: &mut usize) -> Option<u8> {
+    let (val, len) = match *aml.get(*at)? {
+        AML_ZERO_OP => (0, 1),
+        AML_ONE_OP => (1, 1),
+        AML_BYTE_PREFIX => (*aml.get(*at + 1)?, 2),
+        // - some firmware emits bare bytes
+        b => (b, 1),
+    };
+
+    *at += len;
+    Some(val)
+}
+
+// Internal: little-endian field readers
+fn read_u32(b: &[u8], at: usize) -> Option<u32> {
+    let bytes = b.get(at..at.checked_add(4)?)?;
+    Some(u32::from_le_bytes(bytes.try_into().ok()?))
+}
+
+fn read_u64(b: &[u8], at: usize) -> Option<u64> {
+    let bytes = b.get(at..at.checked_add(8)?)?;
+    Some(u64::from_le_bytes(bytes.try_into().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use std::vec;
+    use std::vec::Vec;
+
+    // Internal: build a table with a valid header and checksum
+    fn table(sig: &[u8; 4], revision: u8, data: &[u8]) -> Vec<u8> {
+        let mut t = vec![0u8; SDT_HEADER_LEN];
+        t[..4].copy_from_slice(sig);
+        t[8] = revision;
+        t.extend_from_slice(data);
+
+        let len = t.len() as u32;
+        t[4..8].copy_from_slice(&len.to_le_bytes());
+        t[9] = 0u8.wrapping_sub(t.iter().fold(0u8, |a, &x| a.wrapping_add(x)));
+        t
+    }
+
+    // Internal: view a synthetic table
+    fn sdt(t: &[u8]) -> Option<Sdt<'_>> {
+        unsafe { Sdt::from_addr(t.as_ptr() as usize) }
+    }
+
+    // Internal: build a FADT, with fields set at their absolute offsets
+    fn fadt(len: usize, fields: &[(usize, &[u8])]) -> Vec<u8> {
+        let mut data = vec![0u8; len - SDT_HEADER_LEN];
+        for (at, bytes) in fields {
+            data[at - SDT_HEADER_LEN..][..bytes.len()].copy_from_slice(bytes);
+        }
+        table(SIG_FADT, 4, &data)
+    }
+
+    #[test]
+    fn sdt_checksum() {
+        let t = table(b"TEST", 2, b"payload");
+        let s = sdt(&t).unwrap();
+
+        assert_eq!(s.signature(), b"TEST");
+        assert_eq!(s.revision(), 2);
+        assert_eq!(s.data(), b"payload");
+        assert_eq!(s.as_bytes().len(), SDT_HEADER_LEN + 7);
+
+        // - any flipped byte breaks the checksum
+        let mut bad = t.clone();
+        bad[SDT_HEADER_LEN] ^= 1;
+        assert!(sdt(&bad).is_none());
+
+        assert!(unsafe { Sdt::from_addr(0) }.is_none());
+    }
+
+    #[test]
+    fn sdt_implausible_length() {
+        // - shorter than its own header
+        let mut t = table(b"TEST", 1, &[]);
+        t[4..8].copy_from_slice(&(SDT_HEADER_LEN as u32 - 1).to_le_bytes());
+        assert!(sdt(&t).is_none());
+
+        // - larger than any sane table (the length isn't trusted)
+        t[4..8].copy_from_slice(&(MAX_SDT_LEN as u32 + 1).to_le_bytes());
+        assert!(sdt(&t).is_none());
+    }
+
+    #[test]
+    fn fadt_fields() {
+        let reset = [SPACE_IO, 8, 0, 1, 0xf9, 0x0c, 0, 0, 0, 0, 0, 0];
+        let t = fadt(
+            244,
+            &[
+                (FADT_DSDT, &0x1234_0000u32.to_le_bytes()),
+                (FADT_SMI_CMD, &0xb2u32.to_le_bytes()),
+                (FADT_ACPI_ENABLE, &[0xf0]),
+                (FADT_PM1A_CNT_BLK, &0x604u32.to_le_bytes()),
+                (FADT_PM1B_CNT_BLK, &0x0u32.to_le_bytes()),
+                (FADT_FLAGS, &FLAG_RESET_REG_SUP.to_le_bytes()),
+                (FADT_RESET_REG, &reset),
+                (FADT_RESET_VALUE, &[0x06]),
+                (FADT_X_DSDT, &0x5678_0000u64.to_le_bytes()),
+            ],
+        );
+        let f = Fadt::new(sdt(&t).unwrap()).unwrap();
+
+        // - the 64-bit pointer takes precedence
+        assert_eq!(f.dsdt_addr(), 0x5678_0000);
+        assert_eq!((f.smi_cmd(), f.acpi_enable()), (0xb2, 0xf0));
+        assert_eq!((f.pm1a_cnt_blk(), f.pm1b_cnt_blk()), (0x604, 0));
+
+        let (reg, value) = f.reset_reg().unwrap();
+        assert_eq!(
+            (reg.space(), reg.bit_width(), reg.bit_offset()),
+            (SPACE_IO, 8, 0)
+        );
+        assert_eq!((reg.access_size(), reg.address(), value), (1, 0xcf9, 6));
+
+        let t = table(b"APIC", 4, &[0; 208]);
+        assert!(Fadt::new(sdt(&t).unwrap()).is_none());
+    }
+
+    #[test]
+    fn fadt_short_revision() {
+        // - an ACPI 1.0 FADT ends before the reset register
+        let t = fadt(
+            116,
+            &[
+                (FADT_DSDT, &0x1234_0000u32.to_le_bytes()),
+                (FADT_FLAGS, &FLAG_RESET_REG_SUP.to_le_bytes()),
+            ],
+        );
+        let f = Fadt::new(sdt(&t).unwrap()).unwrap();
+
+        assert_eq!(f.dsdt_addr(), 0x1234_0000);
+        assert!(f.reset_reg().is_none());
+
+        // - a zero 64-bit pointer falls back to the 32-bit one
+        let t = fadt(244, &[(FADT_DSDT, &0x1234_0000u32.to_le_bytes())]);
+        let f = Fadt::new(sdt(&t).unwrap()).unwrap();
+        assert_eq!(f.dsdt_addr(), 0x1234_0000);
+        assert!(f.reset_reg().is_none());
+
+        // - fields past the end read as zero
+        let t = fadt(48, &[]);
+        let f = Fadt::new(sdt(&t).unwrap()).unwrap();
+        assert_eq!((f.smi_cmd(), f.acpi_enable(), f.pm1a_cnt_blk()), (0, 0, 0));
+    }
+
+    #[test]
+    fn s5_packages() {
+        let s5 = |aml: &[u8]| s5_sleep_type(&sdt(&table(b"DSDT", 2, aml)).unwrap());
+
+        // - byte prefixes, behind a root prefix
+        assert_eq!(
+            s5(b"\x10\x00\x08\\_S5_\x12\x08\x04\x0a\x05\x0a\x07\x00\x00"),
+            Some((5, 7))
+        );
+
+        // - zero and one opcodes, at the very start
+        assert_eq!(s5(b"\x08_S5_\x12\x06\x04\x00\x01\x00\x00"), Some((0, 1)));
+
+        // - bare bytes, after a two-byte package length
+        assert_eq!(s5(b"\x08_S5_\x12\x46\x00\x04\x07\x07"), Some((7, 7)));
+
+        // - unnamed occurrences are skipped
+        assert_eq!(
+            s5(b"\x0d_S5_\x00\x08_S5_\x12\x06\x02\x0a\x03\x0a\x04"),
+            Some((3, 4))
+        );
+    }
+
+    #[test]
+    fn s5_malformed() {
+        let s5 = |aml: &[u8]| s5_sleep_type(&sdt(&table(b"DSDT", 2, aml)).unwrap());
+
+        assert_eq!(s5(b""), None);
+        assert_eq!(s5(b"_S5_\x12\x06\x04\x00\x00"), None);
+        assert_eq!(s5(b"\x08_S5_\x0a\x05"), None);
+
+        // - truncated packages
+        assert_eq!(s5(b"\x08_S5_"), None);
+        assert_eq!(s5(b"\x08_S5_\x12"), None);
+        assert_eq!(s5(b"\x08_S5_\x12\x06\x04\x0a\x05"), None);
+        assert_eq!(s5(b"\x08_S5_\x12\x06\x04\x0a\x05\x0a"), None);
+        assert_eq!(s5(b"\x08_S5_\x12\xc6\x00\x00"), None);
+    }
+}
@@ -0,0 +1,236 @@
+/*!
+    Module defining a polling ATA PIO driver
+
+    This driver is meant for early-boot use, where the BIOS
+    disk services are no longer reachable (as we left real
+    mode behind), and where interrupts are not yet set up.
+    It supports 28-bit LBA reads from legacy IDE channels,
+    which suffices for loading files from the boot volume.
+*/
+
+// Port I/O definitions
+use crate::arch::__io::{ReadOnlyPort, ReadWritePort, WriteOnlyPort};
+
+// I/O helpers
+use crate::shared::io::{Error, ErrorKind, ErrorPayload};
+use crate::shared::traits::BlockDevice;
+
+/// I/O base of the primary IDE channel
+pub const PRIMARY_IO_BASE: u16 = 0x1f0;
+
+/// Control base of the primary IDE channel
+pub const PRIMARY_CTRL_BASE: u16 = 0x3f6;
+
+/// I/O base of the secondary IDE channel
+pub const SECONDARY_IO_BASE: u16 = 0x170;
+
+/// Control base of the secondary IDE channel
+pub const SECONDARY_CTRL_BASE: u16 = 0x376;
+
+/// Size of a sector in bytes
+pub const SECTOR_SIZE: usize = 512;
+
+/// Highest addressable sector in 28-bit LBA mode (exclusive)
+pub const LBA28_LIMIT: u64 = 1 << 28;
+
+crate::port_block! {
+    /// Command block registers of an IDE channel
+    pub struct AtaRegs {
+        pub data: ReadWritePort<u16> = 0,
+        pub error: ReadOnlyPort<u8> = 1,
+        pub sector_count: ReadWritePort<u8> = 2,
+        pub lba_low: ReadWritePort<u8> = 3,
+        pub lba_mid: ReadWritePort<u8> = 4,
+        pub lba_high: ReadWritePort<u8> = 5,
+        pub drive: ReadWritePort<u8> = 6,
+        pub status: ReadOnlyPort<u8> = 7,
+        pub command: WriteOnlyPort<u8> = 7,
+    }
+}
+
+crate::port_block! {
+    /// Control block registers of an IDE channel
+    pub struct AtaCtrlRegs {
+        pub alt_status: ReadOnlyPort<u8> = 0,
+        pub device_ctrl: WriteOnlyPort<u8> = 0,
+    }
+}
+
+// Status register bits
+const STATUS_ERR: u8 = 1 << 0;
+const STATUS_DRQ: u8 = 1 << 3;
+const STATUS_DF: u8 = 1 << 5;
+const STATUS_BSY: u8 = 1 << 7;
+
+// Commands
+const CMD_READ_SECTORS: u8 = 0x20;
+
+// Number of status polls before giving up
+// - there is no timer to rely on yet, so this is
+//   a (generous) iteration count rather than a duration
+const POLL_LIMIT: usize = 1 << 20;
+
+/// Drive position on an IDE channel
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum AtaDrive {
+    Master,
+    Slave,
+}
+
+/**
+    Polling ATA PIO driver for a single drive
+
+    # Usage
+    ```rust,no_run
+    # use common::plat::pc_bios::ata::{AtaDrive, AtaPio, SECTOR_SIZE};
+    # use common::shared::traits::BlockDevice;
+    # fn main() -> Result<(), common::shared::io::Error> {
+    let mut disk = unsafe { AtaPio::primary(AtaDrive::Master) };
+    let mut buf = [0u8; SECTOR_SIZE];
+    disk.read_blocks(0, &mut buf)?;
+    # Ok(())
+    # }
+    ```
+*/
+#[derive(Debug)]
+pub struct AtaPio {
+    regs: AtaRegs,
+    ctrl: AtaCtrlRegs,
+    drive: AtaDrive,
+}
+
+impl AtaPio {
+    /**
+        Create new instance of `AtaPio` for the provided ports and drive
+
+        # Safety
+        The caller must ensure that the ports belong to an IDE channel,
+        and that no other code accesses the channel concurrently.
+    */
+    pub const unsafe fn new(io_base: u16, ctrl_base: u16, drive: AtaDrive) -> Self {
+        unsafe {
+            AtaPio {
+                regs: AtaRegs::new(io_base),
+                ctrl: AtaCtrlRegs::new(ctrl_base),
+                drive,
+            }
+        }
+    }
+
+    /**
+        Create new instance of `AtaPio` on the primary IDE channel
+
+        # Safety
+        Refer to [`new()`](Self::new).
+    */
+    pub const unsafe fn primary(drive: AtaDrive) -> Self {
+        unsafe { Self::new(PRIMARY_IO_BASE, PRIMARY_CTRL_BASE, drive) }
+    }
+
+    // Read the alternate status register, which doesn't acknowledge interrupts
+    fn alt_status(&self) -> u8 {
+        self.ctrl.alt_status.read()
+    }
+
+    // Wait for roughly 400 ns, as required after selecting a drive
+    // - each read of the alternate status register takes ~100 ns
+    fn delay_400ns(&self) {
+        for _ in 0..4 {
+            let _ = self.alt_status();
+        }
+    }
+
+    // Poll until BSY clears, then until DRQ sets (if requested)
+    fn poll(&self, want_drq: bool) -> Result<(), Error> {
+        for _ in 0..POLL_LIMIT {
+            let status = self.regs.status.read();
+
+            if status & STATUS_BSY != 0 {
+                continue;
+            }
+
+            if status & (STATUS_ERR | STATUS_DF) != 0 {
+                return Err(Error::new(
+                    ErrorKind::Other,
+                    ErrorPayload::Message("ATA drive reported an error"),
+                ));
+            }
+
+            if !want_drq || status & STATUS_DRQ != 0 {
+                return Ok(());
+            }
+        }
+
+        Err(Error::new(
+            ErrorKind::TimedOut,
+            ErrorPayload::Message("ATA drive did not respond"),
+        ))
+    }
+
+    // Read up to 256 sectors with a single command
+    fn read_chunk(&mut self, lba: u64, buf: &mut [u8]) -> Result<(), Error> {
+        let count = buf.len() / SECTOR_SIZE;
+        let drive_bit: u8 = match self.drive {
+            AtaDrive::Master => 0x00,
+            AtaDrive::Slave => 0x10,
+        };
+
+        // - wait for the drive to settle before issuing a command
+        self.poll(false)?;
+
+        // - select drive in LBA mode, and pass LBA bits 24-27
+        self.regs
+            .drive
+            .write(0xe0 | drive_bit | ((lba >> 24) as u8 & 0x0f));
+        self.delay_400ns();
+
+        // - a count of 0 means 256 sectors
+        self.regs.sector_count.write(count as u8);
+        self.regs.lba_low.write(lba as u8);
+        self.regs.lba_mid.write((lba >> 8) as u8);
+        self.regs.lba_high.write((lba >> 16) as u8);
+        self.regs.command.write(CMD_READ_SECTORS);
+
+        let mut words = [0u16; SECTOR_SIZE / 2];
+        for sector in buf.chunks_exact_mut(SECTOR_SIZE) {
+            self.delay_400ns();
+            self.poll(true)?;
+
+            // - transfer the whole sector at once (`rep insw`)
+            self.regs.data.read_into(&mut words);
+            for (bytes, w) in sector.chunks_exact_mut(2).zip(words) {
+                bytes.copy_from_slice(&w.to_le_bytes());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl BlockDevice for AtaPio {
+    fn block_size(&self) -> usize {
+        SECTOR_SIZE
+    }
+
+    fn read_blocks(&mut self, lba: u64, buf: &mut [u8]) -> Result<(), Error> {
+        if !buf.len().is_multiple_of(SECTOR_SIZE) {
+            return Err(Error::E_INVALID_INPUT);
+        }
+
+        let count = (buf.len() / SECTOR_SIZE) as u64;
+        if lba.checked_add(count).is_none_or(|end| end > LBA28_LIMIT) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                ErrorPayload::Message("sector out of 28-bit LBA range"),
+            ));
+        }
+
+        let mut lba = lba;
+        for chunk in buf.chunks_mut(256 * SECTOR_SIZE) {
+            self.read_chunk(lba, chunk)?;
+            lba += (chunk.len() / SECTOR_SIZE) as u64;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,198 @@
+/*!
+    Module defining the native boot-to-kernel handoff
+
+    `magnetite_os/boot` starts the kernel's `main` routine with a
+    pointer to a [`KernHandoff`], which describes everything the
+    bootloader has gathered: the E820 memory map, the screen, the
+    command line of the selected boot entry, the boot log (refer
+    to [`kmsg`]), and the stack that `main` starts on.
+
+    # Layout
+    The structure is `repr(C)`, and only holds integers, so that
+    it can be passed across the FFI boundary as-is. Pointers are
+    physical addresses, which both stages identity-map.
+
+    [`kmsg`]: crate::shared::log::kmsg
+*/
+
+// Definition uses
+use core::marker::PhantomData;
+use core::{slice, str};
+
+use super::structs::LongE820;
+use super::vesa::ScreenInfo;
+use crate::shared::mm::RegionSpan;
+
+/// Magic number identifying a native handoff (`"MGNTBOOT"`)
+pub const HANDOFF_MAGIC: u64 = u64::from_le_bytes(*b"MGNTBOOT");
+
+/**
+    Information passed from `magnetite_os/boot` to the kernel
+
+    # Safety
+    It is the bootloader's responsibility to ensure that every
+    range described here outlives the handoff, and that the kernel
+    does not reuse the memory before it is done with the handoff.
+*/
+#[repr(C)]
+pub struct KernHandoff<'a> {
+    magic: u64,
+    bootdev: u64,
+    e820_base: usize,
+    e820_len: usize,
+    screen_info: usize,
+    cmdline_base: usize,
+    cmdline_len: usize,
+    log_base: usize,
+    log_len: usize,
+    stack_base: usize,
+    stack_len: usize,
+    _marker: PhantomData<&'a ()>,
+}
+
+impl<'a> KernHandoff<'a> {
+    /**
+        Create new instance of `KernHandoff`
+
+        `log_ring` is the span of the boot log, as returned by
+        [`LogRing::span()`], and `stack` is the span of the stack
+        that the kernel's `main` routine is started on.
+
+        [`LogRing::span()`]: crate::shared::log::kmsg::LogRing::span
+    */
+    pub fn new(
+        bootdev: u64,
+        e820_map: &'a [LongE820],
+        screen_info: &'a ScreenInfo,
+        cmdline: &'a str,
+        log_ring: Option<RegionSpan>,
+        stack: RegionSpan,
+    ) -> Self {
+        let log_ring = log_ring.unwrap_or(RegionSpan::new(0, 0));
+
+        KernHandoff {
+            magic: HANDOFF_MAGIC,
+            bootdev,
+            e820_base: e820_map.as_ptr() as usize,
+            e820_len: e820_map.len(),
+            screen_info: screen_info as *const _ as usize,
+            cmdline_base: cmdline.as_ptr() as usize,
+            cmdline_len: cmdline.len(),
+            log_base: log_ring.base(),
+            log_len: log_ring.size(),
+            stack_base: stack.base(),
+            stack_len: stack.size(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Checks whether the handoff carries the expected magic number
+    pub fn is_valid(&self) -> bool {
+        self.magic == HANDOFF_MAGIC
+    }
+
+    /// Returns the BIOS identifier of the boot device
+    pub fn bootdev(&self) -> u64 {
+        self.bootdev
+    }
+
+    /// Returns the E820 memory map
+    pub fn e820_map(&self) -> &'a [LongE820] {
+        if self.e820_len == 0 {
+            return &[];
+        }
+
+        // SAFETY: we trust the instantiator to provide valid
+        // ranges (refer to the type-level documentation)
+        unsafe { slice::from_raw_parts(self.e820_base as *const LongE820, self.e820_len) }
+    }
+
+    /// Returns the screen information, if any
+    pub fn screen_info(&self) -> Option<&'a ScreenInfo> {
+        // SAFETY: as above
+        unsafe { (self.screen_info as *const ScreenInfo).as_ref() }
+    }
+
+    /// Returns the command line, or `None` if it isn't valid UTF-8
+    pub fn cmdline(&self) -> Option<&'a str> {
+        if self.cmdline_len == 0 {
+            return Some("");
+        }
+
+        // SAFETY: as above
+        let bytes =
+            unsafe { slice::from_raw_parts(self.cmdline_base as *const u8, self.cmdline_len) };
+        str::from_utf8(bytes).ok()
+    }
+
+    /// Returns the span of the boot log, if there is one
+    pub fn log_ring(&self) -> Option<RegionSpan> {
+        if self.log_base == 0 || self.log_len == 0 {
+            None
+        } else {
+            Some(RegionSpan::new(self.log_base, self.log_len))
+        }
+    }
+
+    /// Returns the span of the stack the kernel was started on, if known
+    pub fn stack(&self) -> Option<RegionSpan> {
+        if self.stack_base == 0 || self.stack_len == 0 {
+            None
+        } else {
+            Some(RegionSpan::new(self.stack_base, self.stack_len))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::mem::transmute;
+
+    #[test]
+    fn round_trip() {
+        // SAFETY: both types only hold integers
+        let map: [LongE820; 2] =
+            unsafe { transmute([[0u64, 0x9fc00, 1], [0x10_0000, 0x100_0000, 1]]) };
+        let screen: ScreenInfo =
+            unsafe { transmute([3u16, 0, 0, 0, 0, 0, 80, 25, 0, 0, 0, 0, 0, 0]) };
+        let log = [0u8; 64];
+
+        let h = KernHandoff::new(
+            0x80,
+            &map,
+            &screen,
+            "loglevel=debug",
+            Some(RegionSpan::new(log.as_ptr() as usize, log.len())),
+            RegionSpan::new(0x1000, 0x6b00),
+        );
+        assert!(h.is_valid());
+        assert_eq!(h.bootdev(), 0x80);
+        assert_eq!(h.e820_map().len(), 2);
+        assert_eq!(h.e820_map()[1].base(), 0x10_0000);
+        assert_eq!(h.screen_info().unwrap().cells_x(), 80);
+        assert_eq!(h.cmdline(), Some("loglevel=debug"));
+
+        let span = h.log_ring().unwrap();
+        assert_eq!((span.base(), span.size()), (log.as_ptr() as usize, 64));
+
+        let stack = h.stack().unwrap();
+        assert_eq!((stack.base(), stack.size()), (0x1000, 0x6b00));
+    }
+
+    #[test]
+    fn empty_fields() {
+        let screen: ScreenInfo = unsafe { transmute([0u16; 14]) };
+        let h = KernHandoff::new(0, &[], &screen, "", None, RegionSpan::new(0, 0));
+
+        assert!(h.e820_map().is_empty());
+        assert_eq!(h.cmdline(), Some(""));
+        assert!(h.log_ring().is_none());
+        assert!(h.stack().is_none());
+
+        // - a zeroed handoff is rejected by its magic number
+        let zeroed: KernHandoff<'_> = unsafe { core::mem::zeroed() };
+        assert!(!zeroed.is_valid());
+        assert!(zeroed.screen_info().is_none());
+    }
+}
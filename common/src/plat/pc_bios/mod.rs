@@ -11,3 +11,30 @@ pub mod vga;
 
 // VESA framebuffer definitions
 pub mod vesa;
+
+// Multiboot2 boot information parser
+pub mod multiboot2;
+
+// Native boot-to-kernel handoff
+pub mod handoff;
+
+// Polling ATA PIO driver
+pub mod ata;
+
+// Polling PS/2 keyboard driver
+pub mod ps2;
+
+// Polled PIT-based clock
+pub mod pit;
+
+// Polling 16550 UART driver
+pub mod serial;
+
+// QEMU exit device and in-OS test runner
+pub mod qemu;
+
+// Minimal ACPI table access
+pub mod acpi;
+
+// Reset, power-off and halt routines
+pub mod power;
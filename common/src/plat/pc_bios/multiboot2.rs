@@ -0,0 +1,973 @@
+/*!
+    Parser for the Multiboot2 boot information structure
+
+    When the kernel is loaded by a Multiboot2-compliant bootloader
+    (such as GRUB), the bootloader passes a pointer to a tagged boot
+    information structure instead of the E820 map and screen info
+    produced by `magnetite_os/boot`. This module parses that structure,
+    and converts the relevant tags into [`PhysMemRegion`] and
+    [`ScreenInfo`] instances, so that the kernel can proceed in the
+    same manner regardless of the boot path.
+
+    # Layout
+    The structure starts with an 8-byte header (`total_size`, reserved),
+    followed by a sequence of 8-byte aligned tags, each of which starts
+    with a `type` and a `size` field. The sequence is terminated by a
+    tag of type [`TAG_END`].
+
+    [`PhysMemRegion`]: crate::shared::mm::PhysMemRegion
+    [`ScreenInfo`]: super::vesa::ScreenInfo
+*/
+
+// Definition uses
+use core::mem;
+use core::ptr;
+use core::slice;
+use core::str;
+
+use super::vesa::{MODE_FOREIGN_LFB, MODE_VGA_TEXT, ScreenInfo};
+use crate::shared::GenericError;
+use crate::shared::mm::{PhysMemKind, PhysMemRegion, RegionSpan};
+
+/// Magic number found in the Multiboot2 header
+pub const HEADER_MAGIC: u32 = 0xe852_50d6;
+
+/// Magic number passed in EAX by a Multiboot2-compliant bootloader
+pub const BOOTLOADER_MAGIC: u32 = 0x36d7_6289;
+
+/// Alignment of tags within the boot information structure
+pub const TAG_ALIGN: usize = 8;
+
+/* Tag types */
+
+/// Terminating tag
+pub const TAG_END: u32 = 0;
+
+/// Kernel command line
+pub const TAG_CMDLINE: u32 = 1;
+
+/// Bootloader name
+pub const TAG_BOOTLOADER_NAME: u32 = 2;
+
+/// Boot module
+pub const TAG_MODULE: u32 = 3;
+
+/// Basic lower/upper memory information
+pub const TAG_BASIC_MEMINFO: u32 = 4;
+
+/// BIOS boot device
+pub const TAG_BOOTDEV: u32 = 5;
+
+/// Memory map
+pub const TAG_MMAP: u32 = 6;
+
+/// Framebuffer information
+pub const TAG_FRAMEBUFFER: u32 = 8;
+
+/// Copy of the ACPI 1.0 RSDP
+pub const TAG_ACPI_OLD: u32 = 14;
+
+/// Copy of the ACPI 2.0+ RSDP
+pub const TAG_ACPI_NEW: u32 = 15;
+
+// Internal: size of the fixed header and of tag headers
+const HEADER_SIZE: usize = 8;
+
+// Internal: read little-endian `u8` at offset, if in bounds
+#[inline(always)]
+fn read_u8(bytes: &[u8], off: usize) -> Option<u8> {
+    bytes.get(off).copied()
+}
+
+// Internal: read little-endian `u32` at offset, if in bounds
+#[inline(always)]
+fn read_u32(bytes: &[u8], off: usize) -> Option<u32> {
+    let b = bytes.get(off..off.checked_add(4)?)?;
+    Some(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+// Internal: read little-endian `u64` at offset, if in bounds
+#[inline(always)]
+fn read_u64(bytes: &[u8], off: usize) -> Option<u64> {
+    let lo = read_u32(bytes, off)? as u64;
+    let hi = read_u32(bytes, off.checked_add(4)?)? as u64;
+    Some((hi << 32) | lo)
+}
+
+// Internal: interpret bytes as a null-terminated UTF-8 string
+#[inline(always)]
+fn read_cstr(bytes: &[u8]) -> Option<&str> {
+    let n = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    str::from_utf8(&bytes[..n]).ok()
+}
+
+/**
+    Multiboot2 boot information structure
+
+    This type merely borrows the structure; nothing is copied.
+*/
+#[derive(Copy, Clone, Debug)]
+pub struct BootInfo<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> BootInfo<'a> {
+    /**
+        Create new instance of `BootInfo` from a byte slice
+
+        The slice must contain at least `total_size` bytes, where
+        `total_size` is the first field of the structure.
+    */
+    pub fn from_bytes(data: &'a [u8]) -> Result<Self, GenericError> {
+        let total_size = match read_u32(data, 0) {
+            Some(s) => s as usize,
+            None => {
+                return Err(GenericError::ErrorMessage(
+                    "Multiboot2 information truncated before header",
+                ));
+            }
+        };
+
+        if total_size < HEADER_SIZE || total_size > data.len() {
+            return Err(GenericError::ErrorMessage(
+                "Multiboot2 information has an invalid total size",
+            ));
+        }
+
+        Ok(BootInfo {
+            data: &data[..total_size],
+        })
+    }
+
+    /**
+        Create new instance of `BootInfo` from the physical
+        address passed in EBX by the bootloader
+
+        # Safety
+        The caller must ensure that `addr` is identity-mapped, and
+        that it points to a boot information structure that will
+        not be overwritten for the remainder of its use.
+    */
+    pub unsafe fn from_addr(addr: usize) -> Result<BootInfo<'static>, GenericError> {
+        if addr == 0 || !addr.is_multiple_of(TAG_ALIGN) {
+            return Err(GenericError::ErrorMessage(
+                "Multiboot2 information pointer is null or misaligned",
+            ));
+        }
+
+        // - read the total size first, then widen the window
+        let total_size = unsafe { ptr::read(addr as *const u32) } as usize;
+        let data: &'static [u8] = unsafe { slice::from_raw_parts(addr as *const u8, total_size) };
+
+        BootInfo::from_bytes(data)
+    }
+
+    /// Returns the total size of the structure in bytes
+    pub fn total_size(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Returns an iterator over all tags, up until the terminating tag
+    pub fn tags(&self) -> Tags<'a> {
+        Tags {
+            data: self.data,
+            offset: HEADER_SIZE,
+        }
+    }
+
+    /// Returns the kernel command line, if provided
+    pub fn command_line(&self) -> Option<&'a str> {
+        self.tags().find_map(|t| match t {
+            Tag::CommandLine(s) => Some(s),
+            _ => None,
+        })
+    }
+
+    /// Returns the bootloader name, if provided
+    pub fn bootloader_name(&self) -> Option<&'a str> {
+        self.tags().find_map(|t| match t {
+            Tag::BootLoaderName(s) => Some(s),
+            _ => None,
+        })
+    }
+
+    /// Returns the memory map, if provided
+    pub fn memory_map(&self) -> Option<MemoryMap<'a>> {
+        self.tags().find_map(|t| match t {
+            Tag::MemoryMap(m) => Some(m),
+            _ => None,
+        })
+    }
+
+    /// Returns the framebuffer information, if provided
+    pub fn framebuffer(&self) -> Option<Framebuffer> {
+        self.tags().find_map(|t| match t {
+            Tag::Framebuffer(f) => Some(f),
+            _ => None,
+        })
+    }
+
+    /// Returns an iterator over boot modules
+    pub fn modules(&self) -> impl Iterator<Item = Module<'a>> + use<'a> {
+        self.tags().filter_map(|t| match t {
+            Tag::Module(m) => Some(m),
+            _ => None,
+        })
+    }
+
+    /**
+        Returns a copy of the ACPI RSDP, if provided
+
+        The ACPI 2.0+ copy is preferred over the ACPI 1.0 copy.
+    */
+    pub fn rsdp(&self) -> Option<Rsdp<'a>> {
+        let mut old: Option<Rsdp<'a>> = None;
+
+        for t in self.tags() {
+            if let Tag::AcpiRsdp(r) = t {
+                if r.revision() >= 2 {
+                    return Some(r);
+                }
+
+                old = Some(r);
+            }
+        }
+
+        old
+    }
+
+    /**
+        Returns screen information equivalent to the one
+        produced by `magnetite_os/boot`, if representable
+
+        If no framebuffer tag is present, the bootloader is
+        assumed to have left the display in VGA text mode 3.
+    */
+    pub fn screen_info(&self) -> Option<ScreenInfo> {
+        match self.framebuffer() {
+            Some(f) => f.screen_info(),
+            None => Some(ScreenInfo::new_text(MODE_VGA_TEXT, 80, 25)),
+        }
+    }
+}
+
+/**
+    A single boot information tag
+
+    Tags that aren't interpreted by this module, as well as
+    tags with malformed payloads, are exposed as [`Tag::Other`].
+*/
+#[derive(Copy, Clone, Debug)]
+#[non_exhaustive]
+pub enum Tag<'a> {
+    /// Kernel command line
+    CommandLine(&'a str),
+
+    /// Bootloader name
+    BootLoaderName(&'a str),
+
+    /// Boot module
+    Module(Module<'a>),
+
+    /// Amount of lower and upper memory in KiB
+    BasicMemInfo { lower_kib: u32, upper_kib: u32 },
+
+    /// Memory map
+    MemoryMap(MemoryMap<'a>),
+
+    /// Framebuffer information
+    Framebuffer(Framebuffer),
+
+    /// Copy of the ACPI RSDP
+    AcpiRsdp(Rsdp<'a>),
+
+    /// Other tag (type, payload)
+    Other(u32, &'a [u8]),
+}
+
+impl<'a> Tag<'a> {
+    // Internal: interpret a tag payload
+    fn parse(tag_type: u32, payload: &'a [u8]) -> Self {
+        let parsed = match tag_type {
+            TAG_CMDLINE => read_cstr(payload).map(Tag::CommandLine),
+            TAG_BOOTLOADER_NAME => read_cstr(payload).map(Tag::BootLoaderName),
+            TAG_MODULE => Module::parse(payload).map(Tag::Module),
+            TAG_BASIC_MEMINFO => match (read_u32(payload, 0), read_u32(payload, 4)) {
+                (Some(lower_kib), Some(upper_kib)) => Some(Tag::BasicMemInfo {
+                    lower_kib,
+                    upper_kib,
+                }),
+                _ => None,
+            },
+            TAG_MMAP => MemoryMap::parse(payload).map(Tag::MemoryMap),
+            TAG_FRAMEBUFFER => Framebuffer::parse(payload).map(Tag::Framebuffer),
+            TAG_ACPI_OLD | TAG_ACPI_NEW => Rsdp::parse(payload).map(Tag::AcpiRsdp),
+            _ => None,
+        };
+
+        parsed.unwrap_or(Tag::Other(tag_type, payload))
+    }
+}
+
+/// Iterator over boot information tags
+#[derive(Clone, Debug)]
+pub struct Tags<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Iterator for Tags<'a> {
+    type Item = Tag<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // - stop at the terminating tag, or at the first
+        //   sign of a malformed tag sequence
+        let tag_type = read_u32(self.data, self.offset)?;
+        let size = read_u32(self.data, self.offset + 4)? as usize;
+        let end = self.offset.checked_add(size)?;
+
+        if tag_type == TAG_END || size < HEADER_SIZE || end > self.data.len() {
+            self.offset = self.data.len();
+            return None;
+        }
+
+        let payload = &self.data[self.offset + HEADER_SIZE..end];
+
+        // - advance to the next 8-byte aligned tag
+        self.offset = end.next_multiple_of(TAG_ALIGN);
+
+        Some(Tag::parse(tag_type, payload))
+    }
+}
+
+/// Boot module loaded alongside the kernel
+#[derive(Copy, Clone, Debug)]
+pub struct Module<'a> {
+    start: u32,
+    end: u32,
+    string: &'a str,
+}
+
+impl<'a> Module<'a> {
+    // Internal: parse tag payload
+    fn parse(payload: &'a [u8]) -> Option<Self> {
+        Some(Module {
+            start: read_u32(payload, 0)?,
+            end: read_u32(payload, 4)?,
+            string: read_cstr(payload.get(8..)?)?,
+        })
+    }
+
+    /// Returns the physical span occupied by the module
+    pub fn span(&self) -> RegionSpan {
+        let start = self.start as usize;
+        let end = (self.end as usize).max(start);
+
+        RegionSpan::new(start, end - start)
+    }
+
+    /// Returns the string associated with the module (usually its command line)
+    pub fn string(&self) -> &'a str {
+        self.string
+    }
+}
+
+/**
+    Multiboot2 memory map entry
+
+    The layout is identical to that of a long E820 entry, and
+    the area types carry the same meaning.
+*/
+#[derive(Debug, Copy, Clone)]
+#[repr(C, align(8))]
+pub struct MemoryMapEntry {
+    _base: u64,
+    _size: u64,
+    _area_type: u32,
+    _reserved: u32,
+}
+
+impl MemoryMapEntry {
+    /// Return region base
+    pub const fn base(&self) -> u64 {
+        self._base
+    }
+
+    /// Return region size
+    pub const fn size(&self) -> u64 {
+        self._size
+    }
+
+    /// Return area type
+    pub const fn area_type(&self) -> u32 {
+        self._area_type
+    }
+}
+
+impl From<MemoryMapEntry> for PhysMemRegion {
+    fn from(value: MemoryMapEntry) -> PhysMemRegion {
+        let base = value.base() as usize;
+        let size = value.size() as usize;
+        let kind = match value.area_type() {
+            1 => PhysMemKind::regular(),
+            2 => PhysMemKind::reserved(None),
+            3 => PhysMemKind::reclaimable(None),
+            4 => PhysMemKind::non_volatile(None),
+            _ => PhysMemKind::other(None),
+        };
+
+        PhysMemRegion::new(base, size, kind)
+    }
+}
+
+/// Memory map provided by the bootloader
+#[derive(Copy, Clone, Debug)]
+pub struct MemoryMap<'a> {
+    entry_size: usize,
+    entry_version: u32,
+    entries: &'a [u8],
+}
+
+impl<'a> MemoryMap<'a> {
+    // Internal: parse tag payload
+    fn parse(payload: &'a [u8]) -> Option<Self> {
+        let entry_size = read_u32(payload, 0)? as usize;
+        let entry_version = read_u32(payload, 4)?;
+
+        // - entries must at least contain base, size and type
+        if entry_size < 20 {
+            return None;
+        }
+
+        Some(MemoryMap {
+            entry_size,
+            entry_version,
+            entries: payload.get(8..)?,
+        })
+    }
+
+    /// Returns the size of each entry in bytes
+    pub fn entry_size(&self) -> usize {
+        self.entry_size
+    }
+
+    /// Returns the entry version
+    pub fn entry_version(&self) -> u32 {
+        self.entry_version
+    }
+
+    /// Returns the number of entries
+    pub fn len(&self) -> usize {
+        self.entries.len() / self.entry_size
+    }
+
+    /// Checks whether the memory map is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns an iterator over (copied) entries
+    pub fn iter(&self) -> MemoryMapIter<'a> {
+        MemoryMapIter {
+            map: *self,
+            index: 0,
+        }
+    }
+
+    /// Returns an iterator over the entries as physical memory regions
+    pub fn regions(&self) -> impl Iterator<Item = PhysMemRegion> + use<'a> {
+        self.iter().map(PhysMemRegion::from)
+    }
+
+    /**
+        Returns the entries as a slice, if they are laid out
+        exactly like [`MemoryMapEntry`]
+
+        This is the case for every known bootloader, and allows the
+        memory map to be used wherever the E820 map is expected.
+    */
+    pub fn as_slice(&self) -> Option<&'a [MemoryMapEntry]> {
+        let ptr = self.entries.as_ptr();
+
+        if self.entry_size != mem::size_of::<MemoryMapEntry>()
+            || !(ptr as usize).is_multiple_of(mem::align_of::<MemoryMapEntry>())
+        {
+            return None;
+        }
+
+        // SAFETY: size and alignment have been checked above, and
+        // `MemoryMapEntry` is valid for any bit pattern
+        Some(unsafe { slice::from_raw_parts(ptr as *const MemoryMapEntry, self.len()) })
+    }
+}
+
+/// Iterator over memory map entries
+#[derive(Clone, Debug)]
+pub struct MemoryMapIter<'a> {
+    map: MemoryMap<'a>,
+    index: usize,
+}
+
+impl Iterator for MemoryMapIter<'_> {
+    type Item = MemoryMapEntry;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let off = self.index.checked_mul(self.map.entry_size)?;
+        let bytes = self.map.entries.get(off..off + self.map.entry_size)?;
+        self.index += 1;
+
+        Some(MemoryMapEntry {
+            _base: read_u64(bytes, 0)?,
+            _size: read_u64(bytes, 8)?,
+            _area_type: read_u32(bytes, 16)?,
+            _reserved: 0,
+        })
+    }
+}
+
+/// Framebuffer pixel format
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum FramebufferKind {
+    /// Indexed (palette-based) color
+    Indexed,
+
+    /// Direct RGB color (position and size of each channel)
+    Rgb {
+        red_pos: u8,
+        red_size: u8,
+        green_pos: u8,
+        green_size: u8,
+        blue_pos: u8,
+        blue_size: u8,
+    },
+
+    /// EGA-compatible text mode (width and height are in characters)
+    EgaText,
+
+    /// Unknown framebuffer type
+    Unknown(u8),
+}
+
+/// Framebuffer information provided by the bootloader
+#[derive(Copy, Clone, Debug)]
+pub struct Framebuffer {
+    addr: u64,
+    pitch: u32,
+    width: u32,
+    height: u32,
+    bpp: u8,
+    kind: FramebufferKind,
+}
+
+impl Framebuffer {
+    // Internal: parse tag payload
+    fn parse(payload: &[u8]) -> Option<Self> {
+        let kind = match read_u8(payload, 21)? {
+            0 => FramebufferKind::Indexed,
+            1 => FramebufferKind::Rgb {
+                red_pos: read_u8(payload, 24)?,
+                red_size: read_u8(payload, 25)?,
+                green_pos: read_u8(payload, 26)?,
+                green_size: read_u8(payload, 27)?,
+                blue_pos: read_u8(payload, 28)?,
+                blue_size: read_u8(payload, 29)?,
+            },
+            2 => FramebufferKind::EgaText,
+            k => FramebufferKind::Unknown(k),
+        };
+
+        Some(Framebuffer {
+            addr: read_u64(payload, 0)?,
+            pitch: read_u32(payload, 8)?,
+            width: read_u32(payload, 12)?,
+            height: read_u32(payload, 16)?,
+            bpp: read_u8(payload, 20)?,
+            kind,
+        })
+    }
+
+    /// Returns the physical address of the framebuffer
+    pub fn addr(&self) -> u64 {
+        self.addr
+    }
+
+    /// Returns the number of bytes per scanline
+    pub fn pitch(&self) -> u32 {
+        self.pitch
+    }
+
+    /// Returns the width (in pixels, or characters in text mode)
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Returns the height (in pixels, or characters in text mode)
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Returns the number of bits per pixel
+    pub fn bits_per_pixel(&self) -> u8 {
+        self.bpp
+    }
+
+    /// Returns the pixel format
+    pub fn kind(&self) -> FramebufferKind {
+        self.kind
+    }
+
+    /**
+        Converts the framebuffer information into [`ScreenInfo`]
+
+        Returns `None` for indexed and unknown formats, and
+        for geometries that [`ScreenInfo`] cannot represent
+        (such as framebuffers above 4 GiB).
+
+        [`ScreenInfo`]: super::vesa::ScreenInfo
+    */
+    pub fn screen_info(&self) -> Option<ScreenInfo> {
+        let width = u16::try_from(self.width).ok()?;
+        let height = u16::try_from(self.height).ok()?;
+
+        match self.kind {
+            FramebufferKind::EgaText => Some(ScreenInfo::new_text(MODE_VGA_TEXT, width, height)),
+            FramebufferKind::Rgb {
+                red_pos,
+                red_size,
+                green_pos,
+                green_size,
+                blue_pos,
+                blue_size,
+            } => {
+                let pitch = u16::try_from(self.pitch).ok()?;
+                let frame_buf = u32::try_from(self.addr).ok()?;
+
+                // - whatever isn't covered by R, G and B is
+                //   considered to be the X/A channel
+                let used = red_size as u32 + green_size as u32 + blue_size as u32;
+                let x_size = (self.bpp as u32).saturating_sub(used);
+                let x_pos = [
+                    red_pos as u32 + red_size as u32,
+                    green_pos as u32 + green_size as u32,
+                    blue_pos as u32 + blue_size as u32,
+                ]
+                .into_iter()
+                .max()
+                .unwrap_or(0);
+                let x_pos = if x_size == 0 { 0 } else { x_pos };
+
+                let packed_mask = (x_size << 24)
+                    | ((red_size as u32) << 16)
+                    | ((green_size as u32) << 8)
+                    | (blue_size as u32);
+                let packed_pos = (x_pos << 24)
+                    | ((red_pos as u32) << 16)
+                    | ((green_pos as u32) << 8)
+                    | (blue_pos as u32);
+
+                Some(
+                    ScreenInfo::new_graphics(
+                        MODE_FOREIGN_LFB,
+                        width,
+                        height,
+                        pitch,
+                        self.bpp as u16,
+                        frame_buf,
+                    )
+                    .with_channels(packed_mask, packed_pos),
+                )
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Copy of the ACPI root system description pointer
+#[derive(Copy, Clone, Debug)]
+pub struct Rsdp<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Rsdp<'a> {
+    // Internal: parse tag payload
+    // - an ACPI 1.0 RSDP is 20 bytes long
+    fn parse(payload: &'a [u8]) -> Option<Self> {
+        if payload.len() < 20 || &payload[0..8] != b"RSD PTR " {
+            return None;
+        }
+
+        Some(Rsdp { bytes: payload })
+    }
+
+    /**
+        Create new instance of `Rsdp` from raw bytes, such as
+        those found by scanning the BIOS areas
+
+        Returns `None` if the signature doesn't match. The
+        checksums are not verified (refer to [`is_valid()`]).
+
+        [`is_valid()`]: Self::is_valid
+    */
+    pub fn from_bytes(bytes: &'a [u8]) -> Option<Self> {
+        Self::parse(bytes)
+    }
+
+    /// Returns the raw RSDP bytes
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// Returns the ACPI revision (0 for ACPI 1.0, 2 for ACPI 2.0+)
+    pub fn revision(&self) -> u8 {
+        self.bytes[15]
+    }
+
+    /// Returns the physical address of the RSDT
+    pub fn rsdt_addr(&self) -> usize {
+        read_u32(self.bytes, 16).unwrap_or(0) as usize
+    }
+
+    /// Returns the physical address of the XSDT, if available
+    pub fn xsdt_addr(&self) -> Option<usize> {
+        if self.revision() < 2 {
+            return None;
+        }
+
+        read_u64(self.bytes, 24).map(|a| a as usize)
+    }
+
+    /**
+        Checks whether the RSDP checksums are valid
+
+        The first 20 bytes must sum to zero, as must the whole
+        structure (up to its stated length) for ACPI 2.0+.
+    */
+    pub fn is_valid(&self) -> bool {
+        let sum = |b: &[u8]| b.iter().fold(0u8, |a, &x| a.wrapping_add(x));
+
+        if sum(&self.bytes[..20]) != 0 {
+            return false;
+        }
+
+        if self.revision() >= 2 {
+            let len = read_u32(self.bytes, 20).unwrap_or(0) as usize;
+
+            match self.bytes.get(..len) {
+                Some(b) if len >= 36 => sum(b) == 0,
+                _ => false,
+            }
+        } else {
+            true
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use crate::shared::mm::PhysMemClass;
+    use std::vec::Vec;
+
+    // Internal: 8-byte aligned backing storage for a synthetic structure
+    #[repr(C, align(8))]
+    struct Aligned([u8; 512]);
+
+    // Internal: lay out tags as a bootloader would, padding each one
+    // to the tag alignment, and terminating the sequence
+    fn build(tags: &[(u32, &[u8])]) -> Vec<u8> {
+        let mut out = std::vec![0u8; HEADER_SIZE];
+        for &(tag_type, payload) in tags.iter().chain([(TAG_END, &[][..])].iter()) {
+            out.extend_from_slice(&tag_type.to_le_bytes());
+            out.extend_from_slice(&((HEADER_SIZE + payload.len()) as u32).to_le_bytes());
+            out.extend_from_slice(payload);
+            out.resize(out.len().next_multiple_of(TAG_ALIGN), 0);
+        }
+
+        let total_size = out.len() as u32;
+        out[..4].copy_from_slice(&total_size.to_le_bytes());
+        out
+    }
+
+    // Internal: copy a structure into aligned storage
+    fn aligned(bytes: &[u8]) -> Aligned {
+        let mut a = Aligned([0; 512]);
+        a.0[..bytes.len()].copy_from_slice(bytes);
+        a
+    }
+
+    // Internal: memory map payload with 24-byte entries
+    fn mmap(entries: &[(u64, u64, u32)]) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(&24u32.to_le_bytes());
+        out.extend_from_slice(&0u32.to_le_bytes());
+        for &(base, size, area_type) in entries {
+            out.extend_from_slice(&base.to_le_bytes());
+            out.extend_from_slice(&size.to_le_bytes());
+            out.extend_from_slice(&area_type.to_le_bytes());
+            out.extend_from_slice(&0u32.to_le_bytes());
+        }
+        out
+    }
+
+    #[test]
+    fn tag_iteration() {
+        let meminfo = [0x80u32.to_le_bytes(), 0x7fc00u32.to_le_bytes()].concat();
+        let raw = build(&[
+            (TAG_CMDLINE, b"quiet\0"),
+            (TAG_BOOTLOADER_NAME, b"GRUB 2.12\0"),
+            (TAG_BASIC_MEMINFO, &meminfo),
+            (21, b"abc"),
+        ]);
+        let info = BootInfo::from_bytes(&raw).unwrap();
+        assert_eq!(info.total_size(), raw.len());
+
+        let tags: Vec<_> = info.tags().collect();
+        assert_eq!(tags.len(), 4);
+        assert!(matches!(tags[0], Tag::CommandLine("quiet")));
+        assert!(matches!(tags[1], Tag::BootLoaderName("GRUB 2.12")));
+        assert!(matches!(
+            tags[2],
+            Tag::BasicMemInfo {
+                lower_kib: 0x80,
+                upper_kib: 0x7fc00
+            }
+        ));
+
+        // - payloads exclude the alignment padding
+        assert!(matches!(tags[3], Tag::Other(21, b"abc")));
+
+        assert_eq!(info.command_line(), Some("quiet"));
+        assert_eq!(info.bootloader_name(), Some("GRUB 2.12"));
+        assert!(info.memory_map().is_none());
+    }
+
+    #[test]
+    fn end_tag() {
+        let mut raw = build(&[(TAG_CMDLINE, b"a\0")]);
+
+        // - tags past the terminating tag are ignored
+        let extra = build(&[(TAG_BOOTLOADER_NAME, b"b\0")]);
+        raw.extend_from_slice(&extra[HEADER_SIZE..]);
+        let total_size = raw.len() as u32;
+        raw[..4].copy_from_slice(&total_size.to_le_bytes());
+
+        let info = BootInfo::from_bytes(&raw).unwrap();
+        assert_eq!(info.tags().count(), 1);
+        assert!(info.bootloader_name().is_none());
+
+        let raw = build(&[]);
+        assert_eq!(BootInfo::from_bytes(&raw).unwrap().tags().count(), 0);
+    }
+
+    #[test]
+    fn total_size() {
+        let raw = build(&[(TAG_CMDLINE, b"quiet\0")]);
+
+        assert!(BootInfo::from_bytes(&raw[..3]).is_err());
+
+        // - larger than the provided bytes
+        assert!(BootInfo::from_bytes(&raw[..raw.len() - 1]).is_err());
+
+        // - smaller than the header itself
+        let mut bad = raw.clone();
+        bad[..4].copy_from_slice(&4u32.to_le_bytes());
+        assert!(BootInfo::from_bytes(&bad).is_err());
+
+        // - smaller than the tags, which end the iteration
+        let mut short = raw.clone();
+        short[..4].copy_from_slice(&12u32.to_le_bytes());
+        let info = BootInfo::from_bytes(&short).unwrap();
+        assert_eq!(info.total_size(), 12);
+        assert_eq!(info.tags().count(), 0);
+
+        // - trailing bytes past `total_size` are ignored
+        let mut long = raw.clone();
+        long.extend_from_slice(&[0xff; 16]);
+        let info = BootInfo::from_bytes(&long).unwrap();
+        assert_eq!(info.total_size(), raw.len());
+        assert_eq!(info.tags().count(), 1);
+    }
+
+    #[test]
+    fn malformed_tags() {
+        // - a tag smaller than its own header ends the iteration
+        let mut raw = build(&[(TAG_CMDLINE, b"a\0"), (TAG_CMDLINE, b"b\0")]);
+        raw[HEADER_SIZE + 4..HEADER_SIZE + 8].copy_from_slice(&4u32.to_le_bytes());
+        assert_eq!(BootInfo::from_bytes(&raw).unwrap().tags().count(), 0);
+
+        // - as does a tag reaching past the end
+        let mut raw = build(&[(TAG_CMDLINE, b"a\0"), (TAG_CMDLINE, b"b\0")]);
+        raw[HEADER_SIZE + 20..HEADER_SIZE + 24].copy_from_slice(&64u32.to_le_bytes());
+        let info = BootInfo::from_bytes(&raw).unwrap();
+        assert_eq!(info.tags().count(), 1);
+
+        // - malformed payloads are passed on as is
+        let raw = build(&[
+            (TAG_CMDLINE, b"\xff\0"),
+            (TAG_MMAP, &[8, 0, 0, 0, 0, 0, 0, 0]),
+        ]);
+        let tags: Vec<_> = BootInfo::from_bytes(&raw).unwrap().tags().collect();
+        assert!(matches!(tags[0], Tag::Other(TAG_CMDLINE, _)));
+        assert!(matches!(tags[1], Tag::Other(TAG_MMAP, _)));
+    }
+
+    #[test]
+    fn memory_map() {
+        let payload = mmap(&[
+            (0x0, 0x9fc00, 1),
+            (0x10_0000, 0x1ef_0000, 1),
+            (0xfffc_0000, 0x4_0000, 2),
+        ]);
+        let storage = aligned(&build(&[(TAG_CMDLINE, b"x\0"), (TAG_MMAP, &payload)]));
+        let info = BootInfo::from_bytes(&storage.0).unwrap();
+        let map = info.memory_map().unwrap();
+
+        assert_eq!(
+            (map.len(), map.entry_size(), map.entry_version()),
+            (3, 24, 0)
+        );
+        assert_eq!(map.iter().nth(1).map(|e| e.base()), Some(0x10_0000));
+
+        let slice = map.as_slice().unwrap();
+        assert_eq!(slice[2].size(), 0x4_0000);
+        assert_eq!(slice[2].area_type(), 2);
+
+        let classes: Vec<_> = map.regions().map(|r| r.kind().class()).collect();
+        assert_eq!(
+            classes,
+            [
+                PhysMemClass::Regular,
+                PhysMemClass::Regular,
+                PhysMemClass::Reserved
+            ]
+        );
+    }
+
+    #[test]
+    fn screen_info() {
+        let mut fb = Vec::new();
+        fb.extend_from_slice(&0xfd00_0000u64.to_le_bytes());
+        fb.extend_from_slice(&4096u32.to_le_bytes());
+        fb.extend_from_slice(&1024u32.to_le_bytes());
+        fb.extend_from_slice(&768u32.to_le_bytes());
+        fb.extend_from_slice(&[32, 1, 0, 0, 16, 8, 8, 8, 0, 8]);
+
+        let raw = build(&[(TAG_FRAMEBUFFER, &fb)]);
+        let info = BootInfo::from_bytes(&raw).unwrap();
+        let screen = info.screen_info().unwrap();
+        assert_eq!(
+            (screen.width(), screen.height(), screen.pitch()),
+            (1024, 768, 4096)
+        );
+        assert_eq!(screen.bits_per_pixel(), 32);
+        assert_eq!(screen.packed_mask(), 0x0808_0808);
+        assert_eq!(screen.packed_pos(), 0x1810_0800);
+
+        // - without a framebuffer tag, the display is in text mode
+        let raw = build(&[]);
+        let screen = BootInfo::from_bytes(&raw).unwrap().screen_info().unwrap();
+        assert_eq!((screen.cells_x(), screen.cells_y()), (80, 25));
+    }
+}
@@ -0,0 +1,134 @@
+/*!
+    Module defining a polled clock based on the 8253/8254 PIT
+
+    Channel 0 is programmed as a free-running rate generator with
+    the longest possible period (65536 ticks, or ~54.9 ms). Elapsed
+    time is accumulated by latching and reading the counter, so the
+    clock must be polled at least once per period to stay accurate.
+
+    Every [`PitClock`] shares the same count, and the PIT is only
+    programmed once, so polling any instance keeps the others
+    accurate. Nothing polls the clock on a timer tick yet: when
+    nobody polls it for longer than a period, whole periods are
+    lost, so readings stay monotonic but fall behind real time.
+*/
+
+// Definition uses
+use core::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+// Port I/O definitions
+use crate::arch::__io::{ReadWritePort, WriteOnlyPort};
+
+// Clock contract
+use crate::shared::traits::Clock;
+
+/// Input frequency of the PIT in Hz
+pub const PIT_FREQ: u64 = 1_193_182;
+
+crate::port_block! {
+    // Internal: registers of the PIT
+    struct PitPorts {
+        ch0: ReadWritePort<u8> = 0,
+        cmd: WriteOnlyPort<u8> = 3,
+    }
+}
+
+// - nothing else in the tree programs the PIT
+const PIT: PitPorts = unsafe { PitPorts::new(0x40) };
+
+// Elapsed ticks (upper 48 bits), and counter value (lower 16 bits)
+// as of the last poll
+// - 48 bits of ticks last for more than 7 years
+static STATE: AtomicU64 = AtomicU64::new(0);
+
+// Whether channel 0 was programmed already
+static PROGRAMMED: AtomicBool = AtomicBool::new(false);
+
+// Commands
+// - channel 0, lobyte/hibyte access, mode 2 (rate generator), binary
+const CMD_CH0_RATE_GEN: u8 = 0x34;
+// - channel 0, counter latch
+const CMD_CH0_LATCH: u8 = 0x00;
+
+/**
+    Polled clock based on PIT channel 0
+
+    # Usage
+    ```rust,no_run
+    # use common::plat::pc_bios::pit::PitClock;
+    # use common::shared::traits::Clock;
+    let mut clock = unsafe { PitClock::new() };
+    let start = clock.millis();
+    while clock.millis() - start < 1000 {
+        core::hint::spin_loop();
+    }
+    ```
+*/
+#[derive(Debug)]
+pub struct PitClock {
+    start: u64,
+}
+
+impl PitClock {
+    /**
+        Create new instance of `PitClock`, programming
+        channel 0 if no other instance did so before
+
+        # Safety
+        The caller must ensure that no other code uses channel 0,
+        and that IRQ 0 is either masked or handled appropriately.
+    */
+    pub unsafe fn new() -> Self {
+        if !PROGRAMMED.swap(true, Ordering::Relaxed) {
+            // - a reload value of zero stands for 65536
+            PIT.cmd.write(CMD_CH0_RATE_GEN);
+            PIT.ch0.write(0);
+            PIT.ch0.write(0);
+
+            STATE.store(read_counter() as u64, Ordering::Relaxed);
+        }
+
+        PitClock { start: poll() }
+    }
+
+    /// Return PIT ticks elapsed since instantiation
+    pub fn ticks(&mut self) -> u64 {
+        poll() - self.start
+    }
+}
+
+impl Clock for PitClock {
+    fn millis(&mut self) -> u64 {
+        self.ticks() * 1000 / PIT_FREQ
+    }
+}
+
+// Accumulate the ticks elapsed since the last poll, and return the total
+fn poll() -> u64 {
+    let mut state = STATE.load(Ordering::Relaxed);
+
+    loop {
+        // - the counter counts down, and wraps around at zero
+        let now = read_counter();
+        let ticks = (state >> 16) + (state as u16).wrapping_sub(now) as u64;
+
+        match STATE.compare_exchange_weak(
+            state,
+            (ticks << 16) | now as u64,
+            Ordering::Relaxed,
+            Ordering::Relaxed,
+        ) {
+            Ok(_) => return ticks,
+            Err(s) => state = s,
+        }
+    }
+}
+
+// Latch and read the current value of channel 0
+fn read_counter() -> u16 {
+    PIT.cmd.write(CMD_CH0_LATCH);
+    let lo = PIT.ch0.read();
+    let hi = PIT.ch0.read();
+
+    u16::from_le_bytes([lo, hi])
+}
@@ -0,0 +1,427 @@
+/*!
+    Module defining reset and power-off routines
+
+    No single reset method works everywhere, so [`PowerControl::reboot()`]
+    tries them in order of decreasing politeness:
+    1. the ACPI reset register, if the FADT provides one,
+    2. the keyboard controller (`0xfe` to port `0x64`),
+    3. the reset control register (port `0xcf9`),
+    4. a deliberate triple fault, which no processor survives.
+
+    Likewise, [`PowerControl::power_off()`] tries ACPI S5 first, and
+    then the shutdown ports of common emulators, before giving up
+    and halting.
+*/
+
+// Standard definitions
+use core::arch::asm;
+use core::fmt;
+use core::hint::spin_loop;
+use core::ptr::write_volatile;
+use core::str::FromStr;
+
+// Port I/O routines
+use crate::arch::__io::{in_b, in_w, io_wait, out_b, out_w};
+
+// Internal definitions
+use super::acpi::{self, Fadt, GenericAddress, SPACE_IO, SPACE_MEMORY, Sdt};
+use super::multiboot2::Rsdp;
+use super::ps2::STATUS_PORT;
+use crate::arch::x86::idle::{disable_interrupts, halt_forever};
+use crate::arch::x86::structs::gdt::DescriptorTablePointer;
+use crate::shared::traits::Clock;
+
+/// Keyboard controller command that pulses the reset line
+pub const KBC_CMD_RESET: u8 = 0xfe;
+
+/// Reset control register
+pub const RESET_CTRL_PORT: u16 = 0xcf9;
+
+/// Shutdown port and value of QEMU (`-machine q35` and newer `pc`)
+pub const QEMU_SHUTDOWN: (u16, u16) = (0x604, 0x2000);
+
+/// Shutdown port and value of bochs and older QEMU versions
+pub const BOCHS_SHUTDOWN: (u16, u16) = (0xb004, 0x2000);
+
+/// Shutdown port and value of VirtualBox
+pub const VBOX_SHUTDOWN: (u16, u16) = (0x4004, 0x3400);
+
+// Keyboard controller status: input buffer full
+const KBC_STATUS_INPUT_FULL: u8 = 1 << 1;
+
+// Reset control register bits
+const RESET_CTRL_SYS_RST: u8 = 1 << 1;
+const RESET_CTRL_RST_CPU: u8 = 1 << 2;
+
+// PM1 control register bits
+const PM1_SCI_EN: u16 = 1 << 0;
+const PM1_SLP_TYP_SHIFT: u16 = 10;
+const PM1_SLP_EN: u16 = 1 << 13;
+
+// Number of polls or delays before giving up on a method
+const ATTEMPTS: usize = 0x10000;
+
+/// Action to take after a panic has been reported
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum PanicPolicy {
+    /// Halt the processor
+    #[default]
+    Halt,
+
+    /// Reboot after the provided number of seconds
+    Reboot(u32),
+
+    /// Power off the machine
+    PowerOff,
+}
+
+/// Error reported when parsing a [`PanicPolicy`] fails
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ParsePolicyError {
+    /// The action isn't one of `halt`, `poweroff` and `reboot`
+    UnknownAction,
+
+    /// The delay isn't a number of seconds, or the action takes none
+    InvalidDelay,
+}
+
+impl fmt::Display for ParsePolicyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ParsePolicyError::UnknownAction => "expected `halt`, `poweroff` or `reboot`",
+            ParsePolicyError::InvalidDelay => "invalid reboot delay",
+        })
+    }
+}
+
+impl core::error::Error for ParsePolicyError {}
+
+impl FromStr for PanicPolicy {
+    type Err = ParsePolicyError;
+
+    /// Parses `halt`, `poweroff`, `reboot` or `reboot,<seconds>`, ignoring case
+    fn from_str(s: &str) -> Result<Self, ParsePolicyError> {
+        let (action, delay) = match s.split_once(',') {
+            Some((a, d)) => (a, Some(d)),
+            None => (s, None),
+        };
+
+        let is = |name: &str| action.eq_ignore_ascii_case(name);
+
+        match delay {
+            None if is("halt") => Ok(PanicPolicy::Halt),
+            None if is("poweroff") => Ok(PanicPolicy::PowerOff),
+            None if is("reboot") => Ok(PanicPolicy::Reboot(0)),
+            Some(d) if is("reboot") => d
+                .parse()
+                .map(PanicPolicy::Reboot)
+                .map_err(|_| ParsePolicyError::InvalidDelay),
+            // - only reboots are delayed
+            Some(_) if is("halt") || is("poweroff") => Err(ParsePolicyError::InvalidDelay),
+            _ => Err(ParsePolicyError::UnknownAction),
+        }
+    }
+}
+
+impl fmt::Display for PanicPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PanicPolicy::Halt => f.write_str("halt"),
+            PanicPolicy::Reboot(0) => f.write_str("reboot"),
+            PanicPolicy::Reboot(n) => write!(f, "reboot,{}", n),
+            PanicPolicy::PowerOff => f.write_str("poweroff"),
+        }
+    }
+}
+
+/**
+    Power management state gathered from ACPI
+
+    The state is plain data, so that it can be gathered
+    up front and used from panic handlers without touching
+    the ACPI tables again.
+    ```rust,no_run
+    # use common::plat::pc_bios::power::PowerControl;
+    # use common::shared::structs::spin_lock::Mutex;
+    static POWER: Mutex<PowerControl> = Mutex::new(PowerControl::legacy());
+
+    *POWER.lock() = unsafe { PowerControl::from_acpi(None) };
+    unsafe { POWER.lock().reboot() };
+    ```
+*/
+#[derive(Copy, Clone, Debug, Default)]
+pub struct PowerControl {
+    reset_reg: Option<(GenericAddress, u8)>,
+    smi_cmd: u16,
+    acpi_enable: u8,
+    pm1_cnt: (u16, u16),
+    slp_typ: Option<(u8, u8)>,
+}
+
+impl PowerControl {
+    /// Create new instance of `PowerControl` that only uses legacy methods
+    pub const fn legacy() -> Self {
+        PowerControl {
+            reset_reg: None,
+            smi_cmd: 0,
+            acpi_enable: 0,
+            pm1_cnt: (0, 0),
+            slp_typ: None,
+        }
+    }
+
+    /**
+        Create new instance of `PowerControl` from the ACPI tables
+
+        The tables are found through the provided RSDP, or through
+        the BIOS areas if none is provided. Falls back to legacy
+        methods if they cannot be found.
+
+        # Safety
+        The ACPI tables must be identity-mapped (refer to [`acpi`]).
+    */
+    pub unsafe fn from_acpi(rsdp: Option<Rsdp<'_>>) -> Self {
+        let fadt = match unsafe { Fadt::locate(rsdp) } {
+            Some(f) => f,
+            None => return Self::legacy(),
+        };
+
+        let dsdt = unsafe { Sdt::from_addr(fadt.dsdt_addr()) };
+
+        PowerControl {
+            reset_reg: fadt.reset_reg(),
+            smi_cmd: fadt.smi_cmd(),
+            acpi_enable: fadt.acpi_enable(),
+            pm1_cnt: (fadt.pm1a_cnt_blk(), fadt.pm1b_cnt_blk()),
+            slp_typ: dsdt.as_ref().and_then(acpi::s5_sleep_type),
+        }
+    }
+
+    /// Checks whether an ACPI reset register is available
+    pub fn has_acpi_reset(&self) -> bool {
+        self.reset_reg.is_some()
+    }
+
+    /// Checks whether ACPI S5 power-off is available
+    pub fn has_acpi_power_off(&self) -> bool {
+        self.slp_typ.is_some() && self.pm1_cnt.0 != 0
+    }
+
+    /**
+        Reset the machine, trying every method in turn
+
+        # Safety
+        Everything that hasn't been persisted is lost.
+    */
+    pub unsafe fn reboot(&self) -> ! {
+        disable_interrupts();
+
+        unsafe {
+            if self.reset_acpi() {
+                settle();
+            }
+
+            reset_kbc();
+            settle();
+
+            reset_cf9();
+            settle();
+
+            triple_fault();
+        }
+    }
+
+    /**
+        Power the machine off, halting if all methods fail
+
+        # Safety
+        Everything that hasn't been persisted is lost.
+    */
+    pub unsafe fn power_off(&self) -> ! {
+        disable_interrupts();
+
+        unsafe {
+            if self.power_off_acpi() {
+                settle();
+            }
+
+            for (port, val) in [QEMU_SHUTDOWN, BOCHS_SHUTDOWN, VBOX_SHUTDOWN] {
+                out_w(port, val);
+            }
+        }
+
+        halt_forever();
+    }
+
+    /**
+        Carry out a panic policy, waiting on `clock` before rebooting
+
+        # Safety
+        Refer to [`reboot()`] and [`power_off()`].
+
+        [`reboot()`]: Self::reboot
+        [`power_off()`]: Self::power_off
+    */
+    pub unsafe fn apply(&self, policy: PanicPolicy, clock: &mut dyn Clock) -> ! {
+        match policy {
+            PanicPolicy::Halt => halt_forever(),
+            PanicPolicy::Reboot(secs) => {
+                let start = clock.millis();
+                while clock.millis() - start < secs as u64 * 1000 {
+                    spin_loop();
+                }
+
+                unsafe { self.reboot() }
+            }
+            PanicPolicy::PowerOff => unsafe { self.power_off() },
+        }
+    }
+
+    // Internal: write the ACPI reset register, if supported
+    unsafe fn reset_acpi(&self) -> bool {
+        let (reg, value) = match self.reset_reg {
+            Some(r) => r,
+            None => return false,
+        };
+
+        // - PCI configuration space is not supported
+        match reg.space() {
+            SPACE_IO => unsafe { out_b(reg.address() as u16, value) },
+            SPACE_MEMORY => unsafe { write_volatile(reg.address() as usize as *mut u8, value) },
+            _ => return false,
+        }
+
+        true
+    }
+
+    // Internal: enter S5 through the PM1 control blocks, if supported
+    unsafe fn power_off_acpi(&self) -> bool {
+        let (typ_a, typ_b) = match self.slp_typ {
+            Some(t) if self.pm1_cnt.0 != 0 => t,
+            _ => return false,
+        };
+
+        let (pm1a, pm1b) = self.pm1_cnt;
+
+        unsafe {
+            // - hand control over to ACPI, if the firmware still has it
+            if in_w(pm1a) & PM1_SCI_EN == 0 && self.smi_cmd != 0 && self.acpi_enable != 0 {
+                out_b(self.smi_cmd, self.acpi_enable);
+                for _ in 0..ATTEMPTS {
+                    if in_w(pm1a) & PM1_SCI_EN != 0 {
+                        break;
+                    }
+                    io_wait();
+                }
+            }
+
+            out_w(pm1a, ((typ_a as u16) << PM1_SLP_TYP_SHIFT) | PM1_SLP_EN);
+            if pm1b != 0 {
+                out_w(pm1b, ((typ_b as u16) << PM1_SLP_TYP_SHIFT) | PM1_SLP_EN);
+            }
+        }
+
+        true
+    }
+}
+
+/**
+    Reset the machine through the keyboard controller
+
+    # Safety
+    Everything that hasn't been persisted is lost.
+*/
+pub unsafe fn reset_kbc() {
+    unsafe {
+        // - the controller ignores commands while its input buffer is full
+        for _ in 0..ATTEMPTS {
+            if in_b(STATUS_PORT) & KBC_STATUS_INPUT_FULL == 0 {
+                break;
+            }
+            io_wait();
+        }
+
+        out_b(STATUS_PORT, KBC_CMD_RESET);
+    }
+}
+
+/**
+    Reset the machine through the reset control register
+
+    # Safety
+    Everything that hasn't been persisted is lost.
+*/
+pub unsafe fn reset_cf9() {
+    unsafe {
+        // - the reset happens on the rising edge of `RST_CPU`
+        out_b(RESET_CTRL_PORT, RESET_CTRL_SYS_RST);
+        io_wait();
+        out_b(RESET_CTRL_PORT, RESET_CTRL_SYS_RST | RESET_CTRL_RST_CPU);
+    }
+}
+
+/**
+    Reset the machine by triple-faulting the processor
+
+    An empty IDT is loaded, so the breakpoint exception cannot be
+    delivered, and neither can the resulting double fault.
+
+    # Safety
+    Everything that hasn't been persisted is lost.
+*/
+pub unsafe fn triple_fault() -> ! {
+    let idt = DescriptorTablePointer { limit: 0, base: 0 };
+
+    unsafe {
+        asm!("lidt [{}]", "int3", in(reg) &idt, options(noreturn));
+    }
+}
+
+// Internal: give a reset method some time to take effect
+fn settle() {
+    for _ in 0..ATTEMPTS {
+        io_wait();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::structs::array_string::ArrayString;
+    use core::fmt::Write;
+
+    #[test]
+    fn parse_panic_policy() {
+        assert_eq!("halt".parse(), Ok(PanicPolicy::Halt));
+        assert_eq!("PowerOff".parse(), Ok(PanicPolicy::PowerOff));
+        assert_eq!("reboot".parse(), Ok(PanicPolicy::Reboot(0)));
+        assert_eq!("reboot,30".parse(), Ok(PanicPolicy::Reboot(30)));
+
+        for (s, e) in [
+            ("sleep", ParsePolicyError::UnknownAction),
+            ("", ParsePolicyError::UnknownAction),
+            ("sleep,5", ParsePolicyError::UnknownAction),
+            ("reboot,", ParsePolicyError::InvalidDelay),
+            ("reboot,-1", ParsePolicyError::InvalidDelay),
+            ("reboot,99999999999", ParsePolicyError::InvalidDelay),
+            ("halt,5", ParsePolicyError::InvalidDelay),
+            ("poweroff,0", ParsePolicyError::InvalidDelay),
+        ] {
+            assert_eq!(s.parse::<PanicPolicy>(), Err(e), "{:?}", s);
+        }
+    }
+
+    #[test]
+    fn panic_policy_round_trip() {
+        for policy in [
+            PanicPolicy::Halt,
+            PanicPolicy::Reboot(0),
+            PanicPolicy::Reboot(5),
+            PanicPolicy::PowerOff,
+        ] {
+            let mut buf: ArrayString<16> = ArrayString::new();
+            write!(buf, "{}", policy).unwrap();
+            assert_eq!(buf.parse(), Ok(policy));
+        }
+    }
+}
@@ -0,0 +1,186 @@
+/*!
+    Module defining a polling PS/2 keyboard driver
+
+    The driver reads scancodes directly from the 8042 keyboard
+    controller, and relies on the controller translating them to
+    scancode set 1 (which is the default after the BIOS is done).
+    It is meant for early-boot use, where interrupts are not set up.
+*/
+
+// Port I/O definitions
+use crate::arch::__io::ReadOnlyPort;
+
+// Key event definitions
+use crate::shared::traits::{KeyEvent, KeySource};
+
+/// Data port of the 8042 controller
+pub const DATA_PORT: u16 = 0x60;
+
+/// Status port of the 8042 controller
+pub const STATUS_PORT: u16 = 0x64;
+
+// Status register bits
+const STATUS_OUTPUT_FULL: u8 = 1 << 0;
+const STATUS_AUX_DATA: u8 = 1 << 5;
+
+// Scancode prefixes and flags
+const SC_EXTENDED: u8 = 0xe0;
+const SC_RELEASE: u8 = 0x80;
+
+// Scancodes of modifier keys
+const SC_LSHIFT: u8 = 0x2a;
+const SC_RSHIFT: u8 = 0x36;
+
+// Scancode set 1 to ASCII, without and with shift
+// - covers scancodes 0x00-0x39, with zero marking non-printable keys
+const MAP_PLAIN: &[u8; 0x3a] = b"\
+    \0\x001234567890-=\0\0\
+    qwertyuiop[]\0\0as\
+    dfghjkl;'`\0\\zxcv\
+    bnm,./\0*\0 ";
+const MAP_SHIFT: &[u8; 0x3a] = b"\
+    \0\0!@#$%^&*()_+\0\0\
+    QWERTYUIOP{}\0\0AS\
+    DFGHJKL:\"~\0|ZXCV\
+    BNM<>?\0*\0 ";
+
+/**
+    Polling PS/2 keyboard driver
+
+    # Usage
+    ```rust,no_run
+    # use common::plat::pc_bios::ps2::Ps2Keyboard;
+    # use common::shared::traits::{KeyEvent, KeySource};
+    let mut kbd = unsafe { Ps2Keyboard::new() };
+    if let Some(KeyEvent::Enter) = kbd.poll_key() {
+        // ...
+    }
+    ```
+*/
+#[derive(Debug)]
+pub struct Ps2Keyboard {
+    ports: Ps2Ports,
+    shift: u8,
+    extended: bool,
+}
+
+crate::port_block! {
+    // Internal: registers of the 8042 controller, as seen by readers
+    struct Ps2Ports {
+        data: ReadOnlyPort<u8> = 0,
+        status: ReadOnlyPort<u8> = STATUS_PORT - DATA_PORT,
+    }
+}
+
+impl Ps2Keyboard {
+    /**
+        Create new instance of `Ps2Keyboard`
+
+        # Safety
+        The caller must ensure that no other code (including
+        interrupt handlers) reads from the 8042 controller.
+    */
+    pub const unsafe fn new() -> Self {
+        Ps2Keyboard {
+            ports: unsafe { Ps2Ports::new(DATA_PORT) },
+            shift: 0,
+            extended: false,
+        }
+    }
+
+    /**
+        Decode a single scancode (set 1), updating modifier state
+
+        This is exposed separately from [`poll_key()`], so that
+        interrupt-driven users can share the decoder.
+
+        [`poll_key()`]: KeySource::poll_key
+    */
+    pub fn decode(&mut self, sc: u8) -> Option<KeyEvent> {
+        if sc == SC_EXTENDED {
+            self.extended = true;
+            return None;
+        }
+
+        let extended = self.extended;
+        self.extended = false;
+
+        let released = sc & SC_RELEASE != 0;
+        let code = sc & !SC_RELEASE;
+
+        // - track both shift keys separately
+        let shift_bit = match code {
+            SC_LSHIFT if !extended => 1,
+            SC_RSHIFT if !extended => 2,
+            _ => 0,
+        };
+
+        if shift_bit != 0 {
+            if released {
+                self.shift &= !shift_bit;
+            } else {
+                self.shift |= shift_bit;
+            }
+            return None;
+        }
+
+        if released {
+            return None;
+        }
+
+        if extended {
+            return match code {
+                0x48 => Some(KeyEvent::Up),
+                0x50 => Some(KeyEvent::Down),
+                0x4b => Some(KeyEvent::Left),
+                0x4d => Some(KeyEvent::Right),
+                0x47 => Some(KeyEvent::Home),
+                0x4f => Some(KeyEvent::End),
+                0x53 => Some(KeyEvent::Delete),
+                0x1c => Some(KeyEvent::Enter),
+                _ => None,
+            };
+        }
+
+        match code {
+            0x01 => Some(KeyEvent::Escape),
+            0x0e => Some(KeyEvent::Backspace),
+            0x0f => Some(KeyEvent::Tab),
+            0x1c => Some(KeyEvent::Enter),
+            _ => {
+                let map = if self.shift != 0 {
+                    MAP_SHIFT
+                } else {
+                    MAP_PLAIN
+                };
+                match map.get(code as usize) {
+                    Some(&c) if c != 0 => Some(KeyEvent::Char(c)),
+                    _ => None,
+                }
+            }
+        }
+    }
+}
+
+impl KeySource for Ps2Keyboard {
+    fn poll_key(&mut self) -> Option<KeyEvent> {
+        // - drain the controller until a key event is produced
+        loop {
+            let status = self.ports.status.read();
+            if status & STATUS_OUTPUT_FULL == 0 {
+                return None;
+            }
+
+            let sc = self.ports.data.read();
+
+            // - discard mouse data
+            if status & STATUS_AUX_DATA != 0 {
+                continue;
+            }
+
+            if let Some(ev) = self.decode(sc) {
+                return Some(ev);
+            }
+        }
+    }
+}
@@ -0,0 +1,88 @@
+/*!
+    Module defining QEMU-specific test support
+
+    QEMU's `isa-debug-exit` device makes the emulator exit as soon as
+    a value is written to its port, with the status `(value << 1) | 1`,
+    which lets tests report their outcome to the host. The device
+    must be attached explicitly, as in:
+    ```text
+    qemu-system-x86_64 -device isa-debug-exit,iobase=0xf4,iosize=0x04
+    ```
+
+    The in-OS test runner reports over COM1 (refer to
+    [`shared::testing`](crate::shared::testing) for the format).
+*/
+
+// Definition uses
+use core::panic::PanicInfo;
+
+use super::serial::{COM1_BASE, DEF_BAUD_RATE, SerialPort};
+use crate::arch::__io::WriteOnlyPort;
+use crate::arch::x86::idle::halt_forever;
+use crate::shared::testing::{self, Testable};
+
+/// Port of the `isa-debug-exit` device
+pub const DEBUG_EXIT_PORT: u16 = 0xf4;
+
+/**
+    Exit codes written to the `isa-debug-exit` device
+
+    QEMU exits with the status `(code << 1) | 1`, that is `33` on
+    success and `35` on failure. Both are distinct from the statuses
+    of QEMU's own failures, and from a plain `0`.
+*/
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u32)]
+pub enum QemuExitCode {
+    Success = 0x10,
+    Failed = 0x11,
+}
+
+/**
+    Exit QEMU with the provided exit code
+
+    If the `isa-debug-exit` device isn't attached (or this isn't
+    QEMU at all), the write has no effect, and the system halts.
+*/
+pub fn exit_qemu(code: QemuExitCode) -> ! {
+    // SAFETY: writing to an unclaimed port has no effect
+    let port: WriteOnlyPort<u32> = unsafe { WriteOnlyPort::new(DEBUG_EXIT_PORT) };
+    port.write(code as u32);
+
+    halt_forever()
+}
+
+// Internal: open COM1 for reporting
+// - an absent UART is not an error, as the
+//   exit code still reports the outcome
+fn com1() -> SerialPort {
+    // SAFETY: tests have the machine to themselves
+    let mut com1 = unsafe { SerialPort::new(COM1_BASE) };
+    let _ = com1.init(DEF_BAUD_RATE);
+    com1
+}
+
+/**
+    Run in-OS tests, reporting over COM1, then exit QEMU
+
+    Intended for use as the `#![test_runner]` of a crate.
+*/
+pub fn test_runner(tests: &[&dyn Testable]) {
+    let code = match testing::run_tests(&mut com1(), tests) {
+        Ok(_) => QemuExitCode::Success,
+        Err(_) => QemuExitCode::Failed,
+    };
+
+    exit_qemu(code)
+}
+
+/**
+    Report a failing in-OS test over COM1, then exit QEMU
+
+    Intended for use by the `#[panic_handler]` of test builds.
+*/
+pub fn test_panic(info: &PanicInfo<'_>) -> ! {
+    let _ = testing::report_panic(&mut com1(), info);
+
+    exit_qemu(QemuExitCode::Failed)
+}
@@ -0,0 +1,178 @@
+/*!
+    Module defining a polling 16550 UART driver
+
+    The driver is meant for early-boot and test output, where
+    interrupts are not set up: transmission waits for the holding
+    register to empty, and reception is polled.
+
+    # Usage
+    ```rust,no_run
+    # use common::plat::pc_bios::serial::{COM1_BASE, DEF_BAUD_RATE, SerialPort};
+    # use common::shared::io::Write;
+    # fn main() -> Result<(), common::shared::io::Error> {
+    let mut com1 = unsafe { SerialPort::new(COM1_BASE) };
+    com1.init(DEF_BAUD_RATE)?;
+    writeln!(com1, "hello over the wire")?;
+    # Ok(())
+    # }
+    ```
+*/
+
+// Port I/O definitions
+use crate::arch::__io::{ReadOnlyPort, ReadWritePort, WriteOnlyPort};
+
+// I/O helpers
+use crate::shared::io::{Error, ErrorKind, ErrorPayload, Write};
+
+/// Base port of the first serial port (COM1)
+pub const COM1_BASE: u16 = 0x3f8;
+
+/// Base port of the second serial port (COM2)
+pub const COM2_BASE: u16 = 0x2f8;
+
+/// Default baud rate
+pub const DEF_BAUD_RATE: u32 = 115200;
+
+// Clock of the divisor latch, in Hz
+const UART_CLOCK: u32 = 115200;
+
+// Line control register bits
+const LCR_8N1: u8 = 0x03;
+const LCR_DLAB: u8 = 1 << 7;
+
+// FIFO control: enable and clear both FIFOs, with a 14-byte threshold
+const FCR_ENABLE: u8 = 0xc7;
+
+// Modem control: assert DTR and RTS, and enable OUT2
+const MCR_NORMAL: u8 = 0x0b;
+
+// Line status register bits
+const LSR_DATA_READY: u8 = 1 << 0;
+const LSR_THR_EMPTY: u8 = 1 << 5;
+
+// Value written to the scratch register, to detect the UART
+const SCRATCH_PROBE: u8 = 0xa5;
+
+crate::port_block! {
+    // Internal: registers of a 16550 UART
+    // - the divisor latch overlays `data` and `ier` while `LCR_DLAB` is set
+    struct UartRegs {
+        data: ReadWritePort<u8> = 0,
+        ier: ReadWritePort<u8> = 1,
+        fcr: WriteOnlyPort<u8> = 2,
+        lcr: ReadWritePort<u8> = 3,
+        mcr: ReadWritePort<u8> = 4,
+        lsr: ReadOnlyPort<u8> = 5,
+        scratch: ReadWritePort<u8> = 7,
+    }
+}
+
+/**
+    Polling 16550 UART driver
+
+    Writes translate `\n` to `\r\n`, so that output reads
+    correctly on terminals as well as in logs.
+*/
+#[derive(Debug)]
+pub struct SerialPort {
+    regs: UartRegs,
+}
+
+impl SerialPort {
+    /**
+        Create new instance of `SerialPort` at the port `base`
+
+        # Safety
+        The caller must ensure that `base` is the base port of a
+        16550-compatible UART (or of nothing at all), and that no
+        other code accesses the UART concurrently.
+    */
+    pub const unsafe fn new(base: u16) -> Self {
+        SerialPort {
+            regs: unsafe { UartRegs::new(base) },
+        }
+    }
+
+    /// Returns the base port of the UART
+    pub const fn base(&self) -> u16 {
+        self.regs.base()
+    }
+
+    /**
+        Initialize the UART for polled 8N1 operation at `baud`
+
+        # Errors
+        Returns an error of the kind [`ErrorKind::InvalidInput`] if
+        `baud` can't be derived from the UART clock, and of the kind
+        [`ErrorKind::NotFound`] if no UART answers at the base port.
+    */
+    pub fn init(&mut self, baud: u32) -> Result<(), Error> {
+        let divisor = match UART_CLOCK.checked_div(baud) {
+            Some(d @ 1..=0xffff) => d as u16,
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    ErrorPayload::Message("unsupported baud rate"),
+                ));
+            }
+        };
+
+        // - the scratch register is absent on 8250s, and
+        // floating buses read back all ones
+        self.regs.scratch.write(SCRATCH_PROBE);
+        if self.regs.scratch.read() != SCRATCH_PROBE {
+            return Err(Error::new(
+                ErrorKind::NotFound,
+                ErrorPayload::Message("no UART at the base port"),
+            ));
+        }
+
+        // - disable interrupts, then program the divisor
+        self.regs.ier.write(0x00);
+        self.regs.lcr.write(LCR_DLAB);
+        self.regs.data.write(divisor as u8);
+        self.regs.ier.write((divisor >> 8) as u8);
+        self.regs.lcr.write(LCR_8N1);
+
+        self.regs.fcr.write(FCR_ENABLE);
+        self.regs.mcr.write(MCR_NORMAL);
+
+        Ok(())
+    }
+
+    /// Write a single byte, waiting for the transmitter to be ready
+    pub fn write_byte(&mut self, b: u8) {
+        while self.regs.lsr.read() & LSR_THR_EMPTY == 0 {
+            core::hint::spin_loop();
+        }
+
+        self.regs.data.write(b);
+    }
+
+    /// Read a single byte, if one has been received
+    pub fn read_byte(&mut self) -> Option<u8> {
+        if self.regs.lsr.read() & LSR_DATA_READY != 0 {
+            Some(self.regs.data.read())
+        } else {
+            None
+        }
+    }
+}
+
+impl Write for SerialPort {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        for &b in buf {
+            if b == b'\n' {
+                self.write_byte(b'\r');
+            }
+            self.write_byte(b);
+        }
+
+        Ok(buf.len())
+    }
+
+    // - the transmitter drains on its own
+    fn flush(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+}
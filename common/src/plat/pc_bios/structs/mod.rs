@@ -133,7 +133,8 @@ pub struct BiosPB {
     _sectors: u16,
     _medium_type: u8,
     _sectors_per_fat: u16,
-    _heads: u8,
+    _sectors_per_track: u16,
+    _heads: u16,
     _hidden_sectors: u32,
     _large_sectors: u32,
     _drive_number: u16,
@@ -189,9 +190,14 @@ impl BiosPB {
         unsafe { ptr::read_unaligned(&raw const self._sectors_per_fat) as usize }
     }
 
+    /// Return number of sectors per track
+    pub fn sectors_per_track(&self) -> usize {
+        unsafe { ptr::read_unaligned(&raw const self._sectors_per_track) as usize }
+    }
+
     /// Return number of drive heads
     pub fn heads(&self) -> usize {
-        self._heads as usize
+        unsafe { ptr::read_unaligned(&raw const self._heads) as usize }
     }
 
     /// Return number of hidden sectors
@@ -9,8 +9,10 @@ pub mod fb;
     VGA/VESA screen information
 
     # Usage
-    A constructor will not be provided, as this structure
-    is intended to be instantiated across FFI boundaries.
+    A public constructor will not be provided, as this structure
+    is intended to be instantiated across FFI boundaries. Alternative
+    boot paths within this crate (such as [`multiboot2`]) may still
+    synthesize instances from foreign boot information.
 
     The instantiator of this structure must ensure that
     the fields contain valid values that reflect the
@@ -52,7 +54,10 @@ pub mod fb;
     must be set to zero, as the instantiatior cannot make any
     assumptions about the dimensions of a character cell, should
     the display be divided into character cells.
+
+    [`multiboot2`]: crate::plat::pc_bios::multiboot2
 */
+#[derive(Clone, Copy)]
 #[repr(C)]
 pub struct ScreenInfo {
     _mode: u16,
@@ -71,7 +76,78 @@ pub struct ScreenInfo {
     _frame_buf_high: u16,
 }
 
+/// VGA text mode 3 (80x25 16-color text mode)
+pub const MODE_VGA_TEXT: u16 = 0x0003;
+
+/**
+    Placeholder mode number for linear framebuffers set up by
+    a foreign bootloader, where the VBE mode number is unknown
+
+    The value is greater than `0xff`, so it is still recognized
+    as a VESA graphics mode.
+*/
+pub const MODE_FOREIGN_LFB: u16 = 0xffff;
+
 impl ScreenInfo {
+    // Internal: create an instance describing a text mode
+    pub(crate) const fn new_text(mode: u16, cells_x: u16, cells_y: u16) -> Self {
+        ScreenInfo {
+            _mode: mode,
+            _bytes_per_pixel: 0,
+            _width: 0,
+            _height: 0,
+            _pitch: 0,
+            _bits_per_pixel: 0,
+            _cells_x: cells_x,
+            _cells_y: cells_y,
+            _packed_mask_low: 0,
+            _packed_mask_high: 0,
+            _packed_pos_low: 0,
+            _packed_pos_high: 0,
+            _frame_buf_low: 0,
+            _frame_buf_high: 0,
+        }
+    }
+
+    // Internal: create an instance describing a linear framebuffer
+    // - the framebuffer must reside in 32-bit space
+    // - channel layout is set separately (see `with_channels`)
+    pub(crate) const fn new_graphics(
+        mode: u16,
+        width: u16,
+        height: u16,
+        pitch: u16,
+        bits_per_pixel: u16,
+        frame_buf: u32,
+    ) -> Self {
+        ScreenInfo {
+            _mode: mode,
+            _bytes_per_pixel: bits_per_pixel.div_ceil(8),
+            _width: width,
+            _height: height,
+            _pitch: pitch,
+            _bits_per_pixel: bits_per_pixel,
+            _cells_x: 0,
+            _cells_y: 0,
+            _packed_mask_low: 0,
+            _packed_mask_high: 0,
+            _packed_pos_low: 0,
+            _packed_pos_high: 0,
+            _frame_buf_low: frame_buf as u16,
+            _frame_buf_high: (frame_buf >> 16) as u16,
+        }
+    }
+
+    // Internal: set packed channel mask sizes and positions
+    // - both follow the `AA_RR_GG_BBh` layout
+    pub(crate) const fn with_channels(mut self, packed_mask: u32, packed_pos: u32) -> Self {
+        self._packed_mask_low = packed_mask as u16;
+        self._packed_mask_high = (packed_mask >> 16) as u16;
+        self._packed_pos_low = packed_pos as u16;
+        self._packed_pos_high = (packed_pos >> 16) as u16;
+        self
+    }
+
     /// Returns display mode
     pub fn mode(&self) -> usize {
         self._mode as usize
@@ -158,7 +234,7 @@ impl ScreenInfo {
         The packed value is of the form `AA_RR_GG_BBh`. If one
         is concerned only about the (R,G,B) channels, then
         one can trivially obtain the packed value as follows:
-        ```rust
+        ```rust,ignore
         let rgb_mask: u32 = packed_mask() & 0x00_FF_FF_FF;
 
         /* example usage for 8:8:8 */
@@ -168,7 +244,7 @@ impl ScreenInfo {
         ```
 
         The packed value is provided mostly for convenience:
-        ```rust
+        ```rust,ignore
         match packed_mask() & 0x00_FF_FF_FF {
             0x00_05_06_05 => { /* 5:6:5 path */ },
             _ => { /* XRGB/ARGB path */ },
@@ -186,7 +262,7 @@ impl ScreenInfo {
         The packed value is of the form `AA_RR_GG_BBh`. If one
         is concerned only about the (R,G,B) channels, then
         one can trivially obtain the packed value as follows:
-        ```rust
+        ```rust,ignore
         let rgb_pos: u32 = packed_pos() & 0x00_FF_FF_FF;
 
         /* example usage for 8:8:8 */
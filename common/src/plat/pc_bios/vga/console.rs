@@ -2,9 +2,9 @@
     Module defining a wrapper type for the VGA text console
 */
 
-// Wrapper type that assumes internal mutability,
-// and exposes volatile memory operations
-use crate::shared::structs::volatile::VolatileCell;
+// Handle to the text buffer, which never
+// creates references to video memory
+use crate::shared::structs::mmio::{Mmio, ReadWrite, Reg, Register};
 
 // Fundamental data structures
 use crate::shared::structs::RingBuf;
@@ -12,10 +12,6 @@ use crate::shared::structs::RingBuf;
 // I/O helpers
 use crate::shared::io::{Error, Write};
 
-// Standard library imports
-use core::marker::PhantomData;
-use core::slice::from_raw_parts;
-
 /*
     Constants that are assumed to be the de-facto default
     (assuming VGA mode 3, which is 80x25 colored text mode)
@@ -59,12 +55,12 @@ pub const SIZE_TABULATOR: usize = 4;
     mutability, such as [`Mutex`].
 
     An example applicatios following such advice is as follows:
-    ```rust
+    ```rust,no_run
     use common::shared::io::Write;
-    use common::shared::structs:spin_lock::Mutex;
+    use common::shared::structs::spin_lock::Mutex;
     use common::plat::pc_bios::vga::console::VgaConsole;
 
-    static VGA_CONSOLE: Mutex<VgaConsole> = Mutex::new(VgaConsole::defaults());
+    static VGA_CONSOLE: Mutex<VgaConsole> = Mutex::new(unsafe { VgaConsole::defaults() });
 
     fn hello() {
         let mut handle = VGA_CONSOLE.lock();
@@ -75,13 +71,11 @@ pub const SIZE_TABULATOR: usize = 4;
     [`Mutex`]: crate::shared::structs::spin_lock::Mutex
 */
 
-// - lifetime is made explicit, as we are
-// dealing with raw pointer arithmetic
 // TODO: refine shadow buffering
 // TODO: implement page switching
 #[repr(C)]
 pub struct VgaConsole<'a> {
-    buf: *const VolatileCell<u16>,
+    buf: Mmio<[Register<u16>]>,
     cols: usize,
     rows: usize,
     page: usize,
@@ -92,7 +86,6 @@ pub struct VgaConsole<'a> {
     buffered: bool,
     escaped: bool,
     shadow: Option<RingBuf<'a, u16>>,
-    _marker: PhantomData<&'a VolatileCell<u16>>,
 }
 
 impl<'a> VgaConsole<'a> {
@@ -101,22 +94,20 @@ impl<'a> VgaConsole<'a> {
 
         # Safety
         It is the instantiator's responsibility to ensure that `addr`
-        points to valid video memory, and that the provided dimensions
-        `cols` and `rows`
+        is the identity-mapped address of valid video memory, and that
+        the provided dimensions `cols` and `rows`
         - are correct for the current video mode, and
         - if page-switching is reported, `addr + 2 * N * cols * rows`
         does not exceed valid video memory
     */
-    pub const unsafe fn new(buf: *const VolatileCell<u16>, cols: usize, rows: usize) -> Self {
+    pub const unsafe fn new(addr: usize, cols: usize, rows: usize) -> Self {
         // Set address and dimensions, and then
         // never touch them again...
         // (currently configured to work like a typewriter)
-        // - using `&'a VolatileCell<u16>` is unfortunately
-        // UB, as it has no provenance ...which means that
-        // we're operationg at the very edge of UB, which
-        // is not fun
+        // - the buffer is only ever accessed one cell
+        // at a time, through raw pointers
         VgaConsole {
-            buf,
+            buf: unsafe { Mmio::identity_slice(addr, MAX_PAGE * cols * rows) },
             cols,
             rows,
             page: 0,
@@ -127,7 +118,6 @@ impl<'a> VgaConsole<'a> {
             buffered: false,
             escaped: false,
             shadow: None,
-            _marker: PhantomData,
         }
     }
 
@@ -141,7 +131,7 @@ impl<'a> VgaConsole<'a> {
         - VGA video mode is set to mode `0x03` (80x25 text mode)
     */
     pub const unsafe fn defaults() -> Self {
-        unsafe { Self::new(DEF_BUF_ADDR as *const _, DEF_NUM_COLS, DEF_NUM_ROWS) }
+        unsafe { Self::new(DEF_BUF_ADDR, DEF_NUM_COLS, DEF_NUM_ROWS) }
     }
 
     /**
@@ -153,6 +143,10 @@ impl<'a> VgaConsole<'a> {
         display mode.
     */
     pub unsafe fn set_dims(&mut self, cols: usize, rows: usize) {
+        // - resize the window into video memory along with the page
+        let addr = self.buf.phys();
+        self.buf = unsafe { Mmio::identity_slice(addr, MAX_PAGE * cols * rows) };
+
         self.cols = cols;
         self.rows = rows;
     }
@@ -223,6 +217,27 @@ impl<'a> VgaConsole<'a> {
         self.y = y.min(self.rows - 1);
     }
 
+    /// Get number of columns
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    /// Get number of rows
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Get character attribute (in the upper byte)
+    pub fn attr(&self) -> u16 {
+        self.attr
+    }
+
+    /// Set character attribute (in the upper byte)
+    // - the lower byte is ignored
+    pub fn set_attr(&mut self, attr: u16) {
+        self.attr = attr & 0xff00;
+    }
+
     /// Get truncation mode
     pub fn get_trunc(&self) -> bool {
         self.trunc
@@ -248,53 +263,28 @@ impl<'a> VgaConsole<'a> {
         Ok(())
     }
 
-    // Internal: get a linear reference to a page in the text buffer
-    // SAFETY:
-    // We can't possibly guarantee that `page' won't exceed bounds,
-    // It is the instantiator's responsibility to make sure that
-    // `self.addr' is not equal to zero, and that it points to a
-    // valid area in video memory.
-    #[inline(always)]
-    unsafe fn buf_get_ref(&self, page: usize) -> &[VolatileCell<u16>] {
-        // - calculate number of cells per page
-        let n = self.cols * self.rows;
-
-        // - `pointer::add` antics are counter-intuitive, innit?
-        unsafe { from_raw_parts(self.buf.add(page * n), n) }
-    }
-
-    // Internal: get a reference to a specific
-    // line within the current page
-    //
-    // The provided line number will be forcibly clamped
-    // to one less its maximum value, as defined in `rows'
-    //
-    // SAFETY: (see `buf_get_ref')
-    #[inline(always)]
-    unsafe fn line_get_ref(&self, page: usize, y: usize) -> &[VolatileCell<u16>] {
-        let buf_ref = unsafe { self.buf_get_ref(page) };
-        let z = y.min(self.rows - 1);
-        let m = self.cols * z;
-        let n = self.cols * (z + 1);
-
-        // Return a constant range
-        &buf_ref[m..n]
-    }
-
-    // Internal: get a reference to a specific
-    // character cell within the provided page
+    // Internal: get a specific character cell
+    // within the provided page
     //
     // The provided coordinates will be forcibly clamped to one
     // less their maximum values, as defined in `cols' and `rows'
     //
-    // SAFETY: (see `buf_get_ref')
+    // Returns `None` if `page' exceeds the text buffer
     #[inline(always)]
-    unsafe fn char_get_ref(&self, page: usize, x: usize, y: usize) -> &VolatileCell<u16> {
-        let line_ref = unsafe { self.line_get_ref(page, y) };
+    fn cell(&self, page: usize, x: usize, y: usize) -> Option<Reg<'_, u16, ReadWrite>> {
         let u = x.min(self.cols - 1);
+        let v = y.min(self.rows - 1);
+
+        self.buf.get(self.cols * (page * self.rows + v) + u)
+    }
 
-        // Return constant reference
-        &line_ref[u]
+    // Internal: write to a specific character cell
+    // - writes beyond the text buffer are dropped
+    #[inline(always)]
+    fn cell_store(&self, page: usize, x: usize, y: usize, c: u16) {
+        if let Some(cell) = self.cell(page, x, y) {
+            cell.write(c);
+        }
     }
 
     // Internal: scroll the current page by a specific amount
@@ -337,20 +327,17 @@ impl<'a> VgaConsole<'a> {
     fn scroll_in_place(&mut self, m: usize) {
         // Perform manual `memmove` on the text buffer
         for r in 0..(self.rows - m) {
-            let dest = unsafe { self.line_get_ref(self.page, r) };
-            let src = unsafe { self.line_get_ref(self.page, r + m) };
-
             for c in 0..self.cols {
-                dest[c].store(src[c].load());
+                if let Some(src) = self.cell(self.page, c, r + m) {
+                    self.cell_store(self.page, c, r, src.read());
+                }
             }
         }
 
         // Clear the bottom `m` lines
         for r in 0..m {
-            let line = unsafe { self.line_get_ref(self.page, self.rows - m + r) };
-
             for c in 0..self.cols {
-                line[c].store(self.attr | CHR_SPACE);
+                self.cell_store(self.page, c, self.rows - m + r, self.attr | CHR_SPACE);
             }
         }
     }
@@ -467,9 +454,7 @@ impl<'a> VgaConsole<'a> {
             shadow[y][x] = c;
         } else {
             // - perform in-place write
-            unsafe {
-                self.char_get_ref(self.page, x, y).store(c);
-            }
+            self.cell_store(self.page, x, y, c);
         }
 
         // - update `x'
@@ -494,13 +479,11 @@ impl<'a> VgaConsole<'a> {
             // - copy rows by iterating over them, then
             // copying each cell (column-indexed)
             for r in 0..self.rows {
-                // - calculate references to lines, so as to save cycles
-                let buf_line = unsafe { self.line_get_ref(self.page, r) };
                 let shadow_line = &shadow[r];
 
                 for c in 0..self.cols {
                     // - perform volatile write to the text buffer
-                    buf_line[c].store(shadow_line[c]);
+                    self.cell_store(self.page, c, r, shadow_line[c]);
                 }
             }
         }
@@ -545,3 +528,123 @@ impl Write for VgaConsole<'_> {
 // - YOLO!
 unsafe impl Sync for VgaConsole<'_> {}
 unsafe impl Send for VgaConsole<'_> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COLS: usize = 8;
+    const ROWS: usize = 3;
+
+    // Internal: in-memory stand-in for video memory
+    struct TextBuf([u16; COLS * ROWS]);
+
+    impl TextBuf {
+        fn new() -> Self {
+            TextBuf([DEF_ATTR | CHR_SPACE; COLS * ROWS])
+        }
+
+        // - the console only ever reaches the buffer
+        // through the address, like video memory
+        fn console(&mut self) -> VgaConsole<'static> {
+            unsafe { VgaConsole::new(self.0.as_mut_ptr() as usize, COLS, ROWS) }
+        }
+
+        // Returns the characters of row `y`, without attributes
+        fn row(&self, y: usize) -> [u8; COLS] {
+            let mut r = [0; COLS];
+            for (c, &cell) in r.iter_mut().zip(&self.0[y * COLS..(y + 1) * COLS]) {
+                *c = cell as u8;
+            }
+            r
+        }
+    }
+
+    #[test]
+    fn typewriter() {
+        let mut mem = TextBuf::new();
+        let mut con = mem.console();
+
+        // - output starts at the bottom row
+        con.write_all(b"ab").unwrap();
+        con.set_attr(0x1f42);
+        con.write_all(b"c").unwrap();
+
+        assert_eq!(&mem.row(2), b"abc     ");
+        assert_eq!(mem.0[2 * COLS + 2], 0x1f00 | b'c' as u16);
+    }
+
+    #[test]
+    fn scroll_and_wrap() {
+        let mut mem = TextBuf::new();
+        let mut con = mem.console();
+
+        con.write_all(b"one\ntwo\n").unwrap();
+        // - the line wraps once the last column is written
+        con.write_all(b"0123456789").unwrap();
+
+        assert_eq!(&mem.row(0), b"two     ");
+        assert_eq!(&mem.row(1), b"01234567");
+        assert_eq!(&mem.row(2), b"89      ");
+    }
+
+    #[test]
+    fn tabs_and_returns() {
+        let mut mem = TextBuf::new();
+        let mut con = mem.console();
+
+        // - tabs pad to the next multiple of `SIZE_TABULATOR`
+        con.write_all(b"ab\tc").unwrap();
+        con.write_all(b"\rz").unwrap();
+        assert_eq!((con.x, con.y), (1, ROWS - 1));
+        assert_eq!(&mem.row(2), b"zb  c   ");
+
+        // - tabs write spaces, and wrap the line
+        // once they reach the last column
+        con.write_all(b"\t\td").unwrap();
+
+        assert_eq!(&mem.row(1), b"z       ");
+        assert_eq!(&mem.row(2), b"d       ");
+    }
+
+    #[test]
+    fn truncation() {
+        let mut mem = TextBuf::new();
+        let mut con = mem.console();
+
+        // - only the last screenful is written
+        let n = con.write(b"abcdefghijklmnopqrstuvwxyz").unwrap();
+        assert_eq!(n, COLS * ROWS);
+
+        con.set_trunc(false);
+        assert_eq!(con.write(b"AB").unwrap(), 2);
+
+        // - each full row wraps, scrolling "ab" off the screen
+        assert_eq!(&mem.row(0), b"klmnopqr");
+        assert_eq!(&mem.row(1), b"stuvwxyz");
+        assert_eq!(&mem.row(2), b"AB      ");
+    }
+
+    #[test]
+    fn shadowed() {
+        let mut mem = TextBuf::new();
+        let mut shadow = [0u16; COLS * ROWS];
+        let mut con = mem.console();
+
+        assert!(con.try_set_shadowed().is_err());
+        con.init(&mut shadow);
+        assert!(con.is_shadowed());
+
+        // - nothing reaches the buffer until flushed
+        con.write_all(b"hi\nthere").unwrap();
+        let before = unsafe { con.buf.get(2 * COLS).unwrap().as_ptr().read() };
+        assert_eq!(before, DEF_ATTR | CHR_SPACE);
+
+        con.flush().unwrap();
+
+        // - the shadow buffer isn't synchronized with the text
+        // buffer, so only the written cells are compared
+        assert_eq!(&mem.row(1)[..2], b"hi");
+        assert_eq!(&mem.row(2)[..5], b"there");
+    }
+}
@@ -0,0 +1,699 @@
+/*!
+    Allocation-free command-line tokenizer
+
+    Command lines are sequences of whitespace-separated parameters,
+    which are either bare flags (`quiet`) or key-value pairs
+    (`loglevel=debug`). Keys may be repeated; the getters return the
+    last occurrence, whereas [`Cmdline::get_all()`] returns all of them.
+    ```text
+    console=serial,115200 loglevel=debug mem=64M root="disk 0" quiet
+    ```
+
+    # Quoting and escapes
+    - Double quotes group characters, including whitespace and `=`,
+      and can appear anywhere within a parameter (`key="a b"c`).
+    - A backslash makes the next character literal, both inside
+      and outside of quotes (`\"`, `\\`, `\ `).
+
+    Neither the quotes nor the backslashes are part of the resulting
+    keys and values. As the tokenizer does not allocate, keys and
+    values are handed out as [`Value`]s, which refer to the raw
+    source text and unescape it on demand.
+
+    # Usage
+    ```rust
+    # use common::shared::cmdline::{Cmdline, CmdlineError};
+    # fn main() -> Result<(), CmdlineError> {
+    let cmdline = Cmdline::parse("console=serial,115200 mem=64M quiet")?;
+
+    assert_eq!(cmdline.get_int("mem")?, Some(64 << 20));
+    assert_eq!(cmdline.get_bool("quiet")?, Some(true));
+
+    for param in cmdline.unknown(&["console", "mem", "quiet"]) {
+        // report `param.key()`
+    #   let _ = param;
+    }
+    # Ok(())
+    # }
+    ```
+*/
+
+// Standard definitions
+use core::fmt;
+use core::str;
+
+/// Kinds of errors reported by the command-line tokenizer
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum CmdlineErrorKind {
+    /// A quoted section lacks a closing quote
+    UnterminatedQuote,
+
+    /// A backslash ends the command line
+    TrailingEscape,
+
+    /// A value could not be interpreted as a boolean
+    InvalidBool,
+
+    /// A value could not be interpreted as an integer
+    InvalidInt,
+
+    /// An integer value (including its suffix) does not fit
+    IntOverflow,
+
+    /// A provided buffer is too small for the unescaped value
+    BufferTooSmall,
+}
+
+impl CmdlineErrorKind {
+    /// Returns a human-readable description
+    pub fn message(&self) -> &'static str {
+        match self {
+            CmdlineErrorKind::UnterminatedQuote => "missing closing '\"'",
+            CmdlineErrorKind::TrailingEscape => "trailing '\\'",
+            CmdlineErrorKind::InvalidBool => "expected a boolean",
+            CmdlineErrorKind::InvalidInt => "expected an integer",
+            CmdlineErrorKind::IntOverflow => "integer out of range",
+            CmdlineErrorKind::BufferTooSmall => "buffer too small",
+        }
+    }
+}
+
+/**
+    Error reported by the command-line tokenizer
+
+    The position is the byte offset of the offending character
+    (for syntax errors) or value (for conversion errors).
+*/
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct CmdlineError {
+    pos: usize,
+    kind: CmdlineErrorKind,
+}
+
+impl CmdlineError {
+    /// Returns the byte offset within the command line
+    pub fn pos(&self) -> usize {
+        self.pos
+    }
+
+    /// Returns the error kind
+    pub fn kind(&self) -> CmdlineErrorKind {
+        self.kind
+    }
+}
+
+impl fmt::Display for CmdlineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "offset {}: {}", self.pos, self.kind.message())
+    }
+}
+
+/**
+    Raw key or value within a command line
+
+    A `Value` refers to the source text, quotes and backslashes
+    included. Use [`chars()`] or [`unescape_into()`] to obtain
+    the actual contents, or [`as_str()`] if no unescaping is needed.
+
+    [`chars()`]: Self::chars
+    [`unescape_into()`]: Self::unescape_into
+    [`as_str()`]: Self::as_str
+*/
+#[derive(Copy, Clone, Debug)]
+pub struct Value<'a> {
+    raw: &'a str,
+    pos: usize,
+}
+
+impl<'a> Value<'a> {
+    /// Returns the raw source text
+    pub fn raw(&self) -> &'a str {
+        self.raw
+    }
+
+    /// Returns the byte offset within the command line
+    pub fn pos(&self) -> usize {
+        self.pos
+    }
+
+    /// Checks whether the raw text contains quotes or escapes
+    pub fn is_plain(&self) -> bool {
+        !self.raw.contains(['"', '\\'])
+    }
+
+    /// Returns the contents without copying, if they are plain
+    pub fn as_str(&self) -> Option<&'a str> {
+        self.is_plain().then_some(self.raw)
+    }
+
+    /// Checks whether the contents are empty
+    pub fn is_empty(&self) -> bool {
+        self.chars().next().is_none()
+    }
+
+    /// Returns an iterator over the unescaped characters
+    pub fn chars(&self) -> Unescape<'a> {
+        Unescape {
+            inner: self.raw.chars(),
+        }
+    }
+
+    /// Unescape the contents into `buf`, returning the written part
+    pub fn unescape_into<'b>(&self, buf: &'b mut [u8]) -> Result<&'b str, CmdlineError> {
+        let mut n = 0;
+
+        for c in self.chars() {
+            let len = c.len_utf8();
+            if n + len > buf.len() {
+                return Err(CmdlineError {
+                    pos: self.pos,
+                    kind: CmdlineErrorKind::BufferTooSmall,
+                });
+            }
+
+            c.encode_utf8(&mut buf[n..]);
+            n += len;
+        }
+
+        // - only whole characters were encoded
+        Ok(str::from_utf8(&buf[..n]).unwrap_or_default())
+    }
+
+    /// Checks whether the contents are equal to `s`
+    pub fn eq_str(&self, s: &str) -> bool {
+        self.chars().eq(s.chars())
+    }
+
+    /// Checks whether the contents are equal to `s`, ignoring ASCII case
+    pub fn eq_ignore_ascii_case(&self, s: &str) -> bool {
+        self.chars()
+            .map(|c| c.to_ascii_lowercase())
+            .eq(s.chars().map(|c| c.to_ascii_lowercase()))
+    }
+
+    /**
+        Interpret the contents as a boolean
+
+        Accepts `1`, `y`, `yes`, `on` and `true`, as well as
+        `0`, `n`, `no`, `off` and `false` (ignoring case).
+    */
+    pub fn to_bool(&self) -> Result<bool, CmdlineError> {
+        const TRUE: [&str; 5] = ["1", "y", "yes", "on", "true"];
+        const FALSE: [&str; 5] = ["0", "n", "no", "off", "false"];
+
+        if TRUE.iter().any(|s| self.eq_ignore_ascii_case(s)) {
+            Ok(true)
+        } else if FALSE.iter().any(|s| self.eq_ignore_ascii_case(s)) {
+            Ok(false)
+        } else {
+            Err(self.error(CmdlineErrorKind::InvalidBool))
+        }
+    }
+
+    /**
+        Interpret the contents as an unsigned integer
+
+        Accepts decimal, hexadecimal (`0x`), octal (`0o`) and binary
+        (`0b`) numbers, with optional `_` separators and an optional
+        binary size suffix: `K`, `M`, `G` or `T` (ignoring case).
+    */
+    pub fn to_int(&self) -> Result<u64, CmdlineError> {
+        let mut buf = [0u8; 64];
+        let s = self
+            .unescape_into(&mut buf)
+            .map_err(|_| self.error(CmdlineErrorKind::InvalidInt))?;
+
+        // - split off the size suffix
+        let (digits, shift) = match s.as_bytes().last().map(u8::to_ascii_uppercase) {
+            Some(b'K') => (&s[..s.len() - 1], 10),
+            Some(b'M') => (&s[..s.len() - 1], 20),
+            Some(b'G') => (&s[..s.len() - 1], 30),
+            Some(b'T') => (&s[..s.len() - 1], 40),
+            _ => (s, 0),
+        };
+
+        // - then the radix prefix
+        let (digits, radix) = match digits.get(..2) {
+            Some("0x" | "0X") => (&digits[2..], 16),
+            Some("0o" | "0O") => (&digits[2..], 8),
+            Some("0b" | "0B") => (&digits[2..], 2),
+            _ => (digits, 10),
+        };
+
+        if digits.is_empty() || digits.starts_with('_') {
+            return Err(self.error(CmdlineErrorKind::InvalidInt));
+        }
+
+        let mut n: u64 = 0;
+        for c in digits.chars().filter(|&c| c != '_') {
+            let d = c
+                .to_digit(radix)
+                .ok_or(self.error(CmdlineErrorKind::InvalidInt))?;
+
+            n = n
+                .checked_mul(radix as u64)
+                .and_then(|n| n.checked_add(d as u64))
+                .ok_or(self.error(CmdlineErrorKind::IntOverflow))?;
+        }
+
+        // - check that no bits are shifted out
+        let m = n << shift;
+        if m >> shift != n {
+            return Err(self.error(CmdlineErrorKind::IntOverflow));
+        }
+
+        Ok(m)
+    }
+
+    // Generate an error at the start of this value
+    fn error(&self, kind: CmdlineErrorKind) -> CmdlineError {
+        CmdlineError {
+            pos: self.pos,
+            kind,
+        }
+    }
+}
+
+impl PartialEq<str> for Value<'_> {
+    fn eq(&self, other: &str) -> bool {
+        self.eq_str(other)
+    }
+}
+
+impl PartialEq<&str> for Value<'_> {
+    fn eq(&self, other: &&str) -> bool {
+        self.eq_str(other)
+    }
+}
+
+impl fmt::Display for Value<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use fmt::Write;
+
+        for c in self.chars() {
+            f.write_char(c)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Iterator over the unescaped characters of a [`Value`]
+#[derive(Clone, Debug)]
+pub struct Unescape<'a> {
+    inner: str::Chars<'a>,
+}
+
+impl Iterator for Unescape<'_> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        loop {
+            match self.inner.next()? {
+                '"' => continue,
+                '\\' => return self.inner.next(),
+                c => return Some(c),
+            }
+        }
+    }
+}
+
+/// Single parameter: either a bare flag or a key-value pair
+#[derive(Copy, Clone, Debug)]
+pub struct Param<'a> {
+    key: Value<'a>,
+    value: Option<Value<'a>>,
+}
+
+impl<'a> Param<'a> {
+    /// Returns the key
+    pub fn key(&self) -> Value<'a> {
+        self.key
+    }
+
+    /// Returns the value, unless the parameter is a bare flag
+    pub fn value(&self) -> Option<Value<'a>> {
+        self.value
+    }
+
+    /// Checks whether the parameter is a bare flag
+    pub fn is_flag(&self) -> bool {
+        self.value.is_none()
+    }
+}
+
+/**
+    Validated command line
+
+    Parsing only checks the syntax; keys and values are
+    interpreted when they are looked up.
+*/
+#[derive(Copy, Clone, Debug)]
+pub struct Cmdline<'a> {
+    src: &'a str,
+}
+
+impl<'a> Cmdline<'a> {
+    /// Validate the provided command line
+    pub fn parse(src: &'a str) -> Result<Self, CmdlineError> {
+        let mut quote = None;
+        let mut chars = src.char_indices();
+
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' if quote.is_some() => quote = None,
+                '"' => quote = Some(i),
+                '\\' if chars.next().is_none() => {
+                    return Err(CmdlineError {
+                        pos: i,
+                        kind: CmdlineErrorKind::TrailingEscape,
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        match quote {
+            Some(pos) => Err(CmdlineError {
+                pos,
+                kind: CmdlineErrorKind::UnterminatedQuote,
+            }),
+            None => Ok(Cmdline { src }),
+        }
+    }
+
+    /// Returns the source text
+    pub fn as_str(&self) -> &'a str {
+        self.src
+    }
+
+    /// Returns an iterator over all parameters
+    pub fn params(&self) -> Params<'a> {
+        Params {
+            src: self.src,
+            pos: 0,
+        }
+    }
+
+    /// Returns an iterator over all parameters with key `key`
+    pub fn get_all<'k>(&self, key: &'k str) -> impl Iterator<Item = Param<'a>> + 'k
+    where
+        'a: 'k,
+    {
+        self.params().filter(move |p| p.key().eq_str(key))
+    }
+
+    /// Returns the last parameter with key `key`
+    pub fn get(&self, key: &str) -> Option<Param<'a>> {
+        self.get_all(key).last()
+    }
+
+    /// Checks whether a parameter with key `key` is present
+    pub fn contains(&self, key: &str) -> bool {
+        self.get_all(key).next().is_some()
+    }
+
+    /**
+        Returns the last value for key `key`
+
+        Bare flags are reported as `None`, as they have no value.
+    */
+    pub fn get_str(&self, key: &str) -> Option<Value<'a>> {
+        self.get(key).and_then(|p| p.value())
+    }
+
+    /**
+        Returns the last value for key `key` as a boolean
+
+        A bare flag stands for `true`.
+    */
+    pub fn get_bool(&self, key: &str) -> Result<Option<bool>, CmdlineError> {
+        match self.get(key) {
+            None => Ok(None),
+            Some(Param { value: None, .. }) => Ok(Some(true)),
+            Some(Param { value: Some(v), .. }) => v.to_bool().map(Some),
+        }
+    }
+
+    /**
+        Returns the last value for key `key` as an integer
+
+        Refer to [`Value::to_int()`] for accepted formats. A bare
+        flag is reported as an invalid integer.
+    */
+    pub fn get_int(&self, key: &str) -> Result<Option<u64>, CmdlineError> {
+        match self.get(key) {
+            None => Ok(None),
+            Some(Param { value: None, key }) => Err(key.error(CmdlineErrorKind::InvalidInt)),
+            Some(Param { value: Some(v), .. }) => v.to_int().map(Some),
+        }
+    }
+
+    /// Returns an iterator over parameters whose keys are not in `known`
+    pub fn unknown<'k>(&self, known: &'k [&'k str]) -> impl Iterator<Item = Param<'a>> + 'k
+    where
+        'a: 'k,
+    {
+        self.params()
+            .filter(move |p| !known.iter().any(|k| p.key().eq_str(k)))
+    }
+}
+
+/// Iterator over the parameters of a [`Cmdline`]
+#[derive(Clone, Debug)]
+pub struct Params<'a> {
+    src: &'a str,
+    pos: usize,
+}
+
+impl<'a> Iterator for Params<'a> {
+    type Item = Param<'a>;
+
+    fn next(&mut self) -> Option<Param<'a>> {
+        let src = self.src;
+
+        // - skip leading whitespace
+        let rest = &src[self.pos..];
+        let start = self.pos + (rest.len() - rest.trim_start().len());
+        if start == src.len() {
+            self.pos = start;
+            return None;
+        }
+
+        let mut chars = src[start..].char_indices();
+        let mut quoted = false;
+        let mut eq = None;
+        let mut end = src.len();
+
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => quoted = !quoted,
+                '\\' => {
+                    chars.next();
+                }
+                '=' if !quoted && eq.is_none() => eq = Some(start + i),
+                c if !quoted && c.is_whitespace() => {
+                    end = start + i;
+                    break;
+                }
+                _ => {}
+            }
+        }
+
+        self.pos = end;
+
+        Some(Param {
+            key: Value {
+                raw: &src[start..eq.unwrap_or(end)],
+                pos: start,
+            },
+            value: eq.map(|e| Value {
+                raw: &src[e + 1..end],
+                pos: e + 1,
+            }),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use std::format;
+    use std::string::{String, ToString};
+    use std::vec::Vec;
+
+    // Internal: parse `src`, then collect (key, value) pairs
+    fn pairs(src: &str) -> Vec<(String, Option<String>)> {
+        Cmdline::parse(src)
+            .unwrap()
+            .params()
+            .map(|p| (p.key().to_string(), p.value().map(|v| v.to_string())))
+            .collect()
+    }
+
+    // Internal: interpret `s` as the value of `n`
+    fn int(s: &str) -> Result<u64, CmdlineErrorKind> {
+        let src = format!("n={}", s);
+        let cmdline = Cmdline::parse(&src).unwrap();
+        cmdline
+            .get_int("n")
+            .map(Option::unwrap)
+            .map_err(|e| e.kind())
+    }
+
+    #[test]
+    fn flags_and_pairs() {
+        assert_eq!(
+            pairs("  console=serial,115200\tquiet  mem=64M a=b=c "),
+            [
+                ("console".into(), Some("serial,115200".into())),
+                ("quiet".into(), None),
+                ("mem".into(), Some("64M".into())),
+                ("a".into(), Some("b=c".into())),
+            ]
+        );
+        assert!(pairs("").is_empty());
+        assert!(pairs(" \t ").is_empty());
+        assert_eq!(pairs("k="), [("k".into(), Some("".into()))]);
+    }
+
+    #[test]
+    fn quoting_and_escapes() {
+        assert_eq!(
+            pairs(r#"root="disk 0" "a b"=c key="x"y\"z"#),
+            [
+                ("root".into(), Some("disk 0".into())),
+                ("a b".into(), Some("c".into())),
+                ("key".into(), Some("xy\"z".into())),
+            ]
+        );
+        assert_eq!(
+            pairs(r#"a\ b=c\\ d="\"=\"""#),
+            [
+                ("a b".into(), Some("c\\".into())),
+                ("d".into(), Some("\"=\"".into())),
+            ]
+        );
+
+        let cmdline = Cmdline::parse(r#"plain=abc quoted="a b""#).unwrap();
+        let plain = cmdline.get_str("plain").unwrap();
+        assert_eq!((plain.as_str(), plain.pos()), (Some("abc"), 6));
+
+        let quoted = cmdline.get_str("quoted").unwrap();
+        assert_eq!((quoted.raw(), quoted.as_str()), ("\"a b\"", None));
+        assert_eq!(quoted, "a b");
+
+        let mut buf = [0u8; 3];
+        assert_eq!(quoted.unescape_into(&mut buf), Ok("a b"));
+        let err = quoted.unescape_into(&mut buf[..2]).unwrap_err();
+        assert_eq!(err.kind(), CmdlineErrorKind::BufferTooSmall);
+    }
+
+    #[test]
+    fn malformed_syntax() {
+        let err = Cmdline::parse(r#"a=1 b="open c=2"#).unwrap_err();
+        assert_eq!(
+            (err.pos(), err.kind()),
+            (6, CmdlineErrorKind::UnterminatedQuote)
+        );
+        assert_eq!(err.to_string(), "offset 6: missing closing '\"'");
+
+        let err = Cmdline::parse(r#"a="x" b=""""#).unwrap_err();
+        assert_eq!(
+            (err.pos(), err.kind()),
+            (10, CmdlineErrorKind::UnterminatedQuote)
+        );
+
+        let err = Cmdline::parse("a=b\\").unwrap_err();
+        assert_eq!(
+            (err.pos(), err.kind()),
+            (3, CmdlineErrorKind::TrailingEscape)
+        );
+
+        // - escaped quotes don't open quoted sections
+        assert!(Cmdline::parse(r#"a=\" b=\\"#).is_ok());
+    }
+
+    #[test]
+    fn repeated_keys() {
+        let cmdline = Cmdline::parse("log=a quiet log=b log").unwrap();
+
+        let all: Vec<_> = cmdline
+            .get_all("log")
+            .map(|p| p.value().map(|v| v.raw()))
+            .collect();
+        assert_eq!(all, [Some("a"), Some("b"), None]);
+
+        // - the last occurrence wins, even if it is a bare flag
+        assert!(cmdline.get("log").unwrap().is_flag());
+        assert!(cmdline.get_str("log").is_none());
+        assert!(cmdline.contains("quiet"));
+        assert!(!cmdline.contains("loud"));
+
+        let unknown: Vec<_> = cmdline.unknown(&["log"]).map(|p| p.key().raw()).collect();
+        assert_eq!(unknown, ["quiet"]);
+    }
+
+    #[test]
+    fn booleans() {
+        let cmdline = Cmdline::parse("a b=YES c=off d=2").unwrap();
+
+        assert_eq!(cmdline.get_bool("a"), Ok(Some(true)));
+        assert_eq!(cmdline.get_bool("b"), Ok(Some(true)));
+        assert_eq!(cmdline.get_bool("c"), Ok(Some(false)));
+        assert_eq!(cmdline.get_bool("e"), Ok(None));
+
+        let err = cmdline.get_bool("d").unwrap_err();
+        assert_eq!((err.pos(), err.kind()), (16, CmdlineErrorKind::InvalidBool));
+    }
+
+    #[test]
+    fn integers() {
+        assert_eq!(int("0"), Ok(0));
+        assert_eq!(int("1_000"), Ok(1000));
+        assert_eq!(int("0x1F"), Ok(0x1f));
+        assert_eq!(int("0o17"), Ok(0o17));
+        assert_eq!(int("0b101"), Ok(0b101));
+        assert_eq!(int("\"4\"k"), Ok(4 << 10));
+        assert_eq!(int("64M"), Ok(64 << 20));
+        assert_eq!(int("2g"), Ok(2 << 30));
+        assert_eq!(int("0x10T"), Ok(16 << 40));
+        assert_eq!(int("18446744073709551615"), Ok(u64::MAX));
+
+        for s in ["", "K", "0x", "_1", "0x_1", "12a", "-1", "1KB", "0b2"] {
+            assert_eq!(int(s), Err(CmdlineErrorKind::InvalidInt), "{:?}", s);
+        }
+
+        let cmdline = Cmdline::parse("flag").unwrap();
+        let err = cmdline.get_int("flag").unwrap_err();
+        assert_eq!((err.pos(), err.kind()), (0, CmdlineErrorKind::InvalidInt));
+    }
+
+    #[test]
+    fn integer_overflow() {
+        assert_eq!(
+            int("18446744073709551616"),
+            Err(CmdlineErrorKind::IntOverflow)
+        );
+        assert_eq!(
+            int("0x1_0000_0000_0000_0000"),
+            Err(CmdlineErrorKind::IntOverflow)
+        );
+
+        // - the suffix must not shift bits out
+        assert_eq!(int("16777215T"), Ok(0xff_ffff << 40));
+        assert_eq!(int("16777216T"), Err(CmdlineErrorKind::IntOverflow));
+        assert_eq!(int("17179869184G"), Err(CmdlineErrorKind::IntOverflow));
+        assert_eq!(
+            int("0x8000_0000_0000_0000K"),
+            Err(CmdlineErrorKind::IntOverflow)
+        );
+
+        // - values longer than the conversion buffer are rejected
+        let long = "1".repeat(65);
+        assert_eq!(int(&long), Err(CmdlineErrorKind::InvalidInt));
+    }
+}
@@ -0,0 +1,465 @@
+/*!
+    Formatting and context for [`GenericError`]
+
+    Errors can't own dynamically-built messages in `no_std`, so
+    context is attached as a short chain of `&'static str` frames,
+    each naming the operation that failed. The innermost error is
+    kept as an [`io::Error`], which has room for both a kind and a
+    payload.
+
+    Frames are interned in a static table, so that an error only
+    carries one byte per frame, and [`GenericError`] stays small
+    enough to be returned by value everywhere.
+
+    # Usage
+    ```rust,ignore
+    ALLOCATOR.init(e820_map, 0, &BOOT_IMAGE_LAYOUT).context("allocator init")?;
+
+    // ... which is reported as:
+    // "allocator init: no suitable region for allocator base was found"
+    ```
+*/
+
+// Definition uses
+use super::GenericError;
+use super::io::{self, ErrorKind, ErrorPayload};
+use super::structs::once::Once;
+use core::fmt;
+
+/// Maximum number of context frames an error can carry
+pub const CONTEXT_DEPTH: usize = 4;
+
+/// Maximum number of distinct context frames, across all errors
+pub const MAX_FRAMES: usize = 64;
+
+// Interned context frames
+// - frames are `&'static str`, so slots are never freed
+static FRAMES: [Once<&'static str>; MAX_FRAMES] = [const { Once::new() }; MAX_FRAMES];
+
+// Index of frames that didn't fit in the table
+const FRAME_LOST: u8 = u8::MAX;
+
+// Internal: returns the index of `frame`, interning it if needed
+fn intern(frame: &'static str) -> u8 {
+    // - slots fill up in order, so the first slot that isn't
+    //   already holding another frame either holds or takes this one
+    for (i, slot) in FRAMES.iter().enumerate() {
+        if *slot.call_once(|| frame) == frame {
+            return i as u8;
+        }
+    }
+
+    FRAME_LOST
+}
+
+// Internal: returns the frame at `index`
+fn frame(index: u8) -> &'static str {
+    match FRAMES.get(index as usize).and_then(Once::get) {
+        Some(frame) => frame,
+        None => "?",
+    }
+}
+
+/**
+    An error along with the operations it bubbled up through
+
+    Frames are recorded innermost first. Once [`CONTEXT_DEPTH`]
+    frames are recorded, further (outer) frames are counted
+    but dropped, and shown as an ellipsis. Frames past the first
+    [`MAX_FRAMES`] distinct ones are shown as `?`.
+*/
+#[derive(Debug, Copy, Clone)]
+pub struct ErrorContext {
+    frames: [u8; CONTEXT_DEPTH],
+    depth: u8,
+    source: io::Error,
+}
+
+impl ErrorContext {
+    /// Create new instance of `ErrorContext`, with a single frame
+    pub fn new(frame: &'static str, source: io::Error) -> Self {
+        let mut frames = [FRAME_LOST; CONTEXT_DEPTH];
+        frames[0] = intern(frame);
+
+        ErrorContext {
+            frames,
+            depth: 1,
+            source,
+        }
+    }
+
+    /// Record an outer frame
+    pub fn push(&mut self, frame: &'static str) {
+        if (self.depth as usize) < CONTEXT_DEPTH {
+            self.frames[self.depth as usize] = intern(frame);
+        }
+        self.depth = self.depth.saturating_add(1);
+    }
+
+    /// Returns the recorded frames, innermost first
+    pub fn frames(&self) -> impl DoubleEndedIterator<Item = &'static str> + '_ {
+        let depth = (self.depth as usize).min(CONTEXT_DEPTH);
+        self.frames[..depth].iter().map(|&i| frame(i))
+    }
+
+    /// Checks whether outer frames were dropped
+    pub fn is_truncated(&self) -> bool {
+        self.depth as usize > CONTEXT_DEPTH
+    }
+
+    /// Returns the innermost error
+    pub fn source(&self) -> &io::Error {
+        &self.source
+    }
+}
+
+impl fmt::Display for ErrorContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // - outermost frame first, as in "boot: allocator init: ..."
+        if self.is_truncated() {
+            f.write_str("...: ")?;
+        }
+        for frame in self.frames().rev() {
+            write!(f, "{}: ", frame)?;
+        }
+
+        write!(f, "{}", self.source)
+    }
+}
+
+impl core::error::Error for ErrorContext {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+impl GenericError {
+    /**
+        Returns the kind of the error
+
+        Errors without a particular kind, such as
+        [`GenericError::ErrorMessage`], are [`ErrorKind::Uncategorized`].
+    */
+    pub fn kind(&self) -> ErrorKind {
+        io::Error::from(*self).kind()
+    }
+
+    /// Returns the number of the error (refer to [`ErrorCode`])
+    pub fn code(&self) -> ErrorCode {
+        ErrorCode::of(&io::Error::from(*self))
+    }
+
+    /**
+        Attach a context frame, naming the operation that failed
+
+        Frames attached later are considered outer frames.
+    */
+    pub fn context(self, frame: &'static str) -> GenericError {
+        match self {
+            GenericError::Context(mut c) => {
+                c.push(frame);
+                GenericError::Context(c)
+            }
+            e => GenericError::Context(ErrorContext::new(frame, e.into())),
+        }
+    }
+}
+
+impl fmt::Display for GenericError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GenericError::Context(c) => write!(f, "{}", c),
+            e => write!(f, "{}", io::Error::from(*e)),
+        }
+    }
+}
+
+impl core::error::Error for GenericError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            GenericError::Io(e) => Some(e),
+            GenericError::Context(c) => Some(c.source()),
+            _ => None,
+        }
+    }
+}
+
+// Conversions for the `?` operator
+// - routed through `io::Error`, which picks the kind
+impl From<fmt::Error> for GenericError {
+    fn from(value: fmt::Error) -> GenericError {
+        io::Error::from(value).into()
+    }
+}
+
+impl From<core::str::Utf8Error> for GenericError {
+    fn from(value: core::str::Utf8Error) -> GenericError {
+        io::Error::from(value).into()
+    }
+}
+
+impl From<core::num::TryFromIntError> for GenericError {
+    fn from(value: core::num::TryFromIntError) -> GenericError {
+        io::Error::from(value).into()
+    }
+}
+
+/**
+    Stable number identifying an error, for reporting over
+    channels that can only carry integers, such as the serial
+    port or an exit code
+
+    The number is laid out as follows, and is the same in the
+    bootloader and the kernel:
+
+    | Bits    | Contents                                          |
+    |---------|---------------------------------------------------|
+    | 31..24  | kind (refer to [`ErrorKind::number()`])           |
+    | 23..16  | payload: 0 none, 1 other, 2 code, 3 message       |
+    | 15..0   | code (truncated), or hash of the message          |
+
+    Messages are hashed with [`ErrorCode::hash_message()`], so a
+    host-side decoder can match them against the known messages.
+    Context frames are not part of the number.
+*/
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct ErrorCode(pub u32);
+
+impl ErrorCode {
+    /// Payload field value for errors without a payload
+    pub const PAYLOAD_EMPTY: u8 = 0;
+    /// Payload field value for opaque payloads
+    pub const PAYLOAD_OTHER: u8 = 1;
+    /// Payload field value for error codes
+    pub const PAYLOAD_CODE: u8 = 2;
+    /// Payload field value for messages
+    pub const PAYLOAD_MESSAGE: u8 = 3;
+
+    /// Returns the number of `e`
+    pub const fn of(e: &io::Error) -> ErrorCode {
+        let (payload, detail) = match e.payload() {
+            ErrorPayload::Empty => (Self::PAYLOAD_EMPTY, 0),
+            ErrorPayload::Other => (Self::PAYLOAD_OTHER, 0),
+            ErrorPayload::Code(c) => (Self::PAYLOAD_CODE, c as u16),
+            ErrorPayload::Message(m) => (Self::PAYLOAD_MESSAGE, Self::hash_message(m)),
+        };
+
+        ErrorCode(((e.kind().number() as u32) << 24) | ((payload as u32) << 16) | detail as u32)
+    }
+
+    /**
+        Returns the 16-bit hash of a message
+
+        This is 32-bit FNV-1a, folded by XOR-ing its halves.
+    */
+    pub const fn hash_message(m: &str) -> u16 {
+        let bytes = m.as_bytes();
+        let mut hash: u32 = 0x811c_9dc5;
+        let mut i = 0;
+
+        while i < bytes.len() {
+            hash ^= bytes[i] as u32;
+            hash = hash.wrapping_mul(0x0100_0193);
+            i += 1;
+        }
+
+        ((hash >> 16) ^ (hash & 0xffff)) as u16
+    }
+
+    /// Returns the error kind, if the number names a known one
+    pub const fn kind(&self) -> Option<ErrorKind> {
+        ErrorKind::from_number((self.0 >> 24) as u8)
+    }
+
+    /// Returns the payload field (refer to the `PAYLOAD_*` constants)
+    pub const fn payload(&self) -> u8 {
+        (self.0 >> 16) as u8
+    }
+
+    /// Returns the code, or the hash of the message
+    pub const fn detail(&self) -> u16 {
+        self.0 as u16
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "E{:08x}", self.0)
+    }
+}
+
+/**
+    Trait to attach context to the errors of a `Result`
+
+    Implemented for every `Result` whose error converts into
+    a [`GenericError`], such as [`io::Error`], whose kind is
+    preserved.
+*/
+pub trait Context<T> {
+    /// Attach a context frame to the error, if any
+    fn context(self, frame: &'static str) -> Result<T, GenericError>;
+}
+
+impl<T, E: Into<GenericError>> Context<T> for Result<T, E> {
+    fn context(self, frame: &'static str) -> Result<T, GenericError> {
+        self.map_err(|e| e.into().context(frame))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use std::format;
+    use std::vec::Vec;
+
+    #[test]
+    fn display() {
+        let e = GenericError::ErrorMessage("no suitable region for allocator base was found");
+        assert_eq!(
+            format!("{}", e),
+            "no suitable region for allocator base was found"
+        );
+        assert_eq!(
+            format!("{}", GenericError::ErrorCode(0x2a)),
+            "uncategorized error (code 0x2a)"
+        );
+
+        let e = io::Error::new(ErrorKind::InvalidData, ErrorPayload::Code(3));
+        assert_eq!(format!("{}", e), "invalid data (code 0x3)");
+        assert_eq!(format!("{}", io::Error::E_WRITE_ZERO), "write zero");
+    }
+
+    #[test]
+    fn context_chain() {
+        let r: Result<(), _> = Err(GenericError::ErrorMessage("no suitable region"));
+        let e = r.context("allocator init").context("boot").unwrap_err();
+
+        assert_eq!(format!("{}", e), "boot: allocator init: no suitable region");
+
+        let GenericError::Context(c) = e else {
+            panic!("expected a context");
+        };
+        assert_eq!(c.frames().collect::<Vec<_>>(), ["allocator init", "boot"]);
+
+        // - io errors keep their kind
+        let r: Result<(), _> = Err(io::Error::E_UNEXPECTED_EOF);
+        let e = r.context("read config").unwrap_err();
+        assert_eq!(format!("{}", e), "read config: unexpected end of file");
+        assert!(core::error::Error::source(&e).is_some());
+    }
+
+    #[test]
+    fn context_truncation() {
+        let mut e = GenericError::Empty;
+        for frame in ["a", "b", "c", "d", "e", "f"] {
+            e = e.context(frame);
+        }
+
+        assert_eq!(format!("{}", e), "...: d: c: b: a: uncategorized error");
+    }
+
+    #[test]
+    fn round_trips() {
+        let generic = [
+            GenericError::ErrorCode(7),
+            GenericError::ErrorMessage("msg"),
+            GenericError::Other,
+            GenericError::Empty,
+            GenericError::Io(io::Error::E_TIMED_OUT),
+        ];
+        for e in generic {
+            let back = GenericError::from(io::Error::from(e));
+            assert_eq!(format!("{:?}", back), format!("{:?}", e));
+        }
+
+        let e = io::Error::new(ErrorKind::NotFound, ErrorPayload::Message("no such file"));
+        let back = io::Error::from(GenericError::from(e));
+        assert_eq!(back.kind(), ErrorKind::NotFound);
+        assert!(matches!(
+            back.payload(),
+            ErrorPayload::Message("no such file")
+        ));
+
+        // - the kind survives context, and is reported
+        let e = GenericError::from(e).context("load config");
+        assert_eq!(e.kind(), ErrorKind::NotFound);
+        assert_eq!(io::Error::from(e).kind(), ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn kinds_agree_with_codes() {
+        let generic = [
+            GenericError::ErrorCode(7),
+            GenericError::ErrorMessage("msg"),
+            GenericError::Other,
+            GenericError::Empty,
+            GenericError::Io(io::Error::E_TIMED_OUT),
+            GenericError::ErrorMessage("msg").context("ctx"),
+        ];
+        for e in generic {
+            assert_eq!(Some(e.kind()), e.code().kind(), "{:?}", e);
+        }
+        assert_eq!(
+            GenericError::ErrorMessage("msg").kind(),
+            ErrorKind::Uncategorized
+        );
+    }
+
+    #[test]
+    fn context_is_small() {
+        // - frames are interned, so context costs a few bytes at most
+        assert!(size_of::<ErrorContext>() <= size_of::<io::Error>() + 8);
+        assert!(size_of::<GenericError>() <= size_of::<io::Error>() + 16);
+
+        // - the same frame is interned once
+        let a = GenericError::Empty.context("interned frame");
+        let b = GenericError::Other.context("interned frame");
+        let (GenericError::Context(a), GenericError::Context(b)) = (a, b) else {
+            panic!("expected a context");
+        };
+        assert_eq!(a.frames, b.frames);
+    }
+
+    #[test]
+    fn foreign_conversions() {
+        fn parse(b: &[u8]) -> Result<u8, GenericError> {
+            let s = core::str::from_utf8(b)?;
+            let n: u32 = s.len().try_into()?;
+            Ok(u8::try_from(n)?)
+        }
+
+        assert_eq!(parse(b"ok").unwrap(), 2);
+        assert_eq!(parse(b"\xff").unwrap_err().kind(), ErrorKind::InvalidData);
+        assert_eq!(
+            parse(&[b'a'; 300]).unwrap_err().kind(),
+            ErrorKind::InvalidInput
+        );
+
+        let e = GenericError::from(fmt::Error);
+        assert_eq!(format!("{}", e), "formatter error");
+    }
+
+    #[test]
+    fn codes() {
+        let e = GenericError::from(io::Error::new(
+            ErrorKind::InvalidData,
+            ErrorPayload::Code(0x1234),
+        ));
+        let code = e.code();
+        assert_eq!(code, ErrorCode(0x0502_1234));
+        assert_eq!(code.kind(), Some(ErrorKind::InvalidData));
+        assert_eq!(format!("{}", code), "E05021234");
+
+        // - messages are hashed, and context doesn't matter
+        let m = "no suitable region for allocator base was found";
+        let code = GenericError::ErrorMessage(m)
+            .context("allocator init")
+            .code();
+        assert_eq!(code.kind(), Some(ErrorKind::Uncategorized));
+        assert_eq!(code.payload(), ErrorCode::PAYLOAD_MESSAGE);
+        assert_eq!(code.detail(), ErrorCode::hash_message(m));
+        assert_eq!(io::Error::E_WRITE_ZERO.kind().number(), 13);
+    }
+}
@@ -0,0 +1,246 @@
+/*
+    Portions of this file are derived from the Rust standard library
+    (std::io), originally licensed under the MIT license.
+
+    Copyright (c) The Rust Project Constributors
+
+    Licensed under the MIT License. A copy is provided in `/LICENSE`.
+
+    Modifications (if any) are Copyright (c) 2026 John Isaac Calderon
+*/
+
+/*!
+    Adapters returned by the provided methods of [`Read`]
+*/
+
+// Definition uses
+use super::{BufRead, Error, Read};
+
+/// Iterator over the bytes of a reader (refer to [`Read::bytes()`])
+#[derive(Debug)]
+pub struct Bytes<R> {
+    inner: R,
+}
+
+impl<R> Bytes<R> {
+    // Internal: constructor for `Read::bytes()`
+    pub(super) fn new(inner: R) -> Self {
+        Bytes { inner }
+    }
+}
+
+impl<R: Read> Iterator for Bytes<R> {
+    type Item = Result<u8, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut byte = 0;
+
+        match self.inner.read(core::slice::from_mut(&mut byte)) {
+            Ok(0) => None,
+            Ok(_) => Some(Ok(byte)),
+            Err(e) => Some(Err(e)),
+        }
+    }
+}
+
+/**
+    Adapter that reads from two readers, one after the other
+
+    Refer to [`Read::chain()`]. Once the first reader reports
+    EOF, it's never read from again.
+*/
+#[derive(Debug)]
+pub struct Chain<A, B> {
+    first: A,
+    second: B,
+    done_first: bool,
+}
+
+impl<A, B> Chain<A, B> {
+    // Internal: constructor for `Read::chain()`
+    pub(super) fn new(first: A, second: B) -> Self {
+        Chain {
+            first,
+            second,
+            done_first: false,
+        }
+    }
+
+    /// Consumes the adapter, returning both readers
+    pub fn into_inner(self) -> (A, B) {
+        (self.first, self.second)
+    }
+
+    /// Returns references to both readers
+    pub fn get_ref(&self) -> (&A, &B) {
+        (&self.first, &self.second)
+    }
+
+    /// Returns mutable references to both readers
+    pub fn get_mut(&mut self) -> (&mut A, &mut B) {
+        (&mut self.first, &mut self.second)
+    }
+}
+
+impl<A: Read, B: Read> Read for Chain<A, B> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        if !self.done_first {
+            match self.first.read(buf)? {
+                // - an empty buffer says nothing about EOF
+                0 if !buf.is_empty() => self.done_first = true,
+                n => return Ok(n),
+            }
+        }
+
+        self.second.read(buf)
+    }
+}
+
+impl<A: BufRead, B: BufRead> BufRead for Chain<A, B> {
+    fn fill_buf(&mut self) -> Result<&[u8], Error> {
+        if !self.done_first {
+            match self.first.fill_buf()? {
+                [] => self.done_first = true,
+                // - re-borrowed, as the borrow checker can't tell
+                //   that the empty case doesn't keep `first` borrowed
+                _ => return self.first.fill_buf(),
+            }
+        }
+
+        self.second.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        if !self.done_first {
+            self.first.consume(amt);
+        } else {
+            self.second.consume(amt);
+        }
+    }
+}
+
+/// Adapter that limits the bytes read from a reader (refer to [`Read::take()`])
+#[derive(Debug)]
+pub struct Take<R> {
+    inner: R,
+    limit: u64,
+}
+
+impl<R> Take<R> {
+    // Internal: constructor for `Read::take()`
+    pub(super) fn new(inner: R, limit: u64) -> Self {
+        Take { inner, limit }
+    }
+
+    /// Returns the number of bytes left to read
+    pub fn limit(&self) -> u64 {
+        self.limit
+    }
+
+    /// Sets the number of bytes left to read
+    pub fn set_limit(&mut self, limit: u64) {
+        self.limit = limit;
+    }
+
+    /// Consumes the adapter, returning the reader
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Returns a reference to the reader
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the reader
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+}
+
+impl<R: Read> Read for Take<R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        if self.limit == 0 {
+            return Ok(0);
+        }
+
+        let max = (buf.len() as u64).min(self.limit) as usize;
+        let n = self.inner.read(&mut buf[..max])?.min(max);
+        self.limit -= n as u64;
+
+        Ok(n)
+    }
+}
+
+impl<R: BufRead> BufRead for Take<R> {
+    fn fill_buf(&mut self) -> Result<&[u8], Error> {
+        if self.limit == 0 {
+            return Ok(&[]);
+        }
+
+        let buf = self.inner.fill_buf()?;
+        let max = (buf.len() as u64).min(self.limit) as usize;
+
+        Ok(&buf[..max])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        let amt = (amt as u64).min(self.limit);
+        self.limit -= amt;
+        self.inner.consume(amt as usize);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use crate::shared::io::Cursor;
+    use crate::shared::structs::array_string::ArrayString;
+    use std::vec::Vec;
+
+    #[test]
+    fn bytes() {
+        let bytes: Vec<u8> = Cursor::new(b"abc").bytes().map(|b| b.unwrap()).collect();
+        assert_eq!(bytes, b"abc");
+    }
+
+    #[test]
+    fn chain() {
+        let mut r = Cursor::new(b"ab").chain(Cursor::new(b"cde"));
+        let mut buf = [0u8; 8];
+
+        assert_eq!(r.read(&mut buf).unwrap(), 2);
+        assert_eq!(r.read(&mut buf[2..]).unwrap(), 3);
+        assert_eq!(&buf[..5], b"abcde");
+        assert_eq!(r.read(&mut buf).unwrap(), 0);
+
+        let mut r = Cursor::new("one\ntw").chain(Cursor::new("o\n"));
+        let mut line: ArrayString<8> = ArrayString::new();
+        r.read_line(&mut line).unwrap();
+        line.clear();
+        r.read_line(&mut line).unwrap();
+        assert_eq!(line, "two\n");
+    }
+
+    #[test]
+    fn take() {
+        let mut c = Cursor::new(b"0123456789");
+        let mut buf = [0u8; 8];
+
+        {
+            let mut r = c.by_ref().take(3);
+            assert_eq!(r.read(&mut buf).unwrap(), 3);
+            assert_eq!(r.read(&mut buf).unwrap(), 0);
+            assert_eq!(r.limit(), 0);
+        }
+
+        // - the reader was only borrowed
+        assert_eq!(c.position(), 3);
+        let mut r = c.take(4);
+        assert_eq!(r.fill_buf().unwrap(), b"3456");
+        r.consume(10);
+        assert_eq!(r.get_ref().position(), 7);
+    }
+}
@@ -0,0 +1,985 @@
+/*
+    Portions of this file are derived from the Rust standard library
+    (std::io), originally licensed under the MIT license.
+
+    Copyright (c) The Rust Project Constributors
+
+    Licensed under the MIT License. A copy is provided in `/LICENSE`.
+
+    Modifications (if any) are Copyright (c) 2026 John Isaac Calderon
+*/
+
+/*!
+    Buffered readers and writers
+
+    Unlike their `std` counterparts, the adapters don't allocate:
+    they work over any buffer that the caller provides, be it an
+    array, a borrowed slice, or (with the `alloc` feature) a boxed
+    slice. As in `std`, unbuffered data is lost if a writer is
+    dropped and its final flush fails.
+
+    # Usage
+    ```rust,ignore
+    let mut buf = [0u8; 256];
+    let mut out = LineWriter::with_buffer(console, &mut buf[..]);
+    writeln!(out, "flushed as a whole line")?;
+
+    let mut reader = BufReader::with_buffer(file, [0u8; 512]);
+    let mut line: ArrayString<80> = ArrayString::new();
+    reader.read_line(&mut line)?;
+    ```
+*/
+
+// Definition uses
+use core::fmt;
+use core::mem::ManuallyDrop;
+use core::ptr;
+use core::str;
+
+#[cfg(feature = "alloc")]
+use alloc::boxed::Box;
+#[cfg(feature = "alloc")]
+use alloc::vec;
+
+use super::{Error, ErrorKind, ErrorPayload, Read, Seek, SeekFrom, Write};
+use crate::shared::structs::array_string::ArrayString;
+
+/// Size of the buffers allocated by the `alloc`-backed constructors
+pub const DEFAULT_BUF_SIZE: usize = 1024;
+
+/**
+    Trait to mark a reader as having an internal buffer
+
+    Buffered readers can hand out their buffered data directly,
+    which makes it cheap to read up to a delimiter.
+*/
+pub trait BufRead: Read {
+    /**
+        Returns the contents of the internal buffer, refilling
+        it from the inner reader if it's empty
+
+        An empty slice signals the end of the stream.
+    */
+    fn fill_buf(&mut self) -> Result<&[u8], Error>;
+
+    /// Marks `amt` bytes of the internal buffer as read
+    fn consume(&mut self, amt: usize);
+
+    /* Given implementations */
+
+    /// Checks whether the stream has no data left
+    fn has_data_left(&mut self) -> Result<bool, Error> {
+        self.fill_buf().map(|b| !b.is_empty())
+    }
+
+    /**
+        Copy bytes to `out` until the delimiter `byte` (inclusive)
+        or the end of the stream, and return the number of bytes read
+
+        Errors reported by `out` are propagated once the data up to
+        the delimiter has been skipped, so that the next read starts
+        past it, rather than at the data that `out` rejected.
+    */
+    fn read_until<W: Write + ?Sized>(&mut self, byte: u8, out: &mut W) -> Result<usize, Error> {
+        let mut read = 0;
+
+        loop {
+            let (done, used, res) = {
+                let available = self.fill_buf()?;
+                let (done, used) = match available.iter().position(|&b| b == byte) {
+                    Some(i) => (true, i + 1),
+                    None => (available.is_empty(), available.len()),
+                };
+                (done, used, out.write_all(&available[..used]))
+            };
+
+            self.consume(used);
+            read += used;
+
+            if let Err(e) = res {
+                if !done {
+                    self.skip_until(byte)?;
+                }
+                return Err(e);
+            }
+
+            if done {
+                return Ok(read);
+            }
+        }
+    }
+
+    /**
+        Discard bytes until the delimiter `byte` (inclusive) or
+        the end of the stream, and return the number of bytes read
+    */
+    fn skip_until(&mut self, byte: u8) -> Result<usize, Error> {
+        let mut read = 0;
+
+        loop {
+            let (done, used) = {
+                let available = self.fill_buf()?;
+                match available.iter().position(|&b| b == byte) {
+                    Some(i) => (true, i + 1),
+                    None => (available.is_empty(), available.len()),
+                }
+            };
+
+            self.consume(used);
+            read += used;
+
+            if done {
+                return Ok(read);
+            }
+        }
+    }
+
+    /**
+        Copy a line, including its line feed (if any), to `out`,
+        and return the number of bytes read
+
+        The line must be valid UTF-8, or else an error of the kind
+        [`ErrorKind::InvalidData`] is returned. If `out` runs out of
+        space, an error of the kind [`ErrorKind::OutOfMemory`] is
+        returned. In both cases, the rest of the line is skipped,
+        and whatever was copied until then stays in `out`.
+    */
+    fn read_line<F: fmt::Write + ?Sized>(&mut self, out: &mut F) -> Result<usize, Error> {
+        let mut utf8 = Utf8Sink {
+            out,
+            pending: [0; 4],
+            pending_len: 0,
+        };
+
+        let read = self.read_until(b'\n', &mut utf8)?;
+
+        // - the stream may end in the middle of a character
+        if utf8.pending_len != 0 {
+            return Err(E_NOT_UTF8);
+        }
+
+        Ok(read)
+    }
+
+    /**
+        Returns an iterator over the lines of the stream, each
+        of them collected into an `ArrayString<N>`
+
+        Line feeds, along with carriage returns preceding them,
+        are stripped. Lines that don't fit are reported as errors
+        (refer to [`read_line()`]), and the iterator moves on to
+        the next line.
+
+        [`read_line()`]: BufRead::read_line
+    */
+    fn lines<const N: usize>(self) -> Lines<Self, N>
+    where
+        Self: Sized,
+    {
+        Lines { inner: self }
+    }
+}
+
+// Errors reported while reading lines
+const E_NOT_UTF8: Error = Error::new(
+    ErrorKind::InvalidData,
+    ErrorPayload::Message("stream did not contain valid UTF-8"),
+);
+const E_LINE_TOO_LONG: Error = Error::new(
+    ErrorKind::OutOfMemory,
+    ErrorPayload::Message("line does not fit the provided buffer"),
+);
+
+// Internal: adapter validating a byte stream as UTF-8, and
+// passing whole characters on to a `fmt::Write`
+// - characters split across writes are held back until complete
+struct Utf8Sink<'a, F: fmt::Write + ?Sized> {
+    out: &'a mut F,
+    pending: [u8; 4],
+    pending_len: usize,
+}
+
+impl<F: fmt::Write + ?Sized> Utf8Sink<'_, F> {
+    // - pass a validated chunk on
+    fn emit(&mut self, s: &str) -> Result<(), Error> {
+        self.out.write_str(s).map_err(|_| E_LINE_TOO_LONG)
+    }
+}
+
+impl<F: fmt::Write + ?Sized> Write for Utf8Sink<'_, F> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        let mut rest = buf;
+
+        // 1. complete the held-back character, if any
+        if self.pending_len != 0 {
+            let need = match self.pending[0] {
+                b if b >= 0xf0 => 4,
+                b if b >= 0xe0 => 3,
+                _ => 2,
+            };
+            let n = (need - self.pending_len).min(rest.len());
+            self.pending[self.pending_len..self.pending_len + n].copy_from_slice(&rest[..n]);
+            self.pending_len += n;
+            rest = &rest[n..];
+
+            if self.pending_len < need {
+                return Ok(buf.len());
+            }
+
+            let pending = self.pending;
+            self.pending_len = 0;
+            let c = str::from_utf8(&pending[..need]).map_err(|_| E_NOT_UTF8)?;
+            self.emit(c)?;
+        }
+
+        // 2. pass on every whole character, holding back
+        //    an incomplete one at the end
+        match str::from_utf8(rest) {
+            Ok(s) => self.emit(s)?,
+            Err(e) if e.error_len().is_none() => {
+                let (valid, tail) = rest.split_at(e.valid_up_to());
+
+                // SAFETY: the prefix has just been validated
+                self.emit(unsafe { str::from_utf8_unchecked(valid) })?;
+                self.pending[..tail.len()].copy_from_slice(tail);
+                self.pending_len = tail.len();
+            }
+            Err(_) => return Err(E_NOT_UTF8),
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+/// Iterator over the lines of a [`BufRead`] (refer to [`BufRead::lines()`])
+#[derive(Debug)]
+pub struct Lines<B, const N: usize> {
+    inner: B,
+}
+
+impl<B: BufRead, const N: usize> Iterator for Lines<B, N> {
+    type Item = Result<ArrayString<N>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut line = ArrayString::new();
+
+        match self.inner.read_line(&mut line) {
+            Ok(0) => None,
+            Ok(_) => {
+                if line.ends_with('\n') {
+                    line.pop();
+                    if line.ends_with('\r') {
+                        line.pop();
+                    }
+                }
+                Some(Ok(line))
+            }
+            Err(e) => Some(Err(e)),
+        }
+    }
+}
+
+/**
+    Buffered reader over a caller-provided buffer
+
+    Small reads are served from the buffer, which is refilled
+    from the inner reader in as few reads as possible. Reads at
+    least as large as the buffer bypass it, once it's empty.
+*/
+pub struct BufReader<R, B> {
+    inner: R,
+    buf: B,
+    pos: usize,
+    filled: usize,
+}
+
+impl<R: Read, B: AsMut<[u8]>> BufReader<R, B> {
+    /// Create new instance of `BufReader`, buffering through `buf`
+    pub fn with_buffer(inner: R, buf: B) -> Self {
+        BufReader {
+            inner,
+            buf,
+            pos: 0,
+            filled: 0,
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<R: Read> BufReader<R, Box<[u8]>> {
+    /// Create new instance of `BufReader`, with a buffer of [`DEFAULT_BUF_SIZE`] bytes
+    pub fn new(inner: R) -> Self {
+        Self::with_capacity(DEFAULT_BUF_SIZE, inner)
+    }
+
+    /// Create new instance of `BufReader`, with a buffer of `capacity` bytes
+    pub fn with_capacity(capacity: usize, inner: R) -> Self {
+        Self::with_buffer(inner, vec![0; capacity].into_boxed_slice())
+    }
+}
+
+impl<R, B: AsRef<[u8]>> BufReader<R, B> {
+    /// Returns a reference to the inner reader
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /**
+        Returns a mutable reference to the inner reader
+
+        Reading from the inner reader directly skips
+        over whatever is still buffered.
+    */
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    /// Returns the buffered data
+    pub fn buffer(&self) -> &[u8] {
+        &self.buf.as_ref()[self.pos..self.filled]
+    }
+
+    /// Returns the capacity of the buffer
+    pub fn capacity(&self) -> usize {
+        self.buf.as_ref().len()
+    }
+
+    /// Discard the buffered data
+    pub fn discard_buffer(&mut self) {
+        self.pos = 0;
+        self.filled = 0;
+    }
+
+    /// Consumes the reader, returning the inner reader and the buffer
+    /// - the buffered data is lost
+    pub fn into_parts(self) -> (R, B) {
+        (self.inner, self.buf)
+    }
+}
+
+impl<R: Read, B: AsRef<[u8]> + AsMut<[u8]>> Read for BufReader<R, B> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        // - bypass the buffer for large reads
+        if self.pos == self.filled && buf.len() >= self.capacity() {
+            self.discard_buffer();
+            return self.inner.read(buf);
+        }
+
+        let available = self.fill_buf()?;
+        let n = available.len().min(buf.len());
+        buf[..n].copy_from_slice(&available[..n]);
+        self.consume(n);
+
+        Ok(n)
+    }
+}
+
+impl<R: Read + Seek, B: AsRef<[u8]>> Seek for BufReader<R, B> {
+    /**
+        Seek within the inner reader, discarding the buffered data
+
+        Relative seeks are relative to the position of the
+        `BufReader`, not to that of the inner reader.
+    */
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64, Error> {
+        let pos = match pos {
+            SeekFrom::Current(n) => {
+                // - the inner reader is ahead by whatever is buffered
+                let ahead = (self.filled - self.pos) as i64;
+                match n.checked_sub(ahead) {
+                    Some(n) => SeekFrom::Current(n),
+                    None => {
+                        // - seek twice rather than overflow
+                        self.inner.seek(SeekFrom::Current(-ahead))?;
+                        self.discard_buffer();
+                        SeekFrom::Current(n)
+                    }
+                }
+            }
+            pos => pos,
+        };
+
+        let result = self.inner.seek(pos)?;
+        self.discard_buffer();
+
+        Ok(result)
+    }
+}
+
+impl<R: Read, B: AsRef<[u8]> + AsMut<[u8]>> BufRead for BufReader<R, B> {
+    fn fill_buf(&mut self) -> Result<&[u8], Error> {
+        if self.pos >= self.filled {
+            let n = self.inner.read(self.buf.as_mut())?;
+
+            // - don't trust the reader with the bounds
+            self.filled = n.min(self.capacity());
+            self.pos = 0;
+        }
+
+        Ok(self.buffer())
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.pos = (self.pos + amt).min(self.filled);
+    }
+}
+
+impl<R: fmt::Debug, B: AsRef<[u8]>> fmt::Debug for BufReader<R, B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BufReader")
+            .field("reader", &self.inner)
+            .field(
+                "buffer",
+                &format_args!("{}/{}", self.filled - self.pos, self.capacity()),
+            )
+            .finish()
+    }
+}
+
+/**
+    Buffered writer over a caller-provided buffer
+
+    Small writes are collected in the buffer, which is written
+    out once it's full, upon [`flush()`], or when the writer is
+    dropped. Writes at least as large as the buffer bypass it.
+
+    [`flush()`]: Write::flush
+*/
+pub struct BufWriter<W: Write, B: AsRef<[u8]> + AsMut<[u8]>> {
+    inner: W,
+    buf: B,
+    len: usize,
+}
+
+impl<W: Write, B: AsRef<[u8]> + AsMut<[u8]>> BufWriter<W, B> {
+    /// Create new instance of `BufWriter`, buffering through `buf`
+    pub fn with_buffer(inner: W, buf: B) -> Self {
+        BufWriter { inner, buf, len: 0 }
+    }
+
+    /// Returns a reference to the inner writer
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /**
+        Returns a mutable reference to the inner writer
+
+        Writing to the inner writer directly
+        jumps ahead of whatever is still buffered.
+    */
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Returns the buffered data
+    pub fn buffer(&self) -> &[u8] {
+        &self.buf.as_ref()[..self.len]
+    }
+
+    /// Returns the capacity of the buffer
+    pub fn capacity(&self) -> usize {
+        self.buf.as_ref().len()
+    }
+
+    // Internal: write the buffered data out, without flushing the inner writer
+    // - on failure, the data that wasn't written stays buffered
+    fn flush_buf(&mut self) -> Result<(), Error> {
+        let mut written = 0;
+        let mut result = Ok(());
+
+        while written < self.len {
+            match self.inner.write(&self.buf.as_mut()[written..self.len]) {
+                Ok(0) => {
+                    result = error!(WriteZero, Message, "failed to write the buffered data");
+                    break;
+                }
+                Ok(n) => written += n.min(self.len - written),
+                Err(e) => {
+                    result = Err(e);
+                    break;
+                }
+            }
+        }
+
+        self.buf.as_mut().copy_within(written..self.len, 0);
+        self.len -= written;
+
+        result
+    }
+
+    /**
+        Consumes the writer, writing the buffered data out,
+        and returns the inner writer and the buffer
+
+        On failure, the writer is handed back along with the error.
+    */
+    pub fn into_parts(mut self) -> Result<(W, B), (Error, Self)> {
+        if let Err(e) = self.flush_buf() {
+            return Err((e, self));
+        }
+
+        // - the writer must not be dropped, as that would flush
+        //   it again, so move its fields out by hand
+        let this = ManuallyDrop::new(self);
+
+        // SAFETY: `this` is never used or dropped again
+        unsafe { Ok((ptr::read(&this.inner), ptr::read(&this.buf))) }
+    }
+
+    /// Consumes the writer, writing the buffered data out, and returns the inner writer
+    pub fn into_inner(self) -> Result<W, (Error, Self)> {
+        self.into_parts().map(|(w, _)| w)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<W: Write> BufWriter<W, Box<[u8]>> {
+    /// Create new instance of `BufWriter`, with a buffer of [`DEFAULT_BUF_SIZE`] bytes
+    pub fn new(inner: W) -> Self {
+        Self::with_capacity(DEFAULT_BUF_SIZE, inner)
+    }
+
+    /// Create new instance of `BufWriter`, with a buffer of `capacity` bytes
+    pub fn with_capacity(capacity: usize, inner: W) -> Self {
+        Self::with_buffer(inner, vec![0; capacity].into_boxed_slice())
+    }
+}
+
+impl<W: Write, B: AsRef<[u8]> + AsMut<[u8]>> Write for BufWriter<W, B> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        if buf.len() > self.capacity() - self.len {
+            self.flush_buf()?;
+        }
+
+        // - bypass the buffer for large writes
+        if buf.len() >= self.capacity() {
+            return self.inner.write(buf);
+        }
+
+        let n = buf.len().min(self.capacity() - self.len);
+        let len = self.len;
+        self.buf.as_mut()[len..len + n].copy_from_slice(&buf[..n]);
+        self.len += n;
+
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        self.flush_buf()?;
+        self.inner.flush()
+    }
+}
+
+impl<W: Write + Seek, B: AsRef<[u8]> + AsMut<[u8]>> Seek for BufWriter<W, B> {
+    /// Seek within the inner writer, after writing the buffered data out
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64, Error> {
+        self.flush_buf()?;
+        self.inner.seek(pos)
+    }
+}
+
+impl<W: Write, B: AsRef<[u8]> + AsMut<[u8]>> Drop for BufWriter<W, B> {
+    fn drop(&mut self) {
+        // - there's nowhere to report errors
+        let _ = self.flush_buf();
+    }
+}
+
+impl<W: Write + fmt::Debug, B: AsRef<[u8]> + AsMut<[u8]>> fmt::Debug for BufWriter<W, B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BufWriter")
+            .field("writer", &self.inner)
+            .field("buffered", &self.len)
+            .finish()
+    }
+}
+
+/**
+    Buffered writer that writes out whole lines as soon as they end
+
+    This suits consoles, where output should show up line by
+    line, without paying for a write per byte.
+*/
+pub struct LineWriter<W: Write, B: AsRef<[u8]> + AsMut<[u8]>> {
+    inner: BufWriter<W, B>,
+}
+
+impl<W: Write, B: AsRef<[u8]> + AsMut<[u8]>> LineWriter<W, B> {
+    /// Create new instance of `LineWriter`, buffering through `buf`
+    pub fn with_buffer(inner: W, buf: B) -> Self {
+        LineWriter {
+            inner: BufWriter::with_buffer(inner, buf),
+        }
+    }
+
+    /// Returns a reference to the inner writer
+    pub fn get_ref(&self) -> &W {
+        self.inner.get_ref()
+    }
+
+    /// Returns a mutable reference to the inner writer
+    pub fn get_mut(&mut self) -> &mut W {
+        self.inner.get_mut()
+    }
+
+    /// Returns the buffered data
+    pub fn buffer(&self) -> &[u8] {
+        self.inner.buffer()
+    }
+
+    /// Returns the capacity of the buffer
+    pub fn capacity(&self) -> usize {
+        self.inner.capacity()
+    }
+
+    /// Consumes the writer, writing the buffered data out, and returns the inner writer
+    pub fn into_inner(self) -> Result<W, (Error, Self)> {
+        self.inner
+            .into_inner()
+            .map_err(|(e, inner)| (e, LineWriter { inner }))
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<W: Write> LineWriter<W, Box<[u8]>> {
+    /// Create new instance of `LineWriter`, with a buffer of [`DEFAULT_BUF_SIZE`] bytes
+    pub fn new(inner: W) -> Self {
+        Self::with_capacity(DEFAULT_BUF_SIZE, inner)
+    }
+
+    /// Create new instance of `LineWriter`, with a buffer of `capacity` bytes
+    pub fn with_capacity(capacity: usize, inner: W) -> Self {
+        Self::with_buffer(inner, vec![0; capacity].into_boxed_slice())
+    }
+}
+
+impl<W: Write, B: AsRef<[u8]> + AsMut<[u8]>> Write for LineWriter<W, B> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        let newline = match buf.iter().rposition(|&b| b == b'\n') {
+            Some(i) => i,
+            None => {
+                // - a complete line may still be buffered from
+                //   an earlier write that came up short
+                if self.inner.buffer().last() == Some(&b'\n') {
+                    self.inner.flush_buf()?;
+                }
+                return self.inner.write(buf);
+            }
+        };
+
+        // 1. write out whatever precedes the new lines, then the
+        //    lines themselves, directly
+        self.inner.flush_buf()?;
+        let (lines, tail) = buf.split_at(newline + 1);
+
+        let n = self.inner.get_mut().write(lines)?;
+        if n != lines.len() {
+            return Ok(n);
+        }
+
+        // 2. buffer as much of the unfinished line as fits
+        // - failures can't be reported now that lines were written
+        let m = self.inner.write(tail).unwrap_or(0);
+
+        Ok(n + m)
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        self.inner.flush()
+    }
+}
+
+impl<W: Write + fmt::Debug, B: AsRef<[u8]> + AsMut<[u8]>> fmt::Debug for LineWriter<W, B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LineWriter")
+            .field("writer", self.inner.get_ref())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use crate::shared::io::Cursor;
+    use crate::shared::structs::array_vec::ArrayVec;
+    use std::vec::Vec;
+
+    // Reader yielding its data in chunks of at most `chunk` bytes
+    struct Chunked<'a> {
+        data: &'a [u8],
+        chunk: usize,
+        reads: usize,
+    }
+
+    impl<'a> Chunked<'a> {
+        fn new(data: &'a [u8], chunk: usize) -> Self {
+            Chunked {
+                data,
+                chunk,
+                reads: 0,
+            }
+        }
+    }
+
+    impl Read for Chunked<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+            let n = self.data.len().min(buf.len()).min(self.chunk);
+            buf[..n].copy_from_slice(&self.data[..n]);
+            self.data = &self.data[n..];
+            self.reads += 1;
+
+            Ok(n)
+        }
+    }
+
+    // Writer recording every write it's handed
+    #[derive(Default)]
+    struct Recorder {
+        writes: Vec<Vec<u8>>,
+        flushes: usize,
+    }
+
+    impl Write for Recorder {
+        fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+            self.writes.push(buf.to_vec());
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> Result<(), Error> {
+            self.flushes += 1;
+            Ok(())
+        }
+    }
+
+    impl Recorder {
+        fn joined(&self) -> Vec<u8> {
+            self.writes.concat()
+        }
+    }
+
+    #[test]
+    fn buf_reader_reads() {
+        let mut r = BufReader::with_buffer(Chunked::new(b"hello world", 64), [0u8; 4]);
+        let mut out = [0u8; 3];
+
+        assert_eq!(r.read(&mut out).unwrap(), 3);
+        assert_eq!(&out, b"hel");
+        assert_eq!(r.buffer(), b"l");
+        assert_eq!(r.read(&mut out).unwrap(), 1);
+        assert_eq!(&out[..1], b"l");
+
+        // - large reads bypass the buffer
+        let mut big = [0u8; 8];
+        assert_eq!(r.read(&mut big).unwrap(), 7);
+        assert_eq!(&big[..7], b"o world");
+        assert_eq!(r.read(&mut big).unwrap(), 0);
+        assert_eq!(r.get_ref().reads, 3);
+    }
+
+    #[test]
+    fn read_until() {
+        let mut r = BufReader::with_buffer(Chunked::new(b"a,bcdefg,,h", 3), [0u8; 2]);
+        let mut out: ArrayVec<u8, 16> = ArrayVec::new();
+
+        assert_eq!(r.read_until(b',', &mut out).unwrap(), 2);
+        assert_eq!(r.read_until(b',', &mut out).unwrap(), 7);
+        assert_eq!(r.read_until(b',', &mut out).unwrap(), 1);
+        assert_eq!(r.read_until(b',', &mut out).unwrap(), 1);
+        assert_eq!(r.read_until(b',', &mut out).unwrap(), 0);
+        assert_eq!(&out[..], b"a,bcdefg,,h");
+        assert!(!r.has_data_left().unwrap());
+    }
+
+    #[test]
+    fn read_line() {
+        // - characters are split across reads and buffer refills
+        let text = "añb€\n𝄞\n";
+        let mut r = BufReader::with_buffer(Chunked::new(text.as_bytes(), 1), [0u8; 3]);
+        let mut line: ArrayString<16> = ArrayString::new();
+
+        assert_eq!(r.read_line(&mut line).unwrap(), 8);
+        assert_eq!(line, "añb€\n");
+        line.clear();
+        assert_eq!(r.read_line(&mut line).unwrap(), 5);
+        assert_eq!(line, "𝄞\n");
+        assert_eq!(r.read_line(&mut line).unwrap(), 0);
+    }
+
+    #[test]
+    fn read_line_errors() {
+        let mut r = BufReader::with_buffer(Chunked::new(b"ab\xffc\n", 8), [0u8; 8]);
+        let mut line: ArrayString<16> = ArrayString::new();
+        let e = r.read_line(&mut line).unwrap_err();
+        assert!(matches!(e.kind(), ErrorKind::InvalidData));
+
+        // - truncated character at the end of the stream
+        let mut r = BufReader::with_buffer(Chunked::new(&"€".as_bytes()[..2], 8), [0u8; 8]);
+        assert!(r.read_line(&mut line).is_err());
+
+        let mut r = BufReader::with_buffer(Chunked::new(b"abcdef\n", 8), [0u8; 8]);
+        let mut short: ArrayString<4> = ArrayString::new();
+        let e = r.read_line(&mut short).unwrap_err();
+        assert!(matches!(e.kind(), ErrorKind::OutOfMemory));
+    }
+
+    #[test]
+    fn lines() {
+        let r = BufReader::with_buffer(Chunked::new(b"one\r\ntwo\n\nthree", 4), [0u8; 4]);
+        let lines: Vec<_> = r.lines::<8>().map(|l| l.unwrap()).collect();
+
+        assert_eq!(lines, ["one", "two", "", "three"]);
+    }
+
+    #[test]
+    fn lines_skip_bad_lines() {
+        // - an overlong line is reported once, then skipped
+        let r = BufReader::with_buffer(Chunked::new(b"abcdefghij\nok\n", 3), [0u8; 4]);
+        let mut lines = r.lines::<4>();
+
+        let e = lines.next().unwrap().unwrap_err();
+        assert!(matches!(e.kind(), ErrorKind::OutOfMemory));
+        assert_eq!(lines.next().unwrap().unwrap(), "ok");
+        assert!(lines.next().is_none());
+
+        // - as is a line that isn't valid UTF-8
+        let r = BufReader::with_buffer(Chunked::new(b"a\xffb\nc", 8), [0u8; 8]);
+        let mut lines = r.lines::<4>();
+
+        let e = lines.next().unwrap().unwrap_err();
+        assert!(matches!(e.kind(), ErrorKind::InvalidData));
+        assert_eq!(lines.next().unwrap().unwrap(), "c");
+        assert!(lines.next().is_none());
+    }
+
+    #[test]
+    fn skip_until() {
+        let mut r = BufReader::with_buffer(Chunked::new(b"abc;de;f", 2), [0u8; 2]);
+        let mut out: ArrayVec<u8, 8> = ArrayVec::new();
+
+        assert_eq!(r.skip_until(b';').unwrap(), 4);
+        assert_eq!(r.read_until(b';', &mut out).unwrap(), 3);
+        assert_eq!(&out[..], b"de;");
+        assert_eq!(r.skip_until(b';').unwrap(), 1);
+        assert_eq!(r.skip_until(b';').unwrap(), 0);
+    }
+
+    #[test]
+    fn buf_writer_buffers() {
+        let mut buf = [0u8; 4];
+        let mut w = BufWriter::with_buffer(Recorder::default(), &mut buf[..]);
+
+        assert_eq!(w.write(b"ab").unwrap(), 2);
+        assert_eq!(w.write(b"c").unwrap(), 1);
+        assert!(w.get_ref().writes.is_empty());
+
+        // - the buffer is written out once it would overflow
+        assert_eq!(w.write(b"de").unwrap(), 2);
+        assert_eq!(w.get_ref().writes, [b"abc".to_vec()]);
+
+        // - large writes bypass the buffer
+        assert_eq!(w.write(b"fghij").unwrap(), 5);
+        assert_eq!(w.get_ref().joined(), b"abcdefghij");
+
+        w.write_all(b"k").unwrap();
+        w.flush().unwrap();
+        assert_eq!(w.get_ref().joined(), b"abcdefghijk");
+        assert_eq!(w.get_ref().flushes, 1);
+
+        let inner = w.into_inner().ok().unwrap();
+        assert_eq!(inner.writes.len(), 4);
+    }
+
+    #[test]
+    fn buf_writer_flushes_on_drop() {
+        let mut out: ArrayVec<u8, 16> = ArrayVec::new();
+        {
+            let mut w = BufWriter::with_buffer(&mut out, [0u8; 8]);
+            w.write_all(b"xyz").unwrap();
+        }
+
+        assert_eq!(&out[..], b"xyz");
+    }
+
+    #[test]
+    fn buf_writer_keeps_unwritten_data() {
+        let mut w = BufWriter::with_buffer(ArrayVec::<u8, 2>::new(), [0u8; 4]);
+
+        w.write_all(b"abc").unwrap();
+        assert!(w.flush().is_err());
+
+        // - the buffer can be inspected through a shared reference
+        let r = &w;
+        assert_eq!(r.buffer(), b"c");
+        assert_eq!(r.capacity(), 4);
+
+        let (e, w) = w.into_inner().err().unwrap();
+        assert!(matches!(e.kind(), ErrorKind::WriteZero));
+        assert_eq!(&w.get_ref()[..], b"ab");
+    }
+
+    #[test]
+    fn line_writer() {
+        let mut w = LineWriter::with_buffer(Recorder::default(), [0u8; 16]);
+
+        w.write_all(b"partial").unwrap();
+        assert!(w.get_ref().writes.is_empty());
+
+        // - whole lines go out at once, along with what preceded them
+        w.write_all(b" line\nnext").unwrap();
+        assert_eq!(w.get_ref().joined(), b"partial line\n");
+        assert_eq!(w.buffer(), b"next");
+
+        w.write_all(b"\na\nb\nc").unwrap();
+        assert_eq!(w.get_ref().joined(), b"partial line\nnext\na\nb\n");
+
+        w.flush().unwrap();
+        assert_eq!(w.get_ref().joined(), b"partial line\nnext\na\nb\nc");
+    }
+
+    #[test]
+    fn seek() {
+        let data = b"0123456789";
+        let mut r = BufReader::with_buffer(Cursor::new(data), [0u8; 4]);
+        let mut out = [0u8; 2];
+
+        r.read_exact(&mut out).unwrap();
+        assert_eq!(r.get_ref().position(), 4);
+        assert_eq!(r.seek(SeekFrom::Current(1)).unwrap(), 3);
+        r.read_exact(&mut out).unwrap();
+        assert_eq!(&out, b"34");
+        assert_eq!(r.stream_position().unwrap(), 5);
+
+        let mut buf = [0u8; 8];
+        let mut w = BufWriter::with_buffer(Cursor::new(&mut buf[..]), [0u8; 4]);
+        w.write_all(b"abc").unwrap();
+        assert_eq!(w.seek(SeekFrom::Start(1)).unwrap(), 1);
+        w.write_all(b"X").unwrap();
+        drop(w);
+        assert_eq!(&buf[..3], b"aXc");
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn alloc_constructors() {
+        let mut r = BufReader::new(Chunked::new(b"abc\n", 8));
+        let mut line = alloc::string::String::new();
+        r.read_line(&mut line).unwrap();
+        assert_eq!(line, "abc\n");
+        assert_eq!(r.capacity(), DEFAULT_BUF_SIZE);
+
+        let mut w = BufWriter::with_capacity(2, Vec::new());
+        w.write_all(b"abc").unwrap();
+        assert_eq!(w.into_inner().ok().unwrap(), b"abc");
+
+        let mut w = LineWriter::new(Vec::new());
+        w.write_all(b"a\nb").unwrap();
+        assert_eq!(w.get_ref(), b"a\n");
+    }
+}
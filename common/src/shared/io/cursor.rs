@@ -0,0 +1,214 @@
+/*
+    Portions of this file are derived from the Rust standard library
+    (std::io), originally licensed under the MIT license.
+
+    Copyright (c) The Rust Project Constributors
+
+    Licensed under the MIT License. A copy is provided in `/LICENSE`.
+
+    Modifications (if any) are Copyright (c) 2026 John Isaac Calderon
+*/
+
+/*!
+    In-memory reader and writer
+
+    A [`Cursor`] wraps a byte buffer, be it a slice, an array or
+    anything else that can be viewed as one, and keeps a position
+    into it, so that in-memory images can be parsed with the same
+    code as any other stream.
+
+    Unlike its `std` counterpart, a `Cursor` never grows its buffer:
+    writes past the end of the buffer write nothing.
+*/
+
+// Definition uses
+use super::{BufRead, Error, Read, Seek, SeekFrom, Write, seek_offset};
+
+/// Reader and writer over an in-memory buffer
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Cursor<T> {
+    inner: T,
+    pos: u64,
+}
+
+impl<T> Cursor<T> {
+    /// Create new instance of `Cursor`, positioned at the start of `inner`
+    pub const fn new(inner: T) -> Self {
+        Cursor { inner, pos: 0 }
+    }
+
+    /// Consumes the cursor, returning the underlying buffer
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    /// Returns a reference to the underlying buffer
+    pub const fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /**
+        Returns a mutable reference to the underlying buffer
+
+        The position is left as is, even if the buffer shrinks.
+    */
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    /// Returns the position of the cursor
+    pub const fn position(&self) -> u64 {
+        self.pos
+    }
+
+    /// Moves the cursor to `pos`, which may be past the end of the buffer
+    pub fn set_position(&mut self, pos: u64) {
+        self.pos = pos;
+    }
+}
+
+impl<T: AsRef<[u8]>> Cursor<T> {
+    /// Returns the part of the buffer after the cursor
+    pub fn remaining_slice(&self) -> &[u8] {
+        let buf = self.inner.as_ref();
+        &buf[self.offset(buf.len())..]
+    }
+
+    /// Checks whether the cursor is at (or past) the end of the buffer
+    pub fn is_empty(&self) -> bool {
+        self.remaining_slice().is_empty()
+    }
+
+    // Internal: the position as an index into a buffer of `len` bytes
+    // - clamped, as the cursor may be past the end
+    fn offset(&self, len: usize) -> usize {
+        self.pos.min(len as u64) as usize
+    }
+}
+
+impl<T: AsRef<[u8]>> Read for Cursor<T> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        let rest = self.remaining_slice();
+        let n = rest.len().min(buf.len());
+        buf[..n].copy_from_slice(&rest[..n]);
+        self.pos += n as u64;
+
+        Ok(n)
+    }
+}
+
+impl<T: AsRef<[u8]>> BufRead for Cursor<T> {
+    fn fill_buf(&mut self) -> Result<&[u8], Error> {
+        Ok(self.remaining_slice())
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.pos += amt.min(self.remaining_slice().len()) as u64;
+    }
+}
+
+impl<T: AsMut<[u8]>> Write for Cursor<T> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        let dest = self.inner.as_mut();
+        let start = self.pos.min(dest.len() as u64) as usize;
+        let n = (dest.len() - start).min(buf.len());
+        dest[start..start + n].copy_from_slice(&buf[..n]);
+        self.pos += n as u64;
+
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl<T: AsRef<[u8]>> Seek for Cursor<T> {
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64, Error> {
+        self.pos = match pos {
+            SeekFrom::Start(n) => n,
+            SeekFrom::End(n) => seek_offset(self.inner.as_ref().len() as u64, n)?,
+            SeekFrom::Current(n) => seek_offset(self.pos, n)?,
+        };
+
+        Ok(self.pos)
+    }
+
+    fn stream_position(&mut self) -> Result<u64, Error> {
+        Ok(self.pos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::io::ErrorKind;
+    use crate::shared::structs::array_string::ArrayString;
+
+    #[test]
+    fn read_and_seek() {
+        let mut c = Cursor::new(b"0123456789");
+        let mut buf = [0u8; 4];
+
+        assert_eq!(c.read(&mut buf).unwrap(), 4);
+        assert_eq!(&buf, b"0123");
+        assert_eq!(c.stream_position().unwrap(), 4);
+
+        assert_eq!(c.seek(SeekFrom::End(-2)).unwrap(), 8);
+        assert_eq!(c.read(&mut buf).unwrap(), 2);
+        assert_eq!(&buf[..2], b"89");
+        assert_eq!(c.read(&mut buf).unwrap(), 0);
+
+        assert_eq!(c.seek(SeekFrom::Current(-5)).unwrap(), 5);
+        assert_eq!(c.remaining_slice(), b"56789");
+
+        c.rewind().unwrap();
+        c.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"0123");
+    }
+
+    #[test]
+    fn seek_bounds() {
+        let mut c = Cursor::new([0u8; 4]);
+
+        let e = c.seek(SeekFrom::Current(-1)).unwrap_err();
+        assert!(matches!(e.kind(), ErrorKind::InvalidInput));
+        assert!(c.seek(SeekFrom::End(-5)).is_err());
+        assert_eq!(c.position(), 0);
+
+        // - past the end, nothing is read or written
+        assert_eq!(c.seek(SeekFrom::Start(10)).unwrap(), 10);
+        assert!(c.is_empty());
+        assert_eq!(c.read(&mut [0u8; 2]).unwrap(), 0);
+        assert_eq!(c.write(b"ab").unwrap(), 0);
+        assert_eq!(c.seek(SeekFrom::End(1)).unwrap(), 5);
+    }
+
+    #[test]
+    fn write() {
+        let mut buf = [0u8; 6];
+        let mut c = Cursor::new(&mut buf[..]);
+
+        c.write_all(b"abcd").unwrap();
+        c.seek(SeekFrom::Start(2)).unwrap();
+        assert_eq!(c.write(b"XYZW").unwrap(), 4);
+        assert_eq!(c.write(b"!").unwrap(), 0);
+        assert!(matches!(
+            c.write_all(b"!").unwrap_err().kind(),
+            ErrorKind::WriteZero
+        ));
+
+        assert_eq!(&buf, b"abXYZW");
+    }
+
+    #[test]
+    fn buf_read() {
+        let mut c = Cursor::new("key=value\nnext\n");
+        let mut line: ArrayString<16> = ArrayString::new();
+
+        assert_eq!(c.read_line(&mut line).unwrap(), 10);
+        assert_eq!(line, "key=value\n");
+        assert_eq!(c.position(), 10);
+        assert_eq!(c.fill_buf().unwrap(), b"next\n");
+    }
+}
@@ -104,7 +104,7 @@ pub(crate) fn default_write_fmt<'a, W: Write + ?Sized + 'a>(
 
     [`std::io::ErrorKind`]: https://doc.rust-lang.org/stable/std/io/enum.ErrorKind.html
 */
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 #[non_exhaustive]
 pub enum ErrorKind {
     /* Error kinds that require a working OS */
@@ -204,7 +204,7 @@ pub enum ErrorPayload {
     [`ErrorPayload`]: ErrorPayload
     [`ErrorKind`]: ErrorKind
 */
-#[derive(Debug)]
+#[derive(Debug, Copy, Clone)]
 pub struct Error {
     e_kind: ErrorKind,
     e_payload: ErrorPayload,
@@ -274,12 +274,12 @@ impl Error {
     }
 
     /// Return error kind
-    pub fn kind(&self) -> ErrorKind {
+    pub const fn kind(&self) -> ErrorKind {
         self.e_kind
     }
 
     /// Return error payload
-    pub fn payload(&self) -> ErrorPayload {
+    pub const fn payload(&self) -> ErrorPayload {
         self.e_payload
     }
 
@@ -304,17 +304,172 @@ impl Error {
     }
 }
 
+impl ErrorKind {
+    /// Returns a short description of the error kind
+    pub const fn description(&self) -> &'static str {
+        match self {
+            ErrorKind::NotFound => "entity not found",
+            ErrorKind::PermissionDenied => "permission denied",
+            ErrorKind::ResourceBusy => "resource busy",
+            ErrorKind::InvalidInput => "invalid input parameter",
+            ErrorKind::InvalidData => "invalid data",
+            ErrorKind::UnexpectedEof => "unexpected end of file",
+            ErrorKind::Interrupted => "operation interrupted",
+            ErrorKind::WouldBlock => "operation would block",
+            ErrorKind::InProgress => "operation in progress",
+            ErrorKind::TimedOut => "timed out",
+            ErrorKind::OutOfMemory => "out of memory",
+            ErrorKind::Unsupported => "unsupported",
+            ErrorKind::WriteZero => "write zero",
+            ErrorKind::Uninitialized => "uninitialized",
+            ErrorKind::Other => "other error",
+            ErrorKind::Uncategorized => "uncategorized error",
+        }
+    }
+}
+
+impl ErrorKind {
+    /**
+        Returns the number of the error kind
+
+        Numbers are stable, and are part of the [`ErrorCode`]
+        scheme; [`Uncategorized`] is 0. New kinds must take
+        new numbers.
+
+        [`ErrorCode`]: crate::shared::ErrorCode
+        [`Uncategorized`]: ErrorKind::Uncategorized
+    */
+    pub const fn number(&self) -> u8 {
+        match self {
+            ErrorKind::Uncategorized => 0,
+            ErrorKind::NotFound => 1,
+            ErrorKind::PermissionDenied => 2,
+            ErrorKind::ResourceBusy => 3,
+            ErrorKind::InvalidInput => 4,
+            ErrorKind::InvalidData => 5,
+            ErrorKind::UnexpectedEof => 6,
+            ErrorKind::Interrupted => 7,
+            ErrorKind::WouldBlock => 8,
+            ErrorKind::InProgress => 9,
+            ErrorKind::TimedOut => 10,
+            ErrorKind::OutOfMemory => 11,
+            ErrorKind::Unsupported => 12,
+            ErrorKind::WriteZero => 13,
+            ErrorKind::Uninitialized => 14,
+            ErrorKind::Other => 15,
+        }
+    }
+
+    /// Returns the error kind numbered `n`, if any (refer to [`number()`](Self::number))
+    pub const fn from_number(n: u8) -> Option<ErrorKind> {
+        Some(match n {
+            0 => ErrorKind::Uncategorized,
+            1 => ErrorKind::NotFound,
+            2 => ErrorKind::PermissionDenied,
+            3 => ErrorKind::ResourceBusy,
+            4 => ErrorKind::InvalidInput,
+            5 => ErrorKind::InvalidData,
+            6 => ErrorKind::UnexpectedEof,
+            7 => ErrorKind::Interrupted,
+            8 => ErrorKind::WouldBlock,
+            9 => ErrorKind::InProgress,
+            10 => ErrorKind::TimedOut,
+            11 => ErrorKind::OutOfMemory,
+            12 => ErrorKind::Unsupported,
+            13 => ErrorKind::WriteZero,
+            14 => ErrorKind::Uninitialized,
+            15 => ErrorKind::Other,
+            _ => return None,
+        })
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.description())
+    }
+}
+
+impl fmt::Display for ErrorPayload {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ErrorPayload::Code(c) => write!(f, "code {:#x}", c),
+            ErrorPayload::Message(m) => f.write_str(m),
+            ErrorPayload::Other => f.write_str("opaque payload"),
+            ErrorPayload::Empty => f.write_str("no payload"),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    // - messages are assumed to be more specific than kinds
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.e_payload {
+            ErrorPayload::Message(m) => f.write_str(m),
+            ErrorPayload::Code(c) => write!(f, "{} (code {:#x})", self.e_kind, c),
+            _ => write!(f, "{}", self.e_kind),
+        }
+    }
+}
+
+impl core::error::Error for Error {}
+
+// - uncategorized errors map to the plain variants of `GenericError`,
+//   and back, so that round trips are lossless both ways
 impl From<Error> for GenericError {
     fn from(value: Error) -> GenericError {
+        if value.kind() != ErrorKind::Uncategorized {
+            return GenericError::Io(value);
+        }
+
         match value.payload() {
             ErrorPayload::Code(c) => GenericError::ErrorCode(c),
             ErrorPayload::Message(m) => GenericError::ErrorMessage(m),
+            ErrorPayload::Other => GenericError::Other,
             ErrorPayload::Empty => GenericError::Empty,
-            _ => GenericError::Other,
         }
     }
 }
 
+impl From<GenericError> for Error {
+    fn from(value: GenericError) -> Error {
+        let payload = match value {
+            GenericError::ErrorCode(c) => ErrorPayload::Code(c),
+            GenericError::ErrorMessage(m) => ErrorPayload::Message(m),
+            GenericError::Other => ErrorPayload::Other,
+            GenericError::Empty => ErrorPayload::Empty,
+            GenericError::Io(e) => return e,
+            GenericError::Context(c) => return *c.source(),
+        };
+
+        Error::new(ErrorKind::Uncategorized, payload)
+    }
+}
+
+impl From<fmt::Error> for Error {
+    fn from(_: fmt::Error) -> Error {
+        Error::new(ErrorKind::Other, ErrorPayload::Message("formatter error"))
+    }
+}
+
+impl From<core::str::Utf8Error> for Error {
+    fn from(_: core::str::Utf8Error) -> Error {
+        Error::new(
+            ErrorKind::InvalidData,
+            ErrorPayload::Message("invalid UTF-8"),
+        )
+    }
+}
+
+impl From<core::num::TryFromIntError> for Error {
+    fn from(_: core::num::TryFromIntError) -> Error {
+        Error::new(
+            ErrorKind::InvalidInput,
+            ErrorPayload::Message("integer out of range"),
+        )
+    }
+}
+
 /**
     Trait to mark type as readable
 
@@ -407,7 +562,99 @@ pub trait Read {
         Ok(())
     }
 
-    // TODO
+    /**
+        Like [`read`](Read::read), except that it reads into
+        a sequence of buffers, filling them in order.
+
+        The default implementation reads into the first
+        non-empty buffer only; readers that can do better
+        should override it.
+    */
+    fn read_vectored(&mut self, bufs: &mut [&mut [u8]]) -> Result<usize, Error> {
+        match bufs.iter_mut().find(|b| !b.is_empty()) {
+            Some(buf) => self.read(buf),
+            None => Ok(0),
+        }
+    }
+
+    /**
+        Reads all bytes until EOF, appending them to `buf`,
+        and returns the number of bytes read.
+
+        # Errors
+        If an error is returned, the bytes read until
+        then are still appended to `buf`.
+    */
+    #[cfg(feature = "alloc")]
+    fn read_to_end(&mut self, buf: &mut alloc::vec::Vec<u8>) -> Result<usize, Error> {
+        let start = buf.len();
+        let mut chunk = [0u8; 256];
+
+        loop {
+            match self.read(&mut chunk) {
+                Ok(0) => return Ok(buf.len() - start),
+                Ok(n) => buf.extend_from_slice(&chunk[..n.min(chunk.len())]),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /**
+        Reads all bytes until EOF, appending them to `buf`,
+        and returns the number of bytes read.
+
+        # Errors
+        If the data is not valid UTF-8, an error of the kind
+        [`ErrorKind::InvalidData`] is returned. If an error is
+        returned, nothing is appended to `buf`.
+    */
+    #[cfg(feature = "alloc")]
+    fn read_to_string(&mut self, buf: &mut alloc::string::String) -> Result<usize, Error> {
+        let mut bytes = alloc::vec::Vec::new();
+        self.read_to_end(&mut bytes)?;
+
+        match alloc::string::String::from_utf8(bytes) {
+            Ok(s) => {
+                buf.push_str(&s);
+                Ok(s.len())
+            }
+            Err(_) => error!(InvalidData, Message, "stream did not contain valid UTF-8"),
+        }
+    }
+
+    /// Creates a "by reference" adapter for this instance of `Read`
+    // - lets adapters borrow a reader rather than consume it
+    fn by_ref(&mut self) -> &mut Self
+    where
+        Self: Sized,
+    {
+        self
+    }
+
+    /// Transforms this reader into an iterator over its bytes
+    // - reads a byte at a time; wrap unbuffered readers in a `BufReader`
+    fn bytes(self) -> Bytes<Self>
+    where
+        Self: Sized,
+    {
+        Bytes::new(self)
+    }
+
+    /// Creates an adapter that reads from this reader, then from `next`
+    fn chain<R: Read>(self, next: R) -> Chain<Self, R>
+    where
+        Self: Sized,
+    {
+        Chain::new(self, next)
+    }
+
+    /// Creates an adapter that reads at most `limit` bytes from this reader
+    fn take(self, limit: u64) -> Take<Self>
+    where
+        Self: Sized,
+    {
+        Take::new(self, limit)
+    }
 }
 
 /**
@@ -496,4 +743,157 @@ pub trait Write {
     fn write_fmt(&mut self, args: fmt::Arguments<'_>) -> Result<(), Error> {
         default_write_fmt(self, args)
     }
+
+    /**
+        Like [`write`](Write::write), except that it writes
+        from a sequence of buffers, taking them in order.
+
+        The default implementation writes from the first
+        non-empty buffer only; writers that can do better
+        should override it.
+    */
+    fn write_vectored(&mut self, bufs: &[&[u8]]) -> Result<usize, Error> {
+        match bufs.iter().find(|b| !b.is_empty()) {
+            Some(buf) => self.write(buf),
+            None => Ok(0),
+        }
+    }
+
+    /// Creates a "by reference" adapter for this instance of `Write`
+    fn by_ref(&mut self) -> &mut Self
+    where
+        Self: Sized,
+    {
+        self
+    }
+}
+
+/**
+    Enumeration of the ways to move within a stream
+
+    Used by [`Seek::seek()`].
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SeekFrom {
+    /// Offset from the start of the stream
+    Start(u64),
+    /// Offset from the end of the stream, which may be negative
+    End(i64),
+    /// Offset from the current position, which may be negative
+    Current(i64),
+}
+
+/**
+    Trait to mark type as seekable
+
+    Implementors of `Seek` keep a cursor that can be moved within
+    the stream they read from or write to. Seeking past the end is
+    allowed, with behavior defined by the implementor, but seeking
+    before the start is an error of the kind [`ErrorKind::InvalidInput`].
+*/
+pub trait Seek {
+    /**
+        Moves the cursor to the position given by `pos`, and
+        returns the new position from the start of the stream.
+
+        # Errors
+        Seeking to a negative offset is an error. The
+        cursor is left in place if an error is returned.
+    */
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64, Error>;
+
+    /* Given implementations */
+
+    /// Moves the cursor to the start of the stream
+    fn rewind(&mut self) -> Result<(), Error> {
+        self.seek(SeekFrom::Start(0)).map(|_| ())
+    }
+
+    /// Returns the position of the cursor from the start of the stream
+    fn stream_position(&mut self) -> Result<u64, Error> {
+        self.seek(SeekFrom::Current(0))
+    }
 }
+
+/// Returns the position that `offset` leads to from `base`, if it's not negative
+// - shared by implementors of `Seek`
+pub(crate) fn seek_offset(base: u64, offset: i64) -> Result<u64, Error> {
+    match base.checked_add_signed(offset) {
+        Some(n) => Ok(n),
+        None => error!(
+            InvalidInput,
+            Message, "invalid seek to a negative or overflowing position"
+        ),
+    }
+}
+
+impl<R: Read + ?Sized> Read for &mut R {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        (**self).read(buf)
+    }
+
+    fn read_vectored(&mut self, bufs: &mut [&mut [u8]]) -> Result<usize, Error> {
+        (**self).read_vectored(bufs)
+    }
+}
+
+impl<W: Write + ?Sized> Write for &mut W {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        (**self).write(buf)
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        (**self).flush()
+    }
+
+    fn write_vectored(&mut self, bufs: &[&[u8]]) -> Result<usize, Error> {
+        (**self).write_vectored(bufs)
+    }
+}
+
+impl<S: Seek + ?Sized> Seek for &mut S {
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64, Error> {
+        (**self).seek(pos)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Write for alloc::vec::Vec<u8> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        self.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn write_vectored(&mut self, bufs: &[&[u8]]) -> Result<usize, Error> {
+        let start = self.len();
+        for buf in bufs {
+            self.extend_from_slice(buf);
+        }
+
+        Ok(self.len() - start)
+    }
+}
+
+/* Buffered I/O */
+
+// Buffered readers and writers
+mod buffered;
+pub use buffered::{BufRead, BufReader, BufWriter, DEFAULT_BUF_SIZE, LineWriter, Lines};
+
+// In-memory reader and writer
+mod cursor;
+pub use cursor::Cursor;
+
+/* Adapters and utilities */
+
+// Adapters returned by `Read`
+mod adapters;
+pub use adapters::{Bytes, Chain, Take};
+
+// Copying, placeholder readers and writers, and `Tee`
+mod util;
+pub use util::{Empty, Repeat, Sink, Tee, copy, empty, repeat, sink};
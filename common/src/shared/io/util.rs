@@ -0,0 +1,285 @@
+/*
+    Portions of this file are derived from the Rust standard library
+    (std::io), originally licensed under the MIT license.
+
+    Copyright (c) The Rust Project Constributors
+
+    Licensed under the MIT License. A copy is provided in `/LICENSE`.
+
+    Modifications (if any) are Copyright (c) 2026 John Isaac Calderon
+*/
+
+/*!
+    Copying, placeholder readers and writers, and [`Tee`]
+
+    # Usage
+    ```rust,ignore
+    // Mirror the console onto the serial port
+    let mut out = Tee::new(vga, serial);
+    writeln!(out, "hello from both")?;
+
+    // Copy an image through a stack buffer
+    let mut buf = [0u8; 512];
+    let n = io::copy(&mut file, &mut out, &mut buf)?;
+    ```
+*/
+
+// Definition uses
+use super::{BufRead, Error, ErrorKind, ErrorPayload, Read, Seek, SeekFrom, Write};
+
+/**
+    Copy the whole contents of `reader` to `writer`, through
+    `buf`, and return the number of bytes copied
+
+    # Errors
+    Errors from either side are propagated as soon as they
+    occur; it's unspecified how much was copied by then.
+    An empty `buf` is an error of the kind
+    [`ErrorKind::InvalidInput`].
+*/
+pub fn copy<R, W>(reader: &mut R, writer: &mut W, buf: &mut [u8]) -> Result<u64, Error>
+where
+    R: Read + ?Sized,
+    W: Write + ?Sized,
+{
+    if buf.is_empty() {
+        return error!(InvalidInput, Message, "copy buffer is empty");
+    }
+
+    let mut copied = 0;
+
+    loop {
+        let n = reader.read(buf)?.min(buf.len());
+        if n == 0 {
+            return Ok(copied);
+        }
+
+        writer.write_all(&buf[..n])?;
+        copied += n as u64;
+    }
+}
+
+/// Writer that discards everything written to it (refer to [`sink()`])
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Sink;
+
+/// Returns a writer that discards everything written to it
+pub const fn sink() -> Sink {
+    Sink
+}
+
+impl Write for Sink {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn write_vectored(&mut self, bufs: &[&[u8]]) -> Result<usize, Error> {
+        Ok(bufs.iter().map(|b| b.len()).sum())
+    }
+}
+
+/// Reader that is always at EOF (refer to [`empty()`])
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Empty;
+
+/**
+    Returns a reader that is always at EOF
+
+    It doubles as a writer that discards everything, like [`sink()`].
+*/
+pub const fn empty() -> Empty {
+    Empty
+}
+
+impl Read for Empty {
+    fn read(&mut self, _buf: &mut [u8]) -> Result<usize, Error> {
+        Ok(0)
+    }
+}
+
+impl BufRead for Empty {
+    fn fill_buf(&mut self) -> Result<&[u8], Error> {
+        Ok(&[])
+    }
+
+    fn consume(&mut self, _amt: usize) {}
+}
+
+impl Write for Empty {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl Seek for Empty {
+    fn seek(&mut self, _pos: SeekFrom) -> Result<u64, Error> {
+        Ok(0)
+    }
+}
+
+/// Reader that yields one byte over and over (refer to [`repeat()`])
+#[derive(Debug, Clone, Copy)]
+pub struct Repeat {
+    byte: u8,
+}
+
+/// Returns a reader that yields `byte` over and over
+pub const fn repeat(byte: u8) -> Repeat {
+    Repeat { byte }
+}
+
+impl Read for Repeat {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        buf.fill(self.byte);
+        Ok(buf.len())
+    }
+
+    fn read_vectored(&mut self, bufs: &mut [&mut [u8]]) -> Result<usize, Error> {
+        let mut n = 0;
+        for buf in bufs.iter_mut() {
+            n += self.read(buf)?;
+        }
+
+        Ok(n)
+    }
+}
+
+/**
+    Writer that duplicates its output to two writers
+
+    Data is written to the first writer, and whatever it
+    accepts is then written in full to the second one, so
+    that both always receive the same bytes. An error from
+    the second writer is reported even if the first one
+    succeeded.
+*/
+#[derive(Debug)]
+pub struct Tee<A, B> {
+    first: A,
+    second: B,
+}
+
+impl<A: Write, B: Write> Tee<A, B> {
+    /// Create new instance of `Tee`, duplicating output to `first` and `second`
+    pub const fn new(first: A, second: B) -> Self {
+        Tee { first, second }
+    }
+
+    /// Consumes the writer, returning both writers
+    pub fn into_inner(self) -> (A, B) {
+        (self.first, self.second)
+    }
+
+    /// Returns references to both writers
+    pub fn get_ref(&self) -> (&A, &B) {
+        (&self.first, &self.second)
+    }
+
+    /// Returns mutable references to both writers
+    pub fn get_mut(&mut self) -> (&mut A, &mut B) {
+        (&mut self.first, &mut self.second)
+    }
+}
+
+impl<A: Write, B: Write> Write for Tee<A, B> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        let n = self.first.write(buf)?.min(buf.len());
+        self.second.write_all(&buf[..n])?;
+
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        // - flush both, even if the first one fails
+        let first = self.first.flush();
+        let second = self.second.flush();
+
+        first.and(second)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::io::Cursor;
+    use crate::shared::structs::array_vec::ArrayVec;
+
+    #[test]
+    fn copy_through_buffer() {
+        let mut src = Cursor::new(b"the quick brown fox");
+        let mut dst: ArrayVec<u8, 32> = ArrayVec::new();
+        let mut buf = [0u8; 4];
+
+        assert_eq!(copy(&mut src, &mut dst, &mut buf).unwrap(), 19);
+        assert_eq!(&dst[..], b"the quick brown fox");
+
+        let e = copy(&mut src, &mut dst, &mut []).unwrap_err();
+        assert!(matches!(e.kind(), ErrorKind::InvalidInput));
+
+        // - the writer fills up
+        let mut small: ArrayVec<u8, 8> = ArrayVec::new();
+        src.rewind().unwrap();
+        assert!(copy(&mut src, &mut small, &mut buf).is_err());
+    }
+
+    #[test]
+    fn placeholders() {
+        let mut buf = [0u8; 4];
+
+        assert_eq!(empty().read(&mut buf).unwrap(), 0);
+        assert_eq!(sink().write(b"abc").unwrap(), 3);
+        assert_eq!(sink().write_vectored(&[b"ab", b"c"]).unwrap(), 3);
+
+        assert_eq!(repeat(7).read(&mut buf).unwrap(), 4);
+        assert_eq!(buf, [7; 4]);
+
+        let mut taken = [0u8; 8];
+        let n = repeat(1).take(5).read(&mut taken).unwrap();
+        assert_eq!(&taken[..n], [1; 5]);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn read_to_end() {
+        let mut v = alloc::vec::Vec::from(*b">");
+        let mut r = repeat(b'x').take(300);
+        assert_eq!(r.read_to_end(&mut v).unwrap(), 300);
+        assert_eq!(v.len(), 301);
+
+        let mut s = alloc::string::String::new();
+        let mut r = Cursor::new("héllo").chain(Cursor::new(" world"));
+        assert_eq!(r.read_to_string(&mut s).unwrap(), 12);
+        assert_eq!(s, "héllo world");
+
+        let e = Cursor::new(b"\xff").read_to_string(&mut s).unwrap_err();
+        assert!(matches!(e.kind(), ErrorKind::InvalidData));
+        assert_eq!(s, "héllo world");
+    }
+
+    #[test]
+    fn tee() {
+        let mut a: ArrayVec<u8, 16> = ArrayVec::new();
+        let mut b: ArrayVec<u8, 16> = ArrayVec::new();
+
+        {
+            let mut t = Tee::new(&mut a, &mut b);
+            t.write_all(b"both").unwrap();
+            t.flush().unwrap();
+        }
+        assert_eq!(&a[..], b"both");
+        assert_eq!(&b[..], b"both");
+
+        // - the second writer only gets what the first one took
+        let mut short: ArrayVec<u8, 2> = ArrayVec::new();
+        let mut t = Tee::new(&mut short, sink());
+        assert_eq!(t.write(b"abc").unwrap(), 2);
+    }
+}
@@ -0,0 +1,814 @@
+/*!
+    Kernel message log ("dmesg")
+
+    [`LogRing`] records log lines in a fixed-size byte ring, each
+    tagged with a sequence number, a timestamp and a [`Level`]. The
+    oldest lines are discarded as new ones come in, but their
+    sequence numbers are never reused, so readers can resume from
+    any position and tell how many lines they have missed.
+
+    # Handoff
+    The ring lives in a caller-provided buffer, and keeps all of
+    its state in that buffer. The bootloader can therefore pass the
+    buffer's physical range (refer to [`LogRing::span()`]) to the
+    kernel through the native handoff (refer to [`KernHandoff`]),
+    which can then take over with [`LogRing::adopt()`] and keep
+    appending to the boot log.
+
+    [`KernHandoff`]: crate::plat::pc_bios::handoff::KernHandoff
+
+    # Layout
+    All integers are little-endian. The buffer starts with a header:
+
+    | Offset | Size | Description                                  |
+    |--------|------|----------------------------------------------|
+    | 0      | 4    | Magic number ([`RING_MAGIC`])                |
+    | 4      | 4    | Capacity of the data area, in bytes          |
+    | 8      | 8    | Running byte offset of the next entry        |
+    | 16     | 8    | Running byte offset of the oldest entry      |
+    | 24     | 8    | Sequence number of the oldest entry          |
+    | 32     | 8    | Sequence number of the next entry            |
+
+    The remainder of the buffer is the data area, in which entries
+    are stored back to back, wrapping around at its end:
+
+    | Offset | Size | Description                                  |
+    |--------|------|----------------------------------------------|
+    | 0      | 2    | Length of the message, in bytes              |
+    | 2      | 1    | Level (refer to [`Level`])                   |
+    | 3      | 5    | Reserved (zero)                              |
+    | 8      | 8    | Sequence number                              |
+    | 16     | 8    | Timestamp, in milliseconds                   |
+    | 24     | *n*  | Message                                      |
+*/
+
+// Standard definitions
+use core::fmt;
+
+// Internal definitions
+use super::{Level, LogSink, PANIC_LOCK_ATTEMPTS, Record};
+use crate::shared::GenericError;
+use crate::shared::io::{Error, Write};
+use crate::shared::mm::RegionSpan;
+use crate::shared::structs::spin_lock::Mutex;
+use crate::shared::traits::Clock;
+
+/// Magic number identifying an initialized ring (`"DMSG"`)
+pub const RING_MAGIC: u32 = u32::from_le_bytes(*b"DMSG");
+
+/// Size of the ring header, in bytes
+pub const HEADER_LEN: usize = 40;
+
+/// Size of the entry header, in bytes
+pub const ENTRY_HEADER_LEN: usize = 24;
+
+/// Maximum length of a single message, in bytes
+pub const MAX_MESSAGE_LEN: usize = u16::MAX as usize;
+
+// Size of the message buffer used by `LogRing::dump()`
+const DUMP_BUF_LEN: usize = 256;
+
+// Header field offsets
+const OFF_MAGIC: usize = 0;
+const OFF_CAPACITY: usize = 4;
+const OFF_HEAD: usize = 8;
+const OFF_TAIL: usize = 16;
+const OFF_FIRST_SEQ: usize = 24;
+const OFF_NEXT_SEQ: usize = 32;
+
+// Entry field offsets
+const ENT_LEN: usize = 0;
+const ENT_LEVEL: usize = 2;
+const ENT_SEQ: usize = 8;
+const ENT_TIMESTAMP: usize = 16;
+
+/// Metadata of a single log entry
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct LogEntry {
+    seq: u64,
+    timestamp: u64,
+    level: Level,
+    len: usize,
+}
+
+impl LogEntry {
+    /// Returns the sequence number
+    pub fn seq(&self) -> u64 {
+        self.seq
+    }
+
+    /// Returns the timestamp, in milliseconds
+    pub fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    /// Returns the level
+    pub fn level(&self) -> Level {
+        self.level
+    }
+
+    /// Returns the full length of the message, in bytes
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Checks whether the message is empty
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+/**
+    Fixed-size log ring with sequence-numbered entries
+
+    Rings are usually wrapped in a [`Mutex`], in which case they
+    serve as log sinks. A detached ring (refer to [`detached()`])
+    discards everything written to it, which allows declaring the
+    ring as a static before its buffer is available.
+    ```rust
+    # use common::shared::GenericError;
+    # use common::shared::log::{self, LevelFilter, kmsg::LogRing};
+    # use common::shared::structs::spin_lock::Mutex;
+    # fn main() -> Result<(), GenericError> {
+    # let buf = Box::leak(Box::new([0u8; 4096]));
+    static DMESG: Mutex<LogRing<'static>> = Mutex::new(LogRing::detached());
+
+    *DMESG.lock() = LogRing::new(buf)?;
+    log::register_sink(&DMESG, LevelFilter::Trace)?;
+    # Ok(())
+    # }
+    ```
+
+    [`detached()`]: LogRing::detached
+*/
+pub struct LogRing<'a> {
+    buf: &'a mut [u8],
+    clock: Option<&'a mut (dyn Clock + Send)>,
+}
+
+impl<'a> LogRing<'a> {
+    /// Create new instance of `LogRing` without a buffer
+    pub const fn detached() -> Self {
+        LogRing {
+            buf: &mut [],
+            clock: None,
+        }
+    }
+
+    /**
+        Create new, empty instance of `LogRing` in the provided buffer
+
+        Returns an error if the buffer cannot hold at least one entry
+        header in addition to the ring header.
+    */
+    pub fn new(buf: &'a mut [u8]) -> Result<Self, GenericError> {
+        let capacity = Self::check_len(buf.len())?;
+
+        let mut ring = LogRing { buf, clock: None };
+        ring.set_u32(OFF_MAGIC, RING_MAGIC);
+        ring.set_u32(OFF_CAPACITY, capacity as u32);
+        ring.set_u64(OFF_HEAD, 0);
+        ring.set_u64(OFF_TAIL, 0);
+        ring.set_u64(OFF_FIRST_SEQ, 0);
+        ring.set_u64(OFF_NEXT_SEQ, 0);
+
+        Ok(ring)
+    }
+
+    /**
+        Take over an existing ring in the provided buffer
+
+        The header and every entry are validated, so that a corrupted
+        or uninitialized buffer is rejected rather than misread.
+    */
+    pub fn adopt(buf: &'a mut [u8]) -> Result<Self, GenericError> {
+        let capacity = Self::check_len(buf.len())?;
+        let ring = LogRing { buf, clock: None };
+
+        if ring.get_u32(OFF_MAGIC) != RING_MAGIC {
+            return Err(GenericError::ErrorMessage(
+                "log ring has a bad magic number",
+            ));
+        }
+        if ring.get_u32(OFF_CAPACITY) as usize != capacity {
+            return Err(GenericError::ErrorMessage(
+                "log ring capacity does not match its buffer",
+            ));
+        }
+
+        let (head, tail) = (ring.head(), ring.tail());
+        let (first, next) = (ring.first_seq(), ring.next_seq());
+
+        if tail > head || head - tail > capacity as u64 || first > next {
+            return Err(GenericError::ErrorMessage("log ring has an invalid header"));
+        }
+
+        // - walk the entries, which must exactly fill `tail..head`
+        let mut off = tail;
+        for seq in first..next {
+            if off + ENTRY_HEADER_LEN as u64 > head || ring.entry_u64(off, ENT_SEQ) != seq {
+                return Err(GenericError::ErrorMessage("log ring has a corrupted entry"));
+            }
+            off += ring.entry_size(off);
+        }
+
+        if off != head {
+            return Err(GenericError::ErrorMessage("log ring has a corrupted entry"));
+        }
+
+        Ok(ring)
+    }
+
+    /// Attach a clock, which is used to timestamp new entries
+    pub fn set_clock(&mut self, clock: &'a mut (dyn Clock + Send)) {
+        self.clock = Some(clock);
+    }
+
+    /// Returns the memory range occupied by the ring, including its header
+    pub fn span(&self) -> RegionSpan {
+        RegionSpan::new(self.buf.as_ptr() as usize, self.buf.len())
+    }
+
+    /// Checks whether the ring has a buffer
+    pub fn is_attached(&self) -> bool {
+        !self.buf.is_empty()
+    }
+
+    /// Returns the capacity of the data area, in bytes
+    pub fn capacity(&self) -> usize {
+        self.buf.len().saturating_sub(HEADER_LEN)
+    }
+
+    /// Returns the maximum length of a single message, in bytes
+    pub fn max_message_len(&self) -> usize {
+        (self.capacity().saturating_sub(ENTRY_HEADER_LEN)).min(MAX_MESSAGE_LEN)
+    }
+
+    /// Returns the sequence number of the oldest retained entry
+    pub fn first_seq(&self) -> u64 {
+        if self.is_attached() {
+            self.get_u64(OFF_FIRST_SEQ)
+        } else {
+            0
+        }
+    }
+
+    /// Returns the sequence number that the next entry will receive
+    pub fn next_seq(&self) -> u64 {
+        if self.is_attached() {
+            self.get_u64(OFF_NEXT_SEQ)
+        } else {
+            0
+        }
+    }
+
+    /// Returns the number of retained entries
+    pub fn len(&self) -> usize {
+        (self.next_seq() - self.first_seq()) as usize
+    }
+
+    /// Checks whether the ring retains no entries
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Discard all entries, without resetting the sequence numbers
+    pub fn clear(&mut self) {
+        if self.is_attached() {
+            self.set_u64(OFF_TAIL, self.head());
+            self.set_u64(OFF_FIRST_SEQ, self.next_seq());
+        }
+    }
+
+    /**
+        Append an entry, returning its sequence number
+
+        Messages longer than [`max_message_len()`] are truncated.
+        Returns `None` if the ring is detached.
+
+        [`max_message_len()`]: LogRing::max_message_len
+    */
+    pub fn push(&mut self, level: Level, msg: &[u8]) -> Option<u64> {
+        let mut w = EntryWriter::begin(self, level)?;
+        w.append(msg);
+        Some(w.commit())
+    }
+
+    /// Append a formatted entry, returning its sequence number (refer to [`push()`])
+    ///
+    /// [`push()`]: LogRing::push
+    pub fn push_fmt(&mut self, level: Level, args: fmt::Arguments<'_>) -> Option<u64> {
+        let mut w = EntryWriter::begin(self, level)?;
+
+        // - the writer never fails, but truncates instead
+        let _ = fmt::Write::write_fmt(&mut w, args);
+        Some(w.commit())
+    }
+
+    /**
+        Read the entry with sequence number `seq`
+
+        If the entry has already been discarded, then the oldest
+        retained entry is read instead, which callers can tell by
+        its sequence number. The message is copied into `out`, and
+        truncated if `out` is too small.
+
+        Returns `None` if there is no such entry yet.
+    */
+    pub fn read(&self, seq: u64, out: &mut [u8]) -> Option<LogEntry> {
+        let seq = seq.max(self.first_seq());
+        if seq >= self.next_seq() {
+            return None;
+        }
+
+        Some(self.read_at(seq, self.offset_of(seq), out))
+    }
+
+    /**
+        Write all retained entries to `w`, one line per entry
+
+        Each line carries the timestamp in seconds and the level tag,
+        followed by the message, which is truncated to 256 bytes.
+    */
+    pub fn dump<W: Write>(&self, w: &mut W) -> Result<(), Error> {
+        let mut reader = LogReader::new(self.first_seq());
+        let mut buf = [0u8; DUMP_BUF_LEN];
+
+        while let Some(e) = reader.read_next(self, &mut buf) {
+            write!(
+                w,
+                "[{:>5}.{:0>3}] {}: ",
+                e.timestamp() / 1000,
+                e.timestamp() % 1000,
+                e.level().tag()
+            )?;
+            w.write_all(&buf[..e.len().min(DUMP_BUF_LEN)])?;
+            w.write_all(b"\n")?;
+        }
+
+        w.flush()
+    }
+
+    // Internal: validate buffer length, returning the data capacity
+    fn check_len(len: usize) -> Result<usize, GenericError> {
+        let capacity = len.saturating_sub(HEADER_LEN);
+
+        if capacity <= ENTRY_HEADER_LEN || capacity > u32::MAX as usize {
+            Err(GenericError::ErrorMessage(
+                "log ring buffer has an unusable size",
+            ))
+        } else {
+            Ok(capacity)
+        }
+    }
+
+    // Internal: locate a retained entry
+    // - entries vary in size, so walk up to the requested one
+    fn offset_of(&self, seq: u64) -> u64 {
+        let mut off = self.tail();
+        for _ in self.first_seq()..seq {
+            off += self.entry_size(off);
+        }
+        off
+    }
+
+    // Internal: read the retained entry `seq`, located at `off`
+    fn read_at(&self, seq: u64, off: u64, out: &mut [u8]) -> LogEntry {
+        let len = self.entry_u16(off, ENT_LEN) as usize;
+        let n = len.min(out.len());
+        self.read_data(off + ENTRY_HEADER_LEN as u64, &mut out[..n]);
+
+        LogEntry {
+            seq,
+            timestamp: self.entry_u64(off, ENT_TIMESTAMP),
+            level: Level::from_u8(self.entry_u8(off, ENT_LEVEL)).unwrap_or(Level::Error),
+            len,
+        }
+    }
+
+    // Internal: header accessors
+    fn head(&self) -> u64 {
+        self.get_u64(OFF_HEAD)
+    }
+
+    fn tail(&self) -> u64 {
+        self.get_u64(OFF_TAIL)
+    }
+
+    fn get_u32(&self, at: usize) -> u32 {
+        let mut b = [0u8; 4];
+        b.copy_from_slice(&self.buf[at..at + 4]);
+        u32::from_le_bytes(b)
+    }
+
+    fn set_u32(&mut self, at: usize, val: u32) {
+        self.buf[at..at + 4].copy_from_slice(&val.to_le_bytes());
+    }
+
+    fn get_u64(&self, at: usize) -> u64 {
+        let mut b = [0u8; 8];
+        b.copy_from_slice(&self.buf[at..at + 8]);
+        u64::from_le_bytes(b)
+    }
+
+    fn set_u64(&mut self, at: usize, val: u64) {
+        self.buf[at..at + 8].copy_from_slice(&val.to_le_bytes());
+    }
+
+    // Internal: entry accessors, taking running byte offsets
+    fn entry_u8(&self, off: u64, field: usize) -> u8 {
+        let mut b = [0u8; 1];
+        self.read_data(off + field as u64, &mut b);
+        b[0]
+    }
+
+    fn entry_u16(&self, off: u64, field: usize) -> u16 {
+        let mut b = [0u8; 2];
+        self.read_data(off + field as u64, &mut b);
+        u16::from_le_bytes(b)
+    }
+
+    fn entry_u64(&self, off: u64, field: usize) -> u64 {
+        let mut b = [0u8; 8];
+        self.read_data(off + field as u64, &mut b);
+        u64::from_le_bytes(b)
+    }
+
+    fn entry_size(&self, off: u64) -> u64 {
+        (ENTRY_HEADER_LEN + self.entry_u16(off, ENT_LEN) as usize) as u64
+    }
+
+    // Internal: copy out of the data area, wrapping around at its end
+    fn read_data(&self, off: u64, out: &mut [u8]) {
+        let cap = self.capacity();
+        let start = (off % cap as u64) as usize;
+        let n = out.len().min(cap - start);
+        let rest = out.len() - n;
+
+        out[..n].copy_from_slice(&self.buf[HEADER_LEN + start..HEADER_LEN + start + n]);
+        out[n..].copy_from_slice(&self.buf[HEADER_LEN..HEADER_LEN + rest]);
+    }
+
+    // Internal: copy into the data area, wrapping around at its end
+    fn write_data(&mut self, off: u64, bytes: &[u8]) {
+        let cap = self.capacity();
+        let start = (off % cap as u64) as usize;
+        let n = bytes.len().min(cap - start);
+
+        self.buf[HEADER_LEN + start..HEADER_LEN + start + n].copy_from_slice(&bytes[..n]);
+        self.buf[HEADER_LEN..HEADER_LEN + bytes.len() - n].copy_from_slice(&bytes[n..]);
+    }
+
+    // Internal: discard the oldest entries until `end` fits
+    // - `end` must not lie more than `capacity` bytes past the head
+    fn reserve(&mut self, end: u64) {
+        let cap = self.capacity() as u64;
+        let mut tail = self.tail();
+        let mut first = self.first_seq();
+
+        while end - tail > cap {
+            tail += self.entry_size(tail);
+            first += 1;
+        }
+
+        self.set_u64(OFF_TAIL, tail);
+        self.set_u64(OFF_FIRST_SEQ, first);
+    }
+}
+
+impl Default for LogRing<'_> {
+    fn default() -> Self {
+        Self::detached()
+    }
+}
+
+// Internal: entry under construction
+// - the head is only advanced on commit, so that a partially
+//   written entry (say, due to a panic while formatting) is
+//   never visible to readers or to an adopting kernel
+struct EntryWriter<'r, 'a> {
+    ring: &'r mut LogRing<'a>,
+    start: u64,
+    len: usize,
+}
+
+impl<'r, 'a> EntryWriter<'r, 'a> {
+    fn begin(ring: &'r mut LogRing<'a>, level: Level) -> Option<Self> {
+        if !ring.is_attached() {
+            return None;
+        }
+
+        let timestamp = ring.clock.as_mut().map_or(0, |c| c.millis());
+        let start = ring.head();
+        let seq = ring.next_seq();
+
+        ring.reserve(start + ENTRY_HEADER_LEN as u64);
+
+        let mut hdr = [0u8; ENTRY_HEADER_LEN];
+        hdr[ENT_LEVEL] = level as u8;
+        hdr[ENT_SEQ..ENT_SEQ + 8].copy_from_slice(&seq.to_le_bytes());
+        hdr[ENT_TIMESTAMP..ENT_TIMESTAMP + 8].copy_from_slice(&timestamp.to_le_bytes());
+        ring.write_data(start, &hdr);
+
+        Some(EntryWriter {
+            ring,
+            start,
+            len: 0,
+        })
+    }
+
+    fn append(&mut self, bytes: &[u8]) {
+        let n = bytes.len().min(self.ring.max_message_len() - self.len);
+        let off = self.start + (ENTRY_HEADER_LEN + self.len) as u64;
+
+        self.ring.reserve(off + n as u64);
+        self.ring.write_data(off, &bytes[..n]);
+        self.len += n;
+    }
+
+    fn commit(self) -> u64 {
+        let seq = self.ring.next_seq();
+
+        self.ring.write_data(
+            self.start + ENT_LEN as u64,
+            &(self.len as u16).to_le_bytes(),
+        );
+        self.ring
+            .set_u64(OFF_HEAD, self.start + (ENTRY_HEADER_LEN + self.len) as u64);
+        self.ring.set_u64(OFF_NEXT_SEQ, seq + 1);
+
+        seq
+    }
+}
+
+impl fmt::Write for EntryWriter<'_, '_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.append(s.as_bytes());
+        Ok(())
+    }
+}
+
+impl LogSink for Mutex<LogRing<'_>> {
+    fn log(&self, record: &Record<'_>, panicking: bool) {
+        let f = |ring: &mut LogRing<'_>| {
+            ring.push_fmt(
+                record.level(),
+                format_args!("[{}] {}", record.target(), record.args()),
+            )
+        };
+
+        // - arbitrate first, then use force (refer to the
+        //   implementation for writers)
+        if panicking {
+            match self.try_lock_repeat(PANIC_LOCK_ATTEMPTS) {
+                Ok(mut g) => f(&mut g),
+                Err(()) => f(unsafe { self.get_mut() }),
+            };
+        } else {
+            f(&mut self.lock());
+        }
+    }
+}
+
+/**
+    Cursor into a [`LogRing`]
+
+    Readers only hold a position in the ring, so any number of
+    them can follow the same ring independently. A reader must
+    only ever be used with a single ring.
+*/
+#[derive(Copy, Clone, Debug)]
+pub struct LogReader {
+    next: u64,
+    dropped: u64,
+    // - running byte offset of `next`, once known
+    off: Option<u64>,
+}
+
+impl LogReader {
+    /// Create new instance of `LogReader`, starting at sequence number `seq`
+    pub const fn new(seq: u64) -> Self {
+        LogReader {
+            next: seq,
+            dropped: 0,
+            off: None,
+        }
+    }
+
+    /// Returns the sequence number of the next entry to be read
+    pub fn seq(&self) -> u64 {
+        self.next
+    }
+
+    /// Returns the number of entries discarded before they could be read
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    /**
+        Read the next entry, if any (refer to [`LogRing::read()`])
+
+        The position of the next entry is kept, so that reading
+        consecutive entries doesn't walk the ring every time.
+    */
+    pub fn read_next(&mut self, ring: &LogRing<'_>, out: &mut [u8]) -> Option<LogEntry> {
+        let seq = self.next.max(ring.first_seq());
+        if seq >= ring.next_seq() {
+            return None;
+        }
+
+        // - running offsets are never reused, so the kept offset
+        //   stays valid for as long as the entry is retained
+        let off = match self.off {
+            Some(off) if seq == self.next => off,
+            _ => ring.offset_of(seq),
+        };
+        let e = ring.read_at(seq, off, out);
+
+        self.dropped += seq - self.next;
+        self.next = seq + 1;
+        self.off = Some(off + ring.entry_size(off));
+
+        Some(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Size of the test rings (room for four 8-byte messages)
+    const RING_LEN: usize = HEADER_LEN + 4 * (ENTRY_HEADER_LEN + 8);
+
+    // Internal: read entry `seq`, returning its sequence number, message and length
+    fn message(ring: &LogRing<'_>, seq: u64) -> (u64, [u8; 16], usize) {
+        let mut out = [0u8; 16];
+        let e = ring.read(seq, &mut out).unwrap();
+        (e.seq(), out, e.len())
+    }
+
+    #[test]
+    fn push_and_read() {
+        let mut buf = [0u8; RING_LEN];
+        let mut ring = LogRing::new(&mut buf).unwrap();
+        assert!(ring.is_empty());
+
+        assert_eq!(ring.push(Level::Info, b"first"), Some(0));
+        assert_eq!(ring.push(Level::Warn, b"second"), Some(1));
+        assert_eq!((ring.first_seq(), ring.next_seq()), (0, 2));
+
+        let mut out = [0u8; 16];
+        let e = ring.read(1, &mut out).unwrap();
+        assert_eq!((e.seq(), e.level(), e.len()), (1, Level::Warn, 6));
+        assert_eq!(&out[..6], b"second");
+
+        // - short buffers truncate, and unwritten entries don't exist
+        let mut short = [0u8; 3];
+        assert_eq!(ring.read(0, &mut short).unwrap().len(), 5);
+        assert_eq!(&short, b"fir");
+        assert!(ring.read(2, &mut out).is_none());
+    }
+
+    #[test]
+    fn wraparound() {
+        let mut buf = [0u8; RING_LEN];
+        let mut ring = LogRing::new(&mut buf).unwrap();
+
+        // - the oldest entries make room for new ones
+        for i in 0..10u8 {
+            ring.push(Level::Info, &[b'0' + i; 8]);
+        }
+        assert_eq!((ring.first_seq(), ring.next_seq()), (6, 10));
+        assert_eq!(ring.len(), 4);
+
+        // - entries straddle the end of the data area
+        //   by now, and still read back intact
+        for seq in 6..10 {
+            let (s, out, len) = message(&ring, seq);
+            assert_eq!((s, len), (seq, 8));
+            assert_eq!(&out[..8], &[b'0' + seq as u8; 8]);
+        }
+
+        // - discarded entries read as the oldest one
+        assert_eq!(message(&ring, 0).0, 6);
+
+        // - oversized messages are truncated to fit the ring
+        ring.push(Level::Info, &[b'x'; 512]);
+        assert_eq!(ring.len(), 1);
+        assert_eq!(message(&ring, 10).2, ring.max_message_len());
+
+        ring.clear();
+        assert!(ring.is_empty());
+        assert_eq!(ring.next_seq(), 11);
+    }
+
+    #[test]
+    fn adopt_valid() {
+        let mut buf = [0u8; RING_LEN];
+        {
+            let mut ring = LogRing::new(&mut buf).unwrap();
+            for i in 0..6u8 {
+                ring.push(Level::Debug, &[b'a' + i; 8]);
+            }
+        }
+
+        let mut ring = LogRing::adopt(&mut buf).unwrap();
+        assert_eq!((ring.first_seq(), ring.next_seq()), (2, 6));
+        assert_eq!(&message(&ring, 5).1[..8], b"ffffffff");
+
+        // - the adopting side keeps appending
+        assert_eq!(ring.push(Level::Info, b"kernel"), Some(6));
+    }
+
+    #[test]
+    fn adopt_rejects_bad_headers() {
+        let fresh = || {
+            let mut buf = [0u8; RING_LEN];
+            let mut ring = LogRing::new(&mut buf).unwrap();
+            ring.push(Level::Info, b"boot");
+            ring.push(Level::Info, b"log");
+            buf
+        };
+        let set = |buf: &mut [u8], at: usize, val: u64| {
+            buf[at..at + 8].copy_from_slice(&val.to_le_bytes());
+        };
+
+        // - uninitialized buffers, and undersized ones
+        assert!(LogRing::adopt(&mut [0u8; RING_LEN]).is_err());
+        assert!(LogRing::adopt(&mut fresh()[..HEADER_LEN + 8]).is_err());
+
+        // - capacity that doesn't match the buffer
+        assert!(LogRing::adopt(&mut fresh()[..RING_LEN - 8]).is_err());
+
+        // - tail past the head, and sequence numbers out of order
+        let mut buf = fresh();
+        set(&mut buf, OFF_TAIL, 1 << 20);
+        assert!(LogRing::adopt(&mut buf).is_err());
+
+        let mut buf = fresh();
+        set(&mut buf, OFF_FIRST_SEQ, 3);
+        assert!(LogRing::adopt(&mut buf).is_err());
+
+        // - entries that don't match the header
+        let mut buf = fresh();
+        set(&mut buf, OFF_NEXT_SEQ, 3);
+        assert!(LogRing::adopt(&mut buf).is_err());
+
+        let mut buf = fresh();
+        set(&mut buf, HEADER_LEN + ENT_SEQ, 7);
+        assert!(LogRing::adopt(&mut buf).is_err());
+
+        assert!(LogRing::adopt(&mut fresh()).is_ok());
+    }
+
+    #[test]
+    fn reader_counts_dropped() {
+        let mut buf = [0u8; RING_LEN];
+        let mut ring = LogRing::new(&mut buf).unwrap();
+        let mut reader = LogReader::new(0);
+        let mut out = [0u8; 16];
+
+        ring.push(Level::Info, b"one");
+        ring.push(Level::Info, b"two");
+        assert_eq!(reader.read_next(&ring, &mut out).unwrap().seq(), 0);
+        assert_eq!(reader.dropped(), 0);
+
+        // - the reader falls behind while the ring wraps
+        for _ in 0..6 {
+            ring.push(Level::Info, &[b'z'; 8]);
+        }
+        assert_eq!(reader.read_next(&ring, &mut out).unwrap().seq(), 4);
+        assert_eq!((reader.dropped(), reader.seq()), (3, 5));
+
+        // - consecutive reads follow the kept position
+        for seq in 5..8 {
+            let e = reader.read_next(&ring, &mut out).unwrap();
+            assert_eq!((e.seq(), e.len()), (seq, 8));
+        }
+        assert!(reader.read_next(&ring, &mut out).is_none());
+        assert_eq!(reader.dropped(), 3);
+
+        // - and resume once more entries come in
+        ring.push(Level::Info, b"late");
+        assert_eq!(reader.read_next(&ring, &mut out).unwrap().seq(), 8);
+        assert_eq!(&out[..4], b"late");
+    }
+
+    #[test]
+    fn dump_lines() {
+        let mut buf = [0u8; RING_LEN];
+        let mut ring = LogRing::new(&mut buf).unwrap();
+        ring.push(Level::Info, b"hello");
+        ring.push(Level::Error, b"oops");
+
+        let mut out: crate::shared::structs::array_vec::ArrayVec<u8, 128> =
+            crate::shared::structs::array_vec::ArrayVec::new();
+        ring.dump(&mut out).unwrap();
+
+        let text = core::str::from_utf8(&out).unwrap();
+        let mut lines = text.lines();
+        assert!(lines.next().unwrap().ends_with("hello"));
+        assert!(lines.next().unwrap().ends_with("oops"));
+        assert!(lines.next().is_none());
+    }
+}
@@ -0,0 +1,667 @@
+/*!
+    Leveled logging facade with pluggable sinks
+
+    The facade is modeled after the `log` crate: records carry a
+    [`Level`], a target (which defaults to the module path), and the
+    formatted message. Records are dispatched to every registered
+    sink whose filter admits them, after passing the global filter.
+    ```rust,ignore
+    static CONSOLE: Mutex<VgaConsole> = Mutex::new(unsafe { VgaConsole::defaults() });
+    static RING: Mutex<ByteRing<4096>> = Mutex::new(ByteRing::new());
+
+    log::register_sink(&CONSOLE, LevelFilter::Info).unwrap();
+    log::register_sink(&RING, LevelFilter::Trace).unwrap();
+    log::set_max_level(LevelFilter::Debug);
+
+    info!("Loaded {} entries", n);
+    warn!(target: "config", "Unknown key `{}`", key);
+    ```
+
+    # Sinks
+    Any [`Mutex`]-protected implementor of [`io::Write`] is a sink.
+    Each record is written as a single line (` I: [target] message`),
+    after which the sink is flushed.
+
+    # Panic safety
+    After [`enter_panic_mode()`] is called, sinks are locked with
+    a bounded number of attempts, and are forcibly accessed if
+    the lock cannot be acquired - in the same way the bootloader's
+    panic handler arbitrates access to its console. Logging can
+    therefore be used from panic handlers, at the cost of
+    possibly interleaved output.
+
+    [`io::Write`]: crate::shared::io::Write
+*/
+
+// Standard definitions
+use core::fmt;
+use core::str::FromStr;
+use core::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+
+// Internal definitions
+use crate::shared::io::{self, ErrorKind, ErrorPayload, Write};
+use crate::shared::structs::spin_lock::Mutex;
+
+// In-memory byte ring sink
+pub mod ring;
+
+// Sequence-numbered kernel message log
+pub mod kmsg;
+
+/// Maximum number of registered sinks
+pub const MAX_SINKS: usize = 4;
+
+// Number of lock attempts before forcing access in panic mode
+const PANIC_LOCK_ATTEMPTS: usize = 255;
+
+// Errors reported while managing sinks
+const E_NO_FREE_SLOT: io::Error = io::Error::new(
+    ErrorKind::OutOfMemory,
+    ErrorPayload::Message("no free log sink slots"),
+);
+const E_NO_SUCH_SINK: io::Error = io::Error::new(
+    ErrorKind::NotFound,
+    ErrorPayload::Message("no log sink registered at index"),
+);
+
+/// Severity of a log record, from most to least severe
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[repr(u8)]
+pub enum Level {
+    Error = 1,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl Level {
+    /// Returns the lowercase name
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Level::Error => "error",
+            Level::Warn => "warn",
+            Level::Info => "info",
+            Level::Debug => "debug",
+            Level::Trace => "trace",
+        }
+    }
+
+    // Internal: inverse of `as u8`
+    const fn from_u8(n: u8) -> Option<Self> {
+        match n {
+            1 => Some(Level::Error),
+            2 => Some(Level::Warn),
+            3 => Some(Level::Info),
+            4 => Some(Level::Debug),
+            5 => Some(Level::Trace),
+            _ => None,
+        }
+    }
+
+    /// Returns the single-character tag used in log lines
+    pub const fn tag(&self) -> char {
+        match self {
+            Level::Error => 'E',
+            Level::Warn => 'W',
+            Level::Info => 'I',
+            Level::Debug => 'D',
+            Level::Trace => 'T',
+        }
+    }
+}
+
+impl FromStr for Level {
+    type Err = ();
+
+    /// Parses a level name, ignoring case
+    fn from_str(s: &str) -> Result<Self, ()> {
+        [
+            Level::Error,
+            Level::Warn,
+            Level::Info,
+            Level::Debug,
+            Level::Trace,
+        ]
+        .into_iter()
+        .find(|l| l.as_str().eq_ignore_ascii_case(s))
+        .ok_or(())
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Most verbose level admitted by a filter
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[repr(u8)]
+pub enum LevelFilter {
+    Off = 0,
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl LevelFilter {
+    /// Checks whether records of level `level` pass this filter
+    pub const fn allows(&self, level: Level) -> bool {
+        level as u8 <= *self as u8
+    }
+
+    // Internal: inverse of `as u8`, saturating at `Trace`
+    const fn from_u8(n: u8) -> Self {
+        match n {
+            0 => LevelFilter::Off,
+            1 => LevelFilter::Error,
+            2 => LevelFilter::Warn,
+            3 => LevelFilter::Info,
+            4 => LevelFilter::Debug,
+            _ => LevelFilter::Trace,
+        }
+    }
+}
+
+impl From<Level> for LevelFilter {
+    fn from(value: Level) -> LevelFilter {
+        LevelFilter::from_u8(value as u8)
+    }
+}
+
+impl FromStr for LevelFilter {
+    type Err = ();
+
+    /// Parses `off` or a level name, ignoring case
+    fn from_str(s: &str) -> Result<Self, ()> {
+        if s.eq_ignore_ascii_case("off") {
+            Ok(LevelFilter::Off)
+        } else {
+            Level::from_str(s).map(LevelFilter::from)
+        }
+    }
+}
+
+/// Single log record, as handed to sinks
+#[derive(Copy, Clone, Debug)]
+pub struct Record<'a> {
+    level: Level,
+    target: &'a str,
+    module_path: &'a str,
+    args: fmt::Arguments<'a>,
+}
+
+impl<'a> Record<'a> {
+    /// Create new instance of `Record`
+    pub const fn new(
+        level: Level,
+        target: &'a str,
+        module_path: &'a str,
+        args: fmt::Arguments<'a>,
+    ) -> Self {
+        Record {
+            level,
+            target,
+            module_path,
+            args,
+        }
+    }
+
+    /// Returns the level
+    pub fn level(&self) -> Level {
+        self.level
+    }
+
+    /// Returns the target, which defaults to the module path
+    pub fn target(&self) -> &'a str {
+        self.target
+    }
+
+    /// Returns the path of the module that emitted the record
+    pub fn module_path(&self) -> &'a str {
+        self.module_path
+    }
+
+    /// Returns the message
+    pub fn args(&self) -> fmt::Arguments<'a> {
+        self.args
+    }
+}
+
+/**
+    Trait to mark type as a destination for log records
+
+    Implementors must not block indefinitely if `panicking` is set.
+    The trait is implemented for every [`Mutex`]-protected writer.
+*/
+pub trait LogSink: Sync {
+    /// Write the provided record, absorbing any errors
+    fn log(&self, record: &Record<'_>, panicking: bool);
+}
+
+impl<W: Write + Send> LogSink for Mutex<W> {
+    fn log(&self, record: &Record<'_>, panicking: bool) {
+        // - absorb errors, as there's nowhere to report them
+        let _ = if panicking {
+            // - arbitrate first, then use force
+            match self.try_lock_repeat(PANIC_LOCK_ATTEMPTS) {
+                Ok(mut g) => write_record(&mut *g, record),
+                Err(()) => write_record(unsafe { self.get_mut() }, record),
+            }
+        } else {
+            write_record(&mut *self.lock(), record)
+        };
+    }
+}
+
+/// Write a record as a single line, then flush the writer
+pub fn write_record<W: Write>(w: &mut W, record: &Record<'_>) -> Result<(), io::Error> {
+    writeln!(
+        w,
+        " {}: [{}] {}",
+        record.level().tag(),
+        record.target(),
+        record.args()
+    )?;
+    w.flush()
+}
+
+// Internal: registered sink and its filter
+#[derive(Copy, Clone)]
+struct SinkSlot {
+    sink: &'static dyn LogSink,
+    filter: LevelFilter,
+}
+
+/**
+    Dispatcher of log records to registered sinks
+
+    A global instance backs the free functions and macros of this
+    module, but separate instances can be created as well.
+*/
+pub struct Logger {
+    max_level: AtomicU8,
+    panicking: AtomicBool,
+    sinks: Mutex<[Option<SinkSlot>; MAX_SINKS]>,
+}
+
+impl Logger {
+    /// Create new instance of `Logger`, with no sinks and an `Info` filter
+    pub const fn new() -> Self {
+        Logger {
+            max_level: AtomicU8::new(LevelFilter::Info as u8),
+            panicking: AtomicBool::new(false),
+            sinks: Mutex::new([None; MAX_SINKS]),
+        }
+    }
+
+    /// Returns the global filter
+    pub fn max_level(&self) -> LevelFilter {
+        LevelFilter::from_u8(self.max_level.load(Ordering::Relaxed))
+    }
+
+    /// Set the global filter
+    pub fn set_max_level(&self, filter: LevelFilter) {
+        self.max_level.store(filter as u8, Ordering::Relaxed);
+    }
+
+    /// Checks whether records of level `level` pass the global filter
+    pub fn enabled(&self, level: Level) -> bool {
+        self.max_level().allows(level)
+    }
+
+    /**
+        Register a sink with its own filter, returning its index
+
+        Fails with [`ErrorKind::OutOfMemory`] if all
+        [`MAX_SINKS`] slots are taken.
+    */
+    pub fn register(
+        &self,
+        sink: &'static dyn LogSink,
+        filter: LevelFilter,
+    ) -> Result<usize, io::Error> {
+        let mut sinks = self.sinks.lock();
+        let (i, slot) = sinks
+            .iter_mut()
+            .enumerate()
+            .find(|(_, s)| s.is_none())
+            .ok_or(E_NO_FREE_SLOT)?;
+
+        *slot = Some(SinkSlot { sink, filter });
+        Ok(i)
+    }
+
+    /// Remove the sink at index `index`
+    pub fn unregister(&self, index: usize) {
+        if let Some(slot) = self.sinks.lock().get_mut(index) {
+            *slot = None;
+        }
+    }
+
+    /**
+        Change the filter of the sink at index `index`
+
+        Fails with [`ErrorKind::NotFound`] if no sink
+        is registered at that index.
+    */
+    pub fn set_sink_filter(&self, index: usize, filter: LevelFilter) -> Result<(), io::Error> {
+        match self.sinks.lock().get_mut(index) {
+            Some(Some(slot)) => {
+                slot.filter = filter;
+                Ok(())
+            }
+            _ => Err(E_NO_SUCH_SINK),
+        }
+    }
+
+    /// Switch to panic-safe (non-blocking) operation for good
+    pub fn enter_panic_mode(&self) {
+        self.panicking.store(true, Ordering::SeqCst);
+    }
+
+    /// Checks whether the logger operates in panic mode
+    pub fn is_panicking(&self) -> bool {
+        self.panicking.load(Ordering::SeqCst)
+    }
+
+    /// Dispatch a record to all sinks that admit it
+    pub fn log(&self, record: &Record<'_>) {
+        if !self.enabled(record.level()) {
+            return;
+        }
+
+        let panicking = self.is_panicking();
+
+        // - copy the registry, so that sinks are
+        //   invoked without holding its lock
+        let sinks = if panicking {
+            match self.sinks.try_lock_repeat(PANIC_LOCK_ATTEMPTS) {
+                Ok(g) => *g,
+                Err(()) => unsafe { *self.sinks.get_mut() },
+            }
+        } else {
+            *self.sinks.lock()
+        };
+
+        for slot in sinks.iter().flatten() {
+            if slot.filter.allows(record.level()) {
+                slot.sink.log(record, panicking);
+            }
+        }
+    }
+}
+
+impl Default for Logger {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Global logger instance
+static LOGGER: Logger = Logger::new();
+
+/// Returns the global logger
+pub fn logger() -> &'static Logger {
+    &LOGGER
+}
+
+/// Returns the filter of the global logger
+pub fn max_level() -> LevelFilter {
+    LOGGER.max_level()
+}
+
+/// Set the filter of the global logger
+pub fn set_max_level(filter: LevelFilter) {
+    LOGGER.set_max_level(filter);
+}
+
+/// Checks whether records of level `level` pass the global filter
+pub fn enabled(level: Level) -> bool {
+    LOGGER.enabled(level)
+}
+
+/// Register a sink with the global logger
+pub fn register_sink(sink: &'static dyn LogSink, filter: LevelFilter) -> Result<usize, io::Error> {
+    LOGGER.register(sink, filter)
+}
+
+/// Switch the global logger to panic-safe operation
+pub fn enter_panic_mode() {
+    LOGGER.enter_panic_mode();
+}
+
+// Internal: entry point of the logging macros
+#[doc(hidden)]
+pub fn __log(level: Level, target: &str, module_path: &str, args: fmt::Arguments<'_>) {
+    LOGGER.log(&Record::new(level, target, module_path, args));
+}
+
+/**
+    Logs a message at the provided level
+
+    # Syntax
+    - `log!(level, "format", args...)`
+    - `log!(target: "target", level, "format", args...)`
+*/
+#[macro_export]
+macro_rules! log {
+    (target: $target:expr, $level:expr, $($arg:tt)+) => {{
+        let level: $crate::shared::log::Level = $level;
+        if $crate::shared::log::enabled(level) {
+            $crate::shared::log::__log(
+                level,
+                $target,
+                ::core::module_path!(),
+                ::core::format_args!($($arg)+),
+            );
+        }
+    }};
+
+    ($level:expr, $($arg:tt)+) => {
+        $crate::log!(target: ::core::module_path!(), $level, $($arg)+)
+    };
+}
+
+/// Logs a message at the error level (refer to [`log!`])
+#[macro_export]
+macro_rules! error {
+    (target: $target:expr, $($arg:tt)+) => {
+        $crate::log!(target: $target, $crate::shared::log::Level::Error, $($arg)+)
+    };
+
+    ($($arg:tt)+) => {
+        $crate::log!($crate::shared::log::Level::Error, $($arg)+)
+    };
+}
+
+/// Logs a message at the warning level (refer to [`log!`])
+#[macro_export]
+macro_rules! warn {
+    (target: $target:expr, $($arg:tt)+) => {
+        $crate::log!(target: $target, $crate::shared::log::Level::Warn, $($arg)+)
+    };
+
+    ($($arg:tt)+) => {
+        $crate::log!($crate::shared::log::Level::Warn, $($arg)+)
+    };
+}
+
+/// Logs a message at the info level (refer to [`log!`])
+#[macro_export]
+macro_rules! info {
+    (target: $target:expr, $($arg:tt)+) => {
+        $crate::log!(target: $target, $crate::shared::log::Level::Info, $($arg)+)
+    };
+
+    ($($arg:tt)+) => {
+        $crate::log!($crate::shared::log::Level::Info, $($arg)+)
+    };
+}
+
+/// Logs a message at the debug level (refer to [`log!`])
+#[macro_export]
+macro_rules! debug {
+    (target: $target:expr, $($arg:tt)+) => {
+        $crate::log!(target: $target, $crate::shared::log::Level::Debug, $($arg)+)
+    };
+
+    ($($arg:tt)+) => {
+        $crate::log!($crate::shared::log::Level::Debug, $($arg)+)
+    };
+}
+
+/// Logs a message at the trace level (refer to [`log!`])
+#[macro_export]
+macro_rules! trace {
+    (target: $target:expr, $($arg:tt)+) => {
+        $crate::log!(target: $target, $crate::shared::log::Level::Trace, $($arg)+)
+    };
+
+    ($($arg:tt)+) => {
+        $crate::log!($crate::shared::log::Level::Trace, $($arg)+)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use crate::shared::structs::array_vec::ArrayVec;
+    use std::boxed::Box;
+
+    type Sink = Mutex<ArrayVec<u8, 256>>;
+
+    // Internal: leak a fresh sink, as loggers only take static ones
+    fn sink() -> &'static Sink {
+        Box::leak(Box::new(Mutex::new(ArrayVec::new())))
+    }
+
+    // Internal: log one record per level through `logger`
+    fn log_all(logger: &Logger) {
+        for (level, msg) in [
+            (Level::Error, "e"),
+            (Level::Warn, "w"),
+            (Level::Info, "i"),
+            (Level::Debug, "d"),
+            (Level::Trace, "t"),
+        ] {
+            logger.log(&Record::new(level, "test", "mod", format_args!("{}", msg)));
+        }
+    }
+
+    // Internal: take the contents of a sink
+    fn contents(sink: &Sink) -> std::string::String {
+        let mut g = sink.lock();
+        let s = core::str::from_utf8(&g).unwrap().into();
+        g.clear();
+        s
+    }
+
+    #[test]
+    fn level_filters() {
+        assert!(LevelFilter::Trace.allows(Level::Trace));
+        assert!(LevelFilter::Info.allows(Level::Error));
+        assert!(LevelFilter::Info.allows(Level::Info));
+        assert!(!LevelFilter::Info.allows(Level::Debug));
+        assert!(!LevelFilter::Off.allows(Level::Error));
+        assert_eq!(LevelFilter::from(Level::Warn), LevelFilter::Warn);
+    }
+
+    #[test]
+    fn parse_levels() {
+        assert_eq!("DEBUG".parse(), Ok(Level::Debug));
+        assert_eq!("warn".parse(), Ok(Level::Warn));
+        assert_eq!("off".parse::<Level>(), Err(()));
+        assert_eq!("Off".parse(), Ok(LevelFilter::Off));
+        assert_eq!("trace".parse(), Ok(LevelFilter::Trace));
+        assert_eq!("verbose".parse::<LevelFilter>(), Err(()));
+
+        for level in [Level::Error, Level::Trace] {
+            assert_eq!(level.as_str().parse(), Ok(level));
+        }
+    }
+
+    #[test]
+    fn global_filter() {
+        let logger = Logger::new();
+        let s = sink();
+        logger.register(s, LevelFilter::Trace).unwrap();
+
+        assert_eq!(logger.max_level(), LevelFilter::Info);
+        log_all(&logger);
+        assert_eq!(contents(s), " E: [test] e\n W: [test] w\n I: [test] i\n");
+
+        logger.set_max_level(LevelFilter::Off);
+        assert!(!logger.enabled(Level::Error));
+        log_all(&logger);
+        assert_eq!(contents(s), "");
+    }
+
+    #[test]
+    fn sink_filters() {
+        let logger = Logger::new();
+        logger.set_max_level(LevelFilter::Trace);
+
+        let (a, b) = (sink(), sink());
+        assert_eq!(logger.register(a, LevelFilter::Error).unwrap(), 0);
+        assert_eq!(logger.register(b, LevelFilter::Debug).unwrap(), 1);
+
+        log_all(&logger);
+        assert_eq!(contents(a), " E: [test] e\n");
+        assert_eq!(contents(b).lines().count(), 4);
+
+        logger.set_sink_filter(0, LevelFilter::Warn).unwrap();
+        logger.unregister(1);
+        log_all(&logger);
+        assert_eq!(contents(a), " E: [test] e\n W: [test] w\n");
+        assert_eq!(contents(b), "");
+
+        let e = logger.set_sink_filter(1, LevelFilter::Trace).unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::NotFound);
+        assert!(
+            logger
+                .set_sink_filter(MAX_SINKS, LevelFilter::Trace)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn sink_slots() {
+        let logger = Logger::new();
+        for i in 0..MAX_SINKS {
+            assert_eq!(logger.register(sink(), LevelFilter::Info).unwrap(), i);
+        }
+
+        let e = logger.register(sink(), LevelFilter::Info).unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::OutOfMemory);
+
+        // - freed slots are reused
+        logger.unregister(2);
+        logger.unregister(MAX_SINKS);
+        assert_eq!(logger.register(sink(), LevelFilter::Info).unwrap(), 2);
+    }
+
+    #[test]
+    fn panic_mode() {
+        let logger = Logger::new();
+        let s = sink();
+        logger.register(s, LevelFilter::Trace).unwrap();
+
+        assert!(!logger.is_panicking());
+        logger.enter_panic_mode();
+        assert!(logger.is_panicking());
+
+        logger.log(&Record::new(
+            Level::Error,
+            "panic",
+            "mod",
+            format_args!("at {}", 1),
+        ));
+        assert_eq!(contents(s), " E: [panic] at 1\n");
+    }
+}
@@ -0,0 +1,199 @@
+/*!
+    Fixed-size in-memory byte ring
+
+    The ring keeps the most recent `N` bytes written to it, silently
+    discarding the oldest ones. Wrapped in a [`Mutex`], it serves as
+    a log sink that retains messages after they scroll off-screen.
+
+    [`Mutex`]: crate::shared::structs::spin_lock::Mutex
+*/
+
+// I/O helpers
+use crate::shared::io::{Error, Write};
+
+/// Fixed-size byte ring that overwrites its oldest contents
+pub struct ByteRing<const N: usize> {
+    buf: [u8; N],
+    head: usize,
+    len: usize,
+}
+
+impl<const N: usize> ByteRing<N> {
+    /// Create new, empty instance of `ByteRing`
+    pub const fn new() -> Self {
+        ByteRing {
+            buf: [0; N],
+            head: 0,
+            len: 0,
+        }
+    }
+
+    /// Returns the capacity in bytes
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Returns the number of retained bytes
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Checks whether the ring is empty
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Discard all contents
+    pub fn clear(&mut self) {
+        self.head = 0;
+        self.len = 0;
+    }
+
+    /// Append bytes, discarding the oldest ones if necessary
+    pub fn push(&mut self, bytes: &[u8]) {
+        if N == 0 {
+            return;
+        }
+
+        // - only the last `N` bytes can survive
+        let bytes = &bytes[bytes.len().saturating_sub(N)..];
+
+        for &b in bytes {
+            self.buf[self.head] = b;
+            self.head = (self.head + 1) % N;
+        }
+
+        self.len = (self.len + bytes.len()).min(N);
+    }
+
+    /// Returns the contents, oldest first, as two consecutive slices
+    pub fn as_slices(&self) -> (&[u8], &[u8]) {
+        // - the oldest byte sits `len` bytes behind the head
+        let start = (self.head + N - self.len) % N.max(1);
+
+        if start + self.len <= N {
+            (&self.buf[start..start + self.len], &[])
+        } else {
+            (&self.buf[start..], &self.buf[..self.head])
+        }
+    }
+
+    /// Copy the most recent contents into `out`, returning the number of bytes copied
+    pub fn copy_to(&self, out: &mut [u8]) -> usize {
+        let (a, b) = self.as_slices();
+
+        // - skip the oldest bytes if `out` is too small
+        let skip = (a.len() + b.len()).saturating_sub(out.len());
+        let (a, b) = if skip <= a.len() {
+            (&a[skip..], b)
+        } else {
+            (&[][..], &b[skip - a.len()..])
+        };
+
+        out[..a.len()].copy_from_slice(a);
+        out[a.len()..a.len() + b.len()].copy_from_slice(b);
+
+        a.len() + b.len()
+    }
+}
+
+impl<const N: usize> Default for ByteRing<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> Write for ByteRing<N> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        self.push(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Internal: join both halves of the ring
+    fn joined<const N: usize>(ring: &ByteRing<N>) -> ([u8; N], usize) {
+        let mut out = [0u8; N];
+        let n = ring.copy_to(&mut out);
+        (out, n)
+    }
+
+    #[test]
+    fn push_without_wrapping() {
+        let mut ring: ByteRing<8> = ByteRing::new();
+        assert!(ring.is_empty());
+        assert_eq!(ring.as_slices(), (&[][..], &[][..]));
+
+        ring.push(b"abc");
+        ring.write_all(b"de").unwrap();
+        assert_eq!(ring.len(), 5);
+        assert_eq!(ring.as_slices(), (&b"abcde"[..], &[][..]));
+
+        // - filling the ring exactly doesn't split it
+        ring.push(b"fgh");
+        assert_eq!(ring.as_slices(), (&b"abcdefgh"[..], &[][..]));
+    }
+
+    #[test]
+    fn wraparound() {
+        let mut ring: ByteRing<8> = ByteRing::new();
+        ring.push(b"abcdef");
+        ring.push(b"ghij");
+
+        assert_eq!(ring.len(), 8);
+        assert_eq!(ring.as_slices(), (&b"cdefgh"[..], &b"ij"[..]));
+
+        let (out, n) = joined(&ring);
+        assert_eq!(&out[..n], b"cdefghij");
+
+        // - oversized writes keep their tail only
+        ring.push(b"0123456789");
+        assert_eq!(ring.as_slices(), (&b"234567"[..], &b"89"[..]));
+
+        ring.clear();
+        assert!(ring.is_empty());
+        ring.push(b"xy");
+        assert_eq!(ring.as_slices(), (&b"xy"[..], &[][..]));
+    }
+
+    #[test]
+    fn copy_to_keeps_most_recent() {
+        let mut ring: ByteRing<8> = ByteRing::new();
+        ring.push(b"abcdef");
+        ring.push(b"ghij");
+        assert_eq!(ring.as_slices(), (&b"cdefgh"[..], &b"ij"[..]));
+
+        // - the skipped bytes fall within the first half...
+        let mut out = [0u8; 5];
+        assert_eq!(ring.copy_to(&mut out), 5);
+        assert_eq!(&out, b"fghij");
+
+        // - ...or reach into the second one
+        let mut out = [0u8; 1];
+        assert_eq!(ring.copy_to(&mut out), 1);
+        assert_eq!(&out, b"j");
+
+        let mut out = [0u8; 16];
+        assert_eq!(ring.copy_to(&mut out), 8);
+        assert_eq!(&out[..8], b"cdefghij");
+        assert_eq!(ring.copy_to(&mut []), 0);
+    }
+
+    #[test]
+    fn zero_capacity() {
+        let mut ring: ByteRing<0> = ByteRing::new();
+        ring.push(b"abc");
+
+        assert!(ring.is_empty());
+        assert_eq!(ring.capacity(), 0);
+        assert_eq!(ring.as_slices(), (&[][..], &[][..]));
+        assert_eq!(ring.copy_to(&mut [0u8; 4]), 0);
+    }
+}
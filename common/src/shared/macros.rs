@@ -12,7 +12,7 @@
     A potential application for `relocate!` is relocating statics
     and constants to a specific section in an executable or linkable
     library:
-    ```rust
+    ```text
         relocate! {
             pub static EMPTY_BUF: [u8; 1024] = [0u8; 1024];
                 => ".bss";
@@ -21,7 +21,7 @@
 
     A more common use-case is relocating function ("vectors")
     to a defined location in an executable:
-    ```rust
+    ```text
         relocate! {
             pub fn in_b(port: u8) -> u8 {
                 ...
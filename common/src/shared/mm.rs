@@ -289,3 +289,73 @@ impl<K: MemoryRegionKind> MemoryRegion<K> {
 
 /// Type alias for a physical memory region
 pub type PhysMemRegion = MemoryRegion<PhysMemKind>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn span_limit() {
+        assert_eq!(RegionSpan::new(0x1000, 0x200).limit(), 0x1200);
+        assert_eq!(RegionSpan::new(0x1000, 0).limit(), 0x1000);
+
+        // - garbage spans are capped, not wrapped
+        assert_eq!(RegionSpan::new(usize::MAX - 1, 2).limit(), usize::MAX);
+        assert_eq!(RegionSpan::new(usize::MAX, usize::MAX).limit(), usize::MAX);
+    }
+
+    #[test]
+    fn span_relations() {
+        let a = RegionSpan::new(0x1000, 0x1000);
+        let b = RegionSpan::new(0x1800, 0x1000);
+        let c = RegionSpan::new(0x2000, 0x1000);
+        let inner = RegionSpan::new(0x1400, 0x100);
+
+        assert!(a.overlaps(&b) && b.overlaps(&a));
+        assert!(a.overlaps(&inner) && inner.overlaps(&a));
+
+        // - adjacent spans don't overlap
+        assert!(!a.overlaps(&c) && !c.overlaps(&a));
+        assert!(a.is_below(&c) && c.is_above(&a));
+        assert!(!a.is_below(&b) && !b.is_above(&a));
+
+        assert!(a.contains(&inner) && !inner.contains(&a));
+        assert!(a.contains(&a));
+        assert!(!a.contains(&b));
+
+        assert!(a.contains_addr(0x1000) && a.contains_addr(0x1fff));
+        assert!(!a.contains_addr(0x2000) && !a.contains_addr(0xfff));
+    }
+
+    #[test]
+    fn span_edges() {
+        // - empty spans contain no address, and don't overlap each other
+        let empty = RegionSpan::new(0x1000, 0);
+        assert!(!empty.contains_addr(0x1000));
+        assert!(!empty.overlaps(&empty));
+
+        // - a span reaching the top of memory still
+        // overlaps with one below its capped limit
+        let top = RegionSpan::new(usize::MAX - 0xff, usize::MAX);
+        let near_top = RegionSpan::new(usize::MAX - 0x10, 0x8);
+        assert!(top.overlaps(&near_top));
+        assert!(top.contains(&near_top));
+        assert!(!top.contains_addr(usize::MAX));
+    }
+
+    #[test]
+    fn kind_attributes() {
+        let r = PhysMemKind::reserved(Some(0x2));
+        assert_eq!(r.class(), PhysMemClass::Reserved);
+        assert_eq!(r.attr(), Some(0x2));
+        assert!(!r.is_usable() && !r.is_reclaimable());
+
+        assert_eq!(PhysMemKind::regular().attr(), None);
+        assert!(PhysMemKind::regular().is_usable());
+        assert!(PhysMemKind::reclaimable(None).is_reclaimable());
+
+        let region = PhysMemRegion::new(0x10_0000, 0x1000, PhysMemKind::hole());
+        assert_eq!(region.span().limit(), 0x10_1000);
+        assert_eq!(region.kind().class(), PhysMemClass::Hole);
+    }
+}
@@ -26,6 +26,22 @@ pub mod io;
 // Memory management definitions
 pub mod mm;
 
+// Command-line tokenizer
+pub mod cmdline;
+
+// Leveled logging facade
+pub mod log;
+
+// Embedded symbol tables
+pub mod symbols;
+
+// In-OS test framework
+pub mod testing;
+
+// Error formatting and context
+mod error;
+pub use error::{CONTEXT_DEPTH, Context, ErrorCode, ErrorContext, MAX_FRAMES};
+
 /**
     A finite set of error types
 
@@ -33,6 +49,12 @@ pub mod mm;
     that it can be used outside of I/O contexts. The variants that this
     type offers reflect the most common error types found in early-stage
     `no_std` environments.
+
+    An [`io::Error`] of a particular kind is kept whole in [`Io`], so
+    conversions to and from `io::Error` are lossless, except that
+    context frames are dropped when converting to `io::Error`.
+
+    [`Io`]: GenericError::Io
 */
 #[derive(Debug, Copy, Clone)]
 #[non_exhaustive]
@@ -48,4 +70,10 @@ pub enum GenericError {
 
     /// No payload
     Empty,
+
+    /// I/O error of a particular kind
+    Io(io::Error),
+
+    /// Error with context frames (refer to [`GenericError::context()`])
+    Context(ErrorContext),
 }
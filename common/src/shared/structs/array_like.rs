@@ -16,7 +16,7 @@ use core::mem;
 
     # Example use
     A potential application for `ArrayLike` is bare-metal argument passing:
-    ```rust
+    ```rust,ignore
     #[inline(never)]
     #[unsafe(no_mangle)]
     pub extern "C" fn main(
@@ -78,7 +78,7 @@ impl<'a, T> TryFrom<&'a ArrayLike<'a, T>> for &'a [T] {
 
     # Example use
     A potential application for `ArrayLikeMut` is passing pointers to mutable buffers:
-    ```rust
+    ```rust,ignore
     #[inline(never)]
     #[unsafe(no_mangle)]
     pub extern "C" fn main(
@@ -158,3 +158,66 @@ impl<'a, T> TryFrom<&'a mut ArrayLikeMut<'a, T>> for &'a mut [T] {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::ptr;
+
+    // Internal: describe `size` elements at `data`
+    fn like<'a, T>(data: *const T, size: usize) -> ArrayLike<'a, T> {
+        ArrayLike {
+            data,
+            size,
+            _marker: PhantomData,
+        }
+    }
+
+    #[test]
+    fn try_from_valid() {
+        let values = [1u32, 2, 3];
+        let a = like(values.as_ptr(), values.len());
+        let s: &[u32] = (&a).try_into().unwrap();
+        assert_eq!(s, [1, 2, 3]);
+
+        // - empty arrays only need a well-aligned pointer
+        let a = like(values.as_ptr(), 0);
+        let s: &[u32] = (&a).try_into().unwrap();
+        assert!(s.is_empty());
+    }
+
+    #[test]
+    fn try_from_invalid() {
+        let values = [0u32; 2];
+
+        let a = like::<u32>(ptr::null(), 0);
+        assert!(<&[u32]>::try_from(&a).is_err());
+
+        let misaligned = values.as_ptr().cast::<u8>().wrapping_add(1).cast::<u32>();
+        let a = like(misaligned, 1);
+        assert!(<&[u32]>::try_from(&a).is_err());
+    }
+
+    #[test]
+    fn try_from_mut() {
+        let mut values = [5u16; 4];
+        let mut a = ArrayLikeMut {
+            data: values.as_mut_ptr(),
+            size: 2,
+            _marker: PhantomData,
+        };
+
+        {
+            let s: &mut [u16] = (&mut a).try_into().unwrap();
+            s[1] = 7;
+        }
+        assert_eq!(values, [5, 7, 5, 5]);
+
+        let a = ArrayLikeMut::<u16> {
+            data: ptr::null_mut(),
+            size: 1,
+            _marker: PhantomData,
+        };
+        assert!(<&[u16]>::try_from(&a).is_err());
+    }
+}
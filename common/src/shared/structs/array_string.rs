@@ -0,0 +1,466 @@
+/*!
+    Module defining a fixed-capacity, stack-allocated string
+
+    [`ArrayString`] is to `String` what [`ArrayVec`] is to `Vec`:
+    it holds up to `N` bytes of UTF-8 inline, and operations that
+    would exceed the capacity fail instead of allocating.
+
+    # Usage
+    ```rust
+    # use core::fmt::Write;
+    # use common::shared::structs::array_string::ArrayString;
+    # let loc = "main.rs:1";
+    let mut s: ArrayString<32> = ArrayString::new();
+
+    write!(s, "panicked at {}", loc).unwrap();
+    s.push_str(", again").unwrap();
+    assert!(s.push_str(" and again").is_err());
+    ```
+
+    [`ArrayVec`]: super::array_vec::ArrayVec
+*/
+
+// Definition uses
+use core::fmt;
+use core::ops::{Deref, DerefMut};
+use core::str;
+
+use super::array_vec::CapacityError;
+use crate::shared::io::{self, Write};
+
+/// String with inline storage for up to `N` bytes of UTF-8
+#[derive(Copy, Clone)]
+pub struct ArrayString<const N: usize> {
+    buf: [u8; N],
+    len: usize,
+}
+
+impl<const N: usize> ArrayString<N> {
+    /// Create new, empty instance of `ArrayString`
+    pub const fn new() -> Self {
+        ArrayString {
+            buf: [0; N],
+            len: 0,
+        }
+    }
+
+    /// Returns the length in bytes
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Checks whether the string is empty
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Checks whether the string is at capacity
+    pub const fn is_full(&self) -> bool {
+        self.len == N
+    }
+
+    /// Returns the capacity in bytes
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Returns the number of bytes that can still be appended
+    pub const fn remaining_capacity(&self) -> usize {
+        N - self.len
+    }
+
+    /// Returns the contents as a string slice
+    pub fn as_str(&self) -> &str {
+        // SAFETY: only whole UTF-8 sequences are ever stored
+        unsafe { str::from_utf8_unchecked(&self.buf[..self.len]) }
+    }
+
+    /// Returns the contents as a mutable string slice
+    pub fn as_mut_str(&mut self) -> &mut str {
+        // SAFETY: only whole UTF-8 sequences are ever stored
+        unsafe { str::from_utf8_unchecked_mut(&mut self.buf[..self.len]) }
+    }
+
+    /// Returns the contents as bytes
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buf[..self.len]
+    }
+
+    /// Append a character, handing it back if it doesn't fit
+    pub fn push(&mut self, c: char) -> Result<(), CapacityError<char>> {
+        let mut enc = [0u8; 4];
+        self.push_str(c.encode_utf8(&mut enc))
+            .map_err(|_| CapacityError::new(c))
+    }
+
+    /// Append a string slice, or nothing if it doesn't fit
+    pub fn push_str(&mut self, s: &str) -> Result<(), CapacityError> {
+        if s.len() > self.remaining_capacity() {
+            return Err(CapacityError::new(()));
+        }
+
+        self.buf[self.len..self.len + s.len()].copy_from_slice(s.as_bytes());
+        self.len += s.len();
+
+        Ok(())
+    }
+
+    /**
+        Append as much of a string slice as fits, without
+        splitting a character, and return the number of bytes
+        appended
+    */
+    pub fn push_str_truncate(&mut self, s: &str) -> usize {
+        let mut n = s.len().min(self.remaining_capacity());
+        while !s.is_char_boundary(n) {
+            n -= 1;
+        }
+
+        // - the prefix fits, so this can't fail
+        let _ = self.push_str(&s[..n]);
+
+        n
+    }
+
+    /// Remove the last character, if any
+    pub fn pop(&mut self) -> Option<char> {
+        let c = self.as_str().chars().next_back()?;
+        self.len -= c.len_utf8();
+
+        Some(c)
+    }
+
+    /**
+        Insert a character at byte position `index`
+
+        # Panics
+        Panics if `index` is out of bounds, or isn't
+        on a character boundary.
+    */
+    pub fn insert(&mut self, index: usize, c: char) -> Result<(), CapacityError<char>> {
+        let mut enc = [0u8; 4];
+        self.insert_str(index, c.encode_utf8(&mut enc))
+            .map_err(|_| CapacityError::new(c))
+    }
+
+    /**
+        Insert a string slice at byte position `index`,
+        or nothing if it doesn't fit
+
+        # Panics
+        Panics if `index` is out of bounds, or isn't
+        on a character boundary.
+    */
+    pub fn insert_str(&mut self, index: usize, s: &str) -> Result<(), CapacityError> {
+        assert!(
+            self.as_str().is_char_boundary(index),
+            "insertion index is not a character boundary"
+        );
+
+        if s.len() > self.remaining_capacity() {
+            return Err(CapacityError::new(()));
+        }
+
+        let (len, n) = (self.len, s.len());
+        self.buf.copy_within(index..len, index + n);
+        self.buf[index..index + n].copy_from_slice(s.as_bytes());
+        self.len += n;
+
+        Ok(())
+    }
+
+    /**
+        Remove and return the character at byte position `index`
+
+        # Panics
+        Panics if `index` is out of bounds, or isn't
+        on a character boundary.
+    */
+    pub fn remove(&mut self, index: usize) -> char {
+        let c = match self.as_str()[index..].chars().next() {
+            Some(c) => c,
+            None => panic!("removal index out of bounds"),
+        };
+
+        let (len, n) = (self.len, c.len_utf8());
+        self.buf.copy_within(index + n..len, index);
+        self.len -= n;
+
+        c
+    }
+
+    /**
+        Shorten the string to `len` bytes
+
+        # Panics
+        Panics if `len` isn't on a character boundary.
+    */
+    pub fn truncate(&mut self, len: usize) {
+        if len < self.len {
+            assert!(
+                self.as_str().is_char_boundary(len),
+                "new length is not a character boundary"
+            );
+            self.len = len;
+        }
+    }
+
+    /// Remove all contents
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+}
+
+impl<const N: usize> Default for ArrayString<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> Deref for ArrayString<N> {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl<const N: usize> DerefMut for ArrayString<N> {
+    fn deref_mut(&mut self) -> &mut str {
+        self.as_mut_str()
+    }
+}
+
+impl<const N: usize> PartialEq for ArrayString<N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl<const N: usize> Eq for ArrayString<N> {}
+
+impl<const N: usize> PartialEq<str> for ArrayString<N> {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl<const N: usize> PartialEq<&str> for ArrayString<N> {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl<const N: usize> str::FromStr for ArrayString<N> {
+    type Err = CapacityError;
+
+    fn from_str(s: &str) -> Result<Self, CapacityError> {
+        let mut this = Self::new();
+        this.push_str(s)?;
+
+        Ok(this)
+    }
+}
+
+impl<'a, const N: usize> TryFrom<&'a str> for ArrayString<N> {
+    type Error = CapacityError<&'a str>;
+
+    fn try_from(s: &'a str) -> Result<Self, Self::Error> {
+        s.parse().map_err(|_| CapacityError::new(s))
+    }
+}
+
+impl<const N: usize> fmt::Debug for ArrayString<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl<const N: usize> fmt::Display for ArrayString<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self.as_str(), f)
+    }
+}
+
+/// Append formatted output, failing on the first piece that doesn't fit
+/// - pieces written before the failure are kept
+impl<const N: usize> fmt::Write for ArrayString<N> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.push_str(s).map_err(|_| fmt::Error)
+    }
+
+    fn write_char(&mut self, c: char) -> fmt::Result {
+        self.push(c).map_err(|_| fmt::Error)
+    }
+}
+
+/**
+    Append as many whole characters as fit
+
+    The bytes must be valid UTF-8, as a whole. Since writers
+    may be handed a buffer split in the middle of a character,
+    an incomplete sequence at the end is left unwritten, rather
+    than rejected.
+*/
+impl<const N: usize> Write for ArrayString<N> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, io::Error> {
+        let s = match str::from_utf8(buf) {
+            Ok(s) => s,
+            Err(e) if e.error_len().is_none() => {
+                // SAFETY: the prefix has just been validated
+                unsafe { str::from_utf8_unchecked(&buf[..e.valid_up_to()]) }
+            }
+            Err(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    io::ErrorPayload::Message("stream did not contain valid UTF-8"),
+                ));
+            }
+        };
+
+        Ok(self.push_str_truncate(s))
+    }
+
+    fn flush(&mut self) -> Result<(), io::Error> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use core::str::FromStr;
+
+    #[test]
+    fn push_and_pop() {
+        let mut s: ArrayString<5> = ArrayString::new();
+        assert!(s.is_empty());
+        assert_eq!(s.pop(), None);
+
+        s.push('a').unwrap();
+        s.push_str("bc").unwrap();
+        assert_eq!(s.push_str("def"), Err(CapacityError::new(())));
+        assert_eq!(s, "abc");
+
+        // - a multi-byte character only fits as a whole
+        s.push('é').unwrap();
+        assert_eq!(s.remaining_capacity(), 0);
+        assert_eq!(s.push('x'), Err(CapacityError::new('x')));
+        assert!(s.is_full());
+
+        assert_eq!(s.pop(), Some('é'));
+        assert_eq!(s.len(), 3);
+        assert_eq!(s.push('€'), Err(CapacityError::new('€')));
+        assert_eq!(s, "abc");
+    }
+
+    #[test]
+    fn zero_capacity() {
+        let mut s: ArrayString<0> = ArrayString::new();
+
+        assert!(s.is_full());
+        assert_eq!(s.push_str(""), Ok(()));
+        assert!(s.push('a').is_err());
+        assert!(fmt::write(&mut s, format_args!("{}", 1)).is_err());
+        assert_eq!(Write::write(&mut s, b"a").unwrap(), 0);
+    }
+
+    #[test]
+    fn from_str() {
+        let s: ArrayString<4> = ArrayString::from_str("abcd").unwrap();
+        assert_eq!(s.as_bytes(), b"abcd");
+
+        assert_eq!(
+            "abcd".parse::<ArrayString<3>>(),
+            Err(CapacityError::new(()))
+        );
+        assert_eq!(
+            ArrayString::<3>::try_from("abcd"),
+            Err(CapacityError::new("abcd"))
+        );
+        assert!(ArrayString::<3>::try_from("abc").is_ok());
+    }
+
+    #[test]
+    fn insert_and_remove() {
+        let mut s: ArrayString<8> = ArrayString::from_str("ac").unwrap();
+
+        s.insert(1, 'b').unwrap();
+        s.insert_str(0, "ñ").unwrap();
+        s.insert_str(s.len(), "de").unwrap();
+        assert_eq!(s, "ñabcde");
+        assert_eq!(s.insert_str(0, "xy"), Err(CapacityError::new(())));
+        assert_eq!(s.insert(0, 'z'), Ok(()));
+        assert_eq!(s.insert(0, 'z'), Err(CapacityError::new('z')));
+
+        assert_eq!(s.remove(1), 'ñ');
+        assert_eq!(s.remove(0), 'z');
+        assert_eq!(s, "abcde");
+
+        s.truncate(2);
+        assert_eq!(s, "ab");
+        s.clear();
+        assert!(s.is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn insert_inside_character() {
+        let mut s: ArrayString<8> = ArrayString::from_str("é").unwrap();
+        let _ = s.insert(1, 'a');
+    }
+
+    #[test]
+    #[should_panic]
+    fn remove_out_of_bounds() {
+        let mut s: ArrayString<8> = ArrayString::from_str("a").unwrap();
+        s.remove(1);
+    }
+
+    #[test]
+    #[should_panic]
+    fn truncate_inside_character() {
+        let mut s: ArrayString<8> = ArrayString::from_str("é").unwrap();
+        s.truncate(1);
+    }
+
+    #[test]
+    fn fmt_write() {
+        let mut s: ArrayString<8> = ArrayString::new();
+
+        fmt::write(&mut s, format_args!("{}-{}", 12, 34)).unwrap();
+        assert_eq!(s, "12-34");
+
+        // - pieces written before the overflow are kept
+        let (a, b) = ("ab", "cd");
+        assert!(fmt::write(&mut s, format_args!("{}{}", a, b)).is_err());
+        assert_eq!(s, "12-34ab");
+        assert_eq!(std::format!("{}|{:?}", s, s), "12-34ab|\"12-34ab\"");
+    }
+
+    #[test]
+    fn io_write() {
+        let mut s: ArrayString<4> = ArrayString::new();
+
+        assert_eq!(Write::write(&mut s, b"ab").unwrap(), 2);
+
+        // - characters are never split
+        assert_eq!(Write::write(&mut s, "é€".as_bytes()).unwrap(), 2);
+        assert_eq!(s, "abé");
+
+        // - trailing incomplete sequences are left for later
+        s.clear();
+        assert_eq!(Write::write(&mut s, &"€".as_bytes()[..2]).unwrap(), 0);
+        assert_eq!(Write::write(&mut s, b"a\xe2\x82").unwrap(), 1);
+
+        // - invalid sequences are rejected outright
+        assert!(matches!(
+            Write::write(&mut s, b"\xff").unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        ));
+        assert!(s.write_all(b"bcde").is_err());
+        assert_eq!(s, "abcd");
+    }
+}
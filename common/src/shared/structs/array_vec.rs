@@ -0,0 +1,507 @@
+/*!
+    Module defining a fixed-capacity, stack-allocated vector
+
+    [`ArrayVec`] offers most of the `Vec` API without an allocator,
+    so that it can be used before the allocator is up. Operations
+    that would exceed the capacity fail, rather than panic, and
+    hand the rejected element back through a [`CapacityError`].
+
+    # Usage
+    ```rust
+    # use common::shared::structs::array_vec::ArrayVec;
+    let mut v: ArrayVec<u32, 4> = ArrayVec::new();
+
+    v.push(1).unwrap();
+    v.try_extend_from_slice(&[2, 3]).unwrap();
+    assert_eq!(&v[..], &[1, 2, 3]);
+
+    // - the rejected element is handed back
+    v.push(4).unwrap();
+    assert_eq!(v.push(5).unwrap_err().element(), 5);
+    ```
+*/
+
+// Definition uses
+use core::fmt;
+use core::mem::MaybeUninit;
+use core::ops::{Deref, DerefMut, Drop};
+use core::ptr;
+use core::slice;
+
+use crate::shared::io::{self, Write};
+
+/// Error signalling that an operation would exceed the capacity
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub struct CapacityError<T = ()> {
+    element: T,
+}
+
+impl<T> CapacityError<T> {
+    /// Create new instance of `CapacityError`, holding the rejected element
+    pub const fn new(element: T) -> Self {
+        CapacityError { element }
+    }
+
+    /// Returns the rejected element
+    pub fn element(self) -> T {
+        self.element
+    }
+
+    /// Discards the rejected element
+    pub fn simplify(self) -> CapacityError {
+        CapacityError { element: () }
+    }
+}
+
+impl<T> fmt::Debug for CapacityError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("CapacityError: insufficient capacity")
+    }
+}
+
+impl<T> fmt::Display for CapacityError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("insufficient capacity")
+    }
+}
+
+/**
+    Vector with inline storage for up to `N` elements
+
+    Elements are stored in place, so the whole vector can
+    live on the stack or in a `static`.
+*/
+pub struct ArrayVec<T, const N: usize> {
+    buf: [MaybeUninit<T>; N],
+    len: usize,
+}
+
+impl<T, const N: usize> ArrayVec<T, N> {
+    /// Create new, empty instance of `ArrayVec`
+    pub const fn new() -> Self {
+        ArrayVec {
+            buf: [const { MaybeUninit::uninit() }; N],
+            len: 0,
+        }
+    }
+
+    /// Returns the number of elements
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Checks whether the vector has no elements
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Checks whether the vector is at capacity
+    pub const fn is_full(&self) -> bool {
+        self.len == N
+    }
+
+    /// Returns the capacity
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Returns the number of elements that can still be pushed
+    pub const fn remaining_capacity(&self) -> usize {
+        N - self.len
+    }
+
+    /// Returns the elements as a slice
+    pub fn as_slice(&self) -> &[T] {
+        // SAFETY: the first `len` elements are initialized
+        unsafe { slice::from_raw_parts(self.buf.as_ptr().cast(), self.len) }
+    }
+
+    /// Returns the elements as a mutable slice
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        // SAFETY: the first `len` elements are initialized
+        unsafe { slice::from_raw_parts_mut(self.buf.as_mut_ptr().cast(), self.len) }
+    }
+
+    /// Append an element, handing it back if the vector is full
+    pub fn push(&mut self, val: T) -> Result<(), CapacityError<T>> {
+        if self.is_full() {
+            return Err(CapacityError::new(val));
+        }
+
+        self.buf[self.len].write(val);
+        self.len += 1;
+
+        Ok(())
+    }
+
+    /// Remove the last element, if any
+    pub fn pop(&mut self) -> Option<T> {
+        if self.is_empty() {
+            return None;
+        }
+
+        self.len -= 1;
+
+        // SAFETY: the element was initialized, and is now out of bounds
+        Some(unsafe { self.buf[self.len].assume_init_read() })
+    }
+
+    /**
+        Insert an element at position `index`, shifting
+        all elements after it to the right
+
+        # Panics
+        Panics if `index > len`.
+    */
+    pub fn insert(&mut self, index: usize, val: T) -> Result<(), CapacityError<T>> {
+        assert!(index <= self.len, "insertion index out of bounds");
+
+        if self.is_full() {
+            return Err(CapacityError::new(val));
+        }
+
+        // SAFETY: the shifted range stays within the capacity
+        unsafe {
+            let p = self.buf.as_mut_ptr().add(index);
+            ptr::copy(p, p.add(1), self.len - index);
+            (*p).write(val);
+        }
+        self.len += 1;
+
+        Ok(())
+    }
+
+    /**
+        Remove and return the element at position `index`,
+        shifting all elements after it to the left
+
+        # Panics
+        Panics if `index >= len`.
+    */
+    pub fn remove(&mut self, index: usize) -> T {
+        assert!(index < self.len, "removal index out of bounds");
+
+        // SAFETY: the element is initialized, and the shifted
+        // range stays within the initialized elements
+        unsafe {
+            let p = self.buf.as_mut_ptr().add(index);
+            let val = (*p).assume_init_read();
+            ptr::copy(p.add(1), p, self.len - index - 1);
+            self.len -= 1;
+
+            val
+        }
+    }
+
+    /**
+        Remove and return the element at position `index`,
+        replacing it with the last element
+
+        # Panics
+        Panics if `index >= len`.
+    */
+    pub fn swap_remove(&mut self, index: usize) -> T {
+        assert!(index < self.len, "removal index out of bounds");
+
+        let last = self.len - 1;
+        self.as_mut_slice().swap(index, last);
+
+        // - `pop` can't fail, as the vector isn't empty
+        match self.pop() {
+            Some(val) => val,
+            None => unreachable!(),
+        }
+    }
+
+    /// Shorten the vector to `len` elements, dropping the rest
+    pub fn truncate(&mut self, len: usize) {
+        while self.len > len {
+            self.pop();
+        }
+    }
+
+    /// Remove all elements
+    pub fn clear(&mut self) {
+        self.truncate(0);
+    }
+
+    /// Keep only the elements for which `f` returns `true`
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, mut f: F) {
+        let mut i = 0;
+        while i < self.len {
+            if f(&self[i]) {
+                i += 1;
+            } else {
+                self.remove(i);
+            }
+        }
+    }
+
+    /**
+        Append elements from an iterator, stopping at the first
+        one that doesn't fit
+
+        Elements pushed before the failure are kept.
+    */
+    pub fn try_extend<I: IntoIterator<Item = T>>(
+        &mut self,
+        iter: I,
+    ) -> Result<(), CapacityError<T>> {
+        for val in iter {
+            self.push(val)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<T: Clone, const N: usize> ArrayVec<T, N> {
+    /// Append all elements of `other`, or none of them if they don't fit
+    pub fn try_extend_from_slice(&mut self, other: &[T]) -> Result<(), CapacityError> {
+        if other.len() > self.remaining_capacity() {
+            return Err(CapacityError::new(()));
+        }
+
+        for val in other {
+            self.buf[self.len].write(val.clone());
+            self.len += 1;
+        }
+
+        Ok(())
+    }
+}
+
+impl<T, const N: usize> Default for ArrayVec<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> Drop for ArrayVec<T, N> {
+    fn drop(&mut self) {
+        // SAFETY: the first `len` elements are initialized
+        unsafe { ptr::drop_in_place(self.as_mut_slice()) };
+    }
+}
+
+impl<T, const N: usize> Deref for ArrayVec<T, N> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        self.as_slice()
+    }
+}
+
+impl<T, const N: usize> DerefMut for ArrayVec<T, N> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        self.as_mut_slice()
+    }
+}
+
+impl<T: Clone, const N: usize> Clone for ArrayVec<T, N> {
+    fn clone(&self) -> Self {
+        let mut v = Self::new();
+        for val in self.iter() {
+            // - the clone has the same capacity
+            let _ = v.push(val.clone());
+        }
+
+        v
+    }
+}
+
+impl<T: fmt::Debug, const N: usize> fmt::Debug for ArrayVec<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: PartialEq, const N: usize, const M: usize> PartialEq<ArrayVec<T, M>> for ArrayVec<T, N> {
+    fn eq(&self, other: &ArrayVec<T, M>) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl<T: PartialEq, const N: usize> PartialEq<[T]> for ArrayVec<T, N> {
+    fn eq(&self, other: &[T]) -> bool {
+        self.as_slice() == other
+    }
+}
+
+impl<T: Eq, const N: usize> Eq for ArrayVec<T, N> {}
+
+/**
+    Append elements from an iterator
+
+    # Panics
+    Panics if the elements don't fit; use [`ArrayVec::try_extend()`]
+    to handle that case instead.
+*/
+impl<T, const N: usize> Extend<T> for ArrayVec<T, N> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        if self.try_extend(iter).is_err() {
+            panic!("`ArrayVec` capacity exceeded");
+        }
+    }
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a ArrayVec<T, N> {
+    type Item = &'a T;
+    type IntoIter = slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a mut ArrayVec<T, N> {
+    type Item = &'a mut T;
+    type IntoIter = slice::IterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+/// Append as many bytes as fit
+impl<const N: usize> Write for ArrayVec<u8, N> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, io::Error> {
+        let n = buf.len().min(self.remaining_capacity());
+
+        // - the slice fits, so this can't fail
+        let _ = self.try_extend_from_slice(&buf[..n]);
+
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> Result<(), io::Error> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use std::rc::Rc;
+
+    #[test]
+    fn push_and_pop() {
+        let mut v: ArrayVec<u32, 3> = ArrayVec::new();
+        assert!(v.is_empty());
+        assert_eq!(v.pop(), None);
+
+        for i in 0..3 {
+            assert_eq!(v.push(i), Ok(()));
+        }
+        assert!(v.is_full());
+        assert_eq!(v.remaining_capacity(), 0);
+        assert_eq!(v.push(3), Err(CapacityError::new(3)));
+        assert_eq!(v.push(4).unwrap_err().element(), 4);
+        assert_eq!(v, [0, 1, 2][..]);
+
+        assert_eq!(v.pop(), Some(2));
+        assert_eq!(v.push(5), Ok(()));
+        assert_eq!(v, [0, 1, 5][..]);
+    }
+
+    #[test]
+    fn zero_capacity() {
+        let mut v: ArrayVec<u8, 0> = ArrayVec::new();
+
+        assert!(v.is_full());
+        assert_eq!(v.push(1), Err(CapacityError::new(1)));
+        assert_eq!(v.try_extend_from_slice(&[]), Ok(()));
+        assert!(v.try_extend_from_slice(&[1]).is_err());
+        assert_eq!(v.write(b"x").unwrap(), 0);
+    }
+
+    #[test]
+    fn insert_and_remove() {
+        let mut v: ArrayVec<char, 4> = ArrayVec::new();
+
+        v.insert(0, 'b').unwrap();
+        v.insert(0, 'a').unwrap();
+        v.insert(2, 'd').unwrap();
+        v.insert(2, 'c').unwrap();
+        assert_eq!(v, ['a', 'b', 'c', 'd'][..]);
+        assert_eq!(v.insert(1, 'x'), Err(CapacityError::new('x')));
+        assert_eq!(v, ['a', 'b', 'c', 'd'][..]);
+
+        assert_eq!(v.remove(1), 'b');
+        assert_eq!(v.swap_remove(0), 'a');
+        assert_eq!(v, ['d', 'c'][..]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn insert_out_of_bounds() {
+        let mut v: ArrayVec<u8, 4> = ArrayVec::new();
+        let _ = v.insert(1, 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn remove_out_of_bounds() {
+        let mut v: ArrayVec<u8, 4> = ArrayVec::new();
+        v.push(0).unwrap();
+        v.remove(1);
+    }
+
+    #[test]
+    fn extend() {
+        let mut v: ArrayVec<u8, 4> = ArrayVec::new();
+
+        v.extend([1, 2]);
+        assert_eq!(
+            v.try_extend_from_slice(&[3, 4, 5]),
+            Err(CapacityError::new(()))
+        );
+        assert_eq!(v, [1, 2][..]);
+
+        // - elements are pushed up until the first failure
+        assert_eq!(v.try_extend(3..6), Err(CapacityError::new(5)));
+        assert_eq!(v, [1, 2, 3, 4][..]);
+    }
+
+    #[test]
+    #[should_panic(expected = "capacity exceeded")]
+    fn extend_overflow_panics() {
+        let mut v: ArrayVec<u8, 2> = ArrayVec::new();
+        v.extend(0..3);
+    }
+
+    #[test]
+    fn drops_elements() {
+        let val = Rc::new(());
+        let mut v: ArrayVec<Rc<()>, 4> = ArrayVec::new();
+
+        for _ in 0..4 {
+            v.push(Rc::clone(&val)).unwrap();
+        }
+        // - the rejected element is handed back, not leaked
+        drop(v.push(Rc::clone(&val)));
+        assert_eq!(Rc::strong_count(&val), 5);
+
+        v.truncate(2);
+        assert_eq!(Rc::strong_count(&val), 3);
+
+        let w = v.clone();
+        v.retain(|_| false);
+        assert_eq!(Rc::strong_count(&val), 3);
+
+        drop(w);
+        assert_eq!(Rc::strong_count(&val), 1);
+    }
+
+    #[test]
+    fn io_write() {
+        let mut v: ArrayVec<u8, 5> = ArrayVec::new();
+
+        assert_eq!(v.write(b"abc").unwrap(), 3);
+        assert_eq!(v.write(b"defg").unwrap(), 2);
+        assert_eq!(&v[..], b"abcde");
+        assert!(v.write_all(b"f").is_err());
+    }
+}
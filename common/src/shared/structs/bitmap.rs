@@ -0,0 +1,611 @@
+/*!
+    Module defining a bitmap type over 64-bit words
+
+    Bit `i` lives in word `i / 64`, at position `i % 64` (counting
+    from the least significant bit). Searches and range operations
+    work on whole words wherever possible, so that scanning a sparse
+    or dense bitmap costs one comparison per 64 bits.
+
+    # Usage
+    ```rust
+    # use common::shared::structs::bitmap::{Bitmap, words_for};
+    # use common::shared::structs::spin_lock::Mutex;
+    # let num_frames = 1000;
+    # let words = &mut [0u64; words_for(1000)][..];
+    // - owned, e.g. in a static
+    static VECTORS: Mutex<Bitmap<[u64; 4]>> = Mutex::new(Bitmap::new());
+
+    // - borrowed, e.g. from a region found at runtime
+    let mut frames = Bitmap::from_slice(words, num_frames);
+    frames.set_range(0..256);
+    let base = frames.alloc_run(16, 16).expect("out of frames");
+    assert_eq!(base, 256);
+    ```
+*/
+
+// Definition uses
+use core::fmt;
+use core::ops::Range;
+
+// Number of bits in a word
+const WORD_BITS: usize = u64::BITS as usize;
+
+/// Returns the number of words needed to hold `bits` bits
+pub const fn words_for(bits: usize) -> usize {
+    bits.div_ceil(WORD_BITS)
+}
+
+// Internal: mask covering bits `lo..hi` of a word (`hi <= 64`)
+#[inline(always)]
+fn mask(lo: usize, hi: usize) -> u64 {
+    let upper = if hi >= WORD_BITS {
+        u64::MAX
+    } else {
+        (1u64 << hi) - 1
+    };
+
+    upper & !((1u64 << lo) - 1)
+}
+
+/**
+    Fixed-length set of bits, stored in 64-bit words
+
+    The storage is either owned (`[u64; W]`) or borrowed
+    (`&mut [u64]`). Bits past the length, but within the
+    last word, are never reported by searches.
+*/
+#[derive(Clone)]
+pub struct Bitmap<S> {
+    words: S,
+    len: usize,
+}
+
+impl<const W: usize> Bitmap<[u64; W]> {
+    /// Create new, cleared instance of `Bitmap` with `64 * W` bits
+    pub const fn new() -> Self {
+        Bitmap {
+            words: [0; W],
+            len: W * WORD_BITS,
+        }
+    }
+}
+
+impl<const W: usize> Default for Bitmap<[u64; W]> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> Bitmap<&'a mut [u64]> {
+    /**
+        Create new instance of `Bitmap` over `words`, with `len` bits
+
+        The existing contents are kept.
+
+        # Panics
+        Panics if `words` cannot hold `len` bits.
+    */
+    pub fn from_slice(words: &'a mut [u64], len: usize) -> Self {
+        assert!(words_for(len) <= words.len(), "bitmap storage is too small");
+
+        Bitmap { words, len }
+    }
+}
+
+impl<S: AsRef<[u64]>> Bitmap<S> {
+    // Internal: returns the words holding the bits
+    #[inline(always)]
+    fn words(&self) -> &[u64] {
+        &self.words.as_ref()[..words_for(self.len)]
+    }
+
+    /// Returns the number of bits
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Checks whether the bitmap holds no bits at all
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the underlying words
+    pub fn as_words(&self) -> &[u64] {
+        self.words()
+    }
+
+    /**
+        Checks whether bit `i` is set
+
+        # Panics
+        Panics if `i` is out of bounds.
+    */
+    pub fn test(&self, i: usize) -> bool {
+        assert!(i < self.len, "bit index out of bounds");
+
+        self.words()[i / WORD_BITS] & (1 << (i % WORD_BITS)) != 0
+    }
+
+    // Internal: returns the words, with the bits past the length cleared
+    // - borrowed storage may come with those bits set
+    fn masked_words(&self) -> impl Iterator<Item = u64> + '_ {
+        let words = self.words();
+        let last = words.len().wrapping_sub(1);
+        let tail = mask(0, (self.len.wrapping_sub(1) % WORD_BITS) + 1);
+
+        words
+            .iter()
+            .enumerate()
+            .map(move |(w, &word)| if w == last { word & tail } else { word })
+    }
+
+    /// Returns the number of set bits
+    pub fn count_ones(&self) -> usize {
+        self.masked_words().map(|w| w.count_ones() as usize).sum()
+    }
+
+    /// Returns the number of clear bits
+    pub fn count_zeros(&self) -> usize {
+        self.len - self.count_ones()
+    }
+
+    /// Checks whether every bit is clear
+    pub fn all_clear(&self) -> bool {
+        self.masked_words().all(|w| w == 0)
+    }
+
+    // Internal: find the first bit at or after `from` whose value is
+    // `!invert`, by inverting every word if searching for clear bits
+    fn find_from(&self, from: usize, invert: bool) -> Option<usize> {
+        if from >= self.len {
+            return None;
+        }
+
+        let flip = if invert { u64::MAX } else { 0 };
+        let words = self.words();
+
+        let mut w = from / WORD_BITS;
+        let mut word = (words[w] ^ flip) & mask(from % WORD_BITS, WORD_BITS);
+
+        loop {
+            if word != 0 {
+                let i = w * WORD_BITS + word.trailing_zeros() as usize;
+                return if i < self.len { Some(i) } else { None };
+            }
+
+            w += 1;
+            if w >= words.len() {
+                return None;
+            }
+            word = words[w] ^ flip;
+        }
+    }
+
+    /// Returns the index of the first set bit
+    pub fn first_set(&self) -> Option<usize> {
+        self.find_from(0, false)
+    }
+
+    /// Returns the index of the first clear bit
+    pub fn first_zero(&self) -> Option<usize> {
+        self.find_from(0, true)
+    }
+
+    /// Returns the index of the first set bit at or after `from`
+    pub fn next_set(&self, from: usize) -> Option<usize> {
+        self.find_from(from, false)
+    }
+
+    /// Returns the index of the first clear bit at or after `from`
+    pub fn next_zero(&self, from: usize) -> Option<usize> {
+        self.find_from(from, true)
+    }
+
+    /**
+        Find `n` contiguous clear bits, starting at a multiple of `align`
+
+        Returns the index of the first bit of the lowest such run.
+
+        # Panics
+        Panics if `align` isn't a power of two.
+    */
+    pub fn find_clear_run(&self, n: usize, align: usize) -> Option<usize> {
+        assert!(align.is_power_of_two(), "alignment must be a power of two");
+
+        let mut from = 0;
+        loop {
+            let start = self.next_zero(from)?.checked_next_multiple_of(align)?;
+            let end = start.checked_add(n)?;
+
+            if end > self.len {
+                return None;
+            }
+
+            // - skip past the first obstacle, if any
+            match self.next_set(start) {
+                Some(i) if i < end => from = i + 1,
+                _ => return Some(start),
+            }
+        }
+    }
+
+    /// Returns an iterator over the indices of the set bits
+    pub fn iter_set(&self) -> Iter<'_> {
+        Iter::new(self.words(), self.len, false)
+    }
+
+    /// Returns an iterator over the indices of the clear bits
+    pub fn iter_zeros(&self) -> Iter<'_> {
+        Iter::new(self.words(), self.len, true)
+    }
+}
+
+impl<S: AsRef<[u64]> + AsMut<[u64]>> Bitmap<S> {
+    // Internal: returns the words holding the bits
+    #[inline(always)]
+    fn words_mut(&mut self) -> &mut [u64] {
+        let n = words_for(self.len);
+        &mut self.words.as_mut()[..n]
+    }
+
+    /// Returns the underlying words
+    pub fn as_words_mut(&mut self) -> &mut [u64] {
+        self.words_mut()
+    }
+
+    /**
+        Set bit `i`, returning its previous value
+
+        # Panics
+        Panics if `i` is out of bounds.
+    */
+    pub fn set(&mut self, i: usize) -> bool {
+        self.assign(i, true)
+    }
+
+    /**
+        Clear bit `i`, returning its previous value
+
+        # Panics
+        Panics if `i` is out of bounds.
+    */
+    pub fn clear(&mut self, i: usize) -> bool {
+        self.assign(i, false)
+    }
+
+    /**
+        Set bit `i` to `val`, returning its previous value
+
+        # Panics
+        Panics if `i` is out of bounds.
+    */
+    pub fn assign(&mut self, i: usize, val: bool) -> bool {
+        assert!(i < self.len, "bit index out of bounds");
+
+        let word = &mut self.words_mut()[i / WORD_BITS];
+        let bit = 1 << (i % WORD_BITS);
+        let old = *word & bit != 0;
+
+        if val {
+            *word |= bit;
+        } else {
+            *word &= !bit;
+        }
+
+        old
+    }
+
+    // Internal: apply `f` to every word in the range, along with
+    // the mask of the bits that are in range
+    fn for_range<F: FnMut(&mut u64, u64)>(&mut self, range: Range<usize>, mut f: F) {
+        assert!(
+            range.start <= range.end && range.end <= self.len,
+            "bit range out of bounds"
+        );
+
+        if range.is_empty() {
+            return;
+        }
+
+        let (first, last) = (range.start / WORD_BITS, (range.end - 1) / WORD_BITS);
+        let words = self.words_mut();
+
+        for (w, word) in words.iter_mut().enumerate().take(last + 1).skip(first) {
+            let lo = if w == first {
+                range.start % WORD_BITS
+            } else {
+                0
+            };
+            let hi = if w == last {
+                (range.end - 1) % WORD_BITS + 1
+            } else {
+                WORD_BITS
+            };
+
+            f(word, mask(lo, hi));
+        }
+    }
+
+    /**
+        Set every bit in `range`
+
+        # Panics
+        Panics if `range` is out of bounds.
+    */
+    pub fn set_range(&mut self, range: Range<usize>) {
+        self.for_range(range, |w, m| *w |= m);
+    }
+
+    /**
+        Clear every bit in `range`
+
+        # Panics
+        Panics if `range` is out of bounds.
+    */
+    pub fn clear_range(&mut self, range: Range<usize>) {
+        self.for_range(range, |w, m| *w &= !m);
+    }
+
+    /// Set every bit
+    pub fn set_all(&mut self) {
+        let len = self.len;
+        self.set_range(0..len);
+    }
+
+    /// Clear every bit
+    pub fn clear_all(&mut self) {
+        self.words_mut().fill(0);
+    }
+
+    /**
+        Find and set `n` contiguous clear bits, starting at a multiple
+        of `align`, and return the index of the first one
+
+        # Panics
+        Panics if `align` isn't a power of two.
+    */
+    pub fn alloc_run(&mut self, n: usize, align: usize) -> Option<usize> {
+        let start = self.find_clear_run(n, align)?;
+        self.set_range(start..start + n);
+
+        Some(start)
+    }
+}
+
+impl<S: AsRef<[u64]>> fmt::Debug for Bitmap<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Bitmap")
+            .field("len", &self.len)
+            .field("ones", &self.count_ones())
+            .finish()
+    }
+}
+
+/// Iterator over the indices of the set (or clear) bits of a [`Bitmap`]
+#[derive(Clone, Debug)]
+pub struct Iter<'a> {
+    words: &'a [u64],
+    len: usize,
+    flip: u64,
+    // - index of the current word, and its remaining bits
+    w: usize,
+    word: u64,
+}
+
+impl<'a> Iter<'a> {
+    // Internal: create new iterator
+    fn new(words: &'a [u64], len: usize, invert: bool) -> Self {
+        let flip = if invert { u64::MAX } else { 0 };
+
+        Iter {
+            words,
+            len,
+            flip,
+            w: 0,
+            word: words.first().map_or(0, |&w| w ^ flip),
+        }
+    }
+}
+
+impl Iterator for Iter<'_> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        while self.word == 0 {
+            self.w += 1;
+            self.word = *self.words.get(self.w)? ^ self.flip;
+        }
+
+        let i = self.w * WORD_BITS + self.word.trailing_zeros() as usize;
+
+        // - clear the lowest set bit
+        self.word &= self.word - 1;
+
+        if i < self.len {
+            Some(i)
+        } else {
+            self.word = 0;
+            self.w = self.words.len();
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use std::vec::Vec;
+
+    #[test]
+    fn single_bits() {
+        let mut b: Bitmap<[u64; 2]> = Bitmap::new();
+        assert_eq!(b.len(), 128);
+        assert!(b.all_clear());
+
+        assert!(!b.set(0));
+        assert!(b.set(0));
+        assert!(!b.set(63));
+        assert!(!b.set(64));
+        assert!(!b.set(127));
+        assert_eq!(b.as_words(), &[1 | 1 << 63, 1 | 1 << 63]);
+        assert_eq!(b.count_ones(), 4);
+
+        assert!(b.clear(63));
+        assert!(!b.clear(63));
+        assert!(b.test(64));
+        assert!(!b.test(65));
+        assert!(!b.assign(65, true));
+        assert_eq!(b.count_zeros(), 124);
+    }
+
+    #[test]
+    #[should_panic]
+    fn out_of_bounds() {
+        let mut words = [0u64; 2];
+        let mut b = Bitmap::from_slice(&mut words, 70);
+        b.set(70);
+    }
+
+    #[test]
+    #[should_panic]
+    fn storage_too_small() {
+        let mut words = [0u64; 1];
+        Bitmap::from_slice(&mut words, 65);
+    }
+
+    #[test]
+    fn bits_past_len() {
+        // - borrowed storage may have bits set past the length
+        let mut words = [u64::MAX, u64::MAX];
+        let b = Bitmap::from_slice(&mut words, 70);
+        assert_eq!(b.count_ones(), 70);
+        assert_eq!(b.count_zeros(), 0);
+
+        let mut words = [!0x3ffu64];
+        let mut b = Bitmap::from_slice(&mut words, 10);
+        assert!(b.all_clear());
+        assert_eq!((b.count_ones(), b.count_zeros()), (0, 10));
+
+        b.as_words_mut()[0] = u64::MAX;
+        assert_eq!((b.count_ones(), b.count_zeros()), (10, 0));
+        assert_eq!(b.first_zero(), None);
+
+        // - and the empty bitmap has no words at all
+        let b = Bitmap::from_slice(&mut [], 0);
+        assert!(b.all_clear());
+        assert_eq!(b.count_ones(), 0);
+    }
+
+    #[test]
+    fn searches() {
+        let mut words = [0u64; 3];
+        let mut b = Bitmap::from_slice(&mut words, 150);
+
+        assert_eq!(b.first_set(), None);
+        assert_eq!(b.first_zero(), Some(0));
+
+        b.set(70);
+        b.set(149);
+        assert_eq!(b.first_set(), Some(70));
+        assert_eq!(b.next_set(71), Some(149));
+        assert_eq!(b.next_set(150), None);
+
+        b.set_all();
+        assert_eq!(b.first_zero(), None);
+        assert_eq!(b.count_ones(), 150);
+
+        // - bits past the length are never reported
+        b.clear(100);
+        assert_eq!(b.first_zero(), Some(100));
+        assert_eq!(b.next_zero(101), None);
+    }
+
+    #[test]
+    fn ranges() {
+        let mut b: Bitmap<[u64; 4]> = Bitmap::new();
+
+        b.set_range(3..3);
+        assert!(b.all_clear());
+
+        b.set_range(60..200);
+        assert_eq!(b.count_ones(), 140);
+        assert_eq!(b.first_set(), Some(60));
+        assert_eq!(b.next_zero(60), Some(200));
+
+        b.clear_range(64..192);
+        assert_eq!(
+            b.iter_set().collect::<Vec<_>>(),
+            [60, 61, 62, 63, 192, 193, 194, 195, 196, 197, 198, 199]
+        );
+
+        b.set_range(0..256);
+        assert_eq!(b.count_zeros(), 0);
+        b.clear_range(5..6);
+        assert_eq!(b.first_zero(), Some(5));
+
+        b.clear_all();
+        assert!(b.all_clear());
+    }
+
+    #[test]
+    #[should_panic]
+    fn range_out_of_bounds() {
+        let mut b: Bitmap<[u64; 1]> = Bitmap::new();
+        b.set_range(10..65);
+    }
+
+    #[test]
+    fn clear_runs() {
+        let mut b: Bitmap<[u64; 4]> = Bitmap::new();
+
+        assert_eq!(b.find_clear_run(256, 1), Some(0));
+        assert_eq!(b.find_clear_run(257, 1), None);
+        assert_eq!(b.find_clear_run(0, 1), Some(0));
+
+        b.set(0);
+        b.set(10);
+        assert_eq!(b.find_clear_run(9, 1), Some(1));
+        assert_eq!(b.find_clear_run(10, 1), Some(11));
+        assert_eq!(b.find_clear_run(4, 8), Some(16));
+        assert_eq!(b.find_clear_run(8, 64), Some(64));
+
+        // - runs may span words
+        b.set_range(20..60);
+        b.set_range(70..256);
+        assert_eq!(b.find_clear_run(10, 1), Some(60));
+        assert_eq!(b.find_clear_run(11, 1), None);
+        assert_eq!(b.find_clear_run(8, 4), Some(12));
+        assert_eq!(b.find_clear_run(8, 8), None);
+    }
+
+    #[test]
+    fn alloc_runs() {
+        let mut words = [0u64; 2];
+        let mut b = Bitmap::from_slice(&mut words, 100);
+
+        assert_eq!(b.alloc_run(30, 1), Some(0));
+        assert_eq!(b.alloc_run(30, 32), Some(32));
+        assert_eq!(b.alloc_run(30, 1), Some(62));
+        assert_eq!(b.alloc_run(9, 1), None);
+        assert_eq!(b.alloc_run(8, 1), Some(92));
+        assert_eq!(b.iter_zeros().collect::<Vec<_>>(), [30, 31]);
+        assert_eq!(b.count_ones(), 98);
+    }
+
+    #[test]
+    fn iteration() {
+        let mut words = [0u64, u64::MAX];
+        let mut b = Bitmap::from_slice(&mut words, 67);
+
+        assert_eq!(b.iter_set().collect::<Vec<_>>(), [64, 65, 66]);
+        assert_eq!(b.iter_zeros().count(), 64);
+
+        b.set(1);
+        b.set(63);
+        assert_eq!(b.iter_set().collect::<Vec<_>>(), [1, 63, 64, 65, 66]);
+        assert_eq!(b.iter_zeros().next(), Some(0));
+        assert_eq!(b.iter_zeros().last(), Some(62));
+    }
+}
@@ -0,0 +1,670 @@
+/*!
+    Memory-mapped register blocks
+
+    Device registers are described by a `#[repr(C)]` struct of
+    [`Register`] fields, with [`Reserved`] filling the gaps, and
+    are reached through an [`Mmio`] handle tied to the mapped range.
+    The handle never creates a reference to device memory: every
+    access goes through a raw pointer to a single register, handed
+    out as a [`Reg`] by [`mmio_reg!`](crate::mmio_reg).
+
+    Registers are read and written whole, like [`VolatileCell`],
+    or in fenced fashion, like [`FencedVolatileCell`]. Bitfields
+    are described by [`Field`], and updated by read-modify-write.
+
+    # Usage
+    ```rust,no_run
+    # use common::mmio_reg;
+    # use common::shared::structs::mmio::{Field, Mmio, ReadOnly, Register, Reserved};
+    /// Registers of the HPET (only the first few)
+    #[repr(C)]
+    struct Hpet {
+        caps: Register<u64, ReadOnly>,
+        _r0: Reserved<0x08>,
+        config: Register<u64>,
+        _r1: Reserved<0x18>,
+        status: Register<u64>,
+    }
+
+    const ENABLE_CNF: Field<u64> = Field::bit(0);
+
+    let hpet: Mmio<Hpet> = unsafe { Mmio::identity(0xfed0_0000) };
+    let period = mmio_reg!(hpet, caps).read() >> 32;
+    mmio_reg!(hpet, config).set(ENABLE_CNF, 1);
+    ```
+
+    [`VolatileCell`]: super::volatile::VolatileCell
+    [`FencedVolatileCell`]: super::volatile::FencedVolatileCell
+*/
+
+// Definition uses
+use core::fmt;
+use core::marker::PhantomData;
+use core::ptr::{self, NonNull};
+use core::sync::atomic::{self, Ordering};
+
+use super::volatile::VolatileCell;
+
+// Internal: seals the traits below, as register
+// widths and access markers are a closed set
+mod sealed {
+    pub trait Sealed {}
+}
+
+/**
+    Trait to mark type as the value of a register
+
+    Implemented for `u8`, `u16`, `u32` and `u64`. Values are widened
+    to `u64` for bitfield arithmetic (refer to [`Field`]).
+*/
+pub trait RegValue: Copy + sealed::Sealed {
+    /// Width of the value, in bits
+    const BITS: u32;
+
+    /// Returns the value widened to `u64`
+    fn to_u64(self) -> u64;
+
+    /// Returns the low bits of `val`
+    fn from_u64(val: u64) -> Self;
+}
+
+// Implement `RegValue` for an unsigned integer type
+macro_rules! reg_value {
+    ($($ty:ty),*) => {
+        $(
+            impl sealed::Sealed for $ty {}
+
+            impl RegValue for $ty {
+                const BITS: u32 = <$ty>::BITS;
+
+                #[inline(always)]
+                fn to_u64(self) -> u64 {
+                    self as u64
+                }
+
+                #[inline(always)]
+                fn from_u64(val: u64) -> Self {
+                    val as $ty
+                }
+            }
+        )*
+    };
+}
+
+reg_value!(u8, u16, u32, u64);
+
+/// Trait to mark register access as allowing reads
+pub trait Readable: sealed::Sealed {}
+
+/// Trait to mark register access as allowing writes
+pub trait Writable: sealed::Sealed {}
+
+/// Access marker of registers that can only be read
+#[derive(Debug, Copy, Clone)]
+pub struct ReadOnly;
+
+/// Access marker of registers that can only be written
+#[derive(Debug, Copy, Clone)]
+pub struct WriteOnly;
+
+/// Access marker of registers that can be both read and written
+#[derive(Debug, Copy, Clone)]
+pub struct ReadWrite;
+
+impl sealed::Sealed for ReadOnly {}
+impl sealed::Sealed for WriteOnly {}
+impl sealed::Sealed for ReadWrite {}
+
+impl Readable for ReadOnly {}
+impl Readable for ReadWrite {}
+impl Writable for WriteOnly {}
+impl Writable for ReadWrite {}
+
+/**
+    Register of type `V` within a register block
+
+    This type only describes the layout of the block, and is never
+    accessed directly; it has the size and alignment of `V`, so that
+    a `#[repr(C)]` block lays registers out at their natural offsets.
+    Refer to [`mmio_reg!`](crate::mmio_reg) for accessing it.
+*/
+#[repr(transparent)]
+pub struct Register<V, A = ReadWrite> {
+    _cell: VolatileCell<V>,
+    _access: PhantomData<A>,
+}
+
+/// Gap of `N` bytes between registers of a block
+#[repr(transparent)]
+pub struct Reserved<const N: usize>([u8; N]);
+
+/**
+    Bitfield of a register of type `V`
+
+    A field spans `width` bits, starting at bit `shift`. Field values
+    are passed right-aligned, and are truncated to the field's width.
+*/
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Field<V> {
+    shift: u32,
+    width: u32,
+    _marker: PhantomData<V>,
+}
+
+impl<V: RegValue> Field<V> {
+    /**
+        Create new instance of `Field` spanning `width` bits from `shift`
+
+        # Panics
+        Panics (at compile time, in `const` context) if the field is
+        empty or doesn't fit within `V`.
+    */
+    pub const fn new(shift: u32, width: u32) -> Self {
+        assert!(width > 0 && shift < V::BITS && width <= V::BITS - shift);
+
+        Field {
+            shift,
+            width,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Create new instance of `Field` spanning the single bit `bit`
+    pub const fn bit(bit: u32) -> Self {
+        Self::new(bit, 1)
+    }
+
+    /// Returns the position of the lowest bit of the field
+    pub const fn shift(&self) -> u32 {
+        self.shift
+    }
+
+    /// Returns the number of bits in the field
+    pub const fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Returns the mask of the field, in place
+    pub fn mask(&self) -> V {
+        V::from_u64((u64::MAX >> (64 - self.width)) << self.shift)
+    }
+
+    /// Returns the field's value, extracted from `reg`
+    pub fn get(&self, reg: V) -> V {
+        V::from_u64((reg.to_u64() & self.mask().to_u64()) >> self.shift)
+    }
+
+    /// Returns `reg`, with the field replaced by `val`
+    pub fn set(&self, reg: V, val: V) -> V {
+        let mask = self.mask().to_u64();
+        V::from_u64((reg.to_u64() & !mask) | ((val.to_u64() << self.shift) & mask))
+    }
+
+    /// Returns `val` placed in the field, with all other bits clear
+    pub fn value(&self, val: V) -> V {
+        V::from_u64((val.to_u64() << self.shift) & self.mask().to_u64())
+    }
+}
+
+/**
+    Handle to a register block of type `T`, mapped at a physical range
+
+    The handle owns the mapping for its lifetime, in the sense that
+    it's the only way through which the block is accessed. `T` is
+    either a `#[repr(C)]` struct of [`Register`] fields, or a slice
+    of registers, like a text buffer.
+*/
+pub struct Mmio<T: ?Sized> {
+    ptr: NonNull<T>,
+    phys: usize,
+}
+
+impl<T> Mmio<T> {
+    /**
+        Create new instance of `Mmio` for the block mapped at `virt`,
+        backed by the physical address `phys`
+
+        # Safety
+        The caller must ensure that `virt` is non-null, aligned for
+        `T` and maps `size_of::<T>()` bytes of device memory at `phys`,
+        that the mapping outlives the handle, and that no other code
+        accesses the block concurrently in a conflicting way.
+    */
+    pub const unsafe fn new(virt: *mut T, phys: usize) -> Self {
+        Mmio {
+            ptr: unsafe { NonNull::new_unchecked(virt) },
+            phys,
+        }
+    }
+
+    /**
+        Create new instance of `Mmio` for a block that is
+        identity-mapped at `phys`
+
+        # Safety
+        Refer to [`new()`](Self::new).
+    */
+    pub const unsafe fn identity(phys: usize) -> Self {
+        unsafe { Self::new(phys as *mut T, phys) }
+    }
+
+    /// Returns the size of the mapped range, in bytes
+    pub const fn size(&self) -> usize {
+        size_of::<T>()
+    }
+}
+
+impl<V: RegValue, A> Mmio<[Register<V, A>]> {
+    /**
+        Create new instance of `Mmio` for `len` registers mapped
+        at `virt`, backed by the physical address `phys`
+
+        # Safety
+        Refer to [`new()`](Self::new), where `T` is `[V; len]`.
+    */
+    pub const unsafe fn from_raw_parts(virt: *mut Register<V, A>, phys: usize, len: usize) -> Self {
+        Mmio {
+            ptr: unsafe { NonNull::new_unchecked(ptr::slice_from_raw_parts_mut(virt, len)) },
+            phys,
+        }
+    }
+
+    /**
+        Create new instance of `Mmio` for `len` registers that
+        are identity-mapped at `phys`
+
+        # Safety
+        Refer to [`new()`](Self::new), where `T` is `[V; len]`.
+    */
+    pub const unsafe fn identity_slice(phys: usize, len: usize) -> Self {
+        unsafe { Self::from_raw_parts(phys as *mut _, phys, len) }
+    }
+
+    /// Returns the number of registers
+    pub const fn len(&self) -> usize {
+        self.ptr.len()
+    }
+
+    /// Returns `true` if there are no registers
+    pub const fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the size of the mapped range, in bytes
+    pub const fn size(&self) -> usize {
+        self.len() * size_of::<V>()
+    }
+
+    /// Returns the register at `index`, or `None` if out of bounds
+    #[inline(always)]
+    pub fn get(&self, index: usize) -> Option<Reg<'_, V, A>> {
+        if index >= self.len() {
+            return None;
+        }
+
+        // - the element is in bounds, and is only offset
+        // to, never dereferenced
+        let p = self.ptr.cast::<Register<V, A>>();
+        Some(unsafe { Reg::new(p.add(index)) })
+    }
+}
+
+impl<T: ?Sized> Mmio<T> {
+    /// Returns the physical address of the block
+    pub const fn phys(&self) -> usize {
+        self.phys
+    }
+
+    /// Returns the virtual address of the block
+    pub const fn as_ptr(&self) -> *mut T {
+        self.ptr.as_ptr()
+    }
+
+    /**
+        Returns the register that `f` locates within the block
+
+        This is the building block of [`mmio_reg!`](crate::mmio_reg),
+        which should be preferred.
+
+        # Safety
+        `f` must only project the pointer it's given onto one
+        of the registers of the block, without dereferencing it
+        (as in `&raw mut (*p).field`).
+    */
+    #[inline(always)]
+    pub unsafe fn project<V, A, F>(&self, f: F) -> Reg<'_, V, A>
+    where
+        V: RegValue,
+        F: FnOnce(*mut T) -> *mut Register<V, A>,
+    {
+        let p = f(self.ptr.as_ptr());
+        unsafe { Reg::new(NonNull::new_unchecked(p)) }
+    }
+}
+
+impl<T: ?Sized> fmt::Debug for Mmio<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Mmio({:#x} @ {:p})", self.phys, self.ptr)
+    }
+}
+
+// - the handle stands for the mapping, which may move
+// between threads along with it
+unsafe impl<T: ?Sized> Send for Mmio<T> {}
+
+/**
+    Access to a single register of type `V`, borrowed from an [`Mmio`]
+
+    Plain accesses are volatile, like those of [`VolatileCell`].
+    Fenced accesses add the fences of [`FencedVolatileCell`], for
+    registers whose accesses must be ordered against memory, such
+    as doorbells and DMA descriptors.
+
+    [`VolatileCell`]: super::volatile::VolatileCell
+    [`FencedVolatileCell`]: super::volatile::FencedVolatileCell
+*/
+pub struct Reg<'a, V, A> {
+    ptr: NonNull<V>,
+    _marker: PhantomData<(&'a (), A)>,
+}
+
+impl<V: RegValue, A> Reg<'_, V, A> {
+    // Internal: create new instance of `Reg`
+    // SAFETY: `ptr` must point to a register of a live `Mmio`
+    #[inline(always)]
+    unsafe fn new(ptr: NonNull<Register<V, A>>) -> Self {
+        Reg {
+            ptr: ptr.cast(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns the virtual address of the register
+    pub fn as_ptr(&self) -> *mut V {
+        self.ptr.as_ptr()
+    }
+}
+
+impl<V: RegValue, A: Readable> Reg<'_, V, A> {
+    /// Performs volatile read
+    #[inline(always)]
+    pub fn read(&self) -> V {
+        // SAFETY: the register lies within the mapped range (refer to `Mmio`)
+        unsafe { ptr::read_volatile(self.ptr.as_ptr()) }
+    }
+
+    /**
+        Performs weakly fenced volatile read
+
+        This operation uses [`fence()`] and [`Ordering::Acquire`] internally.
+
+        [`fence()`]: core::sync::atomic::fence
+    */
+    #[inline(always)]
+    pub fn read_fenced(&self) -> V {
+        let val = self.read();
+        atomic::fence(Ordering::Acquire);
+        val
+    }
+
+    /// Returns the value of `field`
+    #[inline(always)]
+    pub fn get(&self, field: Field<V>) -> V {
+        field.get(self.read())
+    }
+
+    /// Returns `true` if any bit of `mask` is set
+    #[inline(always)]
+    pub fn any(&self, mask: V) -> bool {
+        self.read().to_u64() & mask.to_u64() != 0
+    }
+}
+
+impl<V: RegValue, A: Writable> Reg<'_, V, A> {
+    /// Performs volatile write
+    #[inline(always)]
+    pub fn write(&self, val: V) {
+        // SAFETY: the register lies within the mapped range (refer to `Mmio`)
+        unsafe { ptr::write_volatile(self.ptr.as_ptr(), val) }
+    }
+
+    /**
+        Performs weakly fenced volatile write
+
+        This operation uses [`fence()`] and [`Ordering::Release`] internally.
+
+        [`fence()`]: core::sync::atomic::fence
+    */
+    #[inline(always)]
+    pub fn write_fenced(&self, val: V) {
+        atomic::fence(Ordering::Release);
+        self.write(val);
+    }
+}
+
+impl<V: RegValue, A: Readable + Writable> Reg<'_, V, A> {
+    /// Read the register, then write back the result of `f`
+    #[inline(always)]
+    pub fn modify(&self, f: impl FnOnce(V) -> V) {
+        self.write(f(self.read()));
+    }
+
+    /// Set `field` to `val`, leaving the other bits untouched
+    #[inline(always)]
+    pub fn set(&self, field: Field<V>, val: V) {
+        self.modify(|r| field.set(r, val));
+    }
+
+    /// Set every bit of `mask`
+    #[inline(always)]
+    pub fn set_bits(&self, mask: V) {
+        self.modify(|r| V::from_u64(r.to_u64() | mask.to_u64()));
+    }
+
+    /// Clear every bit of `mask`
+    #[inline(always)]
+    pub fn clear_bits(&self, mask: V) {
+        self.modify(|r| V::from_u64(r.to_u64() & !mask.to_u64()));
+    }
+}
+
+impl<V, A> fmt::Debug for Reg<'_, V, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Reg({:p})", self.ptr)
+    }
+}
+
+/**
+    Returns the [`Reg`] for a register of an [`Mmio`] block
+
+    The register is named by its field path within the block,
+    which may index into arrays of registers. No reference to
+    the block is created along the way.
+
+    Indices are evaluated before the projection, outside of the
+    `unsafe` block it requires, so they can't sneak in unsafe
+    operations. Anything but a path of fields and indices is
+    rejected.
+
+    # Syntax
+    ```rust,ignore
+    let id = mmio_reg!(lapic, id).read();
+    mmio_reg!(lapic, eoi).write(0);
+    let word = mmio_reg!(lapic, isr[2].value).read();
+    ```
+
+    An index can't perform unsafe operations on its own:
+    ```rust,compile_fail
+    use common::mmio_reg;
+    use common::shared::structs::mmio::{Mmio, ReadOnly, Register};
+
+    #[repr(C)]
+    struct Regs {
+        isr: [Register<u32, ReadOnly>; 4],
+    }
+
+    let mut mem = [0u32; 4];
+    let regs: Mmio<Regs> = unsafe { Mmio::new(mem.as_mut_ptr().cast(), 0) };
+
+    let idx = &2usize as *const usize;
+    mmio_reg!(regs, isr[*idx]).read();
+    ```
+*/
+#[macro_export]
+macro_rules! mmio_reg {
+    // - bind the index to a local, so that it's evaluated outside of `unsafe`
+    (@path $mmio:expr; [$($path:tt)*]; $field:ident [$idx:expr] $(. $($rest:tt)+)?) => {{
+        let idx: usize = $idx;
+        $crate::mmio_reg!(@path $mmio; [$($path)* .$field[idx]]; $($($rest)+)?)
+    }};
+    (@path $mmio:expr; [$($path:tt)*]; $field:ident $(. $($rest:tt)+)?) => {
+        $crate::mmio_reg!(@path $mmio; [$($path)* .$field]; $($($rest)+)?)
+    };
+    (@path $mmio:expr; [$($path:tt)*];) => {{
+        let mmio = &$mmio;
+
+        // SAFETY: the closure only projects onto a field of the block
+        unsafe { $crate::shared::structs::mmio::Mmio::project(mmio, |p| &raw mut (*p)$($path)*) }
+    }};
+    ($mmio:expr, $($field:ident $([$idx:expr])?).+) => {
+        $crate::mmio_reg!(@path $mmio; []; $($field $([$idx])?).+)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::mem::offset_of;
+
+    // - the "device" is plain memory, aligned for the block
+    #[repr(C, align(16))]
+    struct Backing([u32; 16]);
+
+    /// Block for checking the layout and accesses
+    #[repr(C)]
+    struct TestRegs {
+        id: Register<u32, ReadOnly>,
+        _r0: Reserved<0x0c>,
+        ctrl: Register<u32>,
+        cmd: Register<u32, WriteOnly>,
+        _r1: Reserved<0x08>,
+        isr: [Register<u32, ReadOnly>; 4],
+    }
+
+    #[test]
+    fn block_layout() {
+        assert_eq!(offset_of!(TestRegs, ctrl), 0x10);
+        assert_eq!(offset_of!(TestRegs, cmd), 0x14);
+        assert_eq!(offset_of!(TestRegs, isr), 0x20);
+        assert_eq!(size_of::<TestRegs>(), 0x30);
+    }
+
+    #[test]
+    fn register_access() {
+        let mut mem = Backing([0; 16]);
+        mem.0[0] = 0x1234;
+        mem.0[10] = 0xaa;
+
+        let base = mem.0.as_mut_ptr();
+        let regs: Mmio<TestRegs> = unsafe { Mmio::new(base.cast(), 0xfee0_0000) };
+        assert_eq!(regs.phys(), 0xfee0_0000);
+        assert_eq!(regs.size(), 0x30);
+
+        assert_eq!(mmio_reg!(regs, id).read(), 0x1234);
+        assert_eq!(mmio_reg!(regs, isr[2]).read_fenced(), 0xaa);
+
+        mmio_reg!(regs, cmd).write(7);
+        mmio_reg!(regs, ctrl).write_fenced(0xf0);
+        mmio_reg!(regs, ctrl).set_bits(0x01);
+        mmio_reg!(regs, ctrl).clear_bits(0x10);
+        assert_eq!(mmio_reg!(regs, ctrl).read(), 0xe1);
+
+        assert_eq!(mem.0[5], 7);
+    }
+
+    #[test]
+    fn nested_paths() {
+        #[repr(C)]
+        struct Channel {
+            ctrl: Register<u32>,
+            data: [Register<u32>; 3],
+        }
+
+        #[repr(C)]
+        struct Block {
+            id: Register<u32, ReadOnly>,
+            ch: [Channel; 3],
+        }
+
+        let mut mem = Backing([0; 16]);
+        let regs: Mmio<Block> = unsafe { Mmio::new(mem.0.as_mut_ptr().cast(), 0) };
+
+        // - every index is evaluated exactly once
+        let mut calls = 0;
+        let mut next = |i| {
+            calls += 1;
+            i
+        };
+
+        mmio_reg!(regs, ch[next(1)].data[next(2)]).write(0x55);
+        mmio_reg!(regs, ch[2].ctrl).write(0x66);
+        assert_eq!(calls, 2);
+        assert_eq!(mem.0[1 + 4 + 1 + 2], 0x55);
+        assert_eq!(mem.0[1 + 2 * 4], 0x66);
+    }
+
+    #[test]
+    #[should_panic]
+    fn index_out_of_bounds() {
+        let mut mem = Backing([0; 16]);
+        let regs: Mmio<TestRegs> = unsafe { Mmio::new(mem.0.as_mut_ptr().cast(), 0) };
+
+        let i = 4;
+        mmio_reg!(regs, isr[i]).read();
+    }
+
+    #[test]
+    fn bitfields() {
+        const MODE: Field<u32> = Field::new(8, 3);
+        const EN: Field<u32> = Field::bit(31);
+
+        assert_eq!(MODE.mask(), 0x700);
+        assert_eq!(MODE.get(0xffff_f5ff), 5);
+        assert_eq!(MODE.set(0xffff_ffff, 2), 0xffff_faff);
+        assert_eq!(MODE.value(0xf), 0x700);
+        assert_eq!(EN.mask(), 0x8000_0000);
+        assert_eq!(Field::<u64>::new(0, 64).mask(), u64::MAX);
+
+        let mut mem = Backing([0; 16]);
+        let regs: Mmio<TestRegs> = unsafe { Mmio::new(mem.0.as_mut_ptr().cast(), 0) };
+
+        let ctrl = mmio_reg!(regs, ctrl);
+        ctrl.write(0x0000_00ff);
+        ctrl.set(MODE, 6);
+        ctrl.set(EN, 1);
+        assert_eq!(ctrl.read(), 0x8000_06ff);
+        assert_eq!(ctrl.get(MODE), 6);
+        assert!(ctrl.any(EN.mask()));
+
+        assert_eq!(mem.0[4], 0x8000_06ff);
+    }
+
+    #[test]
+    fn register_slice() {
+        let mut mem = [0u16; 8];
+        let buf: Mmio<[Register<u16>]> =
+            unsafe { Mmio::from_raw_parts(mem.as_mut_ptr().cast(), 0xb8000, mem.len()) };
+
+        assert_eq!(buf.len(), 8);
+        assert_eq!(buf.size(), 16);
+        assert!(buf.get(8).is_none());
+
+        for i in 0..buf.len() {
+            buf.get(i).unwrap().write(0x0700 | i as u16);
+        }
+        buf.get(3).unwrap().modify(|c| c | 0x8000);
+
+        assert_eq!(mem[3], 0x8703);
+        assert_eq!(mem[7], 0x0707);
+    }
+}
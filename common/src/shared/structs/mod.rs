@@ -11,12 +11,30 @@ use core::ops::{Index, IndexMut};
 // Volatile wrapper type
 pub mod volatile;
 
+// Memory-mapped register blocks
+pub mod mmio;
+
 // Array-like fat pointer type
 pub mod array_like;
 
 // Spin lock wrapper type
 pub mod spin_lock;
 
+// One-time initialization types
+pub mod once;
+
+// Lock-free queues
+pub mod queue;
+
+// Fixed-capacity vector type
+pub mod array_vec;
+
+// Fixed-capacity string type
+pub mod array_string;
+
+// Bitmap type
+pub mod bitmap;
+
 /**
     Textbook ring buffer with slice-like semantics
 
@@ -70,16 +88,16 @@ impl<'a, T: Default + Copy> RingBuf<'a, T> {
     /// Rotate the buffer left by a specified amount
     /// (equivalent to shifting the buffer head to the right)
     pub fn rol(&mut self, n: usize) {
-        self.head = (self.head + n) % self.rows;
+        self.head = (self.head + n % self.rows) % self.rows;
     }
 
     /// Rotate the buffer right by a specified amount
     /// (equivalent to shifting the buffer head to the left)
     pub fn ror(&mut self, n: usize) {
         // - calculate new head position using N's complement
-        // (we trust that `N != 0`)
-        let m = n.div_ceil(self.rows);
-        self.head = (self.head + m * self.rows - n) % self.rows;
+        // (reduced first, so that large `n` can't overflow)
+        let m = n % self.rows;
+        self.head = (self.head + self.rows - m) % self.rows;
     }
 }
 
@@ -98,3 +116,60 @@ impl<'a, T: Default + Copy> IndexMut<usize> for RingBuf<'a, T> {
         &mut self.inner[n * self.cols..(n + 1) * self.cols]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Internal: returns the first column of every row, in order
+    fn firsts(r: &RingBuf<'_, u8>) -> [u8; 3] {
+        [r[0][0], r[1][0], r[2][0]]
+    }
+
+    #[test]
+    fn ring_buf_geometry() {
+        let mut buf = [0xffu8; 8];
+
+        assert!(RingBuf::new(&mut buf, 0, 2).is_none());
+        assert!(RingBuf::new(&mut buf, 2, 0).is_none());
+        assert!(RingBuf::new(&mut buf, 3, 3).is_none());
+
+        // - only the used part of the buffer is cleared
+        let r = RingBuf::new(&mut buf, 2, 3).unwrap();
+        assert_eq!(r[0], [0, 0]);
+        assert_eq!(r[2].len(), 2);
+        assert_eq!(buf[6..], [0xff, 0xff]);
+    }
+
+    #[test]
+    fn ring_buf_rotation() {
+        let mut buf = [0u8; 6];
+        let mut r = RingBuf::new(&mut buf, 2, 3).unwrap();
+        for i in 0..3 {
+            r[i][0] = i as u8;
+        }
+
+        r.rol(1);
+        assert_eq!(firsts(&r), [1, 2, 0]);
+        r.ror(1);
+        assert_eq!(firsts(&r), [0, 1, 2]);
+
+        // - rotating by a multiple of the row count is a no-op
+        r.ror(0);
+        r.rol(3);
+        r.ror(6);
+        assert_eq!(firsts(&r), [0, 1, 2]);
+
+        r.ror(4);
+        assert_eq!(firsts(&r), [2, 0, 1]);
+
+        // - large amounts must not overflow
+        r.ror(usize::MAX);
+        assert_eq!(firsts(&r), [2, 0, 1]);
+        r.rol(1);
+        r.ror(usize::MAX - 1);
+        assert_eq!(firsts(&r), [1, 2, 0]);
+        r.rol(usize::MAX);
+        assert_eq!(firsts(&r), [1, 2, 0]);
+    }
+}
@@ -0,0 +1,420 @@
+/*!
+    Module defining one-time initialization types
+
+    [`Once`] replaces the `Mutex<Option<T>>` pattern, along with
+    its "already initialized" checks: the value is computed by the
+    first caller, while concurrent callers spin until it's ready.
+    Afterwards, it's read without any locking.
+
+    # Usage
+    ```rust
+    # use common::shared::structs::once::{Lazy, Once};
+    # struct Config;
+    # impl Config { fn load() -> Self { Config } }
+    # fn build_table() -> [u8; 256] { [0; 256] }
+    static CONFIG: Once<Config> = Once::new();
+    static TABLE: Lazy<[u8; 256]> = Lazy::new(build_table);
+
+    let config = CONFIG.call_once(|| Config::load());
+    let entry = TABLE[0x20];
+    ```
+*/
+
+// Definition uses
+use core::cell::{Cell, UnsafeCell};
+use core::fmt;
+use core::hint::spin_loop;
+use core::mem::MaybeUninit;
+use core::ops::{Deref, Drop};
+use core::sync::atomic::{AtomicU8, Ordering};
+
+// Initialization states
+const INCOMPLETE: u8 = 0;
+const RUNNING: u8 = 1;
+const COMPLETE: u8 = 2;
+const POISONED: u8 = 3;
+
+/**
+    Cell initialized at most once, safe under concurrent callers
+
+    If the initializer panics, the cell is poisoned, and any
+    further attempt at initializing or waiting on it panics.
+*/
+pub struct Once<T> {
+    state: AtomicU8,
+    data: UnsafeCell<MaybeUninit<T>>,
+}
+
+/// Alias of [`Once`], named after `core::cell::OnceCell`
+pub type OnceCell<T> = Once<T>;
+
+// Internal: poison the cell if the initializer unwinds
+struct Finish<'a> {
+    state: &'a AtomicU8,
+    done: bool,
+}
+
+impl Drop for Finish<'_> {
+    fn drop(&mut self) {
+        let state = if self.done { COMPLETE } else { POISONED };
+        self.state.store(state, Ordering::Release);
+    }
+}
+
+impl<T> Once<T> {
+    /// Create new, uninitialized instance of `Once`
+    pub const fn new() -> Self {
+        Once {
+            state: AtomicU8::new(INCOMPLETE),
+            data: UnsafeCell::new(MaybeUninit::uninit()),
+        }
+    }
+
+    /// Create new instance of `Once`, initialized to `val`
+    pub const fn initialized(val: T) -> Self {
+        Once {
+            state: AtomicU8::new(COMPLETE),
+            data: UnsafeCell::new(MaybeUninit::new(val)),
+        }
+    }
+
+    /**
+        Returns the value, initializing it with `f` if necessary
+
+        Only one caller ever runs `f`. Concurrent callers spin
+        until it has returned.
+
+        # Safety
+        If `f` calls `call_once` on the same cell,
+        then the calling thread **will deadlock**.
+    */
+    pub fn call_once<F: FnOnce() -> T>(&self, f: F) -> &T {
+        match self.try_call_once(|| Ok::<T, ()>(f())) {
+            Ok(v) => v,
+            Err(()) => unreachable!(),
+        }
+    }
+
+    /**
+        Returns the value, initializing it with `f` if necessary
+
+        If `f` fails, the cell is left uninitialized, and
+        the next caller gets to try again.
+    */
+    pub fn try_call_once<E, F: FnOnce() -> Result<T, E>>(&self, f: F) -> Result<&T, E> {
+        loop {
+            match self.state.compare_exchange_weak(
+                INCOMPLETE,
+                RUNNING,
+                Ordering::Acquire,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => break,
+                Err(COMPLETE) => return Ok(unsafe { self.get_unchecked() }),
+                Err(POISONED) => panic!("`Once` instance has been poisoned"),
+                // - another caller is running its initializer
+                Err(RUNNING) => spin_loop(),
+                // - spurious failure
+                Err(_) => {}
+            }
+        }
+
+        let mut finish = Finish {
+            state: &self.state,
+            done: false,
+        };
+
+        match f() {
+            Ok(val) => {
+                // SAFETY: the `RUNNING` state grants exclusive access
+                unsafe { (*self.data.get()).write(val) };
+                finish.done = true;
+                drop(finish);
+
+                Ok(unsafe { self.get_unchecked() })
+            }
+            Err(e) => {
+                // - let the next caller try again
+                core::mem::forget(finish);
+                self.state.store(INCOMPLETE, Ordering::Release);
+                Err(e)
+            }
+        }
+    }
+
+    /**
+        Initialize the cell to `val`, unless it's already initialized
+
+        The value is handed back if the cell is initialized, or
+        is being initialized by another caller.
+    */
+    pub fn set(&self, val: T) -> Result<(), T> {
+        if self
+            .state
+            .compare_exchange(INCOMPLETE, RUNNING, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            return Err(val);
+        }
+
+        // SAFETY: the `RUNNING` state grants exclusive access
+        unsafe { (*self.data.get()).write(val) };
+        self.state.store(COMPLETE, Ordering::Release);
+
+        Ok(())
+    }
+
+    /// Returns the value, initializing it with `f` if necessary
+    pub fn get_or_init<F: FnOnce() -> T>(&self, f: F) -> &T {
+        self.call_once(f)
+    }
+
+    /// Returns the value, if it's initialized
+    pub fn get(&self) -> Option<&T> {
+        if self.is_completed() {
+            Some(unsafe { self.get_unchecked() })
+        } else {
+            None
+        }
+    }
+
+    /// Returns a mutable reference to the value, if it's initialized
+    pub fn get_mut(&mut self) -> Option<&mut T> {
+        if *self.state.get_mut() == COMPLETE {
+            Some(unsafe { self.data.get_mut().assume_init_mut() })
+        } else {
+            None
+        }
+    }
+
+    /// Returns the value, spinning until another caller has initialized it
+    pub fn wait(&self) -> &T {
+        loop {
+            match self.state.load(Ordering::Acquire) {
+                COMPLETE => return unsafe { self.get_unchecked() },
+                POISONED => panic!("`Once` instance has been poisoned"),
+                _ => spin_loop(),
+            }
+        }
+    }
+
+    /// Checks whether the value is initialized
+    pub fn is_completed(&self) -> bool {
+        self.state.load(Ordering::Acquire) == COMPLETE
+    }
+
+    /// Consumes the cell, returning the value if it's initialized
+    pub fn into_inner(mut self) -> Option<T> {
+        if *self.state.get_mut() != COMPLETE {
+            return None;
+        }
+
+        // - keep `drop` from dropping the value again
+        *self.state.get_mut() = INCOMPLETE;
+        Some(unsafe { self.data.get_mut().assume_init_read() })
+    }
+
+    // Internal: returns the value without checking the state
+    // - callers must have observed `COMPLETE` with acquire ordering
+    unsafe fn get_unchecked(&self) -> &T {
+        unsafe { (*self.data.get()).assume_init_ref() }
+    }
+}
+
+impl<T> Default for Once<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Once<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.get() {
+            Some(v) => f.debug_tuple("Once").field(v).finish(),
+            None => f.write_str("Once(<uninit>)"),
+        }
+    }
+}
+
+impl<T> Drop for Once<T> {
+    fn drop(&mut self) {
+        if *self.state.get_mut() == COMPLETE {
+            unsafe { self.data.get_mut().assume_init_drop() };
+        }
+    }
+}
+
+// - allow reference sharing
+// - the value is shared once initialized, and may be
+//   initialized on a different thread, hence both bounds
+unsafe impl<T: Send + Sync> Sync for Once<T> {}
+unsafe impl<T: Send> Send for Once<T> {}
+
+/**
+    Value initialized on first access, suitable for statics
+
+    If the initializer panics, the value is poisoned (refer
+    to [`Once`]), and every further access panics.
+*/
+pub struct Lazy<T, F = fn() -> T> {
+    cell: Once<T>,
+    init: Cell<Option<F>>,
+}
+
+impl<T, F: FnOnce() -> T> Lazy<T, F> {
+    /// Create new instance of `Lazy`, to be initialized by `init`
+    pub const fn new(init: F) -> Self {
+        Lazy {
+            cell: Once::new(),
+            init: Cell::new(Some(init)),
+        }
+    }
+
+    /// Returns the value, initializing it if necessary
+    pub fn force(this: &Self) -> &T {
+        // - only the caller that wins the race takes the initializer
+        this.cell.call_once(|| match this.init.take() {
+            Some(f) => f(),
+            None => unreachable!(),
+        })
+    }
+
+    /// Returns the value, if it's initialized
+    pub fn get(this: &Self) -> Option<&T> {
+        this.cell.get()
+    }
+}
+
+impl<T, F: FnOnce() -> T> Deref for Lazy<T, F> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        Self::force(self)
+    }
+}
+
+impl<T: fmt::Debug, F> fmt::Debug for Lazy<T, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Lazy").field(&self.cell).finish()
+    }
+}
+
+// - allow reference sharing
+// - the initializer is only ever taken by a single caller,
+//   which may be on a different thread, hence `F: Send`
+unsafe impl<T: Send + Sync, F: Send> Sync for Lazy<T, F> {}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use core::sync::atomic::AtomicUsize;
+    use std::sync::{Arc, Barrier};
+    use std::thread;
+    use std::vec::Vec;
+
+    const THREADS: usize = 8;
+
+    // Run `f` on several threads, released at the same time
+    fn race<F: Fn() + Send + Sync + 'static>(f: F) {
+        let f = Arc::new(f);
+        let barrier = Arc::new(Barrier::new(THREADS));
+
+        let handles: Vec<_> = (0..THREADS)
+            .map(|_| {
+                let (f, barrier) = (Arc::clone(&f), Arc::clone(&barrier));
+                thread::spawn(move || {
+                    barrier.wait();
+                    f();
+                })
+            })
+            .collect();
+
+        for h in handles {
+            h.join().unwrap();
+        }
+    }
+
+    #[test]
+    fn call_once_runs_once() {
+        static CALLS: AtomicUsize = AtomicUsize::new(0);
+        static ONCE: Once<usize> = Once::new();
+
+        race(|| {
+            let v = ONCE.call_once(|| {
+                thread::yield_now();
+                CALLS.fetch_add(1, Ordering::SeqCst) + 42
+            });
+            assert_eq!(*v, 42);
+        });
+
+        assert_eq!(CALLS.load(Ordering::SeqCst), 1);
+        assert_eq!(ONCE.get(), Some(&42));
+    }
+
+    #[test]
+    fn set_and_get() {
+        let once = Once::new();
+        assert!(once.get().is_none());
+        assert!(!once.is_completed());
+
+        assert_eq!(once.set(1), Ok(()));
+        assert_eq!(once.set(2), Err(2));
+        assert_eq!(*once.call_once(|| 3), 1);
+        assert_eq!(*once.wait(), 1);
+        assert_eq!(once.into_inner(), Some(1));
+
+        let once = Once::initialized(5);
+        assert_eq!(once.get(), Some(&5));
+    }
+
+    #[test]
+    fn failed_init_can_be_retried() {
+        let once: OnceCell<u32> = OnceCell::new();
+
+        assert_eq!(once.try_call_once(|| Err("busy")), Err("busy"));
+        assert!(!once.is_completed());
+        assert_eq!(once.try_call_once(|| Ok::<_, ()>(7)), Ok(&7));
+        assert_eq!(*once.get_or_init(|| 8), 7);
+    }
+
+    #[test]
+    fn panicking_init_poisons() {
+        let once = Arc::new(Once::<u32>::new());
+
+        let o = Arc::clone(&once);
+        let r = thread::spawn(move || *o.call_once(|| panic!("init failed"))).join();
+        assert!(r.is_err());
+
+        let o = Arc::clone(&once);
+        let r = thread::spawn(move || *o.call_once(|| 1)).join();
+        assert!(r.is_err());
+    }
+
+    #[test]
+    fn drops_value() {
+        let val = Arc::new(());
+        let once = Once::new();
+        once.call_once(|| Arc::clone(&val));
+
+        assert_eq!(Arc::strong_count(&val), 2);
+        drop(once);
+        assert_eq!(Arc::strong_count(&val), 1);
+    }
+
+    #[test]
+    fn lazy_static() {
+        static CALLS: AtomicUsize = AtomicUsize::new(0);
+        static TABLE: Lazy<[usize; 4]> = Lazy::new(|| {
+            CALLS.fetch_add(1, Ordering::SeqCst);
+            [1, 2, 4, 8]
+        });
+
+        assert!(Lazy::get(&TABLE).is_none());
+        race(|| assert_eq!(TABLE[3], 8));
+
+        assert_eq!(CALLS.load(Ordering::SeqCst), 1);
+        assert_eq!(Lazy::get(&TABLE), Some(&[1, 2, 4, 8]));
+    }
+}
@@ -0,0 +1,586 @@
+/*!
+    Module defining lock-free, fixed-capacity queues
+
+    The queues pass values from interrupt handlers to regular
+    code without locking, so neither side can deadlock on the
+    other, whatever the point at which it gets interrupted.
+
+    - [`SpscQueue`] supports a single producer and a single consumer,
+    - [`MpscQueue`] supports several producers and a single consumer.
+
+    The capacity `N` must be a power of two, so that positions
+    can wrap around without skipping slots.
+
+    # Usage
+    ```rust
+    # use common::shared::structs::queue::SpscQueue;
+    # let scancode = 0x1c;
+    static KEYS: SpscQueue<u8, 64> = SpscQueue::new();
+
+    let (mut tx, mut rx) = KEYS.split().unwrap();
+
+    // - in the IRQ handler
+    let _ = tx.push(scancode);
+
+    // - in regular code
+    while let Some(scancode) = rx.pop() {
+        // - decode `scancode`
+    #   assert_eq!(scancode, 0x1c);
+    }
+    ```
+*/
+
+// Definition uses
+use core::cell::UnsafeCell;
+use core::marker::PhantomData;
+use core::mem::MaybeUninit;
+use core::ops::Drop;
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+// Internal: uninitialized storage slot
+type Slot<T> = UnsafeCell<MaybeUninit<T>>;
+
+/**
+    Lock-free single-producer/single-consumer queue
+
+    The producer only ever writes the tail, and the consumer
+    only ever writes the head, so each side merely has to
+    observe the other side's position.
+*/
+pub struct SpscQueue<T, const N: usize> {
+    buf: [Slot<T>; N],
+    head: AtomicUsize,
+    tail: AtomicUsize,
+    split: AtomicBool,
+}
+
+impl<T, const N: usize> SpscQueue<T, N> {
+    /// Create new, empty instance of `SpscQueue`
+    pub const fn new() -> Self {
+        const { assert!(N.is_power_of_two(), "queue capacity must be a power of two") };
+
+        SpscQueue {
+            buf: [const { UnsafeCell::new(MaybeUninit::uninit()) }; N],
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+            split: AtomicBool::new(false),
+        }
+    }
+
+    /**
+        Returns the producer and consumer handles
+
+        The handles can only be obtained once, which upholds the
+        single-producer and single-consumer contract for safe code.
+    */
+    pub fn split(&self) -> Option<(Producer<'_, T, N>, Consumer<'_, T, N>)> {
+        if self.split.swap(true, Ordering::AcqRel) {
+            return None;
+        }
+
+        let producer = Producer {
+            queue: self,
+            _marker: PhantomData,
+        };
+        let consumer = Consumer {
+            queue: self,
+            _marker: PhantomData,
+        };
+
+        Some((producer, consumer))
+    }
+
+    /**
+        Append a value, handing it back if the queue is full
+
+        # Safety
+        The caller must be the only producer.
+    */
+    pub unsafe fn push(&self, val: T) -> Result<(), T> {
+        // - only the producer writes the tail
+        let tail = self.tail.load(Ordering::Relaxed);
+        let head = self.head.load(Ordering::Acquire);
+
+        if tail.wrapping_sub(head) >= N {
+            return Err(val);
+        }
+
+        // SAFETY: the consumer doesn't touch slots past the tail
+        unsafe { (*self.buf[tail & (N - 1)].get()).write(val) };
+        self.tail.store(tail.wrapping_add(1), Ordering::Release);
+
+        Ok(())
+    }
+
+    /**
+        Remove the oldest value, if any
+
+        # Safety
+        The caller must be the only consumer.
+    */
+    pub unsafe fn pop(&self) -> Option<T> {
+        // - only the consumer writes the head
+        let head = self.head.load(Ordering::Relaxed);
+        let tail = self.tail.load(Ordering::Acquire);
+
+        if head == tail {
+            return None;
+        }
+
+        // SAFETY: the producer doesn't touch slots before the tail
+        let val = unsafe { (*self.buf[head & (N - 1)].get()).assume_init_read() };
+        self.head.store(head.wrapping_add(1), Ordering::Release);
+
+        Some(val)
+    }
+
+    /// Returns the number of queued values
+    /// - it may be stale by the time it's returned
+    pub fn len(&self) -> usize {
+        let head = self.head.load(Ordering::Acquire);
+        let tail = self.tail.load(Ordering::Acquire);
+
+        tail.wrapping_sub(head).min(N)
+    }
+
+    /// Checks whether the queue is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Checks whether the queue is full
+    pub fn is_full(&self) -> bool {
+        self.len() == N
+    }
+
+    /// Returns the capacity
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+}
+
+impl<T, const N: usize> Default for SpscQueue<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> Drop for SpscQueue<T, N> {
+    fn drop(&mut self) {
+        // SAFETY: exclusive access makes this the only consumer
+        while unsafe { self.pop() }.is_some() {}
+    }
+}
+
+// - allow reference sharing
+unsafe impl<T: Send, const N: usize> Sync for SpscQueue<T, N> {}
+
+/// Producing half of a [`SpscQueue`]
+pub struct Producer<'a, T, const N: usize> {
+    queue: &'a SpscQueue<T, N>,
+    // - handles may be sent, but not shared
+    _marker: PhantomData<*mut ()>,
+}
+
+impl<T, const N: usize> Producer<'_, T, N> {
+    /// Append a value, handing it back if the queue is full
+    pub fn push(&mut self, val: T) -> Result<(), T> {
+        // SAFETY: there is a single producer handle
+        unsafe { self.queue.push(val) }
+    }
+
+    /// Returns the number of queued values
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Checks whether the queue is empty
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    /// Checks whether the queue is full
+    pub fn is_full(&self) -> bool {
+        self.queue.is_full()
+    }
+}
+
+unsafe impl<T: Send, const N: usize> Send for Producer<'_, T, N> {}
+
+/// Consuming half of a [`SpscQueue`]
+pub struct Consumer<'a, T, const N: usize> {
+    queue: &'a SpscQueue<T, N>,
+    // - handles may be sent, but not shared
+    _marker: PhantomData<*mut ()>,
+}
+
+impl<T, const N: usize> Consumer<'_, T, N> {
+    /// Remove the oldest value, if any
+    pub fn pop(&mut self) -> Option<T> {
+        // SAFETY: there is a single consumer handle
+        unsafe { self.queue.pop() }
+    }
+
+    /// Returns the number of queued values
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Checks whether the queue is empty
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+}
+
+unsafe impl<T: Send, const N: usize> Send for Consumer<'_, T, N> {}
+
+/**
+    Lock-free multiple-producer/single-consumer queue
+
+    Every slot carries a sequence number, which tells producers
+    whether the slot is free, and the consumer whether the slot
+    has been filled. Producers claim slots by advancing the tail.
+
+    Sequence numbers count in half-steps: a slot that is free for
+    position `p` holds `2p`, and holds `2p + 1` once filled. This
+    keeps a filled slot distinct from a free one even when `N == 1`,
+    where the next position maps to the very same slot.
+
+    A producer that is interrupted between claiming and filling
+    its slot holds back the consumer (but no other producer)
+    until it resumes, so values are always popped in the order
+    in which their slots were claimed.
+*/
+pub struct MpscQueue<T, const N: usize> {
+    buf: [Slot<T>; N],
+    seqs: [AtomicUsize; N],
+    head: AtomicUsize,
+    tail: AtomicUsize,
+    consumer: AtomicBool,
+}
+
+impl<T, const N: usize> MpscQueue<T, N> {
+    /// Create new, empty instance of `MpscQueue`
+    pub const fn new() -> Self {
+        const { assert!(N.is_power_of_two(), "queue capacity must be a power of two") };
+
+        let mut seqs = [const { AtomicUsize::new(0) }; N];
+        let mut i = 0;
+        while i < N {
+            seqs[i] = AtomicUsize::new(2 * i);
+            i += 1;
+        }
+
+        MpscQueue {
+            buf: [const { UnsafeCell::new(MaybeUninit::uninit()) }; N],
+            seqs,
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+            consumer: AtomicBool::new(false),
+        }
+    }
+
+    /**
+        Returns the consumer handle
+
+        The handle can only be obtained once, which upholds
+        the single-consumer contract for safe code.
+    */
+    pub fn consumer(&self) -> Option<MpscConsumer<'_, T, N>> {
+        if self.consumer.swap(true, Ordering::AcqRel) {
+            return None;
+        }
+
+        Some(MpscConsumer {
+            queue: self,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Append a value, handing it back if the queue is full
+    pub fn push(&self, val: T) -> Result<(), T> {
+        let mut tail = self.tail.load(Ordering::Relaxed);
+
+        loop {
+            let seq = self.seqs[tail & (N - 1)].load(Ordering::Acquire);
+
+            // - a free slot's sequence number matches the position,
+            //   a slot filled by another producer is ahead of it,
+            //   and a slot yet to be popped lags a whole lap behind
+            match seq.wrapping_sub(tail.wrapping_mul(2)) as isize {
+                0 => match self.tail.compare_exchange_weak(
+                    tail,
+                    tail.wrapping_add(1),
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => break,
+                    Err(t) => tail = t,
+                },
+                d if d < 0 => return Err(val),
+                _ => tail = self.tail.load(Ordering::Relaxed),
+            }
+        }
+
+        // SAFETY: the claimed slot is ours until its sequence number moves
+        let slot = tail & (N - 1);
+        unsafe { (*self.buf[slot].get()).write(val) };
+        self.seqs[slot].store(tail.wrapping_mul(2).wrapping_add(1), Ordering::Release);
+
+        Ok(())
+    }
+
+    /**
+        Remove the oldest value, if any
+
+        # Safety
+        The caller must be the only consumer.
+    */
+    pub unsafe fn pop(&self) -> Option<T> {
+        // - only the consumer writes the head
+        let head = self.head.load(Ordering::Relaxed);
+        let slot = head & (N - 1);
+
+        if self.seqs[slot].load(Ordering::Acquire) != head.wrapping_mul(2).wrapping_add(1) {
+            return None;
+        }
+
+        // SAFETY: the slot has been filled, and no producer reuses
+        // it until its sequence number moves a lap ahead
+        let val = unsafe { (*self.buf[slot].get()).assume_init_read() };
+        self.head.store(head.wrapping_add(1), Ordering::Relaxed);
+        self.seqs[slot].store(head.wrapping_add(N).wrapping_mul(2), Ordering::Release);
+
+        Some(val)
+    }
+
+    /// Returns the number of claimed slots, including those being filled
+    /// - it may be stale by the time it's returned
+    pub fn len(&self) -> usize {
+        let head = self.head.load(Ordering::Acquire);
+        let tail = self.tail.load(Ordering::Acquire);
+
+        tail.wrapping_sub(head).min(N)
+    }
+
+    /// Checks whether the queue is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the capacity
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+}
+
+impl<T, const N: usize> Default for MpscQueue<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> Drop for MpscQueue<T, N> {
+    fn drop(&mut self) {
+        // SAFETY: exclusive access makes this the only consumer
+        while unsafe { self.pop() }.is_some() {}
+    }
+}
+
+// - allow reference sharing
+unsafe impl<T: Send, const N: usize> Sync for MpscQueue<T, N> {}
+
+/// Consuming half of a [`MpscQueue`]
+pub struct MpscConsumer<'a, T, const N: usize> {
+    queue: &'a MpscQueue<T, N>,
+    // - handles may be sent, but not shared
+    _marker: PhantomData<*mut ()>,
+}
+
+impl<T, const N: usize> MpscConsumer<'_, T, N> {
+    /// Remove the oldest value, if any
+    pub fn pop(&mut self) -> Option<T> {
+        // SAFETY: there is a single consumer handle
+        unsafe { self.queue.pop() }
+    }
+
+    /// Returns the number of claimed slots, including those being filled
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Checks whether the queue is empty
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+}
+
+unsafe impl<T: Send, const N: usize> Send for MpscConsumer<'_, T, N> {}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use std::sync::Arc;
+    use std::thread;
+    use std::vec::Vec;
+
+    const COUNT: usize = 100_000;
+    const PRODUCERS: usize = 4;
+
+    #[test]
+    fn spsc_fifo() {
+        let q: SpscQueue<u32, 4> = SpscQueue::new();
+        let (mut tx, mut rx) = q.split().unwrap();
+
+        assert!(rx.pop().is_none());
+        for i in 0..4 {
+            assert_eq!(tx.push(i), Ok(()));
+        }
+        assert!(tx.is_full());
+        assert_eq!(tx.push(4), Err(4));
+
+        assert_eq!(rx.pop(), Some(0));
+        assert_eq!(tx.push(4), Ok(()));
+        assert_eq!(rx.len(), 4);
+
+        for i in 1..5 {
+            assert_eq!(rx.pop(), Some(i));
+        }
+        assert!(rx.is_empty());
+        assert!(q.split().is_none());
+    }
+
+    #[test]
+    fn spsc_drops_remaining() {
+        let val = Arc::new(());
+        let q: SpscQueue<Arc<()>, 8> = SpscQueue::new();
+
+        for _ in 0..3 {
+            assert!(unsafe { q.push(Arc::clone(&val)) }.is_ok());
+        }
+        assert_eq!(Arc::strong_count(&val), 4);
+
+        drop(q);
+        assert_eq!(Arc::strong_count(&val), 1);
+    }
+
+    #[test]
+    fn spsc_threads() {
+        static Q: SpscQueue<usize, 64> = SpscQueue::new();
+        let (mut tx, mut rx) = Q.split().unwrap();
+
+        let producer = thread::spawn(move || {
+            for i in 0..COUNT {
+                let mut v = i;
+                while let Err(back) = tx.push(v) {
+                    v = back;
+                    thread::yield_now();
+                }
+            }
+        });
+
+        let mut expected = 0;
+        while expected < COUNT {
+            match rx.pop() {
+                Some(v) => {
+                    assert_eq!(v, expected);
+                    expected += 1;
+                }
+                None => thread::yield_now(),
+            }
+        }
+
+        producer.join().unwrap();
+        assert!(rx.pop().is_none());
+    }
+
+    #[test]
+    fn mpsc_fifo() {
+        let q: MpscQueue<u32, 2> = MpscQueue::new();
+        let mut rx = q.consumer().unwrap();
+
+        assert!(rx.pop().is_none());
+        assert_eq!(q.push(1), Ok(()));
+        assert_eq!(q.push(2), Ok(()));
+        assert_eq!(q.push(3), Err(3));
+
+        // - wrap around several times
+        for i in 1..10 {
+            assert_eq!(rx.pop(), Some(i));
+            assert_eq!(q.push(i + 2), Ok(()));
+        }
+
+        assert_eq!(rx.len(), 2);
+        assert!(q.consumer().is_none());
+    }
+
+    #[test]
+    fn capacity_one() {
+        let q: MpscQueue<u32, 1> = MpscQueue::new();
+        let mut rx = q.consumer().unwrap();
+
+        // - a filled slot must not pass for a free one
+        assert_eq!(q.push(1), Ok(()));
+        assert_eq!(q.push(2), Err(2));
+        assert_eq!(rx.len(), 1);
+
+        for i in 2..5 {
+            assert_eq!(rx.pop(), Some(i - 1));
+            assert!(rx.pop().is_none());
+            assert_eq!(q.push(i), Ok(()));
+            assert_eq!(q.push(0), Err(0));
+        }
+        assert_eq!(rx.pop(), Some(4));
+
+        let q: SpscQueue<u32, 1> = SpscQueue::new();
+        let (mut tx, mut rx) = q.split().unwrap();
+
+        assert_eq!(tx.push(1), Ok(()));
+        assert_eq!(tx.push(2), Err(2));
+        assert_eq!(rx.pop(), Some(1));
+        assert!(rx.pop().is_none());
+    }
+
+    #[test]
+    fn mpsc_threads() {
+        let q: Arc<MpscQueue<(usize, usize), 32>> = Arc::new(MpscQueue::new());
+
+        let producers: Vec<_> = (0..PRODUCERS)
+            .map(|id| {
+                let q = Arc::clone(&q);
+                thread::spawn(move || {
+                    for i in 0..COUNT {
+                        while q.push((id, i)).is_err() {
+                            thread::yield_now();
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        // - values of a given producer must come out in order
+        let mut rx = q.consumer().unwrap();
+        let mut next = [0usize; PRODUCERS];
+        let mut total = 0;
+
+        while total < PRODUCERS * COUNT {
+            match rx.pop() {
+                Some((id, i)) => {
+                    assert_eq!(i, next[id]);
+                    next[id] += 1;
+                    total += 1;
+                }
+                None => thread::yield_now(),
+            }
+        }
+
+        for p in producers {
+            p.join().unwrap();
+        }
+
+        assert!(rx.pop().is_none());
+        assert_eq!(next, [COUNT; PRODUCERS]);
+    }
+}
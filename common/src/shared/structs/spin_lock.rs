@@ -1,13 +1,91 @@
 /*!
     Module defining spin-lock wrapper types
+
+    - [`Mutex`] is a plain test-and-set lock,
+    - [`IrqMutex`] additionally disables interrupts while held, so
+      that it can be shared between interrupt handlers and normal code,
+    - [`TicketMutex`] hands the lock out in FIFO order,
+    - [`RwLock`] admits either many readers or a single writer.
+
+    # Owner tracking
+    With the `lock-owner` feature, every lock records the source
+    location that acquired it, which can be queried through `owner()`
+    to find out who is holding a lock that never gets released.
+    Without it, `owner()` always returns `None`.
 */
 
 // Definition uses
 use core::cell::UnsafeCell;
+#[cfg(not(test))]
 use core::hint::spin_loop;
 use core::marker::{Send, Sync};
 use core::ops::{Deref, DerefMut, Drop};
-use core::sync::atomic::{AtomicBool, Ordering};
+use core::panic::Location;
+#[cfg(feature = "lock-owner")]
+use core::ptr::null_mut;
+#[cfg(feature = "lock-owner")]
+use core::sync::atomic::AtomicPtr;
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use crate::arch::__idle::InterruptGuard;
+
+// Internal: back off before polling a lock again
+// - host tests yield instead, as ticket locks are handed out
+//   in order, and spinning waiters would keep a preempted
+//   holder (or the next in line) from being scheduled
+#[inline(always)]
+fn relax() {
+    #[cfg(test)]
+    {
+        extern crate std;
+        std::thread::yield_now();
+    }
+
+    #[cfg(not(test))]
+    spin_loop();
+}
+
+// Internal: location of the current lock holder
+// - it's only tracked with the `lock-owner` feature
+struct Owner {
+    #[cfg(feature = "lock-owner")]
+    loc: AtomicPtr<Location<'static>>,
+}
+
+impl Owner {
+    const fn new() -> Self {
+        Owner {
+            #[cfg(feature = "lock-owner")]
+            loc: AtomicPtr::new(null_mut()),
+        }
+    }
+
+    // - record the caller of the public lock routine
+    #[track_caller]
+    #[inline(always)]
+    fn set(&self) {
+        #[cfg(feature = "lock-owner")]
+        self.loc
+            .store(Location::caller() as *const _ as *mut _, Ordering::Relaxed);
+    }
+
+    #[inline(always)]
+    fn clear(&self) {
+        #[cfg(feature = "lock-owner")]
+        self.loc.store(null_mut(), Ordering::Relaxed);
+    }
+
+    fn get(&self) -> Option<&'static Location<'static>> {
+        #[cfg(feature = "lock-owner")]
+        {
+            // SAFETY: only `&'static` locations are ever stored
+            unsafe { self.loc.load(Ordering::Relaxed).as_ref() }
+        }
+
+        #[cfg(not(feature = "lock-owner"))]
+        None
+    }
+}
 
 /**
     Simple spin lock with a single lock holder
@@ -22,6 +100,7 @@ use core::sync::atomic::{AtomicBool, Ordering};
 pub struct Mutex<T> {
     data: UnsafeCell<T>,
     locked: AtomicBool,
+    owner: Owner,
 }
 
 impl<T> Mutex<T> {
@@ -30,6 +109,7 @@ impl<T> Mutex<T> {
         Mutex {
             data: UnsafeCell::new(val),
             locked: AtomicBool::new(false),
+            owner: Owner::new(),
         }
     }
 
@@ -61,28 +141,34 @@ impl<T> Mutex<T> {
         - the lock is forcibly released, or
         - the thread is properly terminated
     */
+    #[track_caller]
     pub fn lock(&self) -> MutexGuard<'_, T> {
         // Acquire lock by brute force
         while self.cas_weak().is_err() {
-            spin_loop();
+            relax();
         }
 
         // Return lock handle
+        self.owner.set();
         MutexGuard {
             data_ptr: self.data.get(),
             locked_ref: &self.locked,
+            owner_ref: &self.owner,
         }
     }
 
     /**
         Attempts to obtain lock handle once without blocking
     */
+    #[track_caller]
     pub fn try_lock(&self) -> Result<MutexGuard<'_, T>, ()> {
         // Kindly acquire the lock
         if self.cas_strong().is_ok() {
+            self.owner.set();
             Ok(MutexGuard {
                 data_ptr: self.data.get(),
                 locked_ref: &self.locked,
+                owner_ref: &self.owner,
             })
         } else {
             Err(())
@@ -92,6 +178,7 @@ impl<T> Mutex<T> {
     /** Attempts to obtain lock handle several times,
         returning as soon as the lock is obtained
     */
+    #[track_caller]
     pub fn try_lock_repeat(&self, n: usize) -> Result<MutexGuard<'_, T>, ()> {
         // - run a comparator-style setup
         let mut m: usize = 0;
@@ -109,9 +196,20 @@ impl<T> Mutex<T> {
 
     /// Forcibly release the lock
     pub unsafe fn unlock(&self) {
+        self.owner.clear();
         self.locked.store(false, Ordering::Release);
     }
 
+    /// Checks whether the lock is currently held
+    pub fn is_locked(&self) -> bool {
+        self.locked.load(Ordering::Relaxed)
+    }
+
+    /// Returns where the lock was acquired, if it's held and tracked
+    pub fn owner(&self) -> Option<&'static Location<'static>> {
+        self.owner.get()
+    }
+
     /// Forcibly acquire a mutable
     /// reference to the protected data
     pub unsafe fn get_mut(&self) -> &mut T {
@@ -131,6 +229,7 @@ unsafe impl<T: Send> Sync for Mutex<T> {}
 pub struct MutexGuard<'a, T> {
     data_ptr: *mut T,
     locked_ref: &'a AtomicBool,
+    owner_ref: &'a Owner,
 }
 
 impl<T> Deref for MutexGuard<'_, T> {
@@ -151,6 +250,718 @@ impl<T> DerefMut for MutexGuard<'_, T> {
 
 impl<T> Drop for MutexGuard<'_, T> {
     fn drop(&mut self) {
+        self.owner_ref.clear();
         self.locked_ref.store(false, Ordering::Release);
     }
 }
+
+/**
+    Spin lock that disables interrupts while held
+
+    Interrupts are disabled *before* the lock is acquired, and
+    restored to their previous state *after* it is released, so
+    an interrupt handler can never spin on a lock that the code
+    it interrupted is holding.
+*/
+pub struct IrqMutex<T> {
+    inner: Mutex<T>,
+}
+
+impl<T> IrqMutex<T> {
+    /// Create new instance of `IrqMutex`
+    pub const fn new(val: T) -> Self {
+        IrqMutex {
+            inner: Mutex::new(val),
+        }
+    }
+
+    /**
+        Obtains lock handle, blocking if necessary
+
+        # Safety
+        Refer to [`Mutex::lock()`].
+    */
+    #[track_caller]
+    pub fn lock(&self) -> IrqMutexGuard<'_, T> {
+        let irq = InterruptGuard::disable();
+
+        // - the fields are dropped in order, so the lock
+        //   is released before interrupts are restored
+        IrqMutexGuard {
+            guard: self.inner.lock(),
+            _irq: irq,
+        }
+    }
+
+    /**
+        Attempts to obtain lock handle once without blocking
+    */
+    #[track_caller]
+    pub fn try_lock(&self) -> Result<IrqMutexGuard<'_, T>, ()> {
+        let irq = InterruptGuard::disable();
+
+        // - on failure, dropping `irq` restores interrupts
+        self.inner
+            .try_lock()
+            .map(|guard| IrqMutexGuard { guard, _irq: irq })
+    }
+
+    /** Attempts to obtain lock handle several times,
+        returning as soon as the lock is obtained
+    */
+    #[track_caller]
+    pub fn try_lock_repeat(&self, n: usize) -> Result<IrqMutexGuard<'_, T>, ()> {
+        let irq = InterruptGuard::disable();
+
+        self.inner
+            .try_lock_repeat(n)
+            .map(|guard| IrqMutexGuard { guard, _irq: irq })
+    }
+
+    /**
+        Forcibly release the lock
+
+        Interrupts are left as they are, as the state
+        saved by the holder's guard is out of reach.
+
+        # Safety
+        The holder must never touch the data again, nor drop its
+        guard, as that would release the lock a second time.
+    */
+    pub unsafe fn unlock(&self) {
+        unsafe { self.inner.unlock() }
+    }
+
+    /**
+        Returns a mutable reference to the protected data
+
+        As the lock is borrowed mutably, no locking
+        (nor disabling of interrupts) is needed.
+    */
+    pub fn get_mut(&mut self) -> &mut T {
+        self.inner.data.get_mut()
+    }
+
+    /**
+        Forcibly acquire a mutable reference to the protected data
+
+        # Safety
+        Refer to [`Mutex::get_mut()`].
+    */
+    #[allow(clippy::mut_from_ref)]
+    pub unsafe fn force_get(&self) -> &mut T {
+        unsafe { self.inner.get_mut() }
+    }
+
+    /// Checks whether the lock is currently held
+    pub fn is_locked(&self) -> bool {
+        self.inner.is_locked()
+    }
+
+    /// Returns where the lock was acquired, if it's held and tracked
+    pub fn owner(&self) -> Option<&'static Location<'static>> {
+        self.inner.owner()
+    }
+}
+
+/**
+    Transparent lock handle with automatic lock release
+
+    The lock that issued the handle will be released as soon as
+    the handle is dropped, after which interrupts are restored.
+*/
+pub struct IrqMutexGuard<'a, T> {
+    guard: MutexGuard<'a, T>,
+    _irq: InterruptGuard,
+}
+
+impl<T> Deref for IrqMutexGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.guard
+    }
+}
+
+impl<T> DerefMut for IrqMutexGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.guard
+    }
+}
+
+/**
+    Fair spin lock, handing out the lock in FIFO order
+
+    Every locker draws a ticket, then waits until its ticket
+    is served, so no locker can be starved by the others.
+    Non-blocking attempts only succeed if nobody is waiting.
+*/
+pub struct TicketMutex<T> {
+    data: UnsafeCell<T>,
+    next_ticket: AtomicUsize,
+    now_serving: AtomicUsize,
+    owner: Owner,
+}
+
+impl<T> TicketMutex<T> {
+    /// Create new instance of `TicketMutex`
+    pub const fn new(val: T) -> Self {
+        TicketMutex {
+            data: UnsafeCell::new(val),
+            next_ticket: AtomicUsize::new(0),
+            now_serving: AtomicUsize::new(0),
+            owner: Owner::new(),
+        }
+    }
+
+    // Internal: create lock handle for the current holder
+    #[track_caller]
+    #[inline(always)]
+    fn guard(&self) -> TicketMutexGuard<'_, T> {
+        self.owner.set();
+        TicketMutexGuard {
+            data_ptr: self.data.get(),
+            serving_ref: &self.now_serving,
+            owner_ref: &self.owner,
+        }
+    }
+
+    /**
+        Obtains lock handle, blocking if necessary
+
+        # Safety
+        Refer to [`Mutex::lock()`]. Additionally, a deadlocked
+        holder blocks every locker that comes after it.
+    */
+    #[track_caller]
+    pub fn lock(&self) -> TicketMutexGuard<'_, T> {
+        // - tickets wrap around, which is harmless unless
+        //   `usize::MAX` lockers are waiting at once
+        let ticket = self.next_ticket.fetch_add(1, Ordering::Relaxed);
+
+        while self.now_serving.load(Ordering::Acquire) != ticket {
+            relax();
+        }
+
+        self.guard()
+    }
+
+    /**
+        Attempts to obtain lock handle once without blocking
+    */
+    #[track_caller]
+    pub fn try_lock(&self) -> Result<TicketMutexGuard<'_, T>, ()> {
+        // - only draw a ticket if it would be served right away
+        let serving = self.now_serving.load(Ordering::Acquire);
+
+        match self.next_ticket.compare_exchange(
+            serving,
+            serving.wrapping_add(1),
+            Ordering::Acquire,
+            Ordering::Relaxed,
+        ) {
+            Ok(_) => Ok(self.guard()),
+            Err(_) => Err(()),
+        }
+    }
+
+    /** Attempts to obtain lock handle several times,
+        returning as soon as the lock is obtained
+    */
+    #[track_caller]
+    pub fn try_lock_repeat(&self, n: usize) -> Result<TicketMutexGuard<'_, T>, ()> {
+        for _ in 0..n {
+            if let Ok(g) = self.try_lock() {
+                return Ok(g);
+            }
+            relax();
+        }
+
+        Err(())
+    }
+
+    /**
+        Forcibly release the lock
+
+        This serves the next ticket, so it must only be
+        called once for the holder being released.
+
+        # Safety
+        The lock must be held, and the holder must never touch
+        the data again, nor drop its guard, as that would serve
+        a ticket that's still waiting in line.
+    */
+    pub unsafe fn unlock(&self) {
+        self.owner.clear();
+        self.now_serving.fetch_add(1, Ordering::Release);
+    }
+
+    /**
+        Returns a mutable reference to the protected data
+
+        As the lock is borrowed mutably, no locking is needed.
+    */
+    pub fn get_mut(&mut self) -> &mut T {
+        self.data.get_mut()
+    }
+
+    /**
+        Forcibly acquire a mutable reference to the protected data
+
+        # Safety
+        Refer to [`Mutex::get_mut()`].
+    */
+    #[allow(clippy::mut_from_ref)]
+    pub unsafe fn force_get(&self) -> &mut T {
+        unsafe { &mut *(self.data.get()) }
+    }
+
+    /// Checks whether the lock is currently held
+    pub fn is_locked(&self) -> bool {
+        self.next_ticket.load(Ordering::Relaxed) != self.now_serving.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of lockers waiting for the lock
+    pub fn waiters(&self) -> usize {
+        let next = self.next_ticket.load(Ordering::Relaxed);
+        let serving = self.now_serving.load(Ordering::Relaxed);
+
+        next.wrapping_sub(serving).saturating_sub(1)
+    }
+
+    /// Returns where the lock was acquired, if it's held and tracked
+    pub fn owner(&self) -> Option<&'static Location<'static>> {
+        self.owner.get()
+    }
+}
+
+// - allow reference sharing
+unsafe impl<T: Send> Sync for TicketMutex<T> {}
+
+/**
+    Transparent lock handle with automatic lock release
+
+    The lock that issued the handle will serve the next
+    ticket as soon as the handle is dropped.
+*/
+pub struct TicketMutexGuard<'a, T> {
+    data_ptr: *mut T,
+    serving_ref: &'a AtomicUsize,
+    owner_ref: &'a Owner,
+}
+
+impl<T> Deref for TicketMutexGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: We know where `self.data` points to...
+        unsafe { &*self.data_ptr }
+    }
+}
+
+impl<T> DerefMut for TicketMutexGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        // SAFETY: We know where `self.data` points to...
+        unsafe { &mut *self.data_ptr }
+    }
+}
+
+impl<T> Drop for TicketMutexGuard<'_, T> {
+    fn drop(&mut self) {
+        self.owner_ref.clear();
+        self.serving_ref.fetch_add(1, Ordering::Release);
+    }
+}
+
+// Reader-writer lock state: writer bit, and reader count below it
+const RW_WRITER: usize = 1 << (usize::BITS - 1);
+
+/**
+    Spin lock with many readers or a single writer
+
+    Readers are admitted as long as no writer holds the lock,
+    so a steady stream of readers can starve writers. Like
+    [`Mutex`], it should only be used for short critical sections.
+*/
+pub struct RwLock<T> {
+    data: UnsafeCell<T>,
+    state: AtomicUsize,
+}
+
+impl<T> RwLock<T> {
+    /// Create new instance of `RwLock`
+    pub const fn new(val: T) -> Self {
+        RwLock {
+            data: UnsafeCell::new(val),
+            state: AtomicUsize::new(0),
+        }
+    }
+
+    /**
+        Obtains shared lock handle, blocking while a writer holds the lock
+
+        # Safety
+        If a thread holding a write handle calls `read`,
+        then that thread **will deadlock**.
+    */
+    pub fn read(&self) -> RwLockReadGuard<'_, T> {
+        loop {
+            if let Ok(g) = self.try_read() {
+                return g;
+            }
+            relax();
+        }
+    }
+
+    /**
+        Attempts to obtain shared lock handle once without blocking
+    */
+    pub fn try_read(&self) -> Result<RwLockReadGuard<'_, T>, ()> {
+        let mut state = self.state.load(Ordering::Relaxed);
+
+        // - only a writer or a full reader count make this fail,
+        //   so other readers coming and going are retried
+        loop {
+            // - the reader count must never reach the writer bit
+            if state & RW_WRITER != 0 || state + 1 == RW_WRITER {
+                return Err(());
+            }
+
+            match self.state.compare_exchange_weak(
+                state,
+                state + 1,
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => {
+                    return Ok(RwLockReadGuard {
+                        data_ptr: self.data.get(),
+                        state_ref: &self.state,
+                    });
+                }
+                Err(s) => state = s,
+            }
+        }
+    }
+
+    /**
+        Obtains exclusive lock handle, blocking if necessary
+
+        # Safety
+        If a thread holding any handle calls `write`,
+        then that thread **will deadlock**.
+    */
+    pub fn write(&self) -> RwLockWriteGuard<'_, T> {
+        loop {
+            if let Ok(g) = self.try_write() {
+                return g;
+            }
+            relax();
+        }
+    }
+
+    /**
+        Attempts to obtain exclusive lock handle once without blocking
+    */
+    pub fn try_write(&self) -> Result<RwLockWriteGuard<'_, T>, ()> {
+        self.state
+            .compare_exchange(0, RW_WRITER, Ordering::Acquire, Ordering::Relaxed)
+            .map(|_| RwLockWriteGuard {
+                data_ptr: self.data.get(),
+                state_ref: &self.state,
+            })
+            .map_err(|_| ())
+    }
+
+    /** Attempts to obtain exclusive lock handle several
+        times, returning as soon as the lock is obtained
+    */
+    pub fn try_write_repeat(&self, n: usize) -> Result<RwLockWriteGuard<'_, T>, ()> {
+        for _ in 0..n {
+            if let Ok(g) = self.try_write() {
+                return Ok(g);
+            }
+            relax();
+        }
+
+        Err(())
+    }
+
+    /// Returns the number of readers currently holding the lock
+    pub fn readers(&self) -> usize {
+        let state = self.state.load(Ordering::Relaxed);
+
+        if state & RW_WRITER != 0 { 0 } else { state }
+    }
+
+    /// Checks whether a writer currently holds the lock
+    pub fn is_write_locked(&self) -> bool {
+        self.state.load(Ordering::Relaxed) & RW_WRITER != 0
+    }
+
+    /**
+        Forcibly release the lock, dropping every reader and writer
+
+        # Safety
+        No holder may touch the data again, nor drop its guard,
+        as that would corrupt the reader count or writer bit.
+    */
+    pub unsafe fn unlock(&self) {
+        self.state.store(0, Ordering::Release);
+    }
+
+    /**
+        Returns a mutable reference to the protected data
+
+        As the lock is borrowed mutably, no locking is needed.
+    */
+    pub fn get_mut(&mut self) -> &mut T {
+        self.data.get_mut()
+    }
+
+    /**
+        Forcibly acquire a mutable reference to the protected data
+
+        # Safety
+        Refer to [`Mutex::get_mut()`].
+    */
+    #[allow(clippy::mut_from_ref)]
+    pub unsafe fn force_get(&self) -> &mut T {
+        unsafe { &mut *(self.data.get()) }
+    }
+}
+
+// - allow reference sharing
+// - readers on different threads share `&T`, hence `Sync`
+unsafe impl<T: Send + Sync> Sync for RwLock<T> {}
+
+/**
+    Transparent shared lock handle with automatic lock release
+*/
+pub struct RwLockReadGuard<'a, T> {
+    data_ptr: *const T,
+    state_ref: &'a AtomicUsize,
+}
+
+impl<T> Deref for RwLockReadGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: We know where `self.data` points to...
+        unsafe { &*self.data_ptr }
+    }
+}
+
+impl<T> Drop for RwLockReadGuard<'_, T> {
+    fn drop(&mut self) {
+        self.state_ref.fetch_sub(1, Ordering::Release);
+    }
+}
+
+/**
+    Transparent exclusive lock handle with automatic lock release
+*/
+pub struct RwLockWriteGuard<'a, T> {
+    data_ptr: *mut T,
+    state_ref: &'a AtomicUsize,
+}
+
+impl<T> Deref for RwLockWriteGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: We know where `self.data` points to...
+        unsafe { &*self.data_ptr }
+    }
+}
+
+impl<T> DerefMut for RwLockWriteGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        // SAFETY: We know where `self.data` points to...
+        unsafe { &mut *self.data_ptr }
+    }
+}
+
+impl<T> Drop for RwLockWriteGuard<'_, T> {
+    fn drop(&mut self) {
+        self.state_ref.store(0, Ordering::Release);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use std::sync::Arc;
+    use std::thread;
+    use std::vec::Vec;
+
+    const THREADS: usize = 4;
+    const ITERS: usize = 10_000;
+
+    // Increment a shared counter from several threads at once
+    // - the increment is deliberately split into a load and a
+    //   store, so that a broken lock loses updates
+    fn hammer<L: Send + Sync + 'static>(lock: L, incr: fn(&L)) -> L {
+        let lock = Arc::new(lock);
+        let handles: Vec<_> = (0..THREADS)
+            .map(|_| {
+                let lock = Arc::clone(&lock);
+                thread::spawn(move || {
+                    for _ in 0..ITERS {
+                        incr(&lock);
+                    }
+                })
+            })
+            .collect();
+
+        for h in handles {
+            h.join().unwrap();
+        }
+
+        Arc::into_inner(lock).unwrap()
+    }
+
+    #[test]
+    fn mutex_excludes() {
+        let m = hammer(Mutex::new(0usize), |m| {
+            let mut g = m.lock();
+            let n = *g;
+            *g = n + 1;
+        });
+
+        assert_eq!(*m.lock(), THREADS * ITERS);
+        assert!(!m.is_locked());
+    }
+
+    #[test]
+    fn mutex_try_lock() {
+        let m = Mutex::new(());
+        let g = m.lock();
+
+        assert!(m.try_lock().is_err());
+        assert!(m.try_lock_repeat(8).is_err());
+        drop(g);
+        assert!(m.try_lock().is_ok());
+    }
+
+    // - locking an `IrqMutex` executes `cli`, which faults outside of
+    //   ring 0, so that's covered by the bootloader's in-OS tests
+    #[test]
+    fn irq_mutex_accessors() {
+        let mut m = IrqMutex::new(1);
+
+        *m.get_mut() += 1;
+        assert_eq!(unsafe { *m.force_get() }, 2);
+        assert!(!m.is_locked());
+        assert!(m.owner().is_none());
+    }
+
+    #[test]
+    fn ticket_mutex_excludes() {
+        let mut m = hammer(TicketMutex::new(0usize), |m| {
+            let mut g = m.lock();
+            let n = *g;
+            *g = n + 1;
+        });
+
+        assert_eq!(*m.lock(), THREADS * ITERS);
+        assert!(!m.is_locked());
+        assert_eq!(m.waiters(), 0);
+
+        *m.get_mut() = 0;
+        assert_eq!(*m.lock(), 0);
+    }
+
+    #[test]
+    fn ticket_mutex_force_unlock() {
+        let m = TicketMutex::new(0);
+        let g = m.lock();
+
+        // - the holder is abandoned, as after a panic
+        core::mem::forget(g);
+        unsafe {
+            *m.force_get() = 3;
+            m.unlock();
+        }
+
+        assert!(!m.is_locked());
+        assert_eq!(*m.try_lock().unwrap(), 3);
+    }
+
+    #[test]
+    fn ticket_mutex_try_lock() {
+        let m = TicketMutex::new(());
+        let g = m.lock();
+
+        assert!(m.is_locked());
+        assert!(m.try_lock().is_err());
+        assert!(m.try_lock_repeat(8).is_err());
+        drop(g);
+        assert!(m.try_lock().is_ok());
+    }
+
+    #[test]
+    fn rw_lock_excludes_writers() {
+        let mut l = hammer(RwLock::new(0usize), |l| {
+            let mut g = l.write();
+            let n = *g;
+            *g = n + 1;
+        });
+
+        assert_eq!(*l.read(), THREADS * ITERS);
+        assert_eq!(*l.get_mut(), THREADS * ITERS);
+    }
+
+    #[test]
+    fn rw_lock_readers_and_writers() {
+        let l = RwLock::new(7);
+
+        let r1 = l.read();
+        let r2 = l.try_read().unwrap();
+        assert_eq!(l.readers(), 2);
+        assert_eq!(*r1 + *r2, 14);
+        assert!(l.try_write().is_err());
+
+        drop((r1, r2));
+        let mut w = l.try_write().unwrap();
+        *w = 8;
+        assert!(l.is_write_locked());
+        assert!(l.try_read().is_err());
+        assert!(l.try_write_repeat(8).is_err());
+
+        drop(w);
+        assert_eq!(*l.read(), 8);
+        assert_eq!(l.readers(), 0);
+    }
+
+    #[test]
+    fn rw_lock_try_read_under_contention() {
+        // - readers only ever race other readers, so
+        //   non-blocking attempts must never fail
+        let l = hammer(RwLock::new(()), |l| {
+            assert!(l.try_read().is_ok());
+        });
+
+        assert_eq!(l.readers(), 0);
+    }
+
+    #[test]
+    fn rw_lock_readers_see_consistent_state() {
+        // - writers keep both halves equal, so
+        //   readers must never see them differ
+        let l = hammer(RwLock::new((0usize, 0usize)), |l| {
+            {
+                let g = l.read();
+                assert_eq!(g.0, g.1);
+            }
+
+            let mut g = l.write();
+            g.0 += 1;
+            g.1 += 1;
+        });
+
+        assert_eq!(*l.read(), (THREADS * ITERS, THREADS * ITERS));
+    }
+}
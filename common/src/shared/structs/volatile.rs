@@ -17,7 +17,7 @@ use core::sync::atomic::Ordering;
     As this type is intended primarily for use with memory
     mapped I/O, a constructor will not be exposed, and one
     has to create references to `VolatileCell<T>` instead:
-    ```rust
+    ```rust,ignore
     let mmio_ref: &VolatileCell<u8> = unsafe { &*(MMIO_ADDR as *const _) };
 
     let read_val: u8 = mmio_ref.load();
@@ -136,7 +136,7 @@ unsafe impl<T> Send for VolatileCell<T> {}
     As this type is intended primarily for use with memory
     mapped I/O, a constructor will not be exposed, and one
     has to create references to `FencedVolatileCell<T>` instead:
-    ```rust
+    ```rust,ignore
     let mmio_ref: &FencedVolatileCell<u8> = unsafe { &*(MMIO_ADDR as *const _) };
 
     let read_val: u8 = mmio_ref.load();
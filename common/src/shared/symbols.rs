@@ -0,0 +1,307 @@
+/*!
+    Embedded symbol tables
+
+    The build process links each stage twice: the first link is
+    used to extract the (demangled) function symbols, which are
+    then packed by `scripts/gen_ksyms.py` and placed at the end
+    of the second link, so that no other address changes.
+
+    # Layout
+    All integers are little-endian. The table starts with a header:
+
+    | Offset | Size | Description                                  |
+    |--------|------|----------------------------------------------|
+    | 0      | 4    | Magic number ([`SYMTAB_MAGIC`])              |
+    | 4      | 4    | Number of entries                            |
+
+    The header is followed by the entries, sorted by address:
+
+    | Offset | Size | Description                                  |
+    |--------|------|----------------------------------------------|
+    | 0      | 8    | Address                                      |
+    | 8      | 8    | Size, in bytes (zero if unknown)             |
+    | 16     | 4    | Offset of the name, from the table's start   |
+    | 20     | 4    | Length of the name, in bytes                 |
+
+    The names (UTF-8, not terminated) follow the entries.
+*/
+
+// Standard definitions
+use core::str;
+
+// Internal definitions
+use crate::shared::GenericError;
+
+/// Magic number identifying a symbol table (`"SYMS"`)
+pub const SYMTAB_MAGIC: u32 = u32::from_le_bytes(*b"SYMS");
+
+/// Size of the table header, in bytes
+pub const HEADER_LEN: usize = 8;
+
+/// Size of a single entry, in bytes
+pub const ENTRY_LEN: usize = 24;
+
+/// Symbol resolved from a [`SymbolTable`]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Symbol<'a> {
+    name: &'a str,
+    addr: usize,
+    size: usize,
+}
+
+impl<'a> Symbol<'a> {
+    /// Returns the (demangled) name
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+
+    /// Returns the address
+    pub fn addr(&self) -> usize {
+        self.addr
+    }
+
+    /// Returns the size in bytes, which is zero if unknown
+    pub fn size(&self) -> usize {
+        self.size
+    }
+}
+
+/**
+    Read-only view into an embedded symbol table
+
+    An empty table is valid, and resolves nothing - this is
+    what the first link of a stage sees.
+*/
+#[derive(Copy, Clone, Debug)]
+pub struct SymbolTable<'a> {
+    raw: &'a [u8],
+    len: usize,
+}
+
+impl<'a> SymbolTable<'a> {
+    /// Create new, empty instance of `SymbolTable`
+    pub const fn empty() -> Self {
+        SymbolTable { raw: &[], len: 0 }
+    }
+
+    /**
+        Create new instance of `SymbolTable` from raw bytes
+
+        An empty slice yields an empty table. Otherwise, the header
+        is validated, and the entries must fit within the slice.
+        Names and addresses are validated upon lookup.
+    */
+    pub fn from_bytes(raw: &'a [u8]) -> Result<Self, GenericError> {
+        if raw.is_empty() {
+            return Ok(Self::empty());
+        }
+
+        if raw.len() < HEADER_LEN || get_u32(raw, 0) != SYMTAB_MAGIC {
+            return Err(GenericError::ErrorMessage(
+                "symbol table has a bad magic number",
+            ));
+        }
+
+        let len = get_u32(raw, 4) as usize;
+        let fits = len
+            .checked_mul(ENTRY_LEN)
+            .and_then(|n| n.checked_add(HEADER_LEN))
+            .is_some_and(|n| n <= raw.len());
+
+        if !fits {
+            return Err(GenericError::ErrorMessage("symbol table is truncated"));
+        }
+
+        Ok(SymbolTable { raw, len })
+    }
+
+    /// Returns the number of entries
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Checks whether the table has no entries
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the entry at index `index`, if it exists and is valid
+    pub fn get(&self, index: usize) -> Option<Symbol<'a>> {
+        if index >= self.len {
+            return None;
+        }
+
+        let at = HEADER_LEN + index * ENTRY_LEN;
+        let name_off = get_u32(self.raw, at + 16) as usize;
+        let name_len = get_u32(self.raw, at + 20) as usize;
+        let name = self.raw.get(name_off..name_off.checked_add(name_len)?)?;
+
+        Some(Symbol {
+            name: str::from_utf8(name).ok()?,
+            addr: usize::try_from(get_u64(self.raw, at)).ok()?,
+            size: usize::try_from(get_u64(self.raw, at + 8)).ok()?,
+        })
+    }
+
+    /**
+        Find the symbol that contains `addr`, along with
+        the offset of `addr` into it
+
+        If the size of the closest preceding symbol is
+        unknown, then that symbol is assumed to contain `addr`.
+    */
+    pub fn lookup(&self, addr: usize) -> Option<(Symbol<'a>, usize)> {
+        // - find the number of entries at or below `addr`
+        let (mut lo, mut hi) = (0, self.len);
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            let mid_addr = get_u64(self.raw, HEADER_LEN + mid * ENTRY_LEN);
+
+            if mid_addr <= addr as u64 {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+
+        let sym = self.get(lo.checked_sub(1)?)?;
+        let offset = addr - sym.addr();
+
+        if sym.size() != 0 && offset >= sym.size() {
+            None
+        } else {
+            Some((sym, offset))
+        }
+    }
+}
+
+// Internal: little-endian field accessors
+// - callers must have checked the bounds
+fn get_u32(raw: &[u8], at: usize) -> u32 {
+    let mut b = [0u8; 4];
+    b.copy_from_slice(&raw[at..at + 4]);
+    u32::from_le_bytes(b)
+}
+
+fn get_u64(raw: &[u8], at: usize) -> u64 {
+    let mut b = [0u8; 8];
+    b.copy_from_slice(&raw[at..at + 8]);
+    u64::from_le_bytes(b)
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use std::vec::Vec;
+
+    // Internal: pack `(name, addr, size)` entries as `gen_ksyms.py` does
+    fn pack(syms: &[(&str, u64, u64)]) -> Vec<u8> {
+        let mut raw = Vec::new();
+        raw.extend_from_slice(&SYMTAB_MAGIC.to_le_bytes());
+        raw.extend_from_slice(&(syms.len() as u32).to_le_bytes());
+
+        let mut name_off = HEADER_LEN + syms.len() * ENTRY_LEN;
+        for &(name, addr, size) in syms {
+            raw.extend_from_slice(&addr.to_le_bytes());
+            raw.extend_from_slice(&size.to_le_bytes());
+            raw.extend_from_slice(&(name_off as u32).to_le_bytes());
+            raw.extend_from_slice(&(name.len() as u32).to_le_bytes());
+            name_off += name.len();
+        }
+        for &(name, ..) in syms {
+            raw.extend_from_slice(name.as_bytes());
+        }
+
+        raw
+    }
+
+    // Internal: resolve `addr` into a name and an offset
+    fn resolve<'a>(t: &SymbolTable<'a>, addr: usize) -> Option<(&'a str, usize)> {
+        t.lookup(addr).map(|(s, off)| (s.name(), off))
+    }
+
+    #[test]
+    fn malformed_header() {
+        let raw = pack(&[("a", 0x1000, 0x10), ("b", 0x1010, 0x10)]);
+
+        assert!(SymbolTable::from_bytes(&raw[..HEADER_LEN - 1]).is_err());
+
+        let mut bad = raw.clone();
+        bad[0] ^= 0xff;
+        assert!(SymbolTable::from_bytes(&bad).is_err());
+
+        // - the entries must fit, but the names are only checked on lookup
+        assert!(SymbolTable::from_bytes(&raw[..HEADER_LEN + ENTRY_LEN]).is_err());
+        assert!(SymbolTable::from_bytes(&raw[..HEADER_LEN + 2 * ENTRY_LEN]).is_ok());
+
+        let mut huge = raw.clone();
+        huge[4..8].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(SymbolTable::from_bytes(&huge).is_err());
+    }
+
+    #[test]
+    fn empty_table() {
+        let t = SymbolTable::from_bytes(&[]).unwrap();
+        assert!(t.is_empty());
+        assert!(t.lookup(0x1000).is_none());
+
+        let raw = pack(&[]);
+        let t = SymbolTable::from_bytes(&raw).unwrap();
+        assert!(t.is_empty());
+        assert!(t.lookup(0).is_none());
+    }
+
+    #[test]
+    fn lookup_boundaries() {
+        let raw = pack(&[
+            ("alpha", 0x1000, 0x10),
+            ("beta", 0x1020, 0),
+            ("gamma", 0x2000, 0x8),
+        ]);
+        let t = SymbolTable::from_bytes(&raw).unwrap();
+        assert_eq!(t.len(), 3);
+
+        assert_eq!(resolve(&t, 0), None);
+        assert_eq!(resolve(&t, 0xfff), None);
+        assert_eq!(resolve(&t, 0x1000), Some(("alpha", 0)));
+        assert_eq!(resolve(&t, 0x100f), Some(("alpha", 0xf)));
+
+        // - the gap past a sized symbol resolves nothing...
+        assert_eq!(resolve(&t, 0x1010), None);
+        assert_eq!(resolve(&t, 0x101f), None);
+
+        // - ...whereas unsized symbols extend up to the next one
+        assert_eq!(resolve(&t, 0x1020), Some(("beta", 0)));
+        assert_eq!(resolve(&t, 0x1fff), Some(("beta", 0xfdf)));
+
+        assert_eq!(resolve(&t, 0x2000), Some(("gamma", 0)));
+        assert_eq!(resolve(&t, 0x2007), Some(("gamma", 7)));
+        assert_eq!(resolve(&t, 0x2008), None);
+
+        let sym = t.get(2).unwrap();
+        assert_eq!((sym.addr(), sym.size()), (0x2000, 8));
+        assert!(t.get(3).is_none());
+    }
+
+    #[test]
+    fn invalid_names() {
+        let mut raw = pack(&[("ok", 0x1000, 0), ("bad", 0x2000, 0)]);
+        let t = SymbolTable::from_bytes(&raw).unwrap();
+        assert_eq!(resolve(&t, 0x2001), Some(("bad", 1)));
+
+        // - invalid UTF-8
+        let last = raw.len() - 1;
+        raw[last] = 0xff;
+        let t = SymbolTable::from_bytes(&raw).unwrap();
+        assert_eq!(resolve(&t, 0x2001), None);
+        assert_eq!(resolve(&t, 0x1001), Some(("ok", 1)));
+
+        // - name past the end of the table
+        let at = HEADER_LEN + ENTRY_LEN + 20;
+        raw[at..at + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        let t = SymbolTable::from_bytes(&raw).unwrap();
+        assert!(t.get(1).is_none());
+    }
+}
@@ -0,0 +1,141 @@
+/*!
+    In-OS test framework
+
+    Tests that need real hardware (or an emulator) are collected
+    with `#[test_case]`, under the `custom_test_frameworks` feature,
+    and handed to a runner such as the one in
+    [`plat::pc_bios::qemu`](crate::plat::pc_bios::qemu), which
+    reports to a [`Write`] through the routines defined here.
+
+    # Protocol
+    Results are reported in a line-based format, close to that of
+    `libtest`, so that a host-side runner can follow along:
+    ```text
+    running 2 tests
+    test kern::tests::console_writes ... ok
+    test kern::tests::heap_grows ... FAILED
+
+    panicked at kern/src/lib.rs:42:5:
+    assertion failed: v.capacity() > 0
+
+    test result: FAILED. 1 passed; 1 failed
+    ```
+    There is no unwinding, so the first failing test ends the run.
+
+    # Usage
+    ```rust
+    #![feature(custom_test_frameworks)]
+    #![test_runner(common::plat::pc_bios::qemu::test_runner)]
+    #![reexport_test_harness_main = "test_main"]
+
+    #[test_case]
+    fn trivial() {
+        assert_eq!(1 + 1, 2);
+    }
+    ```
+*/
+
+// Definition uses
+use core::any::type_name;
+use core::panic::PanicInfo;
+
+use crate::shared::io::{Error, Write};
+
+/// Trait to mark type as runnable by the in-OS test runner
+pub trait Testable {
+    /// Returns the name of the test
+    fn name(&self) -> &'static str;
+
+    /// Run the test, panicking on failure
+    fn run(&self);
+}
+
+impl<T: Fn()> Testable for T {
+    fn name(&self) -> &'static str {
+        type_name::<T>()
+    }
+
+    fn run(&self) {
+        self()
+    }
+}
+
+/**
+    Run every test in `tests`, reporting to `w`, and return
+    the number of tests that passed
+
+    A failing test panics and doesn't come back, in which case
+    the panic handler should call [`report_panic()`].
+*/
+pub fn run_tests<W: Write + ?Sized>(w: &mut W, tests: &[&dyn Testable]) -> Result<usize, Error> {
+    let plural = if tests.len() == 1 { "" } else { "s" };
+    writeln!(w, "running {} test{}", tests.len(), plural)?;
+
+    for test in tests {
+        write!(w, "test {} ... ", test.name())?;
+        w.flush()?;
+
+        test.run();
+        writeln!(w, "ok")?;
+    }
+
+    writeln!(w, "\ntest result: ok. {} passed; 0 failed", tests.len())?;
+    w.flush()?;
+
+    Ok(tests.len())
+}
+
+/**
+    Report the failure of the test that is currently running
+
+    The counts are unknown to the panic handler, so the summary
+    line only states the outcome.
+*/
+pub fn report_panic<W: Write + ?Sized>(w: &mut W, info: &PanicInfo<'_>) -> Result<(), Error> {
+    writeln!(w, "FAILED\n")?;
+
+    match info.location() {
+        Some(loc) => writeln!(w, "panicked at {}:", loc)?,
+        None => writeln!(w, "panicked (source location unknown):")?,
+    }
+    writeln!(w, "{}", info.message())?;
+
+    writeln!(w, "\ntest result: FAILED")?;
+    w.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::structs::array_vec::ArrayVec;
+    use core::cell::Cell;
+
+    #[test]
+    fn protocol() {
+        let runs = Cell::new(0);
+        let bump = || runs.set(runs.get() + 1);
+        let mut out: ArrayVec<u8, 256> = ArrayVec::new();
+
+        assert_eq!(run_tests(&mut out, &[&bump, &bump]).unwrap(), 2);
+        assert_eq!(runs.get(), 2);
+
+        let text = core::str::from_utf8(&out).unwrap();
+        let mut lines = text.lines();
+        assert_eq!(lines.next(), Some("running 2 tests"));
+
+        let first = lines.next().unwrap();
+        assert!(first.starts_with("test ") && first.ends_with(" ... ok"));
+        assert!(first.contains("protocol"));
+
+        assert_eq!(lines.nth(1), Some(""));
+        assert_eq!(lines.next(), Some("test result: ok. 2 passed; 0 failed"));
+    }
+
+    #[test]
+    fn empty_run() {
+        let mut out: ArrayVec<u8, 64> = ArrayVec::new();
+
+        assert_eq!(run_tests(&mut out, &[]).unwrap(), 0);
+        assert!(out.starts_with(b"running 0 tests\n"));
+    }
+}
@@ -2,6 +2,78 @@
     Shared traits that define contracts between
     platform-agnostic users and platform-specific
     providers
+*/
+
+// Definition uses
+use crate::shared::io;
+
+/**
+    Trait to mark type as a block-addressable storage device
+
+    Implementors transfer whole blocks of [`block_size()`] bytes,
+    addressed by their logical block address (LBA). Users, such as
+    filesystem drivers, must not assume any particular block size.
+
+    [`block_size()`]: BlockDevice::block_size
+*/
+pub trait BlockDevice {
+    /// Return size of a single block in bytes
+    fn block_size(&self) -> usize;
+
+    /**
+        Read consecutive blocks, starting at `lba`, into `buf`
+
+        The length of `buf` must be a multiple of [`block_size()`];
+        otherwise, an error of kind [`InvalidInput`] is returned.
+
+        [`block_size()`]: BlockDevice::block_size
+        [`InvalidInput`]: io::ErrorKind::InvalidInput
+    */
+    fn read_blocks(&mut self, lba: u64, buf: &mut [u8]) -> Result<(), io::Error>;
+}
+
+/**
+    Key events delivered by input devices
+
+    Only keys relevant to simple text-mode interfaces are
+    distinguished; printable ASCII characters are delivered
+    as [`Char`](KeyEvent::Char), with modifiers already applied.
+*/
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum KeyEvent {
+    Up,
+    Down,
+    Left,
+    Right,
+    Home,
+    End,
+    Enter,
+    Escape,
+    Backspace,
+    Delete,
+    Tab,
+
+    /// Printable ASCII character
+    Char(u8),
+}
+
+/// Trait to mark type as a source of key events
+pub trait KeySource {
+    /// Return the next pending key event without blocking, if any
+    fn poll_key(&mut self) -> Option<KeyEvent>;
+}
+
+/**
+    Trait to mark type as a monotonic clock
+
+    Implementors may require [`millis()`] to be called regularly
+    in order to keep track of time, as early-boot clocks are
+    typically polled rather than driven by interrupts.
 
-    Currently a placeholder module
+    [`millis()`]: Clock::millis
 */
+pub trait Clock {
+    /// Return milliseconds elapsed since an arbitrary starting point
+    fn millis(&mut self) -> u64;
+}
@@ -0,0 +1,59 @@
+; magnetite_os - kern/src/asm/defs.asm
+; A definitions file for the kernel entry trampoline
+;
+; No methods should be defined in this file,
+; as we're merely storing definitions here
+;
+; The paging and GDT definitions mirror those found in
+; 'boot/src/asm/defs.asm', as the kernel must be able to
+; set up long mode on its own when loaded by a foreign
+; (Multiboot2-compliant) bootloader
+
+; Multiboot2 definitions
+MB2_HEADER_MAGIC    equ 0xe85250d6      ; Header magic number
+MB2_ARCH_I386       equ 0               ; Architecture: i386 (32-bit protected mode)
+MB2_LOADER_MAGIC    equ 0x36d76289      ; Magic number passed in EAX by the bootloader
+MB2_TAG_END         equ 0               ; Terminating header tag
+
+; Flags for later use
+EXT_CPUID           equ 1 << 31         ; CPUID extensions
+FEAT_CPUID          equ (1 << 31) | 1   ; CPUID extended features
+LM_EDX_CPUID        equ 1 << 29         ; Long mode bit
+
+PAE_ENABLE          equ 1 << 5          ; Enable PAE in CR4
+PG_ENABLE           equ 1 << 31         ; Enable paging in CR0
+
+; Paging hierarchy layout
+SIZEOF_PT           equ 1 << 12         ; Sets page table size to 4 kiB
+NUM_PDTS            equ 4               ; Number of page directory tables (1 GiB each)
+
+; Page masks and flags
+PT_PRESENT          equ 1               ; Marks page as present
+PT_READWRITE        equ 2               ; Marks page as R/W
+PT_PAGESIZE         equ 128             ; Marks page as large/huge (if needed)
+
+SIZEOF_LARGE_PAGE   equ 1 << 21         ; Sets page size to 2 MiB (large pages)
+
+EFER_MSR            equ 0xC0000080      ; EFER MSR address
+EFER_LME            equ 0x100           ; EFER IA-32e set bit
+
+ENTRIES_PER_PT      equ 512             ; Entries per page table
+SIZEOF_PT_ENTRY     equ 8               ; Size of PT entry (64 bits)
+
+; GDT bits
+; - access bits
+SEG_PRESENT         equ 1 << 7          ; Present bit
+SEG_NOT_SYS         equ 1 << 4          ; Non-system segment
+SEG_EXEC            equ 1 << 3          ; Executable segment
+SEG_RW              equ 1 << 1          ; Read-write segment
+
+; - flags bits
+SEG_GRAN_4K         equ 1 << 7          ; Page granularity
+SEG_SZ_32           equ 1 << 6          ; 32-bit size
+SEG_LONG_MODE       equ 1 << 5          ; Long mode segment
+
+; Stack size for the trampoline and early kernel code
+SIZEOF_STACK        equ 1 << 14         ; 16 kiB
+
+; VGA text buffer (for early failure messages)
+VGA_TEXT_BUF        equ 0xb8000
@@ -0,0 +1,245 @@
+; magnetite_os - kern/src/asm/entry32.asm
+; A Multiboot2-compliant entry trampoline for the kernel
+;
+; This allows the kernel to be loaded by a foreign bootloader
+; (such as GRUB), which leaves us in 32-bit protected mode with
+; paging disabled, rather than by 'magnetite_os/boot'.
+;
+; The tasks are not trivial, but are well-defined:
+; - carry a Multiboot2 header
+; - verify that a Multiboot2 bootloader loaded us
+; - check for long mode capability
+; - identity-map the lower 4 GiB using large pages
+; - enable PAE, long mode and paging
+; - load 64-bit GDT
+; - hand over to the Rust routine '_start_mb2'
+;
+; Refer to 'kern/src/asm/defs.asm' for definitions
+
+; Include definitions
+%include "kern/src/asm/defs.asm"
+
+global _mb2_entry                           ; Global export of _mb2_entry
+global _kern_stack_bottom                   ; Stack bounds (for backtraces)
+global _kern_stack_top
+extern _start_mb2                           ; Import of Rust '_start_mb2' routine
+
+; The header must be 8-byte aligned, and must
+; reside within the first 32 kiB of the image
+section .multiboot2 progbits alloc noexec nowrite align=8
+mb2_header:
+    dd MB2_HEADER_MAGIC                     ; Magic number
+    dd MB2_ARCH_I386                        ; Architecture
+    dd mb2_header.end - mb2_header          ; Header length
+    dd 0x100000000 - (MB2_HEADER_MAGIC + MB2_ARCH_I386 + (mb2_header.end - mb2_header))
+    ; --- no optional tags (for now) --- ;
+.end_tag:
+    dw MB2_TAG_END                          ; Type
+    dw 0                                    ; Flags
+    dd 8                                    ; Size
+.end:
+
+; Do not rely on the bootloader for anything
+; other than EAX, EBX, CS and DS - the stack
+; pointer in particular is undefined
+[bits 32]
+section .entry32 progbits alloc exec nowrite align=16
+_mb2_entry:
+    cli                                     ; Kill interrupts (if they are still active)
+    cld                                     ; Clear DF
+    mov esp, stack.top                      ; Set up our own stack
+
+    ; Preserve magic number and info pointer
+    mov [mb2_state.magic], eax
+    mov [mb2_state.info], ebx
+
+    ; Check whether a Multiboot2 bootloader loaded us
+    cmp eax, MB2_LOADER_MAGIC
+    je check_lm
+
+    lea esi, [msgs.no_mb2]                  ; Load pointer to reason
+    jmp panic32                             ; Panic - never to return...
+
+; Check if long mode is supported
+; - CPUID is assumed to be supported, as
+; Multiboot2 requires at least an i386
+check_lm:
+    ; Check if CPUID supports extended features
+    mov eax, EXT_CPUID                      ; Check highest EAX parameter
+    cpuid
+    cmp eax, FEAT_CPUID                     ; If EAX < FEAT_CPUID, no dice...
+    jb .no_lm
+
+    ; Check if long mode is supported
+    mov eax, FEAT_CPUID                     ; Check for features
+    cpuid
+    test edx, LM_EDX_CPUID                  ; Check if the LM bit is set
+    jnz .end                                ; Exit if it is set
+    ; --- fall-through --- ;
+.no_lm:
+    lea esi, [msgs.no_lm]                   ; Load pointer to reason
+    jmp panic32                             ; Panic - never to return...
+.end:
+
+; Identity-map the lower 4 GiB using large pages,
+; which covers the kernel image, the boot information
+; structure, and (typically) the linear framebuffer
+init_paging:
+    ; Clear the paging structures
+    ; - they should already be zeroed, but
+    ;   we don't want to rely on that
+    lea edi, [pml4]                         ; Point EDI to paging structures base
+    xor eax, eax                            ; zero EAX
+    mov ecx, (pts_end - pml4) / 4           ; obtain structure size in double words
+    rep stosd                               ; copy zeroes, one double word at a time
+
+    ; PML4 entry 0 -> PDPT
+    lea eax, [pdpt]                         ; Obtain address for PDPT
+    or eax, PT_PRESENT | PT_READWRITE       ; Apply flags
+    mov [pml4], eax                         ; Write entry to PML4
+
+    ; PDPT entries 0..NUM_PDTS -> PDTs
+    lea edi, [pdpt]                         ; Point EDI to PDPT
+    lea eax, [pdts]                         ; Obtain address for first PDT
+    or eax, PT_PRESENT | PT_READWRITE       ; Apply flags
+    mov ecx, NUM_PDTS                       ; Fill PDPT with this many entries
+.set_pdpt_entry:
+    mov [edi], eax                          ; Write entry to [EDI]
+    add eax, SIZEOF_PT                      ; Point to next PDT
+    add edi, SIZEOF_PT_ENTRY                ; Write to next entry
+    loop .set_pdpt_entry
+
+    ; PDT entries -> 2 MiB pages
+    lea edi, [pdts]                                     ; Point EDI to first PDT
+    mov eax, PT_PRESENT | PT_READWRITE | PT_PAGESIZE    ; Point to +0 GiB, and apply flags
+    mov ecx, NUM_PDTS * ENTRIES_PER_PT                  ; Fill PDTs with this many entries
+.set_pdt_entry:
+    mov [edi], eax                          ; Write entry to [EDI]
+    add eax, SIZEOF_LARGE_PAGE              ; Map next physical page
+    add edi, SIZEOF_PT_ENTRY                ; Write to next entry
+    loop .set_pdt_entry
+
+; Enable long mode and hand over
+; control to 64-bit code
+enable_lm:
+    lea eax, [pml4]                         ; Let the CPU know where the tables are
+    mov cr3, eax
+
+    mov eax, cr4                            ; Load CR4 into EAX
+    or eax, PAE_ENABLE                      ; Enable PAE in CR4
+    mov cr4, eax                            ; Store modified CR4
+
+    mov ecx, EFER_MSR                       ; Enable IA-32e mode
+    rdmsr
+    or eax, EFER_LME
+    wrmsr
+
+    mov eax, cr0                            ; Then enable paging
+    or eax, PG_ENABLE
+    mov cr0, eax
+
+    ; Load 64-bit GDT and perform far jump
+    lgdt [gdt64.pointer]
+    jmp gdt64.code:_mb2_entry64
+
+; Print reason to the VGA text buffer, then halt
+; - does NOT parse LF
+; Accepts:
+; - ESI: pointer to null-terminated string
+panic32:
+    mov edi, VGA_TEXT_BUF                   ; Set target to VGA text buffer
+    mov ah, 0x4f                            ; White-on-red
+.top:
+    lodsb                                   ; Load and auto-increment
+    test al, al                             ; Break loop on null
+    jz .halt
+    stosw                                   ; Write character to buffer
+    jmp .top                                ; Go back to top
+.halt:
+    cli
+    hlt
+    jmp .halt
+
+[bits 64]
+_mb2_entry64:
+    ; Initialize segments
+    mov ax, gdt64.data
+    mov ds, ax
+    mov es, ax
+    mov fs, ax
+    mov gs, ax
+    mov ss, ax
+
+    ; Reset stack, and terminate the frame chain
+    mov rsp, stack.top
+    xor rbp, rbp
+
+    ; Load arguments according to
+    ; the System V AMD64 ABI
+    ; - RDI: bootloader magic number
+    ; - RSI: physical address of the boot information
+    mov edi, [mb2_state.magic]
+    mov esi, [mb2_state.info]
+
+    call _start_mb2
+    ; --- fall-through (unlikely) --- ;
+.halt:
+    cli
+    hlt
+    jmp .halt
+
+section .data
+; 64-bit GDT
+; - identical to the one found in 'boot/src/asm/stub32.asm'
+align 8
+gdt64:
+    .null: equ $ - gdt64
+        dq 0
+    .code: equ $ - gdt64
+        .code.limit_lo   dw 0xffff
+        .code.base_lo    dw 0x0000
+        .code.base_mid   db 0x00
+        .code.access     db SEG_PRESENT | SEG_NOT_SYS | SEG_EXEC | SEG_RW
+        .code.sflags     db SEG_GRAN_4K | SEG_LONG_MODE | 0x0f
+        .code.base_high  db 0x00
+    .data: equ $ - gdt64
+        .data.limit_lo   dw 0xffff
+        .data.base_lo    dw 0x0000
+        .data.base_mid   db 0x00
+        .data.access     db SEG_PRESENT | SEG_NOT_SYS | SEG_RW
+        .data.sflags     db SEG_GRAN_4K | SEG_SZ_32 | 0x0f
+        .data.base_high  db 0x00
+    .pointer:
+        dw $ - gdt64 - 1
+        dd gdt64, 0
+
+; State handed over by the bootloader
+mb2_state:
+    .magic      dd 0
+    .info       dd 0
+
+; Null-terminated messages
+msgs:
+    .no_mb2     db "Kernel was not loaded by a Multiboot2-compliant bootloader", 0
+    .no_lm      db "CPU does not support x86-64 long mode", 0
+
+section .bss
+; Paging structures
+alignb 4096
+pml4:
+    resb SIZEOF_PT
+pdpt:
+    resb SIZEOF_PT
+pdts:
+    resb NUM_PDTS * SIZEOF_PT
+pts_end:
+
+; Trampoline (and early kernel) stack
+; - `.top` attaches to the closest preceding non-local
+;   label, so nothing may come between `stack` and `.top`
+alignb 16
+_kern_stack_bottom:
+stack:
+    resb SIZEOF_STACK
+.top:
+_kern_stack_top:
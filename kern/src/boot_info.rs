@@ -0,0 +1,153 @@
+/*!
+    Boot information gathered by either boot path
+
+    The kernel can be started by `magnetite_os/boot`, or by a
+    Multiboot2-compliant bootloader (such as GRUB). Either way, the
+    information that the kernel cares about is converted into the
+    platform-agnostic definitions found in `common`, so that the
+    remainder of the kernel doesn't care about how it was loaded.
+*/
+
+// Definition uses
+use common::plat::pc_bios::handoff::KernHandoff;
+use common::plat::pc_bios::multiboot2::BootInfo;
+use common::plat::pc_bios::vesa::ScreenInfo;
+use common::shared::GenericError;
+use common::shared::mm::{PhysMemKind, PhysMemRegion, RegionSpan};
+
+/// Maximum number of memory regions retained by the kernel
+pub const MAX_REGIONS: usize = 128;
+
+/// Boot path that started the kernel
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum BootPath {
+    /// Started by `magnetite_os/boot`
+    Native,
+
+    /// Started by a Multiboot2-compliant bootloader
+    Multiboot2,
+}
+
+/**
+    Boot information in the kernel's own terms
+
+    The memory map is copied into a fixed-size array, as the
+    kernel cannot allocate memory at this point.
+*/
+pub struct BootContext<'a> {
+    path: BootPath,
+    cmdline: &'a str,
+    loader_name: Option<&'a str>,
+    screen_info: Option<ScreenInfo>,
+    regions: [PhysMemRegion; MAX_REGIONS],
+    num_regions: usize,
+    log_ring: Option<RegionSpan>,
+}
+
+impl<'a> BootContext<'a> {
+    // Internal: create an empty context
+    fn empty(path: BootPath) -> Self {
+        BootContext {
+            path,
+            cmdline: "",
+            loader_name: None,
+            screen_info: None,
+            regions: [PhysMemRegion::new(0, 0, PhysMemKind::hole()); MAX_REGIONS],
+            num_regions: 0,
+            log_ring: None,
+        }
+    }
+
+    /**
+        Create new instance of `BootContext` from the native handoff
+
+        Memory map entries in excess of [`MAX_REGIONS`] are dropped.
+    */
+    pub fn from_native(handoff: &KernHandoff<'a>) -> Result<Self, GenericError> {
+        let mut ctx = BootContext::empty(BootPath::Native);
+
+        if !handoff.is_valid() {
+            return Err(GenericError::ErrorMessage(
+                "native handoff has a bad magic number",
+            ));
+        }
+
+        for (slot, &entry) in ctx.regions.iter_mut().zip(handoff.e820_map()) {
+            *slot = entry.into();
+            ctx.num_regions += 1;
+        }
+
+        ctx.cmdline = handoff.cmdline().unwrap_or("");
+        ctx.loader_name = Some("magnetite_os/boot");
+        ctx.screen_info = handoff.screen_info().copied();
+        ctx.log_ring = handoff.log_ring();
+
+        Ok(ctx)
+    }
+
+    /**
+        Create new instance of `BootContext` from Multiboot2
+        boot information
+
+        Memory map entries in excess of [`MAX_REGIONS`] are dropped.
+    */
+    pub fn from_multiboot2(info: &BootInfo<'a>) -> Result<Self, GenericError> {
+        let mut ctx = BootContext::empty(BootPath::Multiboot2);
+
+        // - the memory map is the one tag we can't do without
+        let mmap = match info.memory_map() {
+            Some(m) => m,
+            None => {
+                return Err(GenericError::ErrorMessage(
+                    "Multiboot2 information lacks a memory map",
+                ));
+            }
+        };
+
+        for (slot, region) in ctx.regions.iter_mut().zip(mmap.regions()) {
+            *slot = region;
+            ctx.num_regions += 1;
+        }
+
+        ctx.cmdline = info.command_line().unwrap_or("");
+        ctx.loader_name = info.bootloader_name();
+        ctx.screen_info = info.screen_info();
+
+        Ok(ctx)
+    }
+
+    /// Returns the boot path that started the kernel
+    pub fn path(&self) -> BootPath {
+        self.path
+    }
+
+    /// Returns the kernel command line (empty if none was provided)
+    pub fn cmdline(&self) -> &'a str {
+        self.cmdline
+    }
+
+    /// Returns the name of the bootloader, if known
+    pub fn loader_name(&self) -> Option<&'a str> {
+        self.loader_name
+    }
+
+    /// Returns the screen information, if representable
+    pub fn screen_info(&self) -> Option<&ScreenInfo> {
+        self.screen_info.as_ref()
+    }
+
+    /// Returns the physical memory regions
+    pub fn regions(&self) -> &[PhysMemRegion] {
+        &self.regions[..self.num_regions]
+    }
+
+    /**
+        Returns the physical range of the bootloader's log ring, if any
+
+        Only `magnetite_os/boot` keeps a log ring, so this is
+        always `None` on the Multiboot2 boot path.
+    */
+    pub fn log_ring(&self) -> Option<RegionSpan> {
+        self.log_ring
+    }
+}
@@ -1,20 +1,406 @@
 #![no_std]
 #![no_main]
+// - the panic reporting paths are compiled out for in-OS tests
+#![cfg_attr(test, allow(dead_code, unused_imports))]
+// - in-OS tests run under QEMU (refer to `scripts/qemu_test.py`)
+#![cfg_attr(all(test, target_os = "none"), feature(custom_test_frameworks))]
+#![cfg_attr(
+    all(test, target_os = "none"),
+    test_runner(common::plat::pc_bios::qemu::test_runner)
+)]
+#![cfg_attr(
+    all(test, target_os = "none"),
+    reexport_test_harness_main = "test_main"
+)]
 
 // Definition uses
 extern crate common;
+use core::mem::MaybeUninit;
 use core::panic::PanicInfo;
+use core::ptr::addr_of;
+use core::slice::{from_raw_parts, from_raw_parts_mut};
 
-// Initial routine
+use common::arch::x86::backtrace::{self, StackWalker};
+use common::arch::x86::idle::halt_forever;
+use common::plat::pc_bios::handoff::KernHandoff;
+use common::plat::pc_bios::multiboot2::{self, BootInfo};
+use common::plat::pc_bios::pit::PitClock;
+use common::plat::pc_bios::power::{PanicPolicy, PowerControl};
+use common::plat::pc_bios::vga::console::VgaConsole;
+use common::shared::cmdline::Cmdline;
+use common::shared::io::Write;
+use common::shared::log::kmsg::LogRing;
+use common::shared::log::{self, LevelFilter};
+use common::shared::mm::{MemoryRegionKind, RegionSpan};
+use common::shared::structs::once::Once;
+use common::shared::structs::spin_lock::Mutex;
+use common::shared::symbols::SymbolTable;
+use common::{info, warn};
+
+// - expose boot information module
+pub mod boot_info;
+use boot_info::{BootContext, BootPath};
+
+// Command-line parameters understood by the kernel
+static KNOWN_PARAMS: [&str; 5] = ["console", "loglevel", "mem", "panic", "quiet"];
+
+// Instantiate VGA console with default values
+static VGA_CONSOLE: Mutex<VgaConsole> = Mutex::new(unsafe { VgaConsole::defaults() });
+
+// Kernel log, which continues the boot log if there is one
+static DMESG: Mutex<LogRing<'static>> = Mutex::new(LogRing::detached());
+
+// Backing storage for the kernel log, if the bootloader didn't pass one
+const DMESG_LEN: usize = 16 << 10;
+static mut DMESG_BUF: [u8; DMESG_LEN] = [0; DMESG_LEN];
+
+// Clock timestamping the kernel log
+static mut DMESG_CLOCK: MaybeUninit<PitClock> = MaybeUninit::uninit();
+
+// Power management state, and what to do with it after a panic
+// - legacy methods are used until the ACPI tables are parsed
+static POWER: Mutex<PowerControl> = Mutex::new(PowerControl::legacy());
+static PANIC_POLICY: Mutex<PanicPolicy> = Mutex::new(PanicPolicy::Halt);
+
+// Bounds of the stack the kernel runs on, for backtraces
+// - each boot path starts the kernel on a different stack
+static STACK: Once<RegionSpan> = Once::new();
+
+// Stack bounds of the Multiboot2 boot path (refer to
+// `kern/src/asm/entry32.asm`), and bounds of the embedded
+// symbol table (refer to `link_kern.ld`)
+unsafe extern "C" {
+    static _kern_stack_bottom: u8;
+    static _kern_stack_top: u8;
+    static _ksyms_start: u8;
+    static _ksyms_end: u8;
+}
+
+// Initial routine for the native boot path
 //  - call it 'main' for the sake of brevity
-// TODO
+// - called by `magnetite_os/boot`, with the lower 4 GiB
+//   identity-mapped (refer to `common::plat::pc_bios::handoff`)
 #[inline(never)]
 #[unsafe(no_mangle)]
-pub extern "C" fn main() -> ! {
-    loop {}
+pub extern "C" fn main(handoff: &'static KernHandoff<'static>) -> ! {
+    // - an invalid handoff can't be trusted with the stack bounds,
+    //   and no backtrace is better than a fault while walking
+    let stack = Some(handoff)
+        .filter(|h| h.is_valid())
+        .and_then(|h| h.stack());
+    STACK.call_once(|| stack.unwrap_or(RegionSpan::new(0, 0)));
+
+    match BootContext::from_native(handoff) {
+        Ok(ctx) => kmain(&ctx),
+        Err(e) => panic!("failed to process native handoff: {} [{}]", e, e.code()),
+    }
+}
+
+// Initial routine for the Multiboot2 boot path
+// - called by `_mb2_entry64` in `kern/src/asm/entry32.asm`,
+//   with the lower 4 GiB identity-mapped
+#[inline(never)]
+#[unsafe(no_mangle)]
+extern "C" fn _start_mb2(magic: u32, info_addr: usize) -> ! {
+    STACK.call_once(entry32_stack);
+
+    // - the trampoline checks this as well, but be paranoid
+    if magic != multiboot2::BOOTLOADER_MAGIC {
+        panic!("bad Multiboot2 bootloader magic: {:#010x}", magic);
+    }
+
+    // SAFETY: the bootloader places the structure in memory that
+    // we don't touch, and the trampoline identity-maps it
+    let info = match unsafe { BootInfo::from_addr(info_addr) } {
+        Ok(i) => i,
+        Err(e) => panic!("received invalid Multiboot2 information: {:?}", e),
+    };
+
+    // - prefer the bootloader's copy of the RSDP over a scan
+    *POWER.lock() = unsafe { PowerControl::from_acpi(info.rsdp()) };
+
+    match BootContext::from_multiboot2(&info) {
+        Ok(ctx) => kmain(&ctx),
+        Err(e) => panic!(
+            "failed to process Multiboot2 information: {} [{}]",
+            e,
+            e.code()
+        ),
+    }
+}
+
+// Kernel routine shared by all boot paths
+// TODO
+fn kmain(ctx: &BootContext) -> ! {
+    kmain_log(ctx);
+
+    // - the test runner exits QEMU once it's done
+    #[cfg(all(test, target_os = "none"))]
+    test_main();
+
+    // - an invalid policy is reported along with the command line
+    if let Some(policy) = Cmdline::parse(ctx.cmdline())
+        .ok()
+        .and_then(|c| c.get_str("panic"))
+        .and_then(|v| v.as_str()?.parse().ok())
+    {
+        *PANIC_POLICY.lock() = policy;
+    }
+
+    // Only text modes are supported for now
+    if let Some(screen_info) = ctx.screen_info().filter(|s| s.cells_x() > 0) {
+        let mut handle = VGA_CONSOLE.lock();
+
+        // - initialize the console's geometry
+        unsafe {
+            handle.set_dims(screen_info.cells_x(), screen_info.cells_y());
+        }
+
+        // - absorb errors, as there's nowhere to report them
+        let _ = kmain_banner(&mut *handle, ctx);
+        let _ = writeln!(&mut *handle, "\n --- (Kernel log) --- ");
+        let _ = DMESG.lock().dump(&mut *handle);
+    }
+
+    // - there is no IDT yet, so interrupts stay disabled
+    halt_forever();
+}
+
+// Set up the kernel log, adopting the boot log if possible
+fn kmain_log(ctx: &BootContext) {
+    // - the lower 4 GiB are identity-mapped, and nothing else
+    //   claims the boot log, so it can be used as-is
+    let adopted = ctx.log_ring().map(|span| {
+        LogRing::adopt(unsafe { from_raw_parts_mut(span.base() as *mut u8, span.size()) })
+    });
+
+    let valid = adopted.as_ref().map(|r| r.is_ok());
+
+    let mut ring = match adopted {
+        Some(Ok(ring)) => ring,
+        // SAFETY: this is the only reference to `DMESG_BUF`
+        _ => LogRing::new(unsafe { from_raw_parts_mut((&raw mut DMESG_BUF).cast(), DMESG_LEN) })
+            .expect("kernel log buffer is too small"),
+    };
+
+    // - the PIT is programmed anew, so timestamps of kernel
+    //   records restart from zero after the boot log's
+    // SAFETY: this is the only reference to `DMESG_CLOCK`
+    let clock: *mut PitClock = (&raw mut DMESG_CLOCK).cast();
+    ring.set_clock(unsafe {
+        clock.write(PitClock::new());
+        &mut *clock
+    });
+    *DMESG.lock() = ring;
+
+    // - there's nowhere to report a failure yet
+    let _ = log::register_sink(&DMESG, LevelFilter::Trace);
+    log::set_max_level(LevelFilter::Trace);
+
+    match valid {
+        Some(true) => info!("Continuing the boot log"),
+        Some(false) => warn!("Discarded an invalid boot log"),
+        None => info!("No boot log was passed"),
+    }
+}
+
+// Print boot summary to the provided writer
+fn kmain_banner<W: Write>(w: &mut W, ctx: &BootContext) -> Result<(), common::shared::io::Error> {
+    writeln!(w, "*** magnetite_os kernel ***\n")?;
+
+    let path = match ctx.path() {
+        BootPath::Native => "native",
+        BootPath::Multiboot2 => "Multiboot2",
+    };
+
+    writeln!(
+        w,
+        " I: Boot path: {} ({})",
+        path,
+        ctx.loader_name().unwrap_or("unknown bootloader")
+    )?;
+    writeln!(w, " I: Command line: \"{}\"", ctx.cmdline())?;
+    kmain_cmdline(w, ctx.cmdline())?;
+    writeln!(w, " I: Memory regions (base, size, usable):")?;
+
+    for region in ctx.regions() {
+        writeln!(
+            w,
+            " >  0x{:0>16x}\t0x{:0>16x}\t{}",
+            region.span().base(),
+            region.span().size(),
+            region.kind().is_usable()
+        )?;
+    }
+
+    w.flush()
+}
+
+// Report recognized and unknown command-line parameters
+fn kmain_cmdline<W: Write>(w: &mut W, cmdline: &str) -> Result<(), common::shared::io::Error> {
+    let cmdline = match Cmdline::parse(cmdline) {
+        Ok(c) => c,
+        Err(e) => return writeln!(w, " W: Ignoring invalid command line ({})", e),
+    };
+
+    if let Some(v) = cmdline.get_str("console") {
+        writeln!(w, " >  console:\t{}", v)?;
+    }
+    if let Some(v) = cmdline.get_str("loglevel") {
+        writeln!(w, " >  loglevel:\t{}", v)?;
+    }
+
+    match cmdline.get_int("mem") {
+        Ok(Some(mem)) => writeln!(w, " >  mem:\t\t{} KiB", mem >> 10)?,
+        Ok(None) => {}
+        Err(e) => writeln!(w, " W: Invalid `mem` parameter ({})", e)?,
+    }
+
+    if let Some(v) = cmdline.get_str("panic") {
+        match v.as_str().and_then(|s| s.parse::<PanicPolicy>().ok()) {
+            Some(policy) => writeln!(w, " >  panic:\t{}", policy)?,
+            None => writeln!(w, " W: Invalid `panic` parameter \"{}\"", v)?,
+        }
+    }
+
+    match cmdline.get_bool("quiet") {
+        Ok(Some(quiet)) => writeln!(w, " >  quiet:\t{}", quiet)?,
+        Ok(None) => {}
+        Err(e) => writeln!(w, " W: Invalid `quiet` parameter ({})", e)?,
+    }
+
+    for param in cmdline.unknown(&KNOWN_PARAMS) {
+        writeln!(w, " W: Unknown parameter `{}`", param.key())?;
+    }
+
+    Ok(())
+}
+
+#[cfg(not(all(test, target_os = "none")))]
+#[panic_handler]
+fn panic(info: &PanicInfo<'_>) -> ! {
+    // - the frame pointer is the root of the backtrace
+    log::enter_panic_mode();
+    let fp = backtrace::frame_pointer();
+
+    let f = |c: &mut VgaConsole| {
+        match info.location() {
+            Some(loc) => writeln!(c, "\n**kernel panicked** ({})", loc)?,
+            None => writeln!(c, "\n**kernel panicked** (source location unknown)")?,
+        }
+        writeln!(c, " E: {}", info.message())?;
+
+        let frames = unsafe { StackWalker::new(fp, active_stack()) };
+        backtrace::write_backtrace(c, frames, &symbols())
+    };
+
+    // - write to the console, first by arbitration, then by
+    //   force, and absorb errors, as there's nowhere to report them
+    let _ = match VGA_CONSOLE.try_lock_repeat(255) {
+        Ok(mut g) => f(&mut g),
+        Err(()) => {
+            let c = unsafe { VGA_CONSOLE.get_mut() };
+            f(c).and_then(|_| match VGA_CONSOLE.owner() {
+                Some(loc) => writeln!(c, " W: console was held at {}", loc),
+                None => Ok(()),
+            })
+        }
+    };
+
+    // - both locks are only held briefly during startup,
+    //   so fall back to halting if they are taken
+    let policy = PANIC_POLICY.try_lock().map_or(PanicPolicy::Halt, |p| *p);
+    let power = POWER.try_lock().map_or(PowerControl::legacy(), |p| *p);
+    unsafe { power.apply(policy, &mut PitClock::new()) }
 }
 
+// - in-OS tests report failures over the serial port
+#[cfg(all(test, target_os = "none"))]
 #[panic_handler]
-fn panic(_info: &PanicInfo<'_>) -> ! {
-    loop {}
+fn panic(info: &PanicInfo<'_>) -> ! {
+    common::plat::pc_bios::qemu::test_panic(info)
+}
+
+// Bounds of the stack the kernel runs on
+// - a panic before the boot path records them walks no frames
+fn active_stack() -> RegionSpan {
+    STACK.get().copied().unwrap_or(RegionSpan::new(0, 0))
+}
+
+// Bounds of the stack set up by `kern/src/asm/entry32.asm`
+fn entry32_stack() -> RegionSpan {
+    let bottom = addr_of!(_kern_stack_bottom) as usize;
+    let top = addr_of!(_kern_stack_top) as usize;
+
+    RegionSpan::new(bottom, top - bottom)
+}
+
+// Embedded symbol table, for resolving backtraces
+// - the first link of the kernel has an empty table, and
+//   a malformed table is treated as if it were empty
+fn symbols() -> SymbolTable<'static> {
+    let start = addr_of!(_ksyms_start);
+    let len = addr_of!(_ksyms_end) as usize - start as usize;
+
+    // SAFETY: the linker script places the table between the symbols
+    let raw = unsafe { from_raw_parts(start, len) };
+    SymbolTable::from_bytes(raw).unwrap_or(SymbolTable::empty())
+}
+
+// In-OS tests, run by QEMU (refer to `scripts/qemu_test.py`)
+#[cfg(all(test, target_os = "none"))]
+mod tests {
+    use super::*;
+    use common::plat::pc_bios::vga::console;
+    use common::shared::structs::array_vec::ArrayVec;
+    use common::shared::structs::mmio::{Mmio, Register};
+    use common::shared::traits::Clock;
+
+    #[test_case]
+    fn log_reaches_dmesg() {
+        info!("in-OS test marker");
+
+        // - the record is the most recent one
+        let ring = DMESG.lock();
+        let mut msg = [0u8; 64];
+        let entry = ring.read(ring.next_seq() - 1, &mut msg).unwrap();
+        let text = core::str::from_utf8(&msg[..entry.len().min(msg.len())]).unwrap();
+        assert!(text.contains("in-OS test marker"));
+    }
+
+    #[test_case]
+    fn console_writes_video_memory() {
+        let mut handle = VGA_CONSOLE.lock();
+        handle.unset_shadowed();
+        write!(&mut *handle, "\nOK").unwrap();
+        let (cols, rows) = (handle.cols(), handle.rows());
+        drop(handle);
+
+        // - the text lands at the start of the bottom row
+        let buf: Mmio<[Register<u16>]> =
+            unsafe { Mmio::identity_slice(console::DEF_BUF_ADDR, cols * rows) };
+        let cell = |i| buf.get((rows - 1) * cols + i).unwrap().read() as u8;
+        assert_eq!([cell(0), cell(1)], *b"OK");
+    }
+
+    #[test_case]
+    fn cmdline_report() {
+        let mut out: ArrayVec<u8, 256> = ArrayVec::new();
+        kmain_cmdline(&mut out, "quiet bogus=1 panic=reboot").unwrap();
+
+        let text = core::str::from_utf8(&out).unwrap();
+        assert!(text.contains("quiet:\ttrue"));
+        assert!(text.contains("Unknown parameter `bogus`"));
+    }
+
+    #[test_case]
+    fn pit_clock_advances() {
+        let mut clock = unsafe { PitClock::new() };
+        let start = clock.millis();
+
+        // - QEMU's PIT runs in real time
+        while clock.millis() < start + 10 {
+            core::hint::spin_loop();
+        }
+    }
 }
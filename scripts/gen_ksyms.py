@@ -0,0 +1,80 @@
+#!/usr/bin/env python3
+# -*- coding: utf-8 -*-
+
+# Helper script to generate the embedded symbol table
+# of a stage (see `common/src/shared/symbols.rs`)
+#
+# The input is the first link of the stage, and the output
+# is a raw table, to be wrapped into an object file and
+# placed at the very end of the second link.
+# - only function symbols are of interest, as the table
+#   is used to symbolize return addresses
+# - names are demangled by `nm`, and legacy Rust hashes
+#   are stripped, as they only add noise to backtraces
+
+import re
+import struct
+import subprocess
+import sys
+
+SYMTAB_MAGIC = b"SYMS"
+HEADER_LEN = 8
+ENTRY_LEN = 24
+
+HASH_SUFFIX = re.compile(r"::h[0-9a-f]{16}$")
+
+
+def read_symbols(elf):
+    out = subprocess.run(
+        ["nm", "--defined-only", "--demangle", "--print-size", "--numeric-sort", elf],
+        check=True,
+        capture_output=True,
+        text=True,
+    ).stdout
+
+    symbols = {}
+    for line in out.splitlines():
+        fields = line.split(maxsplit=3)
+
+        # - the size column is absent for some symbols
+        if len(fields) == 3:
+            addr, kind, name = fields
+            size = "0"
+        elif len(fields) == 4:
+            addr, size, kind, name = fields
+        else:
+            continue
+
+        if kind not in "TtWw":
+            continue
+
+        # - keep the first name found at each address
+        symbols.setdefault(int(addr, 16), (int(size, 16), HASH_SUFFIX.sub("", name)))
+
+    return sorted(symbols.items())
+
+
+def pack(symbols):
+    names = b""
+    entries = b""
+    names_off = HEADER_LEN + ENTRY_LEN * len(symbols)
+
+    for addr, (size, name) in symbols:
+        raw = name.encode("utf-8")
+        entries += struct.pack("<QQII", addr, size, names_off + len(names), len(raw))
+        names += raw
+
+    return SYMTAB_MAGIC + struct.pack("<I", len(symbols)) + entries + names
+
+
+def main():
+    if len(sys.argv) != 3:
+        print("Usage: gen_ksyms.py <first-link.elf> <output>", file=sys.stderr)
+        sys.exit(1)
+
+    with open(sys.argv[2], "wb") as f:
+        f.write(pack(read_symbols(sys.argv[1])))
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,110 @@
+#!/usr/bin/env python3
+# -*- coding: utf-8 -*-
+
+# Helper script to run an in-OS test image under QEMU
+# (see `common/src/shared/testing.rs`)
+#
+# The image reports over COM1, and exits through the `isa-debug-exit` device.
+# - the exit status of QEMU is `(code << 1) | 1`, so that
+#   `0x10` (success) becomes 33, and `0x11` (failure) 35
+# - `-nographic` forwards COM1 to stdout
+# - a run that doesn't exit in time is treated as a hang
+
+import argparse
+import re
+import subprocess
+import sys
+
+EXIT_SUCCESS = 33
+EXIT_FAILED = 35
+
+DEF_TIMEOUT = 60
+
+QEMU_ARGS = [
+    "qemu-system-x86_64",
+    "-nographic",
+    "-no-reboot",
+    "-monitor", "none",
+    "-device", "isa-debug-exit,iobase=0xf4,iosize=0x04",
+]
+
+RE_RUNNING = re.compile(r"^running (\d+) tests?$")
+RE_CASE = re.compile(r"^test (\S+) \.\.\. (ok|FAILED)$")
+RE_RESULT = re.compile(r"^test result: (ok|FAILED)")
+
+
+def parse_args():
+    parser = argparse.ArgumentParser(description="Run an in-OS test image under QEMU")
+    image = parser.add_mutually_exclusive_group(required=True)
+    image.add_argument("--drive", help="raw disk image to boot from")
+    image.add_argument("--cdrom", help="ISO image to boot from")
+    parser.add_argument("--timeout", type=int, default=DEF_TIMEOUT,
+                        help="seconds to wait before giving up (default: %(default)s)")
+    return parser.parse_args()
+
+
+def qemu_cmd(args):
+    if args.drive:
+        return QEMU_ARGS + ["-drive", "format=raw,file=" + args.drive]
+    return QEMU_ARGS + ["-cdrom", args.cdrom]
+
+
+def summarize(lines):
+    expected = None
+    passed = []
+    failed = []
+    result = None
+
+    for line in lines:
+        if m := RE_RUNNING.match(line):
+            expected = int(m.group(1))
+        elif m := RE_CASE.match(line):
+            (passed if m.group(2) == "ok" else failed).append(m.group(1))
+        elif m := RE_RESULT.match(line):
+            result = m.group(1)
+
+    return expected, passed, failed, result
+
+
+def main():
+    args = parse_args()
+    cmd = qemu_cmd(args)
+    print("$ " + " ".join(cmd), file=sys.stderr)
+
+    try:
+        proc = subprocess.run(cmd, stdout=subprocess.PIPE,
+                              stdin=subprocess.DEVNULL, timeout=args.timeout)
+        output = proc.stdout
+        status = proc.returncode
+    except subprocess.TimeoutExpired as e:
+        output = e.stdout or b""
+        status = None
+
+    # - serial output uses `\r\n` line endings
+    text = output.decode("utf-8", errors="replace").replace("\r\n", "\n")
+    sys.stdout.write(text)
+
+    expected, passed, failed, result = summarize(l.rstrip() for l in text.split("\n"))
+
+    if status is None:
+        print(f"error: timed out after {args.timeout}s", file=sys.stderr)
+        return 1
+    if expected is None:
+        print(f"error: no tests were reported (QEMU exited with {status})", file=sys.stderr)
+        return 1
+
+    if status == EXIT_SUCCESS and result == "ok" and len(passed) == expected:
+        return 0
+
+    if status == EXIT_SUCCESS:
+        print(f"error: {len(passed)} of {expected} tests reported as passed", file=sys.stderr)
+    elif status == EXIT_FAILED:
+        names = ", ".join(failed) if failed else "unknown test"
+        print(f"error: {names} failed", file=sys.stderr)
+    else:
+        print(f"error: QEMU exited with {status}", file=sys.stderr)
+    return 1
+
+
+if __name__ == "__main__":
+    sys.exit(main())